@@ -0,0 +1,891 @@
+use localdomain_shared::domain::validate_san;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::{info, warn};
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::FromDer;
+
+use super::http::{self, Request, Response};
+use super::jws::{self, Jwk, Jws};
+use super::{Backend, Config, Problem};
+
+const MAX_NONCES: usize = 1024;
+const ORDER_LIFETIME: time::Duration = time::Duration::hours(24);
+const VALIDATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pending,
+    Ready,
+    Processing,
+    Valid,
+    Invalid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Account {
+    key: Jwk,
+    #[serde(default)]
+    contact: Vec<String>,
+    #[serde(default)]
+    deactivated: bool,
+}
+
+struct Order {
+    account: String,
+    identifiers: Vec<String>,
+    authorizations: Vec<String>,
+    status: Status,
+    expires: OffsetDateTime,
+    certificate: Option<String>,
+    error: Option<serde_json::Value>,
+}
+
+struct Authorization {
+    account: String,
+    identifier: String,
+    wildcard: bool,
+    status: Status,
+    expires: OffsetDateTime,
+    challenge: Option<String>,
+}
+
+struct Challenge {
+    authorization: String,
+    token: String,
+    status: Status,
+    validated: Option<OffsetDateTime>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Default)]
+struct State {
+    nonces: HashSet<String>,
+    nonce_order: VecDeque<String>,
+    accounts: HashMap<String, Account>,
+    orders: HashMap<String, Order>,
+    authorizations: HashMap<String, Authorization>,
+    challenges: HashMap<String, Challenge>,
+    certificates: HashMap<String, (String, String)>,
+}
+
+/// The authenticated account behind a POST request.
+struct Caller {
+    id: String,
+    key: Jwk,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewAccountPayload {
+    #[serde(default)]
+    contact: Vec<String>,
+    #[serde(default)]
+    only_return_existing: bool,
+}
+
+#[derive(Deserialize)]
+struct AccountUpdate {
+    #[serde(default)]
+    contact: Option<Vec<String>>,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Identifier {
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct NewOrderPayload {
+    identifiers: Vec<Identifier>,
+}
+
+#[derive(Deserialize)]
+struct FinalizePayload {
+    csr: String,
+}
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn timestamp(t: OffsetDateTime) -> String {
+    t.format(&Rfc3339).unwrap_or_default()
+}
+
+pub struct Acme {
+    base_url: String,
+    config: Mutex<Config>,
+    backend: Box<dyn Backend>,
+    accounts_path: Option<PathBuf>,
+    state: Mutex<State>,
+}
+
+impl Acme {
+    pub fn new(
+        base_url: String,
+        config: Config,
+        backend: Box<dyn Backend>,
+        accounts_path: Option<PathBuf>,
+    ) -> Self {
+        let mut state = State::default();
+        if let Some(path) = &accounts_path {
+            if let Ok(content) = fs::read_to_string(path) {
+                match serde_json::from_str(&content) {
+                    Ok(accounts) => state.accounts = accounts,
+                    Err(e) => warn!("Ignoring invalid ACME accounts file: {}", e),
+                }
+            }
+        }
+        Self {
+            base_url,
+            config: Mutex::new(config),
+            backend,
+            accounts_path,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn set_config(&self, config: Config) {
+        *self.config.lock().unwrap() = config;
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn new_nonce(&self) -> String {
+        let nonce = new_id();
+        let mut state = self.state.lock().unwrap();
+        state.nonces.insert(nonce.clone());
+        state.nonce_order.push_back(nonce.clone());
+        while state.nonce_order.len() > MAX_NONCES {
+            if let Some(old) = state.nonce_order.pop_front() {
+                state.nonces.remove(&old);
+            }
+        }
+        nonce
+    }
+
+    /// Route a request. Every response carries a fresh nonce and the directory link.
+    pub fn handle(self: &Arc<Self>, request: &Request) -> Response {
+        let response = self.route(request).unwrap_or_else(|problem| {
+            Response::new(problem.status)
+                .header("Content-Type", "application/problem+json")
+                .body(serde_json::to_vec(&problem.to_json()).unwrap_or_default())
+        });
+        response
+            .header("Replay-Nonce", &self.new_nonce())
+            .header("Cache-Control", "no-store")
+            .header(
+                "Link",
+                &format!("<{}>;rel=\"index\"", self.url("/directory")),
+            )
+    }
+
+    fn route(self: &Arc<Self>, request: &Request) -> Result<Response, Problem> {
+        let path = request.path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["directory"]) => Ok(self.directory()),
+            ("GET" | "HEAD", ["acme", "new-nonce"]) => {
+                Ok(Response::new(if request.method == "HEAD" {
+                    200
+                } else {
+                    204
+                }))
+            }
+            ("POST", ["acme", "new-account"]) => self.new_account(request),
+            ("POST", ["acme", "acct", id]) => self.account(request, id),
+            ("POST", ["acme", "acct", id, "orders"]) => self.account_orders(request, id),
+            ("POST", ["acme", "new-order"]) => self.new_order(request),
+            ("POST", ["acme", "order", id]) => self.order(request, id),
+            ("POST", ["acme", "order", id, "finalize"]) => self.finalize(request, id),
+            ("POST", ["acme", "authz", id]) => self.authorization(request, id),
+            ("POST", ["acme", "chall", id]) => self.challenge(request, id),
+            ("POST", ["acme", "cert", id]) => self.certificate(request, id),
+            (_, ["directory"] | ["acme", ..]) => Err(Problem::new(
+                405,
+                "malformed",
+                format!("{} is not allowed here", request.method),
+            )),
+            _ => Err(Problem::not_found()),
+        }
+    }
+
+    fn directory(&self) -> Response {
+        Response::json(
+            200,
+            &serde_json::json!({
+                "newNonce": self.url("/acme/new-nonce"),
+                "newAccount": self.url("/acme/new-account"),
+                "newOrder": self.url("/acme/new-order"),
+                "meta": {
+                    "externalAccountRequired": false,
+                    "website": "https://github.com/kakha13/localdomain",
+                },
+            }),
+        )
+    }
+
+    /// Parse and authenticate a JWS request (RFC 8555 §6.2-6.3).
+    fn authenticate(&self, request: &Request) -> Result<(Jws, Option<Caller>), Problem> {
+        let content_type = request.header("Content-Type").unwrap_or_default();
+        if !content_type.starts_with("application/jose+json") {
+            return Err(Problem::new(
+                415,
+                "malformed",
+                "Requests must use Content-Type application/jose+json",
+            ));
+        }
+        let jws = Jws::parse(&request.body)?;
+        let nonce = jws.protected.nonce.as_deref().unwrap_or_default();
+        if !self.state.lock().unwrap().nonces.remove(nonce) {
+            return Err(Problem::new(400, "badNonce", "Unknown or reused nonce"));
+        }
+        if jws.protected.url != self.url(&request.path) {
+            return Err(Problem::unauthorized(format!(
+                "JWS url '{}' does not match the request URL",
+                jws.protected.url
+            )));
+        }
+
+        if let Some(key) = &jws.protected.jwk {
+            jws.verify(key)?;
+            return Ok((jws, None));
+        }
+        let kid = jws.protected.kid.as_deref().unwrap_or_default();
+        let id = kid
+            .strip_prefix(&self.url("/acme/acct/"))
+            .unwrap_or_default()
+            .to_string();
+        let account = self.state.lock().unwrap().accounts.get(&id).cloned();
+        let Some(account) = account else {
+            return Err(Problem::new(
+                400,
+                "accountDoesNotExist",
+                format!("No account at '{}'", kid),
+            ));
+        };
+        if account.deactivated {
+            return Err(Problem::unauthorized("Account is deactivated"));
+        }
+        jws.verify(&account.key)?;
+        Ok((
+            jws,
+            Some(Caller {
+                id,
+                key: account.key,
+            }),
+        ))
+    }
+
+    /// Authenticate a request that must be signed by an existing account.
+    fn authenticate_account(&self, request: &Request) -> Result<(Jws, Caller), Problem> {
+        match self.authenticate(request)? {
+            (jws, Some(caller)) => Ok((jws, caller)),
+            (_, None) => Err(Problem::malformed(
+                "This request must be signed with an account kid",
+            )),
+        }
+    }
+
+    fn save_accounts(&self, state: &State) {
+        let Some(path) = &self.accounts_path else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(&state.accounts)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(fs::write(path, json)?));
+        if let Err(e) = result {
+            warn!("Failed to save ACME accounts: {}", e);
+        }
+    }
+
+    fn account_json(&self, id: &str, account: &Account) -> serde_json::Value {
+        serde_json::json!({
+            "status": if account.deactivated { "deactivated" } else { "valid" },
+            "contact": account.contact,
+            "orders": self.url(&format!("/acme/acct/{}/orders", id)),
+        })
+    }
+
+    fn new_account(&self, request: &Request) -> Result<Response, Problem> {
+        let (jws, caller) = self.authenticate(request)?;
+        let (None, Some(key)) = (caller, jws.protected.jwk.clone()) else {
+            return Err(Problem::malformed("newAccount must be signed with a jwk"));
+        };
+        let payload: NewAccountPayload = jws.payload_json()?;
+
+        let thumbprint = jws::thumbprint(&key);
+        let mut state = self.state.lock().unwrap();
+        let existing = state
+            .accounts
+            .iter()
+            .find(|(_, a)| jws::thumbprint(&a.key) == thumbprint)
+            .map(|(id, a)| (id.clone(), a.clone()));
+        if let Some((id, account)) = existing {
+            return Ok(Response::json(200, &self.account_json(&id, &account))
+                .header("Location", &self.url(&format!("/acme/acct/{}", id))));
+        }
+        if payload.only_return_existing {
+            return Err(Problem::new(
+                400,
+                "accountDoesNotExist",
+                "No account exists for this key",
+            ));
+        }
+
+        let id = new_id();
+        let account = Account {
+            key,
+            contact: payload.contact,
+            deactivated: false,
+        };
+        let body = self.account_json(&id, &account);
+        state.accounts.insert(id.clone(), account);
+        self.save_accounts(&state);
+        info!("Registered ACME account {}", id);
+        Ok(Response::json(201, &body).header("Location", &self.url(&format!("/acme/acct/{}", id))))
+    }
+
+    fn account(&self, request: &Request, id: &str) -> Result<Response, Problem> {
+        let (jws, caller) = self.authenticate_account(request)?;
+        if caller.id != id {
+            return Err(Problem::unauthorized("Cannot access another account"));
+        }
+        let mut state = self.state.lock().unwrap();
+        if !jws.payload.is_empty() {
+            let update: AccountUpdate = jws.payload_json()?;
+            let account = state.accounts.get_mut(id).ok_or_else(Problem::not_found)?;
+            if let Some(contact) = update.contact {
+                account.contact = contact;
+            }
+            if update.status.as_deref() == Some("deactivated") {
+                account.deactivated = true;
+            }
+            self.save_accounts(&state);
+        }
+        let account = state.accounts.get(id).ok_or_else(Problem::not_found)?;
+        Ok(Response::json(200, &self.account_json(id, account)))
+    }
+
+    fn account_orders(&self, request: &Request, id: &str) -> Result<Response, Problem> {
+        let (_, caller) = self.authenticate_account(request)?;
+        if caller.id != id {
+            return Err(Problem::unauthorized("Cannot access another account"));
+        }
+        let state = self.state.lock().unwrap();
+        let orders: Vec<String> = state
+            .orders
+            .iter()
+            .filter(|(_, o)| o.account == id)
+            .map(|(order_id, _)| self.url(&format!("/acme/order/{}", order_id)))
+            .collect();
+        Ok(Response::json(
+            200,
+            &serde_json::json!({ "orders": orders }),
+        ))
+    }
+
+    /// Check an identifier against the dev-TLD policy. Returns the lowercased
+    /// name and whether it can skip validation.
+    fn check_identifier(
+        &self,
+        identifier: &Identifier,
+        config: &Config,
+        managed: &[String],
+    ) -> Result<(String, bool), Problem> {
+        if identifier.kind != "dns" {
+            return Err(Problem::new(
+                400,
+                "unsupportedIdentifier",
+                format!("Identifier type '{}' is not supported", identifier.kind),
+            ));
+        }
+        let name = identifier.value.trim().to_lowercase();
+        let rejected = |detail: String| Problem::new(400, "rejectedIdentifier", detail);
+        if name.parse::<std::net::IpAddr>().is_ok() {
+            return Err(rejected(format!("'{}' is not a DNS name", name)));
+        }
+        validate_san(&name).map_err(rejected)?;
+
+        let base = name.strip_prefix("*.").unwrap_or(&name);
+        let permitted = config.permitted_suffixes.iter().any(|suffix| {
+            let suffix = suffix.trim_start_matches('.').to_lowercase();
+            base == suffix || base.ends_with(&format!(".{}", suffix))
+        });
+        if !permitted {
+            return Err(rejected(format!(
+                "'{}' is outside the local development TLDs ({})",
+                name,
+                config.permitted_suffixes.join(", ")
+            )));
+        }
+
+        let approved = config.auto_approve && managed.iter().any(|m| m.eq_ignore_ascii_case(base));
+        if name.starts_with("*.") && !approved {
+            return Err(rejected(format!(
+                "Wildcard '{}' needs DNS-01, which is not offered; add {} to LocalDomain so it is approved automatically",
+                name, base
+            )));
+        }
+        Ok((name, approved))
+    }
+
+    fn new_order(&self, request: &Request) -> Result<Response, Problem> {
+        let (jws, caller) = self.authenticate_account(request)?;
+        let payload: NewOrderPayload = jws.payload_json()?;
+        if payload.identifiers.is_empty() {
+            return Err(Problem::malformed("An order needs at least one identifier"));
+        }
+        let config = self.config.lock().unwrap().clone();
+        let managed = if config.auto_approve {
+            self.backend.managed_domains()
+        } else {
+            Vec::new()
+        };
+        let mut names: Vec<(String, bool)> = Vec::new();
+        for identifier in &payload.identifiers {
+            let (name, approved) = self.check_identifier(identifier, &config, &managed)?;
+            if !names.iter().any(|(n, _)| *n == name) {
+                names.push((name, approved));
+            }
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let expires = now + ORDER_LIFETIME;
+        let mut state = self.state.lock().unwrap();
+        prune_expired(&mut state, now);
+
+        let mut authorizations = Vec::new();
+        for (name, approved) in &names {
+            let authz_id = new_id();
+            let challenge = if *approved {
+                None
+            } else {
+                let challenge_id = new_id();
+                state.challenges.insert(
+                    challenge_id.clone(),
+                    Challenge {
+                        authorization: authz_id.clone(),
+                        token: jws::b64(uuid::Uuid::new_v4().as_bytes()),
+                        status: Status::Pending,
+                        validated: None,
+                        error: None,
+                    },
+                );
+                Some(challenge_id)
+            };
+            state.authorizations.insert(
+                authz_id.clone(),
+                Authorization {
+                    account: caller.id.clone(),
+                    identifier: name.trim_start_matches("*.").to_string(),
+                    wildcard: name.starts_with("*."),
+                    status: if *approved {
+                        Status::Valid
+                    } else {
+                        Status::Pending
+                    },
+                    expires,
+                    challenge,
+                },
+            );
+            authorizations.push(authz_id);
+        }
+
+        let order_id = new_id();
+        let mut order = Order {
+            account: caller.id,
+            identifiers: names.into_iter().map(|(n, _)| n).collect(),
+            authorizations,
+            status: Status::Pending,
+            expires,
+            certificate: None,
+            error: None,
+        };
+        refresh_order(&state, &mut order);
+        let body = self.order_json(&order_id, &order);
+        info!(
+            "ACME order {} for {}",
+            order_id,
+            order.identifiers.join(", ")
+        );
+        state.orders.insert(order_id.clone(), order);
+        Ok(Response::json(201, &body)
+            .header("Location", &self.url(&format!("/acme/order/{}", order_id))))
+    }
+
+    fn order_json(&self, id: &str, order: &Order) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "status": order.status,
+            "expires": timestamp(order.expires),
+            "identifiers": order
+                .identifiers
+                .iter()
+                .map(|name| serde_json::json!({ "type": "dns", "value": name }))
+                .collect::<Vec<_>>(),
+            "authorizations": order
+                .authorizations
+                .iter()
+                .map(|a| self.url(&format!("/acme/authz/{}", a)))
+                .collect::<Vec<_>>(),
+            "finalize": self.url(&format!("/acme/order/{}/finalize", id)),
+        });
+        if let Some(cert) = &order.certificate {
+            json["certificate"] = self.url(&format!("/acme/cert/{}", cert)).into();
+        }
+        if let Some(error) = &order.error {
+            json["error"] = error.clone();
+        }
+        json
+    }
+
+    fn order(&self, request: &Request, id: &str) -> Result<Response, Problem> {
+        let (_, caller) = self.authenticate_account(request)?;
+        let mut state = self.state.lock().unwrap();
+        let mut order = state.orders.remove(id).ok_or_else(Problem::not_found)?;
+        refresh_order(&state, &mut order);
+        let body = self.order_json(id, &order);
+        let owner = order.account == caller.id;
+        state.orders.insert(id.to_string(), order);
+        if !owner {
+            return Err(Problem::unauthorized("Order belongs to another account"));
+        }
+        Ok(Response::json(200, &body))
+    }
+
+    fn finalize(&self, request: &Request, id: &str) -> Result<Response, Problem> {
+        let (jws, caller) = self.authenticate_account(request)?;
+        let payload: FinalizePayload = jws.payload_json()?;
+        let csr_der = jws::unb64(&payload.csr)?;
+
+        let identifiers = {
+            let mut state = self.state.lock().unwrap();
+            let mut order = state.orders.remove(id).ok_or_else(Problem::not_found)?;
+            refresh_order(&state, &mut order);
+            let result = if order.account != caller.id {
+                Err(Problem::unauthorized("Order belongs to another account"))
+            } else if order.status != Status::Ready {
+                Err(Problem::new(
+                    403,
+                    "orderNotReady",
+                    format!("Order is {:?}, not ready", order.status).to_lowercase(),
+                ))
+            } else {
+                order.status = Status::Processing;
+                Ok(order.identifiers.clone())
+            };
+            state.orders.insert(id.to_string(), order);
+            result?
+        };
+
+        // Sign outside the lock; issuance touches the CA files
+        let issued = csr_names(&csr_der)
+            .and_then(|names| {
+                let mut wanted = identifiers.clone();
+                wanted.sort();
+                if wanted == names {
+                    Ok(())
+                } else {
+                    Err(Problem::new(
+                        400,
+                        "badCSR",
+                        format!(
+                            "CSR names ({}) do not match the order ({})",
+                            names.join(", "),
+                            wanted.join(", ")
+                        ),
+                    ))
+                }
+            })
+            .and_then(|()| {
+                self.backend
+                    .issue(&csr_der, &identifiers)
+                    .map_err(|e| Problem::new(400, "badCSR", e.to_string()))
+            });
+
+        let mut state = self.state.lock().unwrap();
+        let mut order = state.orders.remove(id).ok_or_else(Problem::not_found)?;
+        let result = match issued {
+            Ok(chain) => {
+                let cert_id = new_id();
+                state
+                    .certificates
+                    .insert(cert_id.clone(), (caller.id.clone(), chain));
+                order.certificate = Some(cert_id);
+                order.status = Status::Valid;
+                info!("ACME order {} issued for {}", id, identifiers.join(", "));
+                Ok(Response::json(200, &self.order_json(id, &order))
+                    .header("Location", &self.url(&format!("/acme/order/{}", id))))
+            }
+            Err(problem) => {
+                // The order stays ready so the client can retry with a fixed CSR
+                order.status = Status::Ready;
+                Err(problem)
+            }
+        };
+        state.orders.insert(id.to_string(), order);
+        result
+    }
+
+    fn authorization(&self, request: &Request, id: &str) -> Result<Response, Problem> {
+        let (_, caller) = self.authenticate_account(request)?;
+        let state = self.state.lock().unwrap();
+        let authz = state
+            .authorizations
+            .get(id)
+            .ok_or_else(Problem::not_found)?;
+        if authz.account != caller.id {
+            return Err(Problem::unauthorized(
+                "Authorization belongs to another account",
+            ));
+        }
+        let challenges: Vec<serde_json::Value> = authz
+            .challenge
+            .iter()
+            .filter_map(|c| {
+                state
+                    .challenges
+                    .get(c)
+                    .map(|chall| self.challenge_json(c, chall))
+            })
+            .collect();
+        let mut json = serde_json::json!({
+            "identifier": { "type": "dns", "value": authz.identifier },
+            "status": authz.status,
+            "expires": timestamp(authz.expires),
+            "challenges": challenges,
+        });
+        if authz.wildcard {
+            json["wildcard"] = true.into();
+        }
+        Ok(Response::json(200, &json))
+    }
+
+    fn challenge_json(&self, id: &str, challenge: &Challenge) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "type": "http-01",
+            "url": self.url(&format!("/acme/chall/{}", id)),
+            "status": challenge.status,
+            "token": challenge.token,
+        });
+        if let Some(validated) = challenge.validated {
+            json["validated"] = timestamp(validated).into();
+        }
+        if let Some(error) = &challenge.error {
+            json["error"] = error.clone();
+        }
+        json
+    }
+
+    /// POST {} to a challenge starts validation; POST-as-GET just reports it.
+    fn challenge(self: &Arc<Self>, request: &Request, id: &str) -> Result<Response, Problem> {
+        let (jws, caller) = self.authenticate_account(request)?;
+        let mut state = self.state.lock().unwrap();
+        let challenge = state.challenges.get(id).ok_or_else(Problem::not_found)?;
+        let authz = state
+            .authorizations
+            .get(&challenge.authorization)
+            .ok_or_else(Problem::not_found)?;
+        if authz.account != caller.id {
+            return Err(Problem::unauthorized(
+                "Challenge belongs to another account",
+            ));
+        }
+        let identifier = authz.identifier.clone();
+        let link = format!(
+            "<{}>;rel=\"up\"",
+            self.url(&format!("/acme/authz/{}", challenge.authorization))
+        );
+
+        if !jws.payload.is_empty() && challenge.status == Status::Pending {
+            let key_authorization = format!("{}.{}", challenge.token, jws::thumbprint(&caller.key));
+            let token = challenge.token.clone();
+            if let Some(challenge) = state.challenges.get_mut(id) {
+                challenge.status = Status::Processing;
+            }
+            let acme = self.clone();
+            let id = id.to_string();
+            tokio::spawn(async move {
+                let result = acme
+                    .validate_http01(&identifier, &token, &key_authorization)
+                    .await;
+                acme.finish_challenge(&id, result);
+            });
+        }
+        let challenge = state.challenges.get(id).ok_or_else(Problem::not_found)?;
+        Ok(Response::json(200, &self.challenge_json(id, challenge)).header("Link", &link))
+    }
+
+    /// Fetch the key authorization over loopback (RFC 8555 §8.3), sending the
+    /// identifier as the Host so Traefik/nginx/Caddy route it like real traffic.
+    async fn validate_http01(
+        &self,
+        identifier: &str,
+        token: &str,
+        key_authorization: &str,
+    ) -> Result<(), Problem> {
+        let port = self.config.lock().unwrap().http01_port;
+        let addr = format!("127.0.0.1:{}", port);
+        let path = format!("/.well-known/acme-challenge/{}", token);
+        let fetched = http::send(&addr, "GET", identifier, &path, None, &[]);
+        let response = tokio::time::timeout(VALIDATION_TIMEOUT, fetched)
+            .await
+            .map_err(|_| Problem::new(400, "connection", format!("Timed out fetching {}", path)))?
+            .map_err(|e| Problem::new(400, "connection", e.to_string()))?;
+        match (response.status, response.body) {
+            (200, body) if String::from_utf8_lossy(&body).trim() == key_authorization => Ok(()),
+            (200, _) => Err(Problem::new(
+                403,
+                "incorrectResponse",
+                format!("Wrong key authorization at http://{}{}", identifier, path),
+            )),
+            (status, _) => Err(Problem::new(
+                403,
+                "incorrectResponse",
+                format!(
+                    "http://{}{} (via {}) returned HTTP {}",
+                    identifier, path, addr, status
+                ),
+            )),
+        }
+    }
+
+    fn finish_challenge(&self, id: &str, result: Result<(), Problem>) {
+        let mut state = self.state.lock().unwrap();
+        let Some(challenge) = state.challenges.get_mut(id) else {
+            return;
+        };
+        let status = match result {
+            Ok(()) => {
+                challenge.validated = Some(OffsetDateTime::now_utc());
+                Status::Valid
+            }
+            Err(problem) => {
+                warn!("ACME HTTP-01 validation failed: {}", problem.detail);
+                challenge.error = Some(problem.to_json());
+                Status::Invalid
+            }
+        };
+        challenge.status = status;
+        let authz_id = challenge.authorization.clone();
+        if let Some(authz) = state.authorizations.get_mut(&authz_id) {
+            authz.status = status;
+        }
+    }
+
+    fn certificate(&self, request: &Request, id: &str) -> Result<Response, Problem> {
+        let (_, caller) = self.authenticate_account(request)?;
+        let state = self.state.lock().unwrap();
+        let (owner, chain) = state.certificates.get(id).ok_or_else(Problem::not_found)?;
+        if *owner != caller.id {
+            return Err(Problem::unauthorized(
+                "Certificate belongs to another account",
+            ));
+        }
+        Ok(Response::new(200)
+            .header("Content-Type", "application/pem-certificate-chain")
+            .body(chain.clone().into_bytes()))
+    }
+}
+
+/// Move a pending order to ready or invalid based on its authorizations.
+fn refresh_order(state: &State, order: &mut Order) {
+    if order.status != Status::Pending {
+        return;
+    }
+    let statuses: Vec<Status> = order
+        .authorizations
+        .iter()
+        .map(|a| {
+            state
+                .authorizations
+                .get(a)
+                .map(|authz| authz.status)
+                .unwrap_or(Status::Invalid)
+        })
+        .collect();
+    if statuses.contains(&Status::Invalid) {
+        order.status = Status::Invalid;
+        order.error =
+            Some(Problem::unauthorized("An authorization for this order failed").to_json());
+    } else if statuses.iter().all(|s| *s == Status::Valid) {
+        order.status = Status::Ready;
+    }
+}
+
+fn prune_expired(state: &mut State, now: OffsetDateTime) {
+    let expired: Vec<String> = state
+        .orders
+        .iter()
+        .filter(|(_, o)| o.expires < now)
+        .map(|(id, _)| id.clone())
+        .collect();
+    for id in expired {
+        if let Some(order) = state.orders.remove(&id) {
+            for authz_id in order.authorizations {
+                if let Some(authz) = state.authorizations.remove(&authz_id) {
+                    if let Some(challenge) = authz.challenge {
+                        state.challenges.remove(&challenge);
+                    }
+                }
+            }
+            if let Some(cert) = order.certificate {
+                state.certificates.remove(&cert);
+            }
+        }
+    }
+}
+
+/// DNS names requested by a CSR: its SANs, or the CN when it has none.
+fn csr_names(csr_der: &[u8]) -> Result<Vec<String>, Problem> {
+    let bad_csr = |detail: &str| Problem::new(400, "badCSR", detail.to_string());
+    let (_, csr) = x509_parser::certification_request::X509CertificationRequest::from_der(csr_der)
+        .map_err(|_| bad_csr("CSR is not valid DER"))?;
+    csr.verify_signature()
+        .map_err(|_| bad_csr("CSR signature is invalid"))?;
+
+    let mut names: Vec<String> = csr
+        .requested_extensions()
+        .into_iter()
+        .flatten()
+        .filter_map(|ext| match ext {
+            x509_parser::extensions::ParsedExtension::SubjectAlternativeName(san) => {
+                Some(san.general_names.iter())
+            }
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|name| match name {
+            GeneralName::DNSName(dns) => Some(dns.to_lowercase()),
+            _ => None,
+        })
+        .collect();
+    if names.is_empty() {
+        names = csr
+            .certification_request_info
+            .subject
+            .iter_common_name()
+            .filter_map(|cn| cn.as_str().ok())
+            .map(|cn| cn.to_lowercase())
+            .collect();
+    }
+    names.sort();
+    names.dedup();
+    if names.is_empty() {
+        return Err(bad_csr("CSR contains no DNS names"));
+    }
+    Ok(names)
+}
@@ -0,0 +1,245 @@
+use anyhow::{bail, Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// ACME messages are small JWS objects; anything bigger is not a real client.
+const MAX_BODY: usize = 64 * 1024;
+const MAX_HEADER_LINES: usize = 100;
+
+/// Just enough HTTP/1.1 for ACME: one request per connection, sized bodies.
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn json(status: u16, value: &serde_json::Value) -> Self {
+        Self::new(status)
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_vec(value).unwrap_or_default())
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        415 => "Unsupported Media Type",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+async fn read_head<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+) -> Result<(String, Vec<(String, String)>)> {
+    let mut first = String::new();
+    if reader.read_line(&mut first).await? == 0 {
+        bail!("Connection closed before request");
+    }
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            bail!("Connection closed in headers");
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() >= MAX_HEADER_LINES {
+            bail!("Too many headers");
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    Ok((first.trim_end().to_string(), headers))
+}
+
+fn content_length(headers: &[(String, String)]) -> Result<usize> {
+    let length = headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+        .map(|(_, v)| v.parse::<usize>())
+        .transpose()
+        .context("Invalid Content-Length")?
+        .unwrap_or(0);
+    if length > MAX_BODY {
+        bail!("Body too large");
+    }
+    Ok(length)
+}
+
+pub async fn read_request<R: AsyncRead + Unpin>(reader: R) -> Result<Request> {
+    let mut reader = BufReader::new(reader);
+    let (request_line, headers) = read_head(&mut reader).await?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+    let mut body = vec![0u8; content_length(&headers)?];
+    reader.read_exact(&mut body).await?;
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body,
+    })
+}
+
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    response: &Response,
+    include_body: bool,
+) -> Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    writer.write_all(head.as_bytes()).await?;
+    if include_body {
+        writer.write_all(&response.body).await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Minimal client, used for HTTP-01 validation: send one request to `addr`
+/// with the given Host header and read the whole response.
+pub async fn send(
+    addr: &str,
+    method: &str,
+    host: &str,
+    path: &str,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<Response> {
+    let mut stream = tokio::net::TcpStream::connect(addr)
+        .await
+        .with_context(|| format!("Cannot connect to {}", addr))?;
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: localdomain-acme\r\nAccept: */*\r\nConnection: close\r\n",
+        method, path, host
+    );
+    if let Some(content_type) = content_type {
+        head.push_str(&format!("Content-Type: {}\r\n", content_type));
+    }
+    head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+
+    let mut reader = BufReader::new(stream);
+    let (status_line, headers) = read_head(&mut reader).await?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .context("Malformed status line")?;
+    let body = if method == "HEAD" {
+        Vec::new()
+    } else if headers
+        .iter()
+        .any(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+    {
+        let mut body = vec![0u8; content_length(&headers)?];
+        reader.read_exact(&mut body).await?;
+        body
+    } else {
+        let mut body = Vec::new();
+        reader.take(MAX_BODY as u64).read_to_end(&mut body).await?;
+        body
+    };
+    Ok(Response {
+        status,
+        headers,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = b"POST /acme/new-order HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Type: application/jose+json\r\nContent-Length: 4\r\n\r\n{}{}";
+        let request = read_request(&raw[..]).await.unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/acme/new-order");
+        assert_eq!(
+            request.header("content-type"),
+            Some("application/jose+json")
+        );
+        assert_eq!(request.body, b"{}{}");
+
+        let huge = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        );
+        assert!(read_request(huge.as_bytes()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_response() {
+        let response = Response::new(201).header("Location", "/acme/acct/1");
+        let mut out = Vec::new();
+        write_response(&mut out, &response.body(b"{}".to_vec()), true)
+            .await
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("HTTP/1.1 201 Created\r\n"));
+        assert!(text.contains("Location: /acme/acct/1\r\n"));
+        assert!(text.ends_with("Content-Length: 2\r\nConnection: close\r\n\r\n{}"));
+    }
+}
@@ -0,0 +1,180 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::Problem;
+
+/// Account public key as sent in a JWS protected header (RFC 7517).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Protected {
+    pub alg: String,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub jwk: Option<Jwk>,
+    #[serde(default)]
+    pub kid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Flattened {
+    protected: String,
+    payload: String,
+    signature: String,
+}
+
+/// A parsed flattened JWS (RFC 8555 §6.2). The signature is not checked until `verify`.
+#[derive(Debug)]
+pub struct Jws {
+    pub protected: Protected,
+    /// Decoded payload; empty for POST-as-GET
+    pub payload: Vec<u8>,
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+pub fn b64(data: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+pub fn unb64(data: &str) -> Result<Vec<u8>, Problem> {
+    URL_SAFE_NO_PAD
+        .decode(data)
+        .map_err(|_| Problem::malformed("Invalid base64url encoding"))
+}
+
+impl Jws {
+    pub fn parse(body: &[u8]) -> Result<Self, Problem> {
+        let flat: Flattened = serde_json::from_slice(body)
+            .map_err(|e| Problem::malformed(format!("Request is not a flattened JWS: {}", e)))?;
+        let protected: Protected = serde_json::from_slice(&unb64(&flat.protected)?)
+            .map_err(|e| Problem::malformed(format!("Invalid protected header: {}", e)))?;
+        if protected.jwk.is_some() == protected.kid.is_some() {
+            return Err(Problem::malformed(
+                "Protected header must contain exactly one of jwk and kid",
+            ));
+        }
+        Ok(Self {
+            payload: unb64(&flat.payload)?,
+            signing_input: format!("{}.{}", flat.protected, flat.payload),
+            signature: unb64(&flat.signature)?,
+            protected,
+        })
+    }
+
+    /// Check the signature against the account key.
+    pub fn verify(&self, key: &Jwk) -> Result<(), Problem> {
+        let message = self.signing_input.as_bytes();
+        let verified = match (self.protected.alg.as_str(), key.kty.as_str()) {
+            ("ES256", "EC") | ("ES384", "EC") => {
+                let (alg, crv): (&dyn signature::VerificationAlgorithm, _) =
+                    if self.protected.alg == "ES256" {
+                        (&signature::ECDSA_P256_SHA256_FIXED, "P-256")
+                    } else {
+                        (&signature::ECDSA_P384_SHA384_FIXED, "P-384")
+                    };
+                if key.crv.as_deref() != Some(crv) {
+                    return Err(Problem::bad_signature_algorithm(&self.protected.alg));
+                }
+                let mut point = vec![0x04];
+                point.extend(unb64(key.x.as_deref().unwrap_or_default())?);
+                point.extend(unb64(key.y.as_deref().unwrap_or_default())?);
+                UnparsedPublicKey::new(alg, point)
+                    .verify(message, &self.signature)
+                    .is_ok()
+            }
+            ("RS256", "RSA") => RsaPublicKeyComponents {
+                n: unb64(key.n.as_deref().unwrap_or_default())?,
+                e: unb64(key.e.as_deref().unwrap_or_default())?,
+            }
+            .verify(
+                &signature::RSA_PKCS1_2048_8192_SHA256,
+                message,
+                &self.signature,
+            )
+            .is_ok(),
+            (alg, _) => return Err(Problem::bad_signature_algorithm(alg)),
+        };
+        if verified {
+            Ok(())
+        } else {
+            Err(Problem::malformed("JWS signature is invalid"))
+        }
+    }
+
+    pub fn payload_json<T: serde::de::DeserializeOwned>(&self) -> Result<T, Problem> {
+        serde_json::from_slice(&self.payload)
+            .map_err(|e| Problem::malformed(format!("Invalid request payload: {}", e)))
+    }
+}
+
+/// RFC 7638 thumbprint, used in HTTP-01 key authorizations.
+pub fn thumbprint(key: &Jwk) -> String {
+    // Required members only, in lexicographic order, no whitespace
+    let canonical = match key.kty.as_str() {
+        "RSA" => serde_json::json!({
+            "e": key.e,
+            "kty": key.kty,
+            "n": key.n,
+        }),
+        _ => serde_json::json!({
+            "crv": key.crv,
+            "kty": key.kty,
+            "x": key.x,
+            "y": key.y,
+        }),
+    };
+    b64(&Sha256::digest(canonical.to_string().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc7638_thumbprint() {
+        // Example from RFC 7638 §3.1
+        let key = Jwk {
+            kty: "RSA".to_string(),
+            crv: None,
+            x: None,
+            y: None,
+            n: Some("0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw".to_string()),
+            e: Some("AQAB".to_string()),
+        };
+        assert_eq!(
+            thumbprint(&key),
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_requests() {
+        assert!(Jws::parse(b"not json").is_err());
+        let header = b64(br#"{"alg":"ES256","url":"http://x/"}"#);
+        let body = format!(
+            r#"{{"protected":"{}","payload":"","signature":""}}"#,
+            header
+        );
+        // Neither jwk nor kid
+        assert!(Jws::parse(body.as_bytes()).is_err());
+    }
+}
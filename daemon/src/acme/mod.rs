@@ -0,0 +1,471 @@
+//! ACME (RFC 8555) directory on loopback so Traefik, certbot, or Caddy running in
+//! Docker can get certificates from the LocalDomain CA. Plain HTTP is fine here:
+//! the listener only accepts loopback connections.
+
+pub mod handlers;
+pub mod http;
+pub mod jws;
+
+use anyhow::{Context, Result};
+use localdomain_shared::protocol::DaemonSettings;
+use once_cell::sync::Lazy;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info};
+
+use crate::{certs, hosts, paths};
+pub use handlers::Acme;
+
+/// Lifetime of certificates issued over ACME, like public ACME CAs.
+const CERT_VALIDITY_DAYS: i64 = 90;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An RFC 7807 problem document with an ACME error type.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub status: u16,
+    pub kind: &'static str,
+    pub detail: String,
+}
+
+impl Problem {
+    pub fn new(status: u16, kind: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            kind,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn malformed(detail: impl Into<String>) -> Self {
+        Self::new(400, "malformed", detail)
+    }
+
+    pub fn unauthorized(detail: impl Into<String>) -> Self {
+        Self::new(403, "unauthorized", detail)
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(404, "malformed", "Resource not found")
+    }
+
+    pub fn bad_signature_algorithm(alg: &str) -> Self {
+        Self::new(
+            400,
+            "badSignatureAlgorithm",
+            format!(
+                "Unsupported JWS algorithm '{}'; use ES256, ES384 or RS256",
+                alg
+            ),
+        )
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": format!("urn:ietf:params:acme:error:{}", self.kind),
+            "detail": self.detail,
+            "status": self.status,
+        })
+    }
+}
+
+/// Issuance policy, refreshed whenever the daemon settings change.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Loopback port HTTP-01 responses are fetched from
+    pub http01_port: u16,
+    /// Validate domains LocalDomain already manages without a challenge
+    pub auto_approve: bool,
+    /// Only names under these suffixes (the dev TLDs) can be ordered
+    pub permitted_suffixes: Vec<String>,
+}
+
+impl Config {
+    pub fn from_settings(settings: &DaemonSettings) -> Self {
+        Self {
+            http01_port: settings.acme_http01_port,
+            auto_approve: settings.acme_auto_approve,
+            permitted_suffixes: settings.ca_permitted_suffixes.clone(),
+        }
+    }
+}
+
+/// Where the ACME server gets managed domains and certificates from.
+pub trait Backend: Send + Sync {
+    fn managed_domains(&self) -> Vec<String>;
+    /// Sign the CSR for `names`, returning a PEM chain.
+    fn issue(&self, csr_der: &[u8], names: &[String]) -> Result<String>;
+}
+
+struct LocalCa;
+
+impl Backend for LocalCa {
+    fn managed_domains(&self) -> Vec<String> {
+        hosts::managed_domains()
+    }
+
+    fn issue(&self, csr_der: &[u8], names: &[String]) -> Result<String> {
+        certs::domain::sign_csr(csr_der, names, CERT_VALIDITY_DAYS)
+    }
+}
+
+struct Running {
+    port: u16,
+    acme: Arc<Acme>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+static RUNNING: Lazy<Mutex<Option<Running>>> = Lazy::new(|| Mutex::new(None));
+
+/// Start, stop, or reconfigure the ACME server to match the settings.
+pub fn apply_settings(settings: &DaemonSettings) -> Result<()> {
+    let mut running = RUNNING.lock().unwrap();
+    if let Some(current) = running.take() {
+        if settings.acme_enabled && current.port == settings.acme_port {
+            current.acme.set_config(Config::from_settings(settings));
+            *running = Some(current);
+            return Ok(());
+        }
+        current.task.abort();
+        info!("Stopped ACME server on port {}", current.port);
+    }
+    if !settings.acme_enabled {
+        return Ok(());
+    }
+
+    let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, settings.acme_port))
+        .with_context(|| format!("Cannot listen on 127.0.0.1:{}", settings.acme_port))?;
+    listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(listener)?;
+
+    let acme = Arc::new(Acme::new(
+        format!("http://127.0.0.1:{}", settings.acme_port),
+        Config::from_settings(settings),
+        Box::new(LocalCa),
+        Some(PathBuf::from(paths::ACME_ACCOUNTS)),
+    ));
+    let task = tokio::spawn(serve(listener, acme.clone()));
+    info!(
+        "ACME directory at http://127.0.0.1:{}/directory",
+        settings.acme_port
+    );
+    *running = Some(Running {
+        port: settings.acme_port,
+        acme,
+        task,
+    });
+    Ok(())
+}
+
+pub async fn serve(listener: TcpListener, acme: Arc<Acme>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _addr)) => {
+                let acme = acme.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, acme).await {
+                        debug!("ACME connection error: {}", e);
+                    }
+                });
+            }
+            Err(e) => error!("ACME accept error: {}", e),
+        }
+    }
+}
+
+async fn handle_connection(stream: TcpStream, acme: Arc<Acme>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, http::read_request(reader))
+        .await
+        .context("Request timed out")??;
+    let with_body = request.method != "HEAD";
+    // Handling may sign a certificate and write account state to disk
+    let response = tokio::task::spawn_blocking(move || acme.handle(&request)).await?;
+    http::write_response(&mut writer, &response, with_body).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper_util::client::legacy::Client as HyperClient;
+    use hyper_util::rt::TokioExecutor;
+    use instant_acme::{
+        Account, AuthorizationStatus, ChallengeType, Identifier, Key, NewOrder, OrderStatus,
+        RetryPolicy,
+    };
+    use rcgen::{CertificateParams, DnType, IsCa, KeyPair, SanType};
+    use rustls_pki_types::PrivateKeyDer;
+    use std::collections::HashMap;
+
+    struct TestCa {
+        cert: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    impl TestCa {
+        fn new() -> Self {
+            let mut params = CertificateParams::default();
+            params
+                .distinguished_name
+                .push(DnType::CommonName, "Test ACME CA");
+            params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            let key = KeyPair::generate().unwrap();
+            let cert = params.self_signed(&key).unwrap();
+            Self { cert, key }
+        }
+    }
+
+    impl Backend for TestCa {
+        fn managed_domains(&self) -> Vec<String> {
+            vec!["managed.test".to_string()]
+        }
+
+        fn issue(&self, csr_der: &[u8], names: &[String]) -> Result<String> {
+            let mut csr =
+                rcgen::CertificateSigningRequestParams::from_der(&csr_der.to_vec().into())?;
+            csr.params.subject_alt_names = names
+                .iter()
+                .map(|n| Ok(SanType::DnsName(n.clone().try_into()?)))
+                .collect::<Result<_>>()?;
+            let cert = csr.signed_by(&self.cert, &self.key)?;
+            Ok(format!("{}{}", cert.pem(), self.cert.pem()))
+        }
+    }
+
+    /// Stand-in for the user's Traefik/nginx: serves provisioned key authorizations.
+    async fn challenge_responder(tokens: Arc<Mutex<HashMap<String, String>>>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (reader, mut writer) = stream.into_split();
+                let request = http::read_request(reader).await.unwrap();
+                let token = request
+                    .path
+                    .strip_prefix("/.well-known/acme-challenge/")
+                    .unwrap_or_default();
+                let key_authorization = tokens
+                    .lock()
+                    .unwrap()
+                    .get(&format!("{} {}", request.header("Host").unwrap(), token))
+                    .cloned();
+                let response = match key_authorization {
+                    Some(ka) => http::Response::new(200).body(ka.into_bytes()),
+                    None => http::Response::new(404),
+                };
+                http::write_response(&mut writer, &response, true)
+                    .await
+                    .unwrap();
+            }
+        });
+        port
+    }
+
+    fn test_acme(port: u16, http01_port: u16, ca: TestCa) -> Arc<Acme> {
+        Arc::new(Acme::new(
+            format!("http://127.0.0.1:{}", port),
+            Config {
+                http01_port,
+                auto_approve: true,
+                permitted_suffixes: vec!["test".to_string()],
+            },
+            Box::new(ca),
+            None,
+        ))
+    }
+
+    /// Start a server on an ephemeral port; returns the directory URL and the CA cert DER.
+    async fn start(http01_port: u16) -> (String, Vec<u8>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let ca = TestCa::new();
+        let ca_der = ca.cert.der().to_vec();
+        let acme = test_acme(port, http01_port, ca);
+        tokio::spawn(serve(listener, acme));
+        (format!("http://127.0.0.1:{}/directory", port), ca_der)
+    }
+
+    /// instant-acme only speaks HTTPS by default; the directory is plain HTTP on loopback.
+    async fn register(directory: &str, pkcs8: &[u8]) -> Account {
+        let http = HyperClient::builder(TokioExecutor::new()).build_http();
+        let key = (
+            Key::from_pkcs8_der(pkcs8.to_vec().into()).unwrap(),
+            PrivateKeyDer::Pkcs8(pkcs8.to_vec().into()),
+        );
+        let (account, _) = Account::builder_with_http(Box::new(http))
+            .create_from_key(key, directory.to_string())
+            .await
+            .unwrap();
+        account
+    }
+
+    fn csr(names: &[&str]) -> Vec<u8> {
+        let key = KeyPair::generate().unwrap();
+        let params =
+            CertificateParams::new(names.iter().map(|n| n.to_string()).collect::<Vec<_>>())
+                .unwrap();
+        params.serialize_request(&key).unwrap().der().to_vec()
+    }
+
+    fn problem_type(error: instant_acme::Error) -> String {
+        match error {
+            instant_acme::Error::Api(problem) => problem.r#type.unwrap_or_default(),
+            other => panic!("expected an ACME problem, got {}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http01_issuance_end_to_end() {
+        let tokens = Arc::new(Mutex::new(HashMap::new()));
+        let responder = challenge_responder(tokens.clone()).await;
+        let (directory, ca) = start(responder).await;
+
+        let (_, pkcs8) = Key::generate_pkcs8().unwrap();
+        let account = register(&directory, pkcs8.secret_pkcs8_der()).await;
+        // Registering the same key again returns the existing account
+        let again = register(&directory, pkcs8.secret_pkcs8_der()).await;
+        assert_eq!(again.id(), account.id());
+
+        let identifiers = [Identifier::Dns("app.test".to_string())];
+        let mut order = account
+            .new_order(&NewOrder::new(&identifiers))
+            .await
+            .unwrap();
+        assert_eq!(order.state().status, OrderStatus::Pending);
+
+        let mut authorizations = order.authorizations();
+        let mut authz = authorizations.next().await.unwrap().unwrap();
+        assert_eq!(authz.status, AuthorizationStatus::Pending);
+        let mut challenge = authz.challenge(ChallengeType::Http01).unwrap();
+        tokens.lock().unwrap().insert(
+            format!("app.test {}", challenge.token),
+            challenge.key_authorization().as_str().to_string(),
+        );
+        challenge.set_ready().await.unwrap();
+
+        let retries = RetryPolicy::new().initial_delay(Duration::from_millis(20));
+        assert_eq!(
+            order.poll_ready(&retries).await.unwrap(),
+            OrderStatus::Ready
+        );
+
+        // A CSR for other names is refused and the order stays ready
+        let wrong = order.finalize_csr(&csr(&["other.test"])).await.unwrap_err();
+        assert_eq!(problem_type(wrong), "urn:ietf:params:acme:error:badCSR");
+        assert_eq!(order.refresh().await.unwrap().status, OrderStatus::Ready);
+
+        order.finalize_csr(&csr(&["app.test"])).await.unwrap();
+        let chain = order.poll_certificate(&retries).await.unwrap();
+        let chain = pem::parse_many(&chain).unwrap();
+        assert_eq!(chain.len(), 2);
+        let (_, leaf) = x509_parser::parse_x509_certificate(chain[0].contents()).unwrap();
+        let (_, issuer) = x509_parser::parse_x509_certificate(&ca).unwrap();
+        leaf.verify_signature(Some(issuer.public_key())).unwrap();
+        let san = leaf.subject_alternative_name().unwrap().unwrap();
+        assert_eq!(
+            format!("{:?}", san.value.general_names),
+            "[DNSName(\"app.test\")]"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_policy_and_auto_approval() {
+        let tokens = Arc::new(Mutex::new(HashMap::new()));
+        let responder = challenge_responder(tokens).await;
+        let (directory, _) = start(responder).await;
+        let (_, pkcs8) = Key::generate_pkcs8().unwrap();
+        let account = register(&directory, pkcs8.secret_pkcs8_der()).await;
+
+        // Managed domains (and wildcards over them) skip validation
+        let managed = [
+            Identifier::Dns("managed.test".to_string()),
+            Identifier::Dns("*.managed.test".to_string()),
+        ];
+        let mut order = account.new_order(&NewOrder::new(&managed)).await.unwrap();
+        assert_eq!(order.state().status, OrderStatus::Ready);
+
+        for name in ["example.com", "*.app.test", "192.168.1.2"] {
+            let identifiers = [Identifier::Dns(name.to_string())];
+            let Err(rejected) = account.new_order(&NewOrder::new(&identifiers)).await else {
+                panic!("{} was accepted", name);
+            };
+            assert_eq!(
+                problem_type(rejected),
+                "urn:ietf:params:acme:error:rejectedIdentifier",
+                "{}",
+                name
+            );
+        }
+
+        // Nothing answers the challenge, so the order fails
+        let identifiers = [Identifier::Dns("unserved.test".to_string())];
+        let mut order = account
+            .new_order(&NewOrder::new(&identifiers))
+            .await
+            .unwrap();
+        let mut authorizations = order.authorizations();
+        let mut authz = authorizations.next().await.unwrap().unwrap();
+        let mut challenge = authz.challenge(ChallengeType::Http01).unwrap();
+        challenge.set_ready().await.unwrap();
+
+        let retries = RetryPolicy::new().initial_delay(Duration::from_millis(20));
+        let failed = order.poll_ready(&retries).await.unwrap_err();
+        assert_eq!(
+            problem_type(failed),
+            "urn:ietf:params:acme:error:unauthorized"
+        );
+        let mut authorizations = order.authorizations();
+        let mut authz = authorizations.next().await.unwrap().unwrap();
+        let authz = authz.refresh().await.unwrap();
+        assert_eq!(authz.status, AuthorizationStatus::Invalid);
+        let error = authz.challenges[0].error.as_ref().unwrap();
+        assert_eq!(
+            error.r#type.as_deref(),
+            Some("urn:ietf:params:acme:error:incorrectResponse")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replayed_nonce_rejected() {
+        let acme = test_acme(4001, 80, TestCa::new());
+        let request = |method: &str, path: &str, body: Vec<u8>| http::Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: vec![(
+                "Content-Type".to_string(),
+                "application/jose+json".to_string(),
+            )],
+            body,
+        };
+        let issued = acme.handle(&request("HEAD", "/acme/new-nonce", Vec::new()));
+        let nonce = issued
+            .headers
+            .iter()
+            .find(|(name, _)| name == "Replay-Nonce")
+            .map(|(_, value)| value.clone())
+            .unwrap();
+
+        // Nonces are checked before the signature, so an unsigned JWS is enough
+        let protected = serde_json::json!({
+            "alg": "ES256",
+            "nonce": nonce,
+            "url": "http://127.0.0.1:4001/acme/new-order",
+            "kid": "http://127.0.0.1:4001/acme/acct/unknown",
+        });
+        let jws = serde_json::json!({
+            "protected": jws::b64(protected.to_string().as_bytes()),
+            "payload": "",
+            "signature": "",
+        })
+        .to_string()
+        .into_bytes();
+        acme.handle(&request("POST", "/acme/new-order", jws.clone()));
+        let replay = acme.handle(&request("POST", "/acme/new-order", jws));
+        let problem: serde_json::Value = serde_json::from_slice(&replay.body).unwrap();
+        assert_eq!(problem["type"], "urn:ietf:params:acme:error:badNonce");
+    }
+}
@@ -1,12 +1,18 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use localdomain_shared::domain::CaddyDomainConfig;
+use localdomain_shared::protocol::SyncCaddyConfigParams;
+use once_cell::sync::Lazy;
 use std::fs;
 use std::io::Write;
-use tracing::info;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::{info, warn};
 
 /// Validate a Caddy domain config to prevent Caddyfile injection.
 fn validate_caddy_config(domain: &CaddyDomainConfig) -> Result<()> {
-    for (field_name, value) in [("name", &domain.name), ("target_host", &domain.target_host)] {
+    let fields = [("name", &domain.name), ("target_host", &domain.target_host)];
+    let aliases = domain.aliases.iter().map(|alias| ("alias", alias));
+    for (field_name, value) in fields.into_iter().chain(aliases) {
         if value.contains('}') || value.contains('{') || value.contains('\n') || value.contains('\r') || value.contains('\0') {
             bail!("Caddy config {} contains invalid characters: '{}'", field_name, value);
         }
@@ -20,7 +26,28 @@ fn validate_caddy_config(domain: &CaddyDomainConfig) -> Result<()> {
     Ok(())
 }
 
-use crate::paths;
+use crate::onboarding::{self, Site};
+use crate::{overrides, paths};
+
+/// Domains and ports of the last sync, so the daemon can rebuild the Caddyfile
+/// on its own when a hostname override expires. Kept on disk so that still
+/// works after a daemon restart.
+static LAST_SYNC: Lazy<Mutex<Option<SyncCaddyConfigParams>>> =
+    Lazy::new(|| Mutex::new(load_last_sync()));
+
+fn load_last_sync() -> Option<SyncCaddyConfigParams> {
+    match fs::read_to_string(paths::CADDY_SYNC_FILE) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| warn!("Ignoring invalid last Caddy sync file: {}", e))
+            .ok(),
+        Err(_) => None,
+    }
+}
+
+fn save_last_sync(params: &SyncCaddyConfigParams) -> Result<()> {
+    let json = serde_json::to_string_pretty(params)?;
+    fs::write(paths::CADDY_SYNC_FILE, json).context("Failed to write last Caddy sync")
+}
 
 pub fn generate_caddyfile(
     domains: &[CaddyDomainConfig],
@@ -31,7 +58,32 @@ pub fn generate_caddyfile(
     for domain in domains {
         validate_caddy_config(domain)?;
     }
-    let content = build_caddyfile(domains, http_port, https_port);
+    let params = SyncCaddyConfigParams {
+        domains: domains.to_vec(),
+        http_port,
+        https_port,
+    };
+    if let Err(e) = save_last_sync(&params) {
+        warn!("{}", e);
+    }
+    *LAST_SYNC.lock().unwrap() = Some(params);
+    let domains: Vec<CaddyDomainConfig> = domains
+        .iter()
+        .filter(|d| !overrides::is_expired(&d.name))
+        .cloned()
+        .collect();
+    let mut content = build_caddyfile(&domains, http_port, https_port);
+    match onboarding::prepare() {
+        // Sharing a port with the domains would put the LAN bind on their listener too
+        Ok(Some(site)) if site.port == http_port || site.port == https_port => warn!(
+            "Not publishing the device onboarding site: port {} is used for domains",
+            site.port
+        ),
+        Ok(Some(site)) => append_onboarding_site(&mut content, &site),
+        Ok(None) => {}
+        // The domains matter more than the onboarding site
+        Err(e) => warn!("Not publishing the device onboarding site: {}", e),
+    }
 
     let mut f = fs::File::create(paths::CADDYFILE)?;
     f.write_all(content.as_bytes())?;
@@ -46,6 +98,51 @@ pub fn generate_caddyfile(
     Ok(())
 }
 
+/// Rebuild the Caddyfile from the last sync. Returns false when nothing was
+/// ever synced.
+pub fn regenerate() -> Result<bool> {
+    let last = LAST_SYNC.lock().unwrap().clone();
+    match last {
+        Some(params) => {
+            generate_caddyfile(&params.domains, params.http_port, params.https_port)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Whether Caddy serves `cert_path` under the last synced config, or under the
+/// Caddyfile on disk when nothing was ever synced.
+pub fn uses_certificate(cert_path: &str) -> bool {
+    match &*LAST_SYNC.lock().unwrap() {
+        Some(params) => params
+            .domains
+            .iter()
+            .any(|domain| serves_certificate(domain, cert_path)),
+        None => fs::read_to_string(paths::CADDYFILE)
+            .map(|content| caddyfile_uses_certificate(&content, cert_path))
+            .unwrap_or(false),
+    }
+}
+
+fn serves_certificate(domain: &CaddyDomainConfig, cert_path: &str) -> bool {
+    (domain.protocol == "https" || domain.protocol == "both")
+        && domain
+            .cert_path
+            .as_deref()
+            .is_some_and(|path| Path::new(path) == Path::new(cert_path))
+}
+
+fn caddyfile_uses_certificate(content: &str, cert_path: &str) -> bool {
+    content.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("tls")
+            && words
+                .next()
+                .is_some_and(|path| Path::new(path) == Path::new(cert_path))
+    })
+}
+
 fn build_caddyfile(domains: &[CaddyDomainConfig], http_port: u16, https_port: u16) -> String {
     let mut out = String::new();
 
@@ -74,11 +171,10 @@ fn build_caddyfile(domains: &[CaddyDomainConfig], http_port: u16, https_port: u1
         // HTTPS block
         if wants_https {
             if let (Some(cert), Some(key)) = (&domain.cert_path, &domain.key_path) {
-                if https_port != 443 {
-                    out.push_str(&format!("https://{}:{} {{\n", domain.name, https_port));
-                } else {
-                    out.push_str(&format!("https://{} {{\n", domain.name));
-                }
+                out.push_str(&format!(
+                    "{} {{\n",
+                    site_addresses(domain, "https", https_port, 443)
+                ));
                 out.push_str(&format!("\ttls {} {}\n", cert, key));
                 out.push_str(&format!(
                     "\treverse_proxy {}:{} {{\n\t\theader_up Host {{host}}\n\t}}\n",
@@ -94,11 +190,10 @@ fn build_caddyfile(domains: &[CaddyDomainConfig], http_port: u16, https_port: u1
 
         // HTTP block
         if wants_http {
-            if http_port != 80 {
-                out.push_str(&format!("http://{}:{} {{\n", domain.name, http_port));
-            } else {
-                out.push_str(&format!("http://{} {{\n", domain.name));
-            }
+            out.push_str(&format!(
+                "{} {{\n",
+                site_addresses(domain, "http", http_port, 80)
+            ));
             out.push_str(&format!(
                 "\treverse_proxy {}:{} {{\n\t\theader_up Host {{host}}\n\t}}\n",
                 domain.target_host, domain.target_port
@@ -114,6 +209,46 @@ fn build_caddyfile(domains: &[CaddyDomainConfig], http_port: u16, https_port: u1
     out
 }
 
+/// The site address list for a domain and its aliases, e.g.
+/// `https://app.test, https://api.app.test`.
+fn site_addresses(
+    domain: &CaddyDomainConfig,
+    scheme: &str,
+    port: u16,
+    default_port: u16,
+) -> String {
+    let port = if port != default_port {
+        format!(":{}", port)
+    } else {
+        String::new()
+    };
+    std::iter::once(&domain.name)
+        .chain(&domain.aliases)
+        .map(|host| format!("{}://{}{}", scheme, host, port))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Static site serving the CA to phones and tablets. HTTP only, since the devices
+/// don't trust the CA yet; bound to the LAN address as well as loopback, on a port
+/// of its own so the domain sites stay loopback-only.
+fn append_onboarding_site(out: &mut String, site: &Site) {
+    out.push_str(&format!(
+        "http://{}:{}, http://{}:{} {{\n",
+        site.host, site.port, site.lan_ip, site.port
+    ));
+    out.push_str(&format!("\tbind {} 127.0.0.1\n", site.lan_ip));
+    out.push_str(&format!("\troot * {}\n", site.root));
+    for (file, content_type) in onboarding::CONTENT_TYPES {
+        out.push_str(&format!(
+            "\theader /{} Content-Type {}\n",
+            file, content_type
+        ));
+    }
+    out.push_str("\tfile_server\n");
+    out.push_str("}\n\n");
+}
+
 fn append_log_directive(out: &mut String, domain_name: &str) {
     out.push_str("\tlog {\n");
     let log_path = std::path::Path::new(crate::paths::LOGS_DIR)
@@ -145,6 +280,7 @@ mod tests {
     fn test_http_only_domain() {
         let domains = vec![CaddyDomainConfig {
             name: "project.test".to_string(),
+            aliases: vec![],
             target_host: "127.0.0.1".to_string(),
             target_port: 3000,
             protocol: "http".to_string(),
@@ -163,6 +299,7 @@ mod tests {
     fn test_https_only_domain() {
         let domains = vec![CaddyDomainConfig {
             name: "secure.test".to_string(),
+            aliases: vec![],
             target_host: "127.0.0.1".to_string(),
             target_port: 3000,
             protocol: "https".to_string(),
@@ -180,6 +317,7 @@ mod tests {
     fn test_both_protocol_domain() {
         let domains = vec![CaddyDomainConfig {
             name: "both.test".to_string(),
+            aliases: vec![],
             target_host: "127.0.0.1".to_string(),
             target_port: 3000,
             protocol: "both".to_string(),
@@ -197,6 +335,7 @@ mod tests {
     fn test_access_log_directive() {
         let domains = vec![CaddyDomainConfig {
             name: "logged.test".to_string(),
+            aliases: vec![],
             target_host: "127.0.0.1".to_string(),
             target_port: 3000,
             protocol: "http".to_string(),
@@ -218,6 +357,7 @@ mod tests {
     fn test_no_access_log_by_default() {
         let domains = vec![CaddyDomainConfig {
             name: "nolog.test".to_string(),
+            aliases: vec![],
             target_host: "127.0.0.1".to_string(),
             target_port: 3000,
             protocol: "http".to_string(),
@@ -233,6 +373,7 @@ mod tests {
     fn test_standard_ports() {
         let domains = vec![CaddyDomainConfig {
             name: "project.test".to_string(),
+            aliases: vec![],
             target_host: "127.0.0.1".to_string(),
             target_port: 3000,
             protocol: "both".to_string(),
@@ -246,4 +387,93 @@ mod tests {
         assert!(!result.contains("http_port"));
         assert!(!result.contains("https_port"));
     }
+
+    #[test]
+    fn test_aliases_share_site_block() {
+        let domains = vec![CaddyDomainConfig {
+            name: "project.test".to_string(),
+            aliases: vec![
+                "api.project.test".to_string(),
+                "admin.project.test".to_string(),
+            ],
+            target_host: "127.0.0.1".to_string(),
+            target_port: 3000,
+            protocol: "both".to_string(),
+            cert_path: Some("/certs/project.crt".to_string()),
+            key_path: Some("/certs/project.key".to_string()),
+            access_log: false,
+        }];
+        let result = build_caddyfile(&domains, 8080, 443);
+        assert!(result.contains(
+            "https://project.test, https://api.project.test, https://admin.project.test {"
+        ));
+        assert!(result.contains(
+            "http://project.test:8080, http://api.project.test:8080, http://admin.project.test:8080 {"
+        ));
+        assert_eq!(result.matches("reverse_proxy").count(), 2);
+
+        let mut bad = domains[0].clone();
+        bad.aliases = vec!["evil.test {".to_string()];
+        assert!(validate_caddy_config(&bad).is_err());
+    }
+
+    #[test]
+    fn test_onboarding_site() {
+        let site = Site {
+            host: "ca.test".to_string(),
+            lan_ip: std::net::Ipv4Addr::new(192, 168, 1, 20),
+            port: 8554,
+            root: "/var/lib/localdomain/onboarding".to_string(),
+        };
+        let mut result = build_caddyfile(&[], 80, 443);
+        append_onboarding_site(&mut result, &site);
+        assert!(result.contains("http://ca.test:8554, http://192.168.1.20:8554 {"));
+        assert!(result.contains("bind 192.168.1.20 127.0.0.1"));
+        assert!(result.contains("root * /var/lib/localdomain/onboarding"));
+        assert!(result.contains(
+            "header /localdomain-ca.mobileconfig Content-Type application/x-apple-aspen-config"
+        ));
+        assert!(result.contains("file_server"));
+    }
+
+    #[test]
+    fn test_uses_certificate() {
+        let domain = CaddyDomainConfig {
+            name: "secure.test".to_string(),
+            aliases: vec![],
+            target_host: "127.0.0.1".to_string(),
+            target_port: 3000,
+            protocol: "both".to_string(),
+            cert_path: Some("/var/lib/localdomain/certs/secure.test.crt".to_string()),
+            key_path: Some("/var/lib/localdomain/certs/secure.test.key".to_string()),
+            access_log: false,
+        };
+        assert!(serves_certificate(
+            &domain,
+            "/var/lib/localdomain/certs/secure.test.crt"
+        ));
+        assert!(!serves_certificate(
+            &domain,
+            "/var/lib/localdomain/certs/other.test.crt"
+        ));
+        // An HTTP-only domain keeps its cert path but doesn't serve it
+        let http_only = CaddyDomainConfig {
+            protocol: "http".to_string(),
+            ..domain.clone()
+        };
+        assert!(!serves_certificate(
+            &http_only,
+            "/var/lib/localdomain/certs/secure.test.crt"
+        ));
+
+        let caddyfile = build_caddyfile(&[domain], 80, 443);
+        assert!(caddyfile_uses_certificate(
+            &caddyfile,
+            "/var/lib/localdomain/certs/secure.test.crt"
+        ));
+        assert!(!caddyfile_uses_certificate(
+            &caddyfile,
+            "/var/lib/localdomain/certs/secure.test.key"
+        ));
+    }
 }
@@ -1,22 +1,322 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use localdomain_shared::protocol::{CaStatus, RootKeyState};
 use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair, KeyUsagePurpose};
 use std::fs;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
-use tracing::info;
+use std::path::Path;
+use tracing::{info, warn};
 
-use crate::paths;
+use super::constraints::CaConstraints;
+use super::{inventory, keystore};
+use crate::{paths, settings};
+
+/// Common name of every CA LocalDomain generates, across rotations.
+pub const GENERATED_CA_NAME: &str = "LocalDomain Root CA";
+
+/// Intermediates are short-lived so a leaked issuing key expires quickly.
+const INTERMEDIATE_VALIDITY_DAYS: i64 = 180;
 
 pub fn ca_exists() -> bool {
-    std::path::Path::new(paths::CA_CERT).exists() && std::path::Path::new(paths::CA_KEY).exists()
+    Path::new(paths::CA_CERT).exists()
+        && (Path::new(paths::CA_KEY).exists()
+            || Path::new(paths::CA_KEY_ENCRYPTED).exists()
+            || Path::new(paths::INTERMEDIATE_KEY).exists())
 }
 
 pub fn ca_cert_path() -> &'static str {
     paths::CA_CERT
 }
 
-pub fn ca_key_path() -> &'static str {
-    paths::CA_KEY
+pub fn intermediate_cert_path() -> &'static str {
+    paths::INTERMEDIATE_CERT
+}
+
+/// True while a rotated-out CA is kept trusted during the overlap window.
+pub fn previous_ca_exists() -> bool {
+    Path::new(paths::CA_PREVIOUS_CERT).exists()
+}
+
+pub fn root_key_state() -> RootKeyState {
+    if Path::new(paths::CA_KEY).exists() {
+        RootKeyState::Online
+    } else if Path::new(paths::CA_KEY_ENCRYPTED).exists() {
+        RootKeyState::Encrypted
+    } else {
+        RootKeyState::Offline
+    }
+}
+
+pub fn ca_status() -> CaStatus {
+    let intermediate = inventory::inspect_certificate(
+        Path::new(paths::INTERMEDIATE_CERT),
+        Path::new(paths::CA_CERT),
+        None,
+    )
+    .ok();
+    // Until a CA exists, the constraints one generated now would get
+    let constraints = if Path::new(paths::CA_CERT).exists() {
+        CaConstraints::from_ca_cert(Path::new(paths::CA_CERT))
+    } else {
+        CaConstraints::from_settings(&settings::get())
+    };
+    CaStatus {
+        root_key: root_key_state(),
+        intermediate_not_after: intermediate.as_ref().map(|i| i.not_after),
+        intermediate_valid: intermediate
+            .map(|i| i.signed_by_current_ca)
+            .unwrap_or(false),
+        permitted_dns_suffixes: constraints.ok().flatten().map(|c| c.dns),
+    }
+}
+
+/// Rebuild an rcgen certificate for signing from a CA PEM and its key. The params
+/// are parsed from the PEM so issuer names on new certs match the CA subject exactly.
+fn signer_from_pem(cert_pem: &str, key: &KeyPair) -> Result<rcgen::Certificate> {
+    let params =
+        CertificateParams::from_ca_cert_pem(cert_pem).context("Failed to parse CA certificate")?;
+    Ok(params.self_signed(key)?)
+}
+
+/// Load the root CA for signing. `key_pem` overrides the on-disk key (for a root
+/// kept offline); encrypted keys need `passphrase`.
+pub fn load_root(
+    key_pem: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<(rcgen::Certificate, KeyPair)> {
+    let key_pem = match key_pem {
+        Some(pem) => pem.to_string(),
+        None => match root_key_state() {
+            RootKeyState::Online => {
+                fs::read_to_string(paths::CA_KEY).context("Failed to read CA key")?
+            }
+            RootKeyState::Encrypted => fs::read_to_string(paths::CA_KEY_ENCRYPTED)
+                .context("Failed to read encrypted CA key")?,
+            RootKeyState::Offline => bail!("Root CA key is offline. Provide it to continue."),
+        },
+    };
+    let key_pem = if keystore::is_encrypted_pem(&key_pem) {
+        let passphrase =
+            passphrase.context("Root CA key is encrypted. Provide the passphrase to continue.")?;
+        keystore::decrypt_key_pem(&key_pem, passphrase)?
+    } else {
+        key_pem
+    };
+    let key = KeyPair::from_pem(&key_pem)?;
+    let cert_pem = fs::read_to_string(paths::CA_CERT).context("Failed to read CA certificate")?;
+    let cert = signer_from_pem(&cert_pem, &key)?;
+    Ok((cert, key))
+}
+
+/// Load the intermediate that signs domain certificates, plus its PEM for the chain.
+pub fn load_issuer() -> Result<(rcgen::Certificate, KeyPair, String)> {
+    let key_pem =
+        fs::read_to_string(paths::INTERMEDIATE_KEY).context("Failed to read intermediate key")?;
+    let key = KeyPair::from_pem(&key_pem)?;
+    let cert_pem = fs::read_to_string(paths::INTERMEDIATE_CERT)
+        .context("Failed to read intermediate certificate")?;
+    let cert = signer_from_pem(&cert_pem, &key)?;
+    Ok((cert, key, cert_pem))
+}
+
+/// Sign a new intermediate CA with the root. It inherits the root's name constraints.
+pub fn issue_intermediate(
+    root_cert: &rcgen::Certificate,
+    root_key: &KeyPair,
+    constraints: Option<&CaConstraints>,
+    now: time::OffsetDateTime,
+) -> Result<(rcgen::Certificate, KeyPair)> {
+    let mut params = CertificateParams::default();
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::CommonName, "LocalDomain Intermediate CA");
+    dn.push(DnType::OrganizationName, "LocalDomain");
+    params.distinguished_name = dn;
+    params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Constrained(0));
+    params.name_constraints = constraints.map(|c| c.to_rcgen());
+    params.key_usages = vec![
+        KeyUsagePurpose::DigitalSignature,
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+    ];
+    params.use_authority_key_identifier_extension = true;
+    params.not_before = now;
+    params.not_after = now + time::Duration::days(INTERMEDIATE_VALIDITY_DAYS);
+
+    let key = KeyPair::generate()?;
+    let cert = params.signed_by(&key, root_cert, root_key)?;
+    Ok((cert, key))
+}
+
+fn write_intermediate(cert: &rcgen::Certificate, key: &KeyPair) -> Result<()> {
+    fs::write(paths::INTERMEDIATE_CERT, cert.pem())?;
+    fs::write(paths::INTERMEDIATE_KEY, key.serialize_pem())?;
+    #[cfg(unix)]
+    fs::set_permissions(paths::INTERMEDIATE_KEY, fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+fn renew_intermediate_with(root_cert: &rcgen::Certificate, root_key: &KeyPair) -> Result<()> {
+    let constraints = CaConstraints::from_ca_cert(Path::new(paths::CA_CERT))?;
+    let (cert, key) = issue_intermediate(
+        root_cert,
+        root_key,
+        constraints.as_ref(),
+        time::OffsetDateTime::now_utc(),
+    )?;
+    write_intermediate(&cert, &key)?;
+    info!("Issued intermediate CA certificate");
+    Ok(())
+}
+
+/// Issue a fresh intermediate, unlocking an encrypted or offline root key if given.
+pub fn renew_intermediate(root_key_pem: Option<&str>, passphrase: Option<&str>) -> Result<()> {
+    let (root_cert, root_key) = load_root(root_key_pem, passphrase)?;
+    renew_intermediate_with(&root_cert, &root_key)
+}
+
+/// Make sure a usable intermediate exists. Reissues it when missing, not chained to
+/// the current root, or inside the renewal window (the latter only while the root
+/// key is online). Returns true if a new intermediate was written.
+pub fn ensure_intermediate(now: time::OffsetDateTime, window_days: u32) -> Result<bool> {
+    let current = inventory::inspect_certificate(
+        Path::new(paths::INTERMEDIATE_CERT),
+        Path::new(paths::CA_CERT),
+        None,
+    )
+    .ok()
+    .filter(|i| i.signed_by_current_ca && Path::new(paths::INTERMEDIATE_KEY).exists());
+
+    let deadline = (now + time::Duration::days(window_days as i64)).unix_timestamp();
+    match current {
+        Some(i) if i.not_after > deadline => Ok(false),
+        Some(i)
+            if i.not_after > now.unix_timestamp() && root_key_state() != RootKeyState::Online =>
+        {
+            warn!("Intermediate CA expires soon but the root key is locked; renew it manually");
+            Ok(false)
+        }
+        _ => {
+            renew_intermediate(None, None)?;
+            Ok(true)
+        }
+    }
+}
+
+/// Encrypt the root key with a passphrase and remove the plaintext copy. With
+/// `offline`, the encrypted key is returned instead of being kept on disk.
+pub fn protect_root_key(passphrase: &str, offline: bool) -> Result<Option<String>> {
+    if root_key_state() != RootKeyState::Online {
+        bail!("Root CA key is already protected");
+    }
+    // Leaves must stay issuable once the root key is locked away
+    ensure_intermediate(time::OffsetDateTime::now_utc(), 0)?;
+
+    let key_pem = fs::read_to_string(paths::CA_KEY).context("Failed to read CA key")?;
+    let encrypted = keystore::encrypt_key_pem(&key_pem, passphrase)?;
+    let result = if offline {
+        Some(encrypted)
+    } else {
+        fs::write(paths::CA_KEY_ENCRYPTED, &encrypted)?;
+        #[cfg(unix)]
+        fs::set_permissions(paths::CA_KEY_ENCRYPTED, fs::Permissions::from_mode(0o600))?;
+        None
+    };
+    fs::remove_file(paths::CA_KEY).context("Failed to remove plaintext CA key")?;
+
+    info!(
+        "Root CA key {}",
+        if offline {
+            "exported for offline storage"
+        } else {
+            "encrypted at rest"
+        }
+    );
+    Ok(result)
+}
+
+/// A root CA created elsewhere (e.g. by mkcert), checked and ready to install.
+#[derive(Debug)]
+pub struct ImportedCa {
+    pub cert_pem: String,
+    /// Unencrypted PKCS#8
+    pub key_pem: String,
+    pub subject: String,
+    pub fingerprint: String,
+    pub constraints: Option<CaConstraints>,
+}
+
+/// Check that `cert_pem` is a self-signed CA that may sign certificates and that
+/// `key_pem` (optionally encrypted) is its private key.
+pub fn validate_import(
+    cert_pem: &str,
+    key_pem: &str,
+    passphrase: Option<&str>,
+    now: time::OffsetDateTime,
+) -> Result<ImportedCa> {
+    let cert = pem::parse_many(cert_pem)
+        .context("Invalid CA certificate PEM")?
+        .into_iter()
+        .find(|p| p.tag() == "CERTIFICATE")
+        .context("No certificate found in the CA PEM")?;
+    let (_, x509) = x509_parser::parse_x509_certificate(cert.contents())
+        .map_err(|e| anyhow::anyhow!("Failed to parse CA certificate: {}", e))?;
+
+    let is_ca = x509
+        .basic_constraints()
+        .ok()
+        .flatten()
+        .map(|bc| bc.value.ca)
+        .unwrap_or(false);
+    if !is_ca {
+        bail!("The certificate is not a CA");
+    }
+    if let Ok(Some(usage)) = x509.key_usage() {
+        if !usage.value.key_cert_sign() {
+            bail!("The CA certificate is not allowed to sign certificates");
+        }
+    }
+    if x509.verify_signature(None).is_err() {
+        bail!("Only self-signed root CAs can be imported");
+    }
+    if x509.validity().not_after.timestamp() <= now.unix_timestamp() {
+        bail!("The CA certificate has expired");
+    }
+
+    let key_pem = keystore::to_pkcs8_pem(key_pem, passphrase)?;
+    let key = KeyPair::from_pem(&key_pem).context("Unsupported CA key type")?;
+    if key.public_key_der() != x509.public_key().raw {
+        bail!("The private key does not belong to the CA certificate");
+    }
+
+    Ok(ImportedCa {
+        cert_pem: pem::encode(&cert),
+        key_pem,
+        subject: x509.subject().to_string(),
+        fingerprint: inventory::fingerprint_sha256(cert.contents()),
+        constraints: CaConstraints::from_x509(&x509)?,
+    })
+}
+
+/// Write an imported root as the current CA and issue an intermediate under it.
+pub fn install_ca(imported: &ImportedCa) -> Result<()> {
+    let key = KeyPair::from_pem(&imported.key_pem)?;
+    let root = signer_from_pem(&imported.cert_pem, &key)?;
+
+    fs::write(paths::CA_CERT, &imported.cert_pem)?;
+    fs::write(paths::CA_KEY, &imported.key_pem)?;
+    #[cfg(unix)]
+    fs::set_permissions(paths::CA_KEY, fs::Permissions::from_mode(0o600))?;
+    info!("Installed imported root CA {}", imported.subject);
+
+    let (intermediate, intermediate_key) = issue_intermediate(
+        &root,
+        &key,
+        imported.constraints.as_ref(),
+        time::OffsetDateTime::now_utc(),
+    )?;
+    write_intermediate(&intermediate, &intermediate_key)?;
+    info!("Issued intermediate CA certificate");
+    Ok(())
 }
 
 pub fn generate_ca() -> Result<()> {
@@ -27,10 +327,15 @@ pub fn generate_ca() -> Result<()> {
 
     let mut params = CertificateParams::default();
     let mut dn = DistinguishedName::new();
-    dn.push(DnType::CommonName, "LocalDomain Root CA");
+    dn.push(DnType::CommonName, GENERATED_CA_NAME);
     dn.push(DnType::OrganizationName, "LocalDomain");
     params.distinguished_name = dn;
     params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    // Limit the CA to development names so a leaked key cannot mint certs for real sites
+    let constraints = CaConstraints::from_settings(&settings::get())?;
+    if let Some(ref constraints) = constraints {
+        params.name_constraints = Some(constraints.to_rcgen());
+    }
     params.key_usages = vec![
         KeyUsagePurpose::DigitalSignature,
         KeyUsagePurpose::KeyCertSign,
@@ -53,6 +358,142 @@ pub fn generate_ca() -> Result<()> {
     #[cfg(unix)]
     fs::set_permissions(paths::CA_KEY, fs::Permissions::from_mode(0o600))?;
 
-    info!("Generated root CA certificate");
+    match constraints {
+        Some(ref c) => info!(
+            "Generated root CA certificate constrained to {}",
+            c.describe().join(", ")
+        ),
+        None => info!("Generated root CA certificate"),
+    }
+
+    // Domain certs are signed by an intermediate so the root key can go offline
+    let (intermediate, intermediate_key) =
+        issue_intermediate(&cert, &key_pair, constraints.as_ref(), now)?;
+    write_intermediate(&intermediate, &intermediate_key)?;
+    info!("Issued intermediate CA certificate");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{IsCa, SanType};
+    use x509_parser::pem::Pem;
+
+    fn parse(pem: &str) -> Vec<u8> {
+        Pem::iter_from_buffer(pem.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap()
+            .contents
+    }
+
+    fn root(is_ca: bool) -> (rcgen::Certificate, KeyPair) {
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "mkcert development CA");
+        if is_ca {
+            params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            params.key_usages = vec![KeyUsagePurpose::KeyCertSign];
+        }
+        let key = KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+        (cert, key)
+    }
+
+    #[test]
+    fn test_validate_import() {
+        let now = time::OffsetDateTime::now_utc();
+        let (cert, key) = root(true);
+        let imported = validate_import(&cert.pem(), &key.serialize_pem(), None, now).unwrap();
+        assert_eq!(imported.subject, "CN=mkcert development CA");
+        assert_eq!(imported.key_pem, key.serialize_pem());
+        assert_eq!(
+            imported.fingerprint,
+            inventory::fingerprint_sha256(cert.der())
+        );
+        assert!(imported.constraints.is_none());
+
+        // The imported root can sign an intermediate
+        let signer = signer_from_pem(&imported.cert_pem, &key).unwrap();
+        let (intermediate, _) = issue_intermediate(&signer, &key, None, now).unwrap();
+        let (_, int_x509) = x509_parser::parse_x509_certificate(intermediate.der()).unwrap();
+        let (_, root_x509) = x509_parser::parse_x509_certificate(cert.der()).unwrap();
+        assert!(int_x509
+            .verify_signature(Some(root_x509.public_key()))
+            .is_ok());
+
+        let encrypted = keystore::encrypt_key_pem(&key.serialize_pem(), "hunter2").unwrap();
+        assert!(validate_import(&cert.pem(), &encrypted, None, now).is_err());
+        assert!(validate_import(&cert.pem(), &encrypted, Some("hunter2"), now).is_ok());
+
+        let (_, other_key) = root(true);
+        let err = validate_import(&cert.pem(), &other_key.serialize_pem(), None, now).unwrap_err();
+        assert!(err.to_string().contains("does not belong"));
+
+        let (leaf, leaf_key) = root(false);
+        let err = validate_import(&leaf.pem(), &leaf_key.serialize_pem(), None, now).unwrap_err();
+        assert!(err.to_string().contains("not a CA"));
+
+        let later = now + time::Duration::days(365 * 5000);
+        assert!(validate_import(&cert.pem(), &key.serialize_pem(), None, later).is_err());
+    }
+
+    #[test]
+    fn test_chain_leaf_intermediate_root() {
+        let mut root_params = CertificateParams::default();
+        root_params
+            .distinguished_name
+            .push(DnType::CommonName, "LocalDomain Root CA");
+        root_params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let root_key = KeyPair::generate().unwrap();
+        let root = root_params.self_signed(&root_key).unwrap();
+
+        let constraints = CaConstraints::from_settings(&Default::default()).unwrap();
+        let now = time::OffsetDateTime::now_utc();
+        let (intermediate, intermediate_key) =
+            issue_intermediate(&root, &root_key, constraints.as_ref(), now).unwrap();
+
+        // Sign the leaf with the intermediate re-parsed from PEM, as load_issuer does
+        let issuer = signer_from_pem(&intermediate.pem(), &intermediate_key).unwrap();
+        let mut leaf_params = CertificateParams::new(vec!["app.test".to_string()]).unwrap();
+        leaf_params.subject_alt_names = vec![SanType::DnsName("app.test".try_into().unwrap())];
+        leaf_params.use_authority_key_identifier_extension = true;
+        let leaf_key = KeyPair::generate().unwrap();
+        let leaf = leaf_params
+            .signed_by(&leaf_key, &issuer, &intermediate_key)
+            .unwrap();
+
+        let root_der = parse(&root.pem());
+        let intermediate_der = parse(&intermediate.pem());
+        let leaf_der = parse(&leaf.pem());
+        let (_, root_x509) = x509_parser::parse_x509_certificate(&root_der).unwrap();
+        let (_, int_x509) = x509_parser::parse_x509_certificate(&intermediate_der).unwrap();
+        let (_, leaf_x509) = x509_parser::parse_x509_certificate(&leaf_der).unwrap();
+
+        // leaf -> intermediate -> root
+        assert!(leaf_x509
+            .verify_signature(Some(int_x509.public_key()))
+            .is_ok());
+        assert!(int_x509
+            .verify_signature(Some(root_x509.public_key()))
+            .is_ok());
+        assert!(root_x509.verify_signature(None).is_ok());
+        assert!(leaf_x509
+            .verify_signature(Some(root_x509.public_key()))
+            .is_err());
+        assert_eq!(leaf_x509.issuer(), int_x509.subject());
+        assert_eq!(int_x509.issuer(), root_x509.subject());
+
+        // The intermediate cannot sign further CAs and keeps the root's constraints
+        let bc = int_x509.basic_constraints().unwrap().unwrap().value;
+        assert!(bc.ca);
+        assert_eq!(bc.path_len_constraint, Some(0));
+        assert_eq!(CaConstraints::from_x509(&int_x509).unwrap(), constraints);
+        assert!(
+            int_x509.validity().not_after.timestamp()
+                <= (now + time::Duration::days(INTERMEDIATE_VALIDITY_DAYS)).unix_timestamp()
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use anyhow::{bail, Context, Result};
+use localdomain_shared::protocol::DaemonSettings;
+use rcgen::{CidrSubnet, GeneralSubtree, NameConstraints};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+
+/// Permitted subtrees of a name-constrained CA.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaConstraints {
+    /// Lowercase DNS suffixes without a leading dot
+    pub dns: Vec<String>,
+    pub ips: Vec<(IpAddr, u8)>,
+}
+
+impl CaConstraints {
+    /// Constraints to embed in a new CA, or None when the settings disable them.
+    pub fn from_settings(settings: &DaemonSettings) -> Result<Option<Self>> {
+        if !settings.ca_name_constraints {
+            return Ok(None);
+        }
+        let dns = settings
+            .ca_permitted_suffixes
+            .iter()
+            .map(|s| normalize_suffix(s))
+            .collect::<Result<Vec<_>>>()?;
+        let ips = settings
+            .ca_permitted_ips
+            .iter()
+            .map(|s| parse_cidr(s))
+            .collect::<Result<Vec<_>>>()?;
+        if dns.is_empty() {
+            bail!("Name-constrained CA needs at least one permitted suffix");
+        }
+        Ok(Some(Self { dns, ips }))
+    }
+
+    /// Read the constraints embedded in a CA certificate; None when it is unconstrained.
+    pub fn from_ca_cert(path: &Path) -> Result<Option<Self>> {
+        let pem = fs::read(path).context("Failed to read CA certificate")?;
+        let pem = Pem::iter_from_buffer(&pem)
+            .next()
+            .context("No PEM block found")?
+            .context("Invalid PEM")?;
+        let cert = pem
+            .parse_x509()
+            .map_err(|e| anyhow::anyhow!("Invalid certificate: {}", e))?;
+        Self::from_x509(&cert)
+    }
+
+    pub fn from_x509(cert: &X509Certificate) -> Result<Option<Self>> {
+        let Some(ext) = cert
+            .name_constraints()
+            .map_err(|e| anyhow::anyhow!("Invalid name constraints: {}", e))?
+        else {
+            return Ok(None);
+        };
+
+        let mut dns = Vec::new();
+        let mut ips = Vec::new();
+        for subtree in ext.value.permitted_subtrees.iter().flatten() {
+            match &subtree.base {
+                GeneralName::DNSName(name) => dns.push(name.trim_start_matches('.').to_lowercase()),
+                GeneralName::IPAddress(bytes) => {
+                    if let Some(range) = cidr_from_bytes(bytes) {
+                        ips.push(range);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Some(Self { dns, ips }))
+    }
+
+    pub fn to_rcgen(&self) -> NameConstraints {
+        let mut permitted_subtrees: Vec<GeneralSubtree> = self
+            .dns
+            .iter()
+            .map(|s| GeneralSubtree::DnsName(s.clone()))
+            .collect();
+        permitted_subtrees.extend(self.ips.iter().map(|(addr, prefix)| {
+            GeneralSubtree::IpAddress(CidrSubnet::from_addr_prefix(*addr, *prefix))
+        }));
+        NameConstraints {
+            permitted_subtrees,
+            excluded_subtrees: Vec::new(),
+        }
+    }
+
+    /// Human-readable list of the permitted subtrees, e.g. ["test", "127.0.0.0/8"].
+    pub fn describe(&self) -> Vec<String> {
+        self.dns
+            .iter()
+            .cloned()
+            .chain(
+                self.ips
+                    .iter()
+                    .map(|(addr, prefix)| format!("{}/{}", addr, prefix)),
+            )
+            .collect()
+    }
+
+    pub fn permits(&self, san: &str) -> bool {
+        match san.parse::<IpAddr>() {
+            // With no IP subtrees the extension leaves IP SANs unconstrained
+            Ok(ip) => self.ips.is_empty() || self.ips.iter().any(|range| in_range(ip, *range)),
+            Err(_) => {
+                let name = san.trim_start_matches("*.").to_lowercase();
+                self.dns
+                    .iter()
+                    .any(|suffix| name == *suffix || name.ends_with(&format!(".{}", suffix)))
+            }
+        }
+    }
+
+    /// Refuse to issue a name the CA would not be trusted for.
+    pub fn check(&self, san: &str) -> Result<()> {
+        if !self.permits(san) {
+            bail!(
+                "'{}' is outside the CA's permitted names ({}). Add it to the permitted suffixes and rotate the CA.",
+                san,
+                self.describe().join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+fn normalize_suffix(suffix: &str) -> Result<String> {
+    let suffix = suffix.trim().trim_start_matches('.').to_lowercase();
+    if suffix.is_empty()
+        || !suffix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    {
+        bail!("Invalid permitted suffix '{}'", suffix);
+    }
+    Ok(suffix)
+}
+
+/// Parse "192.168.1.0/24" or a bare address (treated as a single host).
+pub fn parse_cidr(value: &str) -> Result<(IpAddr, u8)> {
+    let value = value.trim();
+    let (addr, prefix) = match value.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (value, None),
+    };
+    let addr: IpAddr = addr
+        .parse()
+        .with_context(|| format!("Invalid IP address '{}'", value))?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(p) => p
+            .parse::<u8>()
+            .ok()
+            .filter(|p| *p <= max)
+            .with_context(|| format!("Invalid prefix length in '{}'", value))?,
+        None => max,
+    };
+    Ok((addr, prefix))
+}
+
+fn in_range(ip: IpAddr, (net, prefix): (IpAddr, u8)) -> bool {
+    match (ip, net) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Name constraint IP subtrees are encoded as address followed by mask.
+fn cidr_from_bytes(bytes: &[u8]) -> Option<(IpAddr, u8)> {
+    let (addr, mask) = bytes.split_at(bytes.len() / 2);
+    let prefix = mask.iter().map(|b| b.count_ones()).sum::<u32>() as u8;
+    let addr = match addr.len() {
+        4 => IpAddr::from(<[u8; 4]>::try_from(addr).ok()?),
+        16 => IpAddr::from(<[u8; 16]>::try_from(addr).ok()?),
+        _ => return None,
+    };
+    Some((addr, prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, DnType, IsCa, KeyPair};
+
+    fn constraints() -> CaConstraints {
+        CaConstraints::from_settings(&DaemonSettings::default())
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_permits_configured_suffixes_only() {
+        let c = constraints();
+        assert!(c.permits("app.test"));
+        assert!(c.permits("*.app.test"));
+        assert!(c.permits("localhost"));
+        assert!(c.permits("api.internal"));
+        assert!(c.permits("127.0.0.1"));
+        assert!(c.permits("::1"));
+        assert!(!c.permits("example.com"));
+        assert!(!c.permits("attest"));
+        assert!(!c.permits("192.168.1.10"));
+        assert!(c.check("google.com").is_err());
+    }
+
+    #[test]
+    fn test_parse_cidr() {
+        assert_eq!(
+            parse_cidr("192.168.1.0/24").unwrap(),
+            ("192.168.1.0".parse().unwrap(), 24)
+        );
+        assert_eq!(
+            parse_cidr("10.0.0.5").unwrap(),
+            ("10.0.0.5".parse().unwrap(), 32)
+        );
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+        assert!(parse_cidr("nope").is_err());
+    }
+
+    #[test]
+    fn test_constraints_round_trip_through_ca_cert() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = DaemonSettings::default();
+        settings.ca_permitted_ips.push("192.168.1.0/24".to_string());
+        let c = CaConstraints::from_settings(&settings).unwrap().unwrap();
+
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "Test Root CA");
+        params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        params.name_constraints = Some(c.to_rcgen());
+        let key = KeyPair::generate().unwrap();
+        let path = dir.path().join("ca.crt");
+        fs::write(&path, params.self_signed(&key).unwrap().pem()).unwrap();
+
+        let parsed = CaConstraints::from_ca_cert(&path).unwrap().unwrap();
+        assert_eq!(parsed, c);
+        assert!(parsed.permits("192.168.1.20"));
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::{bail, Context, Result};
+use localdomain_shared::protocol::InstallCustomCertResult;
+use pem::{EncodeConfig, LineEnding, Pem};
+use rcgen::KeyPair;
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::{domain, inventory, keystore};
+
+const PEM_CONFIG: EncodeConfig = EncodeConfig::new().set_line_ending(LineEnding::LF);
+
+/// A user-supplied certificate, checked and ready to write.
+#[derive(Debug)]
+struct CustomCert {
+    chain_pem: String,
+    key_pem: String,
+    not_after: i64,
+}
+
+/// Marker next to the cert file; renewal, rotation and `generate_cert` leave marked
+/// certificates alone.
+fn marker_path(name: &str) -> PathBuf {
+    Path::new(&domain::cert_paths(name).0).with_extension("custom")
+}
+
+pub fn is_custom(name: &str) -> bool {
+    marker_path(name).exists()
+}
+
+/// Validate a certificate and key supplied for `domain` and install them under
+/// CERTS_DIR in place of a LocalDomain-issued one.
+pub fn install_custom_cert(
+    domain: &str,
+    names: &[String],
+    cert_pem: &str,
+    key_pem: &str,
+    passphrase: Option<&str>,
+) -> Result<InstallCustomCertResult> {
+    domain::validate_cert_name(domain)?;
+    let mut required = vec![domain.to_string()];
+    required.extend(names.iter().cloned());
+    let cert = validate(
+        cert_pem,
+        key_pem,
+        passphrase,
+        &required,
+        time::OffsetDateTime::now_utc(),
+    )?;
+
+    let (cert_path, key_path) = domain::cert_paths(domain);
+    fs::create_dir_all(crate::paths::CERTS_DIR)?;
+    fs::write(&cert_path, &cert.chain_pem)?;
+    fs::write(&key_path, &cert.key_pem)?;
+    #[cfg(unix)]
+    fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
+    fs::write(marker_path(domain), b"")?;
+
+    info!("Installed custom certificate for {}", domain);
+    Ok(InstallCustomCertResult {
+        cert_path,
+        key_path,
+        not_after: cert.not_after,
+    })
+}
+
+/// Drop a custom certificate so the next sync issues one from the local CA.
+pub fn remove_custom_cert(domain: &str) -> Result<()> {
+    domain::validate_cert_name(domain)?;
+    if !is_custom(domain) {
+        return Ok(());
+    }
+    let (cert_path, key_path) = domain::cert_paths(domain);
+    for path in [&cert_path, &key_path] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).with_context(|| format!("Failed to remove {}", path))?;
+        }
+    }
+    fs::remove_file(marker_path(domain)).context("Failed to remove custom cert marker")?;
+    info!("Removed custom certificate for {}", domain);
+    Ok(())
+}
+
+fn validate(
+    cert_pem: &str,
+    key_pem: &str,
+    passphrase: Option<&str>,
+    required: &[String],
+    now: time::OffsetDateTime,
+) -> Result<CustomCert> {
+    let certs: Vec<Pem> = pem::parse_many(cert_pem)
+        .context("Invalid certificate PEM")?
+        .into_iter()
+        .filter(|p| p.tag() == "CERTIFICATE")
+        .collect();
+    let leaf = certs.first().context("No certificate found in the PEM")?;
+    let (_, x509) = x509_parser::parse_x509_certificate(leaf.contents())
+        .map_err(|e| anyhow::anyhow!("Failed to parse certificate: {}", e))?;
+
+    if x509.is_ca() {
+        bail!("The first certificate is a CA; put the domain certificate first");
+    }
+    let validity = x509.validity();
+    if validity.not_after.timestamp() <= now.unix_timestamp() {
+        bail!("The certificate has expired");
+    }
+    if validity.not_before.timestamp() > now.unix_timestamp() {
+        bail!("The certificate is not valid yet");
+    }
+
+    let sans = inventory::subject_alt_names(&x509);
+    let missing: Vec<&str> = required
+        .iter()
+        .filter(|name| !sans.iter().any(|san| covers(san, name)))
+        .map(|name| name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        bail!(
+            "The certificate does not cover {} (it is for {})",
+            missing.join(", "),
+            if sans.is_empty() {
+                "no names".to_string()
+            } else {
+                sans.join(", ")
+            }
+        );
+    }
+
+    let key_pem = keystore::to_pkcs8_pem(key_pem, passphrase)?;
+    let key = KeyPair::from_pem(&key_pem).context("Unsupported private key type")?;
+    if key.public_key_der() != x509.public_key().raw {
+        bail!("The private key does not belong to the certificate");
+    }
+
+    Ok(CustomCert {
+        chain_pem: pem::encode_many_config(&certs, PEM_CONFIG),
+        key_pem,
+        not_after: validity.not_after.timestamp(),
+    })
+}
+
+/// Whether SAN `san` matches `name`, with a `*.` SAN covering exactly one label.
+fn covers(san: &str, name: &str) -> bool {
+    if san.eq_ignore_ascii_case(name) {
+        return true;
+    }
+    match (san.strip_prefix("*."), name.split_once('.')) {
+        (Some(base), Some((label, rest))) => {
+            !name.starts_with("*.") && !label.is_empty() && base.eq_ignore_ascii_case(rest)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, IsCa};
+
+    fn leaf(names: &[&str], not_after: time::OffsetDateTime) -> (String, KeyPair) {
+        let mut ca_params = CertificateParams::default();
+        ca_params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let mut params =
+            CertificateParams::new(names.iter().map(|n| n.to_string()).collect::<Vec<_>>())
+                .unwrap();
+        params.not_after = not_after;
+        let key = KeyPair::generate().unwrap();
+        let cert = params.signed_by(&key, &ca, &ca_key).unwrap();
+        (format!("{}{}", cert.pem(), ca.pem()), key)
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_wildcard_coverage() {
+        assert!(covers("app.corp.example", "APP.corp.example"));
+        assert!(covers("*.corp.example", "app.corp.example"));
+        assert!(!covers("*.corp.example", "corp.example"));
+        assert!(!covers("*.corp.example", "a.b.corp.example"));
+        assert!(!covers("*.corp.example", "*.corp.example.evil"));
+        assert!(covers("*.corp.example", "*.corp.example"));
+        assert!(!covers("app.corp.example", "*.corp.example"));
+    }
+
+    #[test]
+    fn test_validate_custom_cert() {
+        let now = time::OffsetDateTime::now_utc();
+        let (chain, key) = leaf(
+            &["*.corp.example", "corp.example"],
+            now + time::Duration::days(90),
+        );
+
+        let cert = validate(
+            &chain,
+            &key.serialize_pem(),
+            None,
+            &names(&["app.corp.example", "corp.example"]),
+            now,
+        )
+        .unwrap();
+        assert_eq!(pem::parse_many(&cert.chain_pem).unwrap().len(), 2);
+        assert_eq!(cert.key_pem, key.serialize_pem());
+        assert!(cert.not_after > now.unix_timestamp());
+
+        let err = validate(
+            &chain,
+            &key.serialize_pem(),
+            None,
+            &names(&["app.other.example"]),
+            now,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("does not cover app.other.example"));
+
+        let other = KeyPair::generate().unwrap();
+        let err = validate(
+            &chain,
+            &other.serialize_pem(),
+            None,
+            &names(&["corp.example"]),
+            now,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("does not belong"));
+
+        let later = now + time::Duration::days(91);
+        let err = validate(
+            &chain,
+            &key.serialize_pem(),
+            None,
+            &names(&["corp.example"]),
+            later,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+}
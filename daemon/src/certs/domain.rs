@@ -1,19 +1,26 @@
 use anyhow::{bail, Context, Result};
-use localdomain_shared::protocol::GenerateCertResult;
+use localdomain_shared::domain::{san_to_ascii, KeyAlgorithm};
+use localdomain_shared::protocol::{CertificateInfo, GenerateCertResult};
 use rcgen::{
     CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, KeyPair,
-    KeyUsagePurpose, SanType,
+    KeyUsagePurpose, SanType, PKCS_ECDSA_P256_SHA256, PKCS_ECDSA_P384_SHA384, PKCS_RSA_SHA256,
 };
+use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+use rsa::rand_core::OsRng;
+use rsa::RsaPrivateKey;
 use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use tracing::info;
 
-use super::ca;
-use crate::paths;
+use super::constraints::CaConstraints;
+use super::{ca, custom, inventory};
+use crate::{paths, settings};
 
-pub fn generate_domain_cert(domain: &str) -> Result<GenerateCertResult> {
-    // Validate domain name to prevent path traversal in cert file paths
+/// Validate a domain name to prevent path traversal in cert file paths.
+pub fn validate_cert_name(domain: &str) -> Result<()> {
     if domain.is_empty()
         || domain.contains('/')
         || domain.contains('\\')
@@ -24,69 +31,316 @@ pub fn generate_domain_cert(domain: &str) -> Result<GenerateCertResult> {
     {
         bail!("Invalid domain name for certificate generation: '{}'", domain);
     }
+    Ok(())
+}
+
+/// Paths of the certificate and key files for a domain under CERTS_DIR.
+pub fn cert_paths(domain: &str) -> (String, String) {
+    let cert_path = std::path::Path::new(paths::CERTS_DIR)
+        .join(format!("{}.crt", domain))
+        .to_string_lossy()
+        .to_string();
+    let key_path = std::path::Path::new(paths::CERTS_DIR)
+        .join(format!("{}.key", domain))
+        .to_string_lossy()
+        .to_string();
+    (cert_path, key_path)
+}
+
+/// Whether an existing cert can be served as-is: it chains to the current CA,
+/// covers every requested SAN, and is not yet inside the renewal window.
+fn is_reusable(
+    info: &CertificateInfo,
+    sans: &[String],
+    now: time::OffsetDateTime,
+    renewal_window_days: u32,
+) -> bool {
+    let deadline = (now + time::Duration::days(renewal_window_days as i64)).unix_timestamp();
+    info.signed_by_current_ca
+        && info.not_before <= now.unix_timestamp()
+        && info.not_after > deadline
+        && sans.iter().all(|san| info.sans.iter().any(|s| s.eq_ignore_ascii_case(san)))
+}
+
+/// The full SAN list for a cert: `domain` first, then the extra names without duplicates.
+fn san_list(domain: &str, extra: &[String]) -> Result<Vec<String>> {
+    let mut sans = vec![domain.to_string()];
+    for san in extra {
+        let san = san_to_ascii(san.trim()).map_err(anyhow::Error::msg)?;
+        if !sans.iter().any(|s| s.eq_ignore_ascii_case(&san)) {
+            sans.push(san);
+        }
+    }
+    Ok(sans)
+}
+
+fn san_type(san: &str) -> Result<SanType> {
+    Ok(match san.parse::<IpAddr>() {
+        Ok(ip) => SanType::IpAddress(ip),
+        Err(_) => SanType::DnsName(san.try_into()?),
+    })
+}
+
+/// Generate a key pair. ring cannot generate RSA keys, so those come from the
+/// rsa crate and are loaded back as PKCS#8.
+fn generate_key(algorithm: KeyAlgorithm) -> Result<KeyPair> {
+    let bits = match algorithm {
+        KeyAlgorithm::EcdsaP256 => return Ok(KeyPair::generate_for(&PKCS_ECDSA_P256_SHA256)?),
+        KeyAlgorithm::EcdsaP384 => return Ok(KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384)?),
+        KeyAlgorithm::Rsa2048 => 2048,
+        KeyAlgorithm::Rsa4096 => 4096,
+    };
+    let key = RsaPrivateKey::new(&mut OsRng, bits).context("Failed to generate RSA key")?;
+    let pem = key
+        .to_pkcs8_pem(LineEnding::LF)
+        .context("Failed to encode RSA key")?;
+    Ok(KeyPair::from_pkcs8_pem_and_sign_algo(
+        &pem,
+        &PKCS_RSA_SHA256,
+    )?)
+}
+
+/// Return a certificate for `domain` covering `extra_sans` as well, reusing the
+/// one on disk when it is still valid and has the requested key algorithm.
+/// With `force`, always issue a fresh key pair and certificate.
+pub fn generate_domain_cert(
+    domain: &str,
+    extra_sans: &[String],
+    key_algorithm: KeyAlgorithm,
+    force: bool,
+) -> Result<GenerateCertResult> {
+    validate_cert_name(domain)?;
+    let sans = san_list(domain, extra_sans)?;
+    if custom::is_custom(domain) {
+        bail!(
+            "{} uses a custom certificate. Remove it before issuing one from the local CA.",
+            domain
+        );
+    }
 
     // Ensure CA exists
     if !ca::ca_exists() {
         ca::generate_ca()?;
     }
 
-    // Load CA key pair
-    let ca_key_pem = fs::read_to_string(ca::ca_key_path()).context("Failed to read CA key")?;
-    let ca_key_pair = KeyPair::from_pem(&ca_key_pem)?;
-
-    // Recreate CA params and self-sign to get a Certificate for signing
-    let mut ca_params = CertificateParams::default();
-    let mut ca_dn = DistinguishedName::new();
-    ca_dn.push(DnType::CommonName, "LocalDomain Root CA");
-    ca_dn.push(DnType::OrganizationName, "LocalDomain");
-    ca_params.distinguished_name = ca_dn;
-    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
-    ca_params.key_usages = vec![
-        KeyUsagePurpose::DigitalSignature,
-        KeyUsagePurpose::KeyCertSign,
-        KeyUsagePurpose::CrlSign,
-    ];
+    let (cert_path, key_path) = cert_paths(domain);
+    if !force && Path::new(&key_path).exists() {
+        if let Ok(info) = inventory::inspect_current(Path::new(&cert_path)) {
+            let window = settings::get().cert_renewal_window_days;
+            let now = time::OffsetDateTime::now_utc();
+            if info.key_type == key_algorithm.key_type() && is_reusable(&info, &sans, now, window) {
+                return Ok(GenerateCertResult {
+                    cert_path,
+                    key_path,
+                    reused: true,
+                });
+            }
+        }
+    }
 
-    let ca_cert = ca_params.self_signed(&ca_key_pair)?;
+    if let Some(constraints) = CaConstraints::from_ca_cert(Path::new(ca::ca_cert_path()))? {
+        for san in &sans {
+            constraints.check(san)?;
+        }
+    }
+
+    let now = time::OffsetDateTime::now_utc();
+    ca::ensure_intermediate(now, 0)?;
+    let (issuer_cert, issuer_key, issuer_pem) = ca::load_issuer()?;
+    let issuer_not_after = inventory::inspect_current(Path::new(ca::intermediate_cert_path()))?
+        .not_after;
 
     // Generate domain certificate
-    let mut params = CertificateParams::new(vec![domain.to_string()])?;
+    let mut params = CertificateParams::default();
     let mut dn = DistinguishedName::new();
     dn.push(DnType::CommonName, domain);
     params.distinguished_name = dn;
 
-    params.subject_alt_names = vec![SanType::DnsName(domain.try_into()?)];
+    params.subject_alt_names = sans
+        .iter()
+        .map(|san| san_type(san))
+        .collect::<Result<_>>()?;
     params.use_authority_key_identifier_extension = true;
     params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+    if matches!(key_algorithm, KeyAlgorithm::Rsa2048 | KeyAlgorithm::Rsa4096) {
+        // RSA key exchange (TLS 1.2 and older clients) encrypts with the leaf key
+        params.key_usages.push(KeyUsagePurpose::KeyEncipherment);
+    }
     params
         .extended_key_usages
         .push(ExtendedKeyUsagePurpose::ServerAuth);
 
-    let now = time::OffsetDateTime::now_utc();
+    // A leaf must not outlive the intermediate that signs it
     params.not_before = now;
-    params.not_after = now + time::Duration::days(365);
+    params.not_after = (now + time::Duration::days(365))
+        .min(time::OffsetDateTime::from_unix_timestamp(issuer_not_after)?);
 
-    let domain_key_pair = KeyPair::generate()?;
-    let domain_cert = params.signed_by(&domain_key_pair, &ca_cert, &ca_key_pair)?;
+    let domain_key_pair = generate_key(key_algorithm)?;
+    let domain_cert = params.signed_by(&domain_key_pair, &issuer_cert, &issuer_key)?;
 
-    let cert_path = std::path::Path::new(paths::CERTS_DIR)
-        .join(format!("{}.crt", domain))
-        .to_string_lossy()
-        .to_string();
-    let key_path = std::path::Path::new(paths::CERTS_DIR)
-        .join(format!("{}.key", domain))
-        .to_string_lossy()
-        .to_string();
-
-    fs::write(&cert_path, domain_cert.pem())?;
+    // Serve the full chain (leaf + intermediate) so clients only need the root
+    fs::write(&cert_path, format!("{}{}", domain_cert.pem(), issuer_pem))?;
     fs::write(&key_path, domain_key_pair.serialize_pem())?;
     #[cfg(unix)]
     fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
 
-    info!("Generated certificate for {}", domain);
+    info!(
+        "Generated {} certificate for {}",
+        key_algorithm.key_type(),
+        sans.join(", ")
+    );
 
     Ok(GenerateCertResult {
         cert_path,
         key_path,
+        reused: false,
     })
 }
+
+/// Sign a PKCS#10 request for `names` with the intermediate CA and return the
+/// PEM chain (leaf + intermediate). Only the CSR's public key is used; names,
+/// usages and validity come from the caller.
+pub fn sign_csr(csr_der: &[u8], names: &[String], validity_days: i64) -> Result<String> {
+    let Some(first) = names.first() else {
+        bail!("A certificate needs at least one name");
+    };
+    if let Some(constraints) = CaConstraints::from_ca_cert(Path::new(ca::ca_cert_path()))? {
+        for name in names {
+            constraints.check(name)?;
+        }
+    }
+
+    let now = time::OffsetDateTime::now_utc();
+    ca::ensure_intermediate(now, 0)?;
+    let (issuer_cert, issuer_key, issuer_pem) = ca::load_issuer()?;
+    let issuer_not_after =
+        inventory::inspect_current(Path::new(ca::intermediate_cert_path()))?.not_after;
+
+    let mut csr = rcgen::CertificateSigningRequestParams::from_der(&csr_der.to_vec().into())
+        .context("Invalid certificate signing request")?;
+    let params = &mut csr.params;
+    params.distinguished_name = DistinguishedName::new();
+    params
+        .distinguished_name
+        .push(DnType::CommonName, first.as_str());
+    params.subject_alt_names = names
+        .iter()
+        .map(|name| san_type(name))
+        .collect::<Result<_>>()?;
+    params.use_authority_key_identifier_extension = true;
+    params.key_usages = vec![
+        KeyUsagePurpose::DigitalSignature,
+        KeyUsagePurpose::KeyEncipherment,
+    ];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    params.not_before = now;
+    params.not_after = (now + time::Duration::days(validity_days))
+        .min(time::OffsetDateTime::from_unix_timestamp(issuer_not_after)?);
+
+    let cert = csr.signed_by(&issuer_cert, &issuer_key)?;
+    info!("Signed certificate request for {}", names.join(", "));
+    Ok(format!("{}{}", cert.pem(), issuer_pem))
+}
+
+/// Issue a fresh certificate with the same names and key algorithm as an existing one.
+pub fn reissue_domain_cert(existing: &CertificateInfo) -> Result<GenerateCertResult> {
+    let extra: Vec<String> = existing
+        .sans
+        .iter()
+        .filter(|san| !san.eq_ignore_ascii_case(&existing.name))
+        .cloned()
+        .collect();
+    let key_algorithm = KeyAlgorithm::from_key_type(&existing.key_type).unwrap_or_default();
+    generate_domain_cert(&existing.name, &extra, key_algorithm, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(sans: &[&str], not_after: time::OffsetDateTime, current: bool) -> CertificateInfo {
+        CertificateInfo {
+            name: "app.test".to_string(),
+            cert_path: "/certs/app.test.crt".to_string(),
+            key_path: Some("/certs/app.test.key".to_string()),
+            subject: "CN=app.test".to_string(),
+            sans: sans.iter().map(|s| s.to_string()).collect(),
+            issuer: "CN=LocalDomain Root CA".to_string(),
+            serial: "01".to_string(),
+            not_before: 0,
+            not_after: not_after.unix_timestamp(),
+            fingerprint_sha256: String::new(),
+            key_type: "ECDSA P-256".to_string(),
+            is_ca: false,
+            signed_by_current_ca: current,
+            in_use: true,
+            name_constraints: Vec::new(),
+            custom: false,
+        }
+    }
+
+    #[test]
+    fn test_reuse_valid_cert() {
+        let now = time::OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let cert = info(&["app.test"], now + time::Duration::days(200), true);
+        assert!(is_reusable(&cert, &["app.test".to_string()], now, 30));
+    }
+
+    #[test]
+    fn test_reissue_when_expiring_or_foreign() {
+        let now = time::OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let expiring = info(&["app.test"], now + time::Duration::days(10), true);
+        assert!(!is_reusable(&expiring, &["app.test".to_string()], now, 30));
+
+        let foreign = info(&["app.test"], now + time::Duration::days(200), false);
+        assert!(!is_reusable(&foreign, &["app.test".to_string()], now, 30));
+    }
+
+    #[test]
+    fn test_reissue_when_san_missing() {
+        let now = time::OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let cert = info(&["app.test"], now + time::Duration::days(200), true);
+        let wanted = vec!["app.test".to_string(), "api.app.test".to_string()];
+        assert!(!is_reusable(&cert, &wanted, now, 30));
+    }
+
+    #[test]
+    fn test_san_list_and_types() {
+        let extra: Vec<String> = ["*.app.test", "localhost", "127.0.0.1", "::1", "APP.test"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let sans = san_list("app.test", &extra).unwrap();
+        assert_eq!(
+            sans,
+            vec!["app.test", "*.app.test", "localhost", "127.0.0.1", "::1"]
+        );
+        assert!(matches!(san_type("::1").unwrap(), SanType::IpAddress(_)));
+        assert!(matches!(
+            san_type("*.app.test").unwrap(),
+            SanType::DnsName(_)
+        ));
+        assert!(san_list("app.test", &["bad name".to_string()]).is_err());
+        assert_eq!(
+            san_list("xn--caf-dma.test", &["*.café.test".to_string()]).unwrap(),
+            vec!["xn--caf-dma.test", "*.xn--caf-dma.test"]
+        );
+    }
+
+    #[test]
+    fn test_key_algorithms() {
+        let p384 = generate_key(KeyAlgorithm::EcdsaP384).unwrap();
+        assert!(p384.is_compatible(&PKCS_ECDSA_P384_SHA384));
+        let rsa = generate_key(KeyAlgorithm::Rsa2048).unwrap();
+        assert!(rsa.is_compatible(&PKCS_RSA_SHA256));
+        let cert = CertificateParams::new(vec!["app.test".to_string()])
+            .unwrap()
+            .self_signed(&rsa)
+            .unwrap();
+        let (_, parsed) = x509_parser::parse_x509_certificate(cert.der()).unwrap();
+        match parsed.public_key().parsed().unwrap() {
+            x509_parser::public_key::PublicKey::RSA(key) => assert_eq!(key.key_size(), 2048),
+            _ => panic!("expected an RSA key"),
+        }
+    }
+}
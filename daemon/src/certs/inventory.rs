@@ -0,0 +1,375 @@
+use anyhow::{bail, Context, Result};
+use localdomain_shared::protocol::CertificateInfo;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+use x509_parser::public_key::PublicKey;
+
+use super::constraints::CaConstraints;
+use super::{ca, domain};
+use crate::{caddy, paths};
+
+/// List every certificate under CERTS_DIR, including the root and intermediate CAs.
+pub fn list_certificates(active_domains: &[String]) -> Result<Vec<CertificateInfo>> {
+    scan_certificates(
+        Path::new(paths::CERTS_DIR),
+        Path::new(ca::ca_cert_path()),
+        Some(Path::new(ca::intermediate_cert_path())),
+        active_domains,
+    )
+}
+
+/// Inspect a certificate against the CAs currently on disk.
+pub fn inspect_current(path: &Path) -> Result<CertificateInfo> {
+    inspect_certificate(
+        path,
+        Path::new(ca::ca_cert_path()),
+        Some(Path::new(ca::intermediate_cert_path())),
+    )
+}
+
+/// Delete an orphaned domain certificate and its key. The root CA and certificates
+/// Caddy still serves cannot be deleted.
+pub fn delete_certificate(name: &str) -> Result<()> {
+    domain::validate_cert_name(name)?;
+    if is_ca_file_name(name) {
+        bail!("Refusing to delete a CA certificate");
+    }
+    let (cert_path, key_path) = domain::cert_paths(name);
+    if !Path::new(&cert_path).exists() {
+        bail!("Certificate '{}' not found", name);
+    }
+    if inspect_current(Path::new(&cert_path))
+        .map(|info| info.is_ca)
+        .unwrap_or(false)
+    {
+        bail!("Refusing to delete a CA certificate");
+    }
+    if caddy::config::uses_certificate(&cert_path) {
+        bail!("Certificate for {} is still used by an HTTPS domain", name);
+    }
+
+    fs::remove_file(&cert_path).context("Failed to remove certificate")?;
+    if Path::new(&key_path).exists() {
+        fs::remove_file(&key_path).context("Failed to remove private key")?;
+    }
+    let _ = fs::remove_file(Path::new(&cert_path).with_extension("custom"));
+
+    info!("Deleted certificate for {}", name);
+    Ok(())
+}
+
+/// Whether a cert name maps onto one of the CA's own files, current or rotated out.
+/// Compared without case, as the filesystem may be case-insensitive.
+fn is_ca_file_name(name: &str) -> bool {
+    [
+        paths::CA_CERT,
+        paths::CA_KEY,
+        paths::CA_KEY_ENCRYPTED,
+        paths::INTERMEDIATE_CERT,
+        paths::INTERMEDIATE_KEY,
+        paths::CA_PREVIOUS_CERT,
+        paths::CA_PREVIOUS_KEY,
+        paths::CA_BUNDLE,
+    ]
+    .iter()
+    .filter_map(|path| Path::new(path).file_stem())
+    .any(|stem| stem.to_string_lossy().eq_ignore_ascii_case(name))
+}
+
+/// Scan `dir` for certificates. CA certs are checked against `ca_cert` (the root);
+/// leaves against `intermediate_cert` when it exists, else the root.
+pub fn scan_certificates(
+    dir: &Path,
+    ca_cert: &Path,
+    intermediate_cert: Option<&Path>,
+    active_domains: &[String],
+) -> Result<Vec<CertificateInfo>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    // DER of the current CAs, used to check which certs chain to them
+    let ca_der = read_ca_der(ca_cert);
+    let issuer_der = intermediate_cert.and_then(read_ca_der);
+
+    let mut certs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("crt") {
+            continue;
+        }
+        let pem = match fs::read(&path) {
+            Ok(p) => p,
+            Err(_) => continue,
+        };
+        match inspect_pem(
+            &path,
+            &pem,
+            ca_der.as_deref(),
+            issuer_der.as_deref(),
+            active_domains,
+        ) {
+            Ok(info) => certs.push(info),
+            Err(e) => warn!("Skipping unreadable certificate {}: {}", path.display(), e),
+        }
+    }
+
+    certs.sort_by(|a, b| b.is_ca.cmp(&a.is_ca).then_with(|| a.name.cmp(&b.name)));
+    Ok(certs)
+}
+
+/// Parse a single certificate file and check it against the given CAs.
+pub fn inspect_certificate(
+    path: &Path,
+    ca_cert: &Path,
+    intermediate_cert: Option<&Path>,
+) -> Result<CertificateInfo> {
+    let ca_der = read_ca_der(ca_cert);
+    let issuer_der = intermediate_cert.and_then(read_ca_der);
+    let pem = fs::read(path).context("Failed to read certificate")?;
+    inspect_pem(path, &pem, ca_der.as_deref(), issuer_der.as_deref(), &[])
+}
+
+fn read_ca_der(ca_cert: &Path) -> Option<Vec<u8>> {
+    let pem = fs::read(ca_cert).ok()?;
+    Pem::iter_from_buffer(&pem)
+        .next()?
+        .ok()
+        .map(|pem| pem.contents)
+}
+
+fn inspect_pem(
+    path: &Path,
+    pem: &[u8],
+    ca_der: Option<&[u8]>,
+    issuer_der: Option<&[u8]>,
+    active_domains: &[String],
+) -> Result<CertificateInfo> {
+    let pem = Pem::iter_from_buffer(pem)
+        .next()
+        .context("No PEM block found")?
+        .context("Invalid PEM")?;
+    let cert = pem
+        .parse_x509()
+        .map_err(|e| anyhow::anyhow!("Invalid certificate: {}", e))?;
+
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let key_path = path.with_extension("key");
+    let key_path = key_path
+        .exists()
+        .then(|| key_path.to_string_lossy().to_string());
+
+    let sans = subject_alt_names(&cert);
+
+    let is_ca = cert.is_ca();
+    // Leaves are issued by the intermediate; CA certs chain to the root
+    let signer_der = if is_ca { ca_der } else { issuer_der.or(ca_der) };
+    let signed_by_current_ca = match signer_der {
+        Some(der) => match x509_parser::parse_x509_certificate(der) {
+            Ok((_, signer)) => cert.verify_signature(Some(signer.public_key())).is_ok(),
+            Err(_) => false,
+        },
+        None => false,
+    };
+
+    let in_use = is_ca || active_domains.iter().any(|d| d == &name);
+    let name_constraints = CaConstraints::from_x509(&cert)
+        .ok()
+        .flatten()
+        .map(|c| c.describe())
+        .unwrap_or_default();
+
+    Ok(CertificateInfo {
+        cert_path: path.to_string_lossy().to_string(),
+        key_path,
+        subject: cert.subject().to_string(),
+        sans,
+        issuer: cert.issuer().to_string(),
+        serial: cert.raw_serial_as_string(),
+        not_before: cert.validity().not_before.timestamp(),
+        not_after: cert.validity().not_after.timestamp(),
+        fingerprint_sha256: fingerprint_sha256(&pem.contents),
+        key_type: key_type(&cert),
+        is_ca,
+        signed_by_current_ca,
+        in_use,
+        name_constraints,
+        custom: path.with_extension("custom").exists(),
+        name,
+    })
+}
+
+/// DNS and IP subject alternative names, as strings.
+pub fn subject_alt_names(cert: &x509_parser::certificate::X509Certificate) -> Vec<String> {
+    let mut sans = Vec::new();
+    if let Ok(Some(ext)) = cert.subject_alternative_name() {
+        for san in &ext.value.general_names {
+            match san {
+                GeneralName::DNSName(dns) => sans.push(dns.to_string()),
+                GeneralName::IPAddress(ip) => {
+                    if let Some(ip) = ip_from_bytes(ip) {
+                        sans.push(ip.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    sans
+}
+
+/// Colon-separated uppercase hex SHA-256 of the DER encoding.
+pub fn fingerprint_sha256(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn key_type(cert: &x509_parser::certificate::X509Certificate) -> String {
+    match cert.public_key().parsed() {
+        Ok(PublicKey::RSA(rsa)) => format!("RSA {}", rsa.key_size()),
+        Ok(PublicKey::EC(ec)) => format!("ECDSA P-{}", ec.key_size()),
+        Ok(PublicKey::Unknown(_)) | Err(_) => "Unknown".to_string(),
+        Ok(_) => cert.public_key().algorithm.algorithm.to_id_string(),
+    }
+}
+
+fn ip_from_bytes(bytes: &[u8]) -> Option<std::net::IpAddr> {
+    match bytes.len() {
+        4 => <[u8; 4]>::try_from(bytes).ok().map(std::net::IpAddr::from),
+        16 => <[u8; 16]>::try_from(bytes).ok().map(std::net::IpAddr::from),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, DnType, IsCa, KeyPair, SanType};
+
+    fn make_ca() -> (rcgen::Certificate, KeyPair) {
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "Test Root CA");
+        params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let key = KeyPair::generate().unwrap();
+        (params.self_signed(&key).unwrap(), key)
+    }
+
+    fn make_leaf(name: &str, ca: &rcgen::Certificate, ca_key: &KeyPair) -> rcgen::Certificate {
+        let mut params = CertificateParams::new(vec![name.to_string()]).unwrap();
+        params.distinguished_name.push(DnType::CommonName, name);
+        params
+            .subject_alt_names
+            .push(SanType::IpAddress("127.0.0.1".parse().unwrap()));
+        let key = KeyPair::generate().unwrap();
+        params.signed_by(&key, ca, ca_key).unwrap()
+    }
+
+    #[test]
+    fn test_scan_reports_chain_and_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let (ca, ca_key) = make_ca();
+        let ca_path = dir.path().join("localdomain-ca.crt");
+        fs::write(&ca_path, ca.pem()).unwrap();
+
+        let leaf = make_leaf("app.test", &ca, &ca_key);
+        fs::write(dir.path().join("app.test.crt"), leaf.pem()).unwrap();
+        fs::write(dir.path().join("app.test.key"), "key").unwrap();
+
+        let (other_ca, other_key) = make_ca();
+        let orphan = make_leaf("old.test", &other_ca, &other_key);
+        fs::write(dir.path().join("old.test.crt"), orphan.pem()).unwrap();
+
+        let certs =
+            scan_certificates(dir.path(), &ca_path, None, &["app.test".to_string()]).unwrap();
+        assert_eq!(certs.len(), 3);
+        assert!(certs[0].is_ca);
+        assert!(certs[0].signed_by_current_ca);
+
+        let app = certs.iter().find(|c| c.name == "app.test").unwrap();
+        assert!(app.signed_by_current_ca);
+        assert!(app.in_use);
+        assert!(app.key_path.is_some());
+        assert_eq!(app.sans, vec!["app.test", "127.0.0.1"]);
+        assert_eq!(app.key_type, "ECDSA P-256");
+        assert!(app.issuer.contains("Test Root CA"));
+        assert_eq!(app.fingerprint_sha256.len(), 32 * 3 - 1);
+
+        let old = certs.iter().find(|c| c.name == "old.test").unwrap();
+        assert!(!old.signed_by_current_ca);
+        assert!(!old.in_use);
+        assert!(old.key_path.is_none());
+    }
+
+    #[test]
+    fn test_scan_checks_leaves_against_intermediate() {
+        let dir = tempfile::tempdir().unwrap();
+        let (root, root_key) = make_ca();
+        let root_path = dir.path().join("localdomain-ca.crt");
+        fs::write(&root_path, root.pem()).unwrap();
+
+        let (intermediate, intermediate_key) =
+            ca::issue_intermediate(&root, &root_key, None, time::OffsetDateTime::now_utc())
+                .unwrap();
+        let intermediate_path = dir.path().join("localdomain-intermediate.crt");
+        fs::write(&intermediate_path, intermediate.pem()).unwrap();
+
+        // Served chain: leaf followed by the intermediate
+        let leaf = make_leaf("app.test", &intermediate, &intermediate_key);
+        fs::write(
+            dir.path().join("app.test.crt"),
+            format!("{}{}", leaf.pem(), intermediate.pem()),
+        )
+        .unwrap();
+        let legacy = make_leaf("legacy.test", &root, &root_key);
+        fs::write(dir.path().join("legacy.test.crt"), legacy.pem()).unwrap();
+
+        let certs =
+            scan_certificates(dir.path(), &root_path, Some(&intermediate_path), &[]).unwrap();
+        let find = |name: &str| certs.iter().find(|c| c.name == name).unwrap();
+
+        assert!(find("localdomain-intermediate").is_ca);
+        assert!(find("localdomain-intermediate").signed_by_current_ca);
+        assert!(find("app.test").signed_by_current_ca);
+        assert_eq!(find("app.test").sans, vec!["app.test", "127.0.0.1"]);
+        assert!(!find("legacy.test").signed_by_current_ca);
+    }
+
+    #[test]
+    fn test_ca_file_names_are_refused() {
+        for name in [
+            "localdomain-ca",
+            "LocalDomain-CA",
+            "localdomain-ca.previous",
+            "localdomain-intermediate",
+            "localdomain-bundle",
+        ] {
+            assert!(is_ca_file_name(name), "{}", name);
+            assert!(delete_certificate(name).is_err(), "{}", name);
+        }
+        assert!(!is_ca_file_name("localdomain-ca.test"));
+        assert!(!is_ca_file_name("app.test"));
+    }
+
+    #[test]
+    fn test_scan_missing_dir_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let certs = scan_certificates(&missing, &missing.join("ca.crt"), None, &[]).unwrap();
+        assert!(certs.is_empty());
+    }
+}
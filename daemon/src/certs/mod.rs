@@ -1,3 +1,13 @@
 pub mod ca;
+pub mod constraints;
+pub mod custom;
 pub mod domain;
+pub mod export;
+pub mod inventory;
+pub mod keystore;
+#[cfg(target_os = "linux")]
+pub mod nss;
+pub mod renewal;
+pub mod rotation;
+pub mod toolchains;
 pub mod trust;
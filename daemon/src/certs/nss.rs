@@ -0,0 +1,491 @@
+//! NSS databases on Linux. Firefox and Chromium keep their own trust stores and
+//! ignore the system CA bundle, so the CA is added to each user database as well.
+
+use anyhow::{bail, Context, Result};
+use localdomain_shared::protocol::{TrustStoreKind, TrustStoreStatus};
+use localdomain_shared::silent_cmd;
+use once_cell::sync::Lazy;
+use std::fs;
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::{ca, inventory};
+use crate::paths;
+
+/// Nicknames of LocalDomain CAs in NSS start with this, followed by the start of the
+/// fingerprint, so an imported or rotated root never reuses a taken nickname.
+const NICKNAME_PREFIX: &str = "LocalDomain CA ";
+
+/// The fixed nickname used before nicknames were derived from the fingerprint.
+const LEGACY_NICKNAME: &str = "LocalDomain Root CA";
+
+/// Status checks spawn certutil per database, so cache them between status polls.
+const STATUS_TTL: Duration = Duration::from_secs(30);
+
+type CachedStatus = (Instant, Vec<TrustStoreStatus>);
+
+static STATUS_CACHE: Lazy<Mutex<Option<CachedStatus>>> = Lazy::new(|| Mutex::new(None));
+
+const FIREFOX_PROFILE_ROOTS: &[(&str, &str)] = &[
+    (".mozilla/firefox", "Firefox"),
+    ("snap/firefox/common/.mozilla/firefox", "Firefox (snap)"),
+    (
+        ".var/app/org.mozilla.firefox/.mozilla/firefox",
+        "Firefox (Flatpak)",
+    ),
+];
+
+const CHROMIUM_DBS: &[(&str, &str)] = &[
+    (".pki/nssdb", "Chrome/Chromium"),
+    ("snap/chromium/current/.pki/nssdb", "Chromium (snap)"),
+    (
+        ".var/app/org.chromium.Chromium/.pki/nssdb",
+        "Chromium (Flatpak)",
+    ),
+    (".var/app/com.google.Chrome/.pki/nssdb", "Chrome (Flatpak)"),
+];
+
+/// A desktop user whose browser databases should trust the CA.
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NssDb {
+    pub kind: TrustStoreKind,
+    pub label: String,
+    pub dir: PathBuf,
+}
+
+/// Users with an active login session (systemd-logind creates /run/user/<uid>).
+pub fn logged_in_users() -> Vec<User> {
+    let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+    let Ok(entries) = fs::read_dir("/run/user") else {
+        return Vec::new();
+    };
+    let mut uids: Vec<u32> = entries
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.parse().ok())
+        // Skip system accounts such as the display manager's greeter
+        .filter(|uid| (1000..65534).contains(uid))
+        .collect();
+    uids.sort_unstable();
+    uids.into_iter()
+        .filter_map(|uid| find_user(&passwd, uid))
+        .collect()
+}
+
+fn find_user(passwd: &str, uid: u32) -> Option<User> {
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() < 7 || fields[2].parse::<u32>().ok()? != uid {
+            return None;
+        }
+        Some(User {
+            name: fields[0].to_string(),
+            uid,
+            gid: fields[3].parse().ok()?,
+            home: PathBuf::from(fields[5]),
+        })
+    })
+}
+
+/// Find the SQL-format NSS databases under a home directory: one per Firefox
+/// profile plus the shared Chromium/Chrome database, including snap and Flatpak installs.
+pub fn discover_databases(home: &Path) -> Vec<NssDb> {
+    let mut dbs = Vec::new();
+
+    for (root, app) in FIREFOX_PROFILE_ROOTS {
+        let Ok(entries) = fs::read_dir(home.join(root)) else {
+            continue;
+        };
+        let mut profiles: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| is_nss_db(p))
+            .collect();
+        profiles.sort();
+        for dir in profiles {
+            let name = dir.file_name().unwrap_or_default().to_string_lossy();
+            // Profile directories are named "<salt>.<profile name>"
+            let profile = name.split_once('.').map(|(_, n)| n).unwrap_or(&name);
+            dbs.push(NssDb {
+                kind: TrustStoreKind::Firefox,
+                label: format!("{} {}", app, profile),
+                dir: dir.clone(),
+            });
+        }
+    }
+
+    for (path, app) in CHROMIUM_DBS {
+        let dir = home.join(path);
+        if is_nss_db(&dir) {
+            dbs.push(NssDb {
+                kind: TrustStoreKind::Chromium,
+                label: app.to_string(),
+                dir,
+            });
+        }
+    }
+
+    dbs
+}
+
+fn is_nss_db(dir: &Path) -> bool {
+    dir.join("cert9.db").is_file()
+}
+
+/// Make every discovered database trust the current CA, and the previous CA
+/// during a rotation overlap when `include_previous` is set.
+pub fn install_all(include_previous: bool) -> Vec<TrustStoreStatus> {
+    invalidate_cache();
+    let mut certs = vec![fs::read_to_string(ca::ca_cert_path()).unwrap_or_default()];
+    if include_previous && ca::previous_ca_exists() {
+        certs.push(fs::read_to_string(paths::CA_PREVIOUS_CERT).unwrap_or_default());
+    }
+
+    for_each_db(|user, db| {
+        delete_all(user, db)?;
+        for pem in &certs {
+            add(user, db, pem)?;
+        }
+        info!("CA trusted in {} ({})", db.label, db.dir.display());
+        Ok(true)
+    })
+}
+
+/// Remove every LocalDomain CA from the discovered databases.
+pub fn remove_all() -> Vec<TrustStoreStatus> {
+    invalidate_cache();
+    for_each_db(|user, db| {
+        delete_all(user, db)?;
+        info!("CA removed from {} ({})", db.label, db.dir.display());
+        Ok(false)
+    })
+}
+
+/// Whether each discovered database trusts the current CA.
+pub fn status_all() -> Vec<TrustStoreStatus> {
+    let mut cache = STATUS_CACHE.lock().unwrap();
+    if let Some((at, statuses)) = cache.as_ref() {
+        if at.elapsed() < STATUS_TTL {
+            return statuses.clone();
+        }
+    }
+
+    let ca_der = fs::read_to_string(ca::ca_cert_path())
+        .ok()
+        .and_then(|pem| pem::parse(pem).ok())
+        .map(|p| p.contents().to_vec());
+    let statuses = for_each_db(|user, db| match &ca_der {
+        Some(der) => contains_cert(user, db, der),
+        None => Ok(false),
+    });
+    *cache = Some((Instant::now(), statuses.clone()));
+    statuses
+}
+
+fn invalidate_cache() {
+    *STATUS_CACHE.lock().unwrap() = None;
+}
+
+fn for_each_db(mut f: impl FnMut(&User, &NssDb) -> Result<bool>) -> Vec<TrustStoreStatus> {
+    let mut statuses = Vec::new();
+    for user in logged_in_users() {
+        for db in discover_databases(&user.home) {
+            let (trusted, error) = match f(&user, &db) {
+                Ok(trusted) => (trusted, None),
+                Err(e) => {
+                    warn!("NSS database {}: {}", db.dir.display(), e);
+                    (false, Some(e.to_string()))
+                }
+            };
+            statuses.push(TrustStoreStatus {
+                kind: db.kind.clone(),
+                label: db.label.clone(),
+                path: db.dir.to_string_lossy().to_string(),
+                trusted,
+                error,
+            });
+        }
+    }
+    statuses
+}
+
+/// Run certutil as the database owner so the files it rewrites stay theirs.
+fn certutil(user: &User, db: &NssDb) -> Command {
+    let mut cmd = silent_cmd("certutil");
+    if unsafe { libc::geteuid() } == 0 {
+        cmd.uid(user.uid).gid(user.gid);
+    }
+    cmd.env("HOME", &user.home)
+        .arg("-d")
+        .arg(format!("sql:{}", db.dir.display()));
+    cmd
+}
+
+fn run(mut cmd: Command, stdin: Option<&str>) -> Result<Output> {
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = cmd.spawn().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            anyhow::anyhow!(
+                "certutil not found. Install libnss3-tools (Debian/Ubuntu) or nss-tools (Fedora/openSUSE/Arch)."
+            )
+        } else {
+            anyhow::anyhow!("Failed to run certutil: {}", e)
+        }
+    })?;
+    if let Some(input) = stdin {
+        child
+            .stdin
+            .take()
+            .context("Failed to open certutil stdin")?
+            .write_all(input.as_bytes())?;
+    }
+    drop(child.stdin.take());
+    Ok(child.wait_with_output()?)
+}
+
+fn nickname(ca_der: &[u8]) -> String {
+    let fingerprint = inventory::fingerprint_sha256(ca_der).replace(':', "");
+    format!("{}{}", NICKNAME_PREFIX, &fingerprint[..16])
+}
+
+fn is_ours(nickname: &str) -> bool {
+    nickname.starts_with(NICKNAME_PREFIX) || nickname == LEGACY_NICKNAME
+}
+
+/// Nicknames in `certutil -L` output, which lists each with its trust flags.
+fn parse_nicknames(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let (nickname, flags) = line.trim_end().rsplit_once(char::is_whitespace)?;
+            let nickname = nickname.trim();
+            // Trust flags look like "C,," or "CT,C,C"; the header has none
+            (!nickname.is_empty() && flags.matches(',').count() == 2 && !flags.contains('/'))
+                .then(|| nickname.to_string())
+        })
+        .collect()
+}
+
+fn our_nicknames(user: &User, db: &NssDb) -> Result<Vec<String>> {
+    let mut cmd = certutil(user, db);
+    cmd.arg("-L");
+    let output = run(cmd, None)?;
+    if !output.status.success() {
+        bail!(
+            "certutil -L failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let mut nicknames = parse_nicknames(&String::from_utf8_lossy(&output.stdout));
+    nicknames.retain(|nickname| is_ours(nickname));
+    nicknames.dedup();
+    Ok(nicknames)
+}
+
+fn add(user: &User, db: &NssDb, ca_pem: &str) -> Result<()> {
+    let der = pem::parse(ca_pem).context("Invalid CA certificate PEM")?;
+    let mut cmd = certutil(user, db);
+    // Without -i certutil reads the certificate from stdin, so the user never
+    // needs read access to the daemon's CA directory.
+    cmd.args(["-A", "-n", &nickname(der.contents()), "-t", "C,,", "-a"]);
+    let output = run(cmd, Some(ca_pem))?;
+    if !output.status.success() {
+        bail!(
+            "certutil -A failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn delete_all(user: &User, db: &NssDb) -> Result<()> {
+    for nickname in our_nicknames(user, db)? {
+        // Each -D removes one certificate with the nickname; stop once none are left
+        for _ in 0..10 {
+            let mut cmd = certutil(user, db);
+            cmd.args(["-D", "-n", &nickname]);
+            if !run(cmd, None)?.status.success() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the database holds `ca_der`. NSS files a certificate under the
+/// nickname of an earlier one with the same subject, so every LocalDomain
+/// nickname is checked by fingerprint.
+fn contains_cert(user: &User, db: &NssDb, ca_der: &[u8]) -> Result<bool> {
+    let fingerprint = inventory::fingerprint_sha256(ca_der);
+    for nickname in our_nicknames(user, db)? {
+        let mut cmd = certutil(user, db);
+        cmd.args(["-L", "-n", &nickname, "-a"]);
+        let output = run(cmd, None)?;
+        if !output.status.success() {
+            continue;
+        }
+        let listed = pem::parse_many(&output.stdout).unwrap_or_default();
+        if listed
+            .iter()
+            .any(|p| inventory::fingerprint_sha256(p.contents()) == fingerprint)
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_user_in_passwd() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n\
+                      gdm:x:120:125:Gnome Display Manager:/var/lib/gdm3:/bin/false\n\
+                      alex:x:1000:1000:Alex,,,:/home/alex:/bin/zsh\n";
+        let user = find_user(passwd, 1000).unwrap();
+        assert_eq!(user.name, "alex");
+        assert_eq!(user.gid, 1000);
+        assert_eq!(user.home, PathBuf::from("/home/alex"));
+        assert!(find_user(passwd, 1001).is_none());
+    }
+
+    #[test]
+    fn test_discover_browser_databases() {
+        let home = tempfile::tempdir().unwrap();
+        let db = |rel: &str| {
+            let dir = home.path().join(rel);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("cert9.db"), b"").unwrap();
+        };
+        db(".mozilla/firefox/abc123.default-release");
+        db("snap/firefox/common/.mozilla/firefox/xyz789.default");
+        db(".var/app/org.mozilla.firefox/.mozilla/firefox/q1w2e3.work");
+        db(".pki/nssdb");
+        db(".var/app/org.chromium.Chromium/.pki/nssdb");
+        // Old dbm-format profiles and stray directories are ignored
+        fs::create_dir_all(home.path().join(".mozilla/firefox/old.legacy")).unwrap();
+        fs::write(
+            home.path().join(".mozilla/firefox/old.legacy/cert8.db"),
+            b"",
+        )
+        .unwrap();
+        fs::create_dir_all(home.path().join(".mozilla/firefox/Crash Reports")).unwrap();
+
+        let labels: Vec<(TrustStoreKind, String)> = discover_databases(home.path())
+            .into_iter()
+            .map(|db| (db.kind, db.label))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                (
+                    TrustStoreKind::Firefox,
+                    "Firefox default-release".to_string()
+                ),
+                (
+                    TrustStoreKind::Firefox,
+                    "Firefox (snap) default".to_string()
+                ),
+                (
+                    TrustStoreKind::Firefox,
+                    "Firefox (Flatpak) work".to_string()
+                ),
+                (TrustStoreKind::Chromium, "Chrome/Chromium".to_string()),
+                (TrustStoreKind::Chromium, "Chromium (Flatpak)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_install_and_verify_with_certutil() {
+        if silent_cmd("certutil").arg("-H").output().is_err() {
+            return;
+        }
+        let home = tempfile::tempdir().unwrap();
+        let dir = home.path().join(".pki/nssdb");
+        fs::create_dir_all(&dir).unwrap();
+        let created = silent_cmd("certutil")
+            .arg("-d")
+            .arg(format!("sql:{}", dir.display()))
+            .args(["-N", "--empty-password"])
+            .output()
+            .unwrap();
+        assert!(created.status.success());
+
+        let user = User {
+            name: "test".to_string(),
+            uid: unsafe { libc::geteuid() },
+            gid: unsafe { libc::getegid() },
+            home: home.path().to_path_buf(),
+        };
+        let db = discover_databases(home.path()).remove(0);
+
+        let ca = |cn: &str| {
+            let mut params = rcgen::CertificateParams::default();
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, cn);
+            params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            params
+                .self_signed(&rcgen::KeyPair::generate().unwrap())
+                .unwrap()
+        };
+        let current = ca("LocalDomain Root CA");
+        // A rotated-out root with the same subject, and an imported team CA
+        let previous = ca("LocalDomain Root CA");
+        let team = ca("Acme Dev Team CA");
+
+        assert!(!contains_cert(&user, &db, current.der()).unwrap());
+        for cert in [&current, &previous, &team] {
+            add(&user, &db, &cert.pem()).unwrap();
+        }
+        for cert in [&current, &previous, &team] {
+            assert!(contains_cert(&user, &db, cert.der()).unwrap());
+        }
+        delete_all(&user, &db).unwrap();
+        for cert in [&current, &previous, &team] {
+            assert!(!contains_cert(&user, &db, cert.der()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_parse_certutil_listing() {
+        let listing = "\n\
+            Certificate Nickname                                         Trust Attributes\n\
+                                                                         SSL,S/MIME,JAR/XPI\n\
+            \n\
+            LocalDomain CA 3F9A1C2B4D5E6F70                              C,,  \n\
+            LocalDomain Root CA                                          C,,  \n\
+            Some Other Root                                              CT,C,C\n";
+        let nicknames = parse_nicknames(listing);
+        assert_eq!(
+            nicknames,
+            vec![
+                "LocalDomain CA 3F9A1C2B4D5E6F70",
+                "LocalDomain Root CA",
+                "Some Other Root"
+            ]
+        );
+        assert!(is_ours(&nicknames[0]));
+        assert!(is_ours(&nicknames[1]));
+        assert!(!is_ours(&nicknames[2]));
+        assert!(nickname(b"der").starts_with(NICKNAME_PREFIX));
+        assert_eq!(nickname(b"der").len(), NICKNAME_PREFIX.len() + 16);
+    }
+}
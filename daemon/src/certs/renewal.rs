@@ -0,0 +1,244 @@
+use anyhow::Result;
+use localdomain_shared::protocol::{CertRenewalEvent, CertificateInfo};
+use time::OffsetDateTime;
+use tracing::{error, info, warn};
+
+use super::{ca, domain, inventory};
+use crate::{caddy, events, settings};
+
+/// Name reported in renewal events for the intermediate CA.
+const INTERMEDIATE_NAME: &str = "localdomain-intermediate";
+
+/// How often the background scheduler checks for expiring certificates.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Source of the current time, injectable so expiry checks are deterministic in tests.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> OffsetDateTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// Leaf certificates issued by the current CA that expire within `window_days`.
+/// Custom certificates are never renewed here.
+pub fn due_for_renewal(
+    certs: &[CertificateInfo],
+    now: OffsetDateTime,
+    window_days: u32,
+) -> Vec<&CertificateInfo> {
+    let deadline = (now + time::Duration::days(window_days as i64)).unix_timestamp();
+    certs
+        .iter()
+        .filter(|c| !c.is_ca && !c.custom && c.signed_by_current_ca && c.not_after <= deadline)
+        .collect()
+}
+
+/// Reissue every certificate due for renewal and reload Caddy if any changed.
+pub fn renew_due_certificates(clock: &dyn Clock) -> Result<Vec<CertRenewalEvent>> {
+    let window_days = settings::get().cert_renewal_window_days;
+    if window_days == 0 || !ca::ca_exists() {
+        return Ok(vec![]);
+    }
+
+    let certs = inventory::list_certificates(&[])?;
+    let now = clock.now();
+    let deadline = (now + time::Duration::days(window_days as i64)).unix_timestamp();
+    for cert in certs.iter().filter(|c| c.custom && c.not_after <= deadline) {
+        warn!(
+            "Custom certificate for {} expires soon; replace it manually",
+            cert.name
+        );
+    }
+
+    let mut events = Vec::new();
+    // Renew the intermediate first so leaves are reissued under the new one
+    let to_renew = match ca::ensure_intermediate(now, window_days) {
+        Ok(true) => {
+            info!("Renewed intermediate CA");
+            issued_by_current(&certs)
+        }
+        Ok(false) => due_for_renewal(&certs, now, window_days),
+        Err(e) => {
+            warn!("Failed to renew intermediate CA: {}", e);
+            let old_not_after = ca::ca_status().intermediate_not_after.unwrap_or_default();
+            events.push(CertRenewalEvent {
+                name: INTERMEDIATE_NAME.to_string(),
+                old_not_after,
+                new_not_after: None,
+                renewed_at: now.unix_timestamp(),
+                error: Some(e.to_string()),
+            });
+            due_for_renewal(&certs, now, window_days)
+        }
+    };
+    events.extend(reissue(&to_renew, now)?);
+    events::push(|p| &mut p.renewals, events.iter().cloned());
+    Ok(events)
+}
+
+/// Issue a new intermediate with a root key the daemon cannot unlock by itself,
+/// then move every leaf over to it.
+pub fn renew_intermediate(
+    root_key_pem: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<Vec<CertRenewalEvent>> {
+    let certs = inventory::list_certificates(&[])?;
+    ca::renew_intermediate(root_key_pem, passphrase)?;
+    let events = reissue(&issued_by_current(&certs), OffsetDateTime::now_utc())?;
+    events::push(|p| &mut p.renewals, events.iter().cloned());
+    Ok(events)
+}
+
+fn issued_by_current(certs: &[CertificateInfo]) -> Vec<&CertificateInfo> {
+    certs
+        .iter()
+        .filter(|c| !c.is_ca && !c.custom && c.signed_by_current_ca)
+        .collect()
+}
+
+fn reissue(certs: &[&CertificateInfo], now: OffsetDateTime) -> Result<Vec<CertRenewalEvent>> {
+    let mut events = Vec::new();
+    for cert in certs {
+        let event = match domain::reissue_domain_cert(cert) {
+            Ok(_) => {
+                info!("Renewed certificate for {}", cert.name);
+                CertRenewalEvent {
+                    name: cert.name.clone(),
+                    old_not_after: cert.not_after,
+                    new_not_after: renewed_not_after(&cert.name),
+                    renewed_at: now.unix_timestamp(),
+                    error: None,
+                }
+            }
+            Err(e) => {
+                warn!("Failed to renew certificate for {}: {}", cert.name, e);
+                CertRenewalEvent {
+                    name: cert.name.clone(),
+                    old_not_after: cert.not_after,
+                    new_not_after: None,
+                    renewed_at: now.unix_timestamp(),
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        events.push(event);
+    }
+
+    // Caddy reads cert files at startup, so restart it to pick up the new certs
+    if events.iter().any(|e| e.error.is_none()) && caddy::process::is_caddy_running() {
+        caddy::process::reload_caddy()?;
+    }
+    Ok(events)
+}
+
+fn renewed_not_after(name: &str) -> Option<i64> {
+    let certs = inventory::list_certificates(&[]).ok()?;
+    certs.into_iter().find(|c| c.name == name).map(|c| c.not_after)
+}
+
+/// Return and clear renewal events recorded since the last call.
+pub fn take_renewal_events() -> Vec<CertRenewalEvent> {
+    events::take(|p| &mut p.renewals)
+}
+
+/// Background task: check expiry (and CA overlap windows) at startup and then daily.
+pub async fn run_scheduler() {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        match tokio::task::spawn_blocking(|| super::rotation::retire_if_due(&SystemClock)).await {
+            Ok(Ok(true)) => info!("Previous CA overlap window ended; retired it"),
+            Ok(Ok(false)) => {}
+            Ok(Err(e)) => error!("Failed to retire previous CA: {}", e),
+            Err(e) => error!("CA retirement task panicked: {}", e),
+        }
+        match tokio::task::spawn_blocking(|| renew_due_certificates(&SystemClock)).await {
+            Ok(Ok(events)) if !events.is_empty() => {
+                info!("Certificate renewal check renewed {} certs", events.len())
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => error!("Certificate renewal check failed: {}", e),
+            Err(e) => error!("Certificate renewal task panicked: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedClock(OffsetDateTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> OffsetDateTime {
+            self.0
+        }
+    }
+
+    fn cert(name: &str, not_after: OffsetDateTime, is_ca: bool, current: bool) -> CertificateInfo {
+        CertificateInfo {
+            name: name.to_string(),
+            cert_path: format!("/certs/{}.crt", name),
+            key_path: None,
+            subject: format!("CN={}", name),
+            sans: vec![name.to_string()],
+            issuer: "CN=LocalDomain Root CA".to_string(),
+            serial: "01".to_string(),
+            not_before: 0,
+            not_after: not_after.unix_timestamp(),
+            fingerprint_sha256: String::new(),
+            key_type: "ECDSA P-256".to_string(),
+            is_ca,
+            signed_by_current_ca: current,
+            in_use: true,
+            name_constraints: Vec::new(),
+            custom: false,
+        }
+    }
+
+    #[test]
+    fn test_due_for_renewal_uses_window() {
+        let clock = FixedClock(OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap());
+        let now = clock.now();
+        let certs = vec![
+            cert("soon.test", now + time::Duration::days(10), false, true),
+            cert("later.test", now + time::Duration::days(90), false, true),
+            cert("expired.test", now - time::Duration::days(1), false, true),
+            cert("ca", now + time::Duration::days(5), true, true),
+            cert("foreign.test", now + time::Duration::days(5), false, false),
+        ];
+
+        let due: Vec<_> = due_for_renewal(&certs, clock.now(), 30)
+            .into_iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(due, vec!["soon.test", "expired.test"]);
+
+        let due = due_for_renewal(&certs, clock.now(), 120);
+        assert_eq!(due.len(), 3);
+    }
+
+    #[test]
+    fn test_custom_certs_are_not_renewed() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let mut custom = cert("corp.test", now + time::Duration::days(1), false, true);
+        custom.custom = true;
+        let certs = vec![custom];
+        assert!(due_for_renewal(&certs, now, 30).is_empty());
+        assert!(issued_by_current(&certs).is_empty());
+    }
+
+    #[test]
+    fn test_due_for_renewal_boundary() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let certs = vec![cert("edge.test", now + time::Duration::days(30), false, true)];
+        assert_eq!(due_for_renewal(&certs, now, 30).len(), 1);
+        assert!(due_for_renewal(&certs, now, 29).is_empty());
+    }
+}
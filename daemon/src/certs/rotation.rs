@@ -0,0 +1,563 @@
+use anyhow::{bail, Context, Result};
+use localdomain_shared::protocol::{CertificateInfo, ImportCaResult, RotateCaResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use super::constraints::CaConstraints;
+use super::renewal::Clock;
+use super::{ca, domain, inventory, toolchains, trust};
+use crate::{caddy, paths, settings};
+
+/// Persisted while a previous CA is kept trusted during the overlap window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RotationState {
+    previous_fingerprint: String,
+    rotated_at: i64,
+    retire_at: i64,
+}
+
+/// The files of the current and the rotated-out CA.
+struct CaFiles {
+    dir: PathBuf,
+    cert: PathBuf,
+    key: PathBuf,
+    key_encrypted: PathBuf,
+    intermediate_cert: PathBuf,
+    previous_cert: PathBuf,
+    previous_key: PathBuf,
+    state: PathBuf,
+}
+
+impl CaFiles {
+    fn system() -> Self {
+        Self {
+            dir: PathBuf::from(paths::CERTS_DIR),
+            cert: PathBuf::from(paths::CA_CERT),
+            key: PathBuf::from(paths::CA_KEY),
+            key_encrypted: PathBuf::from(paths::CA_KEY_ENCRYPTED),
+            intermediate_cert: PathBuf::from(paths::INTERMEDIATE_CERT),
+            previous_cert: PathBuf::from(paths::CA_PREVIOUS_CERT),
+            previous_key: PathBuf::from(paths::CA_PREVIOUS_KEY),
+            state: PathBuf::from(paths::CA_ROTATION_STATE),
+        }
+    }
+}
+
+/// What a rotation does outside the CA files: issuing leaves, trust stores and
+/// the services that read the CA.
+trait CaHost {
+    fn reissue(&self, leaf: &CertificateInfo) -> Result<()>;
+    fn is_trusted(&self) -> bool;
+    fn trust_current(&self) -> Result<()>;
+    fn untrust_previous(&self) -> Result<()>;
+    /// Called whenever the set of CA files changes
+    fn refresh_toolchains(&self);
+    fn reload_caddy(&self) -> Result<()>;
+}
+
+/// The daemon's own trust stores, toolchains and Caddy.
+struct SystemHost;
+
+impl CaHost for SystemHost {
+    fn reissue(&self, leaf: &CertificateInfo) -> Result<()> {
+        domain::reissue_domain_cert(leaf).map(|_| ())
+    }
+
+    fn is_trusted(&self) -> bool {
+        trust::verify_ca_trust()
+    }
+
+    fn trust_current(&self) -> Result<()> {
+        trust::install_ca_trust()
+    }
+
+    fn untrust_previous(&self) -> Result<()> {
+        trust::remove_previous_ca_trust()
+    }
+
+    fn refresh_toolchains(&self) {
+        toolchains::refresh();
+    }
+
+    fn reload_caddy(&self) -> Result<()> {
+        if caddy::process::is_caddy_running() {
+            caddy::process::reload_caddy()?;
+        }
+        Ok(())
+    }
+}
+
+fn read_state(path: &Path) -> Option<RotationState> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Replace the root CA, reissue every leaf it signed, and keep the old CA
+/// trusted for `overlap_days` so already-served certs keep validating.
+pub fn rotate_ca(overlap_days: u32, clock: &dyn Clock) -> Result<RotateCaResult> {
+    if !ca::ca_exists() {
+        bail!("CA certificate does not exist. Generate it first.");
+    }
+    let constraints = CaConstraints::from_settings(&settings::get())?;
+    replace_ca(
+        &CaFiles::system(),
+        &SystemHost,
+        overlap_days,
+        clock,
+        constraints.as_ref(),
+        &ca::generate_ca,
+    )
+}
+
+/// Switch issuance to an existing root CA (e.g. mkcert's). When a CA is already in
+/// use, this goes through the same overlap and reissue steps as a rotation.
+pub fn import_ca(
+    cert_pem: &str,
+    key_pem: &str,
+    passphrase: Option<&str>,
+    overlap_days: u32,
+    clock: &dyn Clock,
+) -> Result<ImportCaResult> {
+    let imported = ca::validate_import(cert_pem, key_pem, passphrase, clock.now())?;
+    let install = || ca::install_ca(&imported);
+
+    let rotation = if ca::ca_exists() {
+        let current = inventory::inspect_current(Path::new(ca::ca_cert_path()))?;
+        if current.fingerprint_sha256 == imported.fingerprint {
+            bail!("This CA is already in use");
+        }
+        Some(replace_ca(
+            &CaFiles::system(),
+            &SystemHost,
+            overlap_days,
+            clock,
+            imported.constraints.as_ref(),
+            &install,
+        )?)
+    } else {
+        install()?;
+        toolchains::refresh();
+        None
+    };
+
+    Ok(ImportCaResult {
+        subject: imported.subject.clone(),
+        fingerprint: imported.fingerprint.clone(),
+        rotation,
+    })
+}
+
+/// Move the current CA aside, install a new one with `install`, and reissue the
+/// leaves the old CA signed. `constraints` are those of the incoming CA.
+fn replace_ca(
+    files: &CaFiles,
+    host: &dyn CaHost,
+    overlap_days: u32,
+    clock: &dyn Clock,
+    constraints: Option<&CaConstraints>,
+    install: &dyn Fn() -> Result<()>,
+) -> Result<RotateCaResult> {
+    if files.previous_cert.exists() {
+        bail!("A CA rotation is already in progress. Retire the previous CA first.");
+    }
+
+    // Leaves signed by the outgoing CA are the ones to reissue
+    let certs =
+        inventory::scan_certificates(&files.dir, &files.cert, Some(&files.intermediate_cert), &[])?;
+    let previous = certs
+        .iter()
+        .find(|c| Path::new(&c.cert_path) == files.cert)
+        .context("Failed to read current CA certificate")?;
+    let previous_fingerprint = previous.fingerprint_sha256.clone();
+    let leaves: Vec<&CertificateInfo> = certs
+        .iter()
+        .filter(|c| !c.is_ca && !c.custom && c.signed_by_current_ca)
+        .collect();
+    // A constrained replacement CA must still cover every leaf it will reissue
+    if let Some(constraints) = constraints {
+        let outside: Vec<&str> = leaves
+            .iter()
+            .flat_map(|c| c.sans.iter())
+            .filter(|san| !constraints.permits(san))
+            .map(|san| san.as_str())
+            .collect();
+        if !outside.is_empty() {
+            bail!(
+                "The new CA would not cover {}. Add their suffixes to the permitted list before rotating.",
+                outside.join(", ")
+            );
+        }
+    }
+    let was_trusted = host.is_trusted();
+
+    // The root key may be plaintext, encrypted, or already offline
+    let moves: Vec<(&Path, &Path)> = [
+        (&files.cert, &files.previous_cert),
+        (&files.key, &files.previous_key),
+        (&files.key_encrypted, &files.previous_key),
+    ]
+    .into_iter()
+    .map(|(from, to)| (from.as_path(), to.as_path()))
+    .filter(|(from, _)| from.exists())
+    .collect();
+    for (from, to) in &moves {
+        fs::rename(from, to).with_context(|| format!("Failed to move {} aside", from.display()))?;
+    }
+    if let Err(e) = install() {
+        // Put the old CA back so issuance keeps working
+        for (from, to) in &moves {
+            let _ = fs::rename(to, from);
+        }
+        return Err(e.context("Failed to install new CA"));
+    }
+
+    let new_fingerprint =
+        inventory::inspect_certificate(&files.cert, &files.cert, None)?.fingerprint_sha256;
+
+    let mut reissued = Vec::new();
+    let mut failed = Vec::new();
+    for leaf in leaves {
+        match host.reissue(leaf) {
+            Ok(()) => reissued.push(leaf.name.clone()),
+            Err(e) => {
+                warn!("Failed to reissue certificate for {}: {}", leaf.name, e);
+                failed.push(leaf.name.clone());
+            }
+        }
+    }
+
+    let trust_updated = if was_trusted {
+        match host.trust_current() {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Could not trust new CA from daemon: {}", e);
+                false
+            }
+        }
+    } else {
+        true
+    };
+    host.refresh_toolchains();
+
+    let now = clock.now().unix_timestamp();
+    let state = RotationState {
+        previous_fingerprint: previous_fingerprint.clone(),
+        rotated_at: now,
+        retire_at: now + overlap_days as i64 * 24 * 60 * 60,
+    };
+    fs::write(&files.state, serde_json::to_string_pretty(&state)?)
+        .context("Failed to write CA rotation state")?;
+
+    host.reload_caddy()?;
+
+    info!(
+        "Rotated root CA ({} certs reissued, {} failed)",
+        reissued.len(),
+        failed.len()
+    );
+
+    let retire_at = if overlap_days == 0 {
+        retire_previous(files, host)?;
+        None
+    } else {
+        Some(state.retire_at)
+    };
+
+    Ok(RotateCaResult {
+        new_fingerprint,
+        previous_fingerprint,
+        reissued,
+        failed,
+        trust_updated,
+        retire_at,
+    })
+}
+
+/// Remove the previous CA from trust stores and delete its files.
+pub fn retire_previous_ca() -> Result<()> {
+    retire_previous(&CaFiles::system(), &SystemHost)
+}
+
+fn retire_previous(files: &CaFiles, host: &dyn CaHost) -> Result<()> {
+    if !files.previous_cert.exists() {
+        return Ok(());
+    }
+    if let Err(e) = host.untrust_previous() {
+        warn!("Failed to remove previous CA trust: {}", e);
+    }
+    fs::remove_file(&files.previous_cert).context("Failed to remove previous CA certificate")?;
+    if files.previous_key.exists() {
+        fs::remove_file(&files.previous_key).context("Failed to remove previous CA key")?;
+    }
+    let _ = fs::remove_file(&files.state);
+    host.refresh_toolchains();
+    info!("Retired previous root CA");
+    Ok(())
+}
+
+/// Retire the previous CA once its overlap window has passed.
+pub fn retire_if_due(clock: &dyn Clock) -> Result<bool> {
+    retire_previous_if_due(&CaFiles::system(), &SystemHost, clock)
+}
+
+fn retire_previous_if_due(files: &CaFiles, host: &dyn CaHost, clock: &dyn Clock) -> Result<bool> {
+    if !files.previous_cert.exists() {
+        return Ok(false);
+    }
+    // Without state we cannot know the deadline; retire rather than keep it forever
+    let due = read_state(&files.state)
+        .map(|s| is_retire_due(&s, clock.now().unix_timestamp()))
+        .unwrap_or(true);
+    if due {
+        retire_previous(files, host)?;
+    }
+    Ok(due)
+}
+
+fn is_retire_due(state: &RotationState, now: i64) -> bool {
+    now >= state.retire_at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, DnType, IsCa, KeyPair};
+    use std::cell::RefCell;
+    use time::OffsetDateTime;
+
+    const DAY: i64 = 86_400;
+
+    struct FixedClock(OffsetDateTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> OffsetDateTime {
+            self.0
+        }
+    }
+
+    fn files_in(dir: &Path) -> CaFiles {
+        let file = |path: &str| dir.join(Path::new(path).file_name().unwrap());
+        CaFiles {
+            dir: dir.to_path_buf(),
+            cert: file(paths::CA_CERT),
+            key: file(paths::CA_KEY),
+            key_encrypted: file(paths::CA_KEY_ENCRYPTED),
+            intermediate_cert: file(paths::INTERMEDIATE_CERT),
+            previous_cert: file(paths::CA_PREVIOUS_CERT),
+            previous_key: file(paths::CA_PREVIOUS_KEY),
+            state: file(paths::CA_ROTATION_STATE),
+        }
+    }
+
+    fn fingerprint(path: &Path) -> String {
+        inventory::inspect_certificate(path, path, None)
+            .unwrap()
+            .fingerprint_sha256
+    }
+
+    /// Write a new self-signed root as the current CA.
+    fn write_root(files: &CaFiles, name: &str) -> Result<()> {
+        let mut params = CertificateParams::default();
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let key = KeyPair::generate()?;
+        fs::write(&files.cert, params.self_signed(&key)?.pem())?;
+        fs::write(&files.key, key.serialize_pem())?;
+        Ok(())
+    }
+
+    fn write_leaf(path: &Path, name: &str, ca_cert: &Path, ca_key: &Path) -> Result<()> {
+        let ca_key = KeyPair::from_pem(&fs::read_to_string(ca_key)?)?;
+        let ca = CertificateParams::from_ca_cert_pem(&fs::read_to_string(ca_cert)?)?
+            .self_signed(&ca_key)?;
+        let mut params = CertificateParams::new(vec![name.to_string()])?;
+        params.distinguished_name.push(DnType::CommonName, name);
+        let key = KeyPair::generate()?;
+        fs::write(path, params.signed_by(&key, &ca, &ca_key)?.pem())?;
+        Ok(())
+    }
+
+    /// Issues from the CA in `files` and tracks trusted CAs by fingerprint.
+    struct TestHost<'a> {
+        files: &'a CaFiles,
+        trusted: RefCell<Vec<String>>,
+    }
+
+    impl CaHost for TestHost<'_> {
+        fn reissue(&self, leaf: &CertificateInfo) -> Result<()> {
+            write_leaf(
+                Path::new(&leaf.cert_path),
+                &leaf.name,
+                &self.files.cert,
+                &self.files.key,
+            )
+        }
+
+        fn is_trusted(&self) -> bool {
+            self.trusted
+                .borrow()
+                .contains(&fingerprint(&self.files.cert))
+        }
+
+        fn trust_current(&self) -> Result<()> {
+            self.trusted
+                .borrow_mut()
+                .push(fingerprint(&self.files.cert));
+            Ok(())
+        }
+
+        fn untrust_previous(&self) -> Result<()> {
+            let previous = fingerprint(&self.files.previous_cert);
+            self.trusted.borrow_mut().retain(|f| *f != previous);
+            Ok(())
+        }
+
+        fn refresh_toolchains(&self) {}
+
+        fn reload_caddy(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A trusted CA in `dir` that has signed app.test, plus other.test from a foreign CA.
+    fn setup(dir: &Path) -> (CaFiles, Vec<String>) {
+        let files = files_in(dir);
+        write_root(&files, "Old Root").unwrap();
+        write_leaf(
+            &dir.join("app.test.crt"),
+            "app.test",
+            &files.cert,
+            &files.key,
+        )
+        .unwrap();
+
+        let foreign = tempfile::tempdir().unwrap();
+        let foreign = files_in(foreign.path());
+        write_root(&foreign, "Foreign Root").unwrap();
+        write_leaf(
+            &dir.join("other.test.crt"),
+            "other.test",
+            &foreign.cert,
+            &foreign.key,
+        )
+        .unwrap();
+
+        let trusted = vec![fingerprint(&files.cert)];
+        (files, trusted)
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_ca_until_retired() {
+        let dir = tempfile::tempdir().unwrap();
+        let (files, trusted) = setup(dir.path());
+        let host = TestHost {
+            files: &files,
+            trusted: RefCell::new(trusted),
+        };
+        let old_pem = fs::read_to_string(&files.cert).unwrap();
+        let old_fingerprint = fingerprint(&files.cert);
+        let rotated_at = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+
+        let result = replace_ca(&files, &host, 7, &FixedClock(rotated_at), None, &|| {
+            write_root(&files, "New Root")
+        })
+        .unwrap();
+        let retire_at = rotated_at.unix_timestamp() + 7 * DAY;
+        assert_eq!(result.previous_fingerprint, old_fingerprint);
+        assert_eq!(result.new_fingerprint, fingerprint(&files.cert));
+        assert_ne!(result.new_fingerprint, old_fingerprint);
+        assert_eq!(result.reissued, vec!["app.test"]);
+        assert!(result.failed.is_empty());
+        assert!(result.trust_updated);
+        assert_eq!(result.retire_at, Some(retire_at));
+
+        // The old CA stays on disk and trusted through the overlap
+        assert_eq!(fs::read_to_string(&files.previous_cert).unwrap(), old_pem);
+        assert!(files.previous_key.exists());
+        assert_eq!(
+            *host.trusted.borrow(),
+            vec![old_fingerprint.clone(), result.new_fingerprint.clone()]
+        );
+
+        // Only leaves of the old CA move to the new one
+        let certs = inventory::scan_certificates(dir.path(), &files.cert, None, &[]).unwrap();
+        let find = |name: &str| certs.iter().find(|c| c.name == name).unwrap();
+        assert!(find("app.test").signed_by_current_ca);
+        assert!(!find("other.test").signed_by_current_ca);
+
+        let before = FixedClock(OffsetDateTime::from_unix_timestamp(retire_at - 1).unwrap());
+        assert!(!retire_previous_if_due(&files, &host, &before).unwrap());
+        assert!(files.previous_cert.exists());
+
+        let after = FixedClock(OffsetDateTime::from_unix_timestamp(retire_at).unwrap());
+        assert!(retire_previous_if_due(&files, &host, &after).unwrap());
+        assert!(!files.previous_cert.exists());
+        assert!(!files.previous_key.exists());
+        assert!(!files.state.exists());
+        assert_eq!(*host.trusted.borrow(), vec![result.new_fingerprint]);
+        assert!(!retire_previous_if_due(&files, &host, &after).unwrap());
+    }
+
+    #[test]
+    fn test_rotation_without_overlap_retires_at_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let (files, trusted) = setup(dir.path());
+        let host = TestHost {
+            files: &files,
+            trusted: RefCell::new(trusted),
+        };
+
+        let result = replace_ca(
+            &files,
+            &host,
+            0,
+            &FixedClock(OffsetDateTime::now_utc()),
+            None,
+            &|| write_root(&files, "New Root"),
+        )
+        .unwrap();
+        assert_eq!(result.retire_at, None);
+        assert!(!files.previous_cert.exists());
+        assert!(!files.previous_key.exists());
+        assert_eq!(*host.trusted.borrow(), vec![result.new_fingerprint]);
+    }
+
+    #[test]
+    fn test_failed_install_restores_ca() {
+        let dir = tempfile::tempdir().unwrap();
+        let (files, trusted) = setup(dir.path());
+        let host = TestHost {
+            files: &files,
+            trusted: RefCell::new(trusted),
+        };
+        let old_fingerprint = fingerprint(&files.cert);
+
+        let result = replace_ca(
+            &files,
+            &host,
+            7,
+            &FixedClock(OffsetDateTime::now_utc()),
+            None,
+            &|| bail!("no new CA"),
+        );
+        assert!(result.is_err());
+        assert_eq!(fingerprint(&files.cert), old_fingerprint);
+        assert!(files.key.exists());
+        assert!(!files.previous_cert.exists());
+        assert!(!files.state.exists());
+    }
+
+    #[test]
+    fn test_retire_due_after_overlap() {
+        let state = RotationState {
+            previous_fingerprint: "AA".to_string(),
+            rotated_at: 1_000,
+            retire_at: 1_000 + 7 * 86_400,
+        };
+        assert!(!is_retire_due(&state, 1_000));
+        assert!(!is_retire_due(&state, 1_000 + 7 * 86_400 - 1));
+        assert!(is_retire_due(&state, 1_000 + 7 * 86_400));
+    }
+}
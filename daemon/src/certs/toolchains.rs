@@ -0,0 +1,406 @@
+//! Trust for developer toolchains that ignore the system store: JDK `cacerts`
+//! keystores, plus a combined PEM bundle and environment variables for Node,
+//! Python, Go, OpenSSL-based tools and curl.
+
+use anyhow::{bail, Context, Result};
+use localdomain_shared::protocol::{
+    EnvVar, ToolchainTrustResult, TrustStoreKind, TrustStoreStatus,
+};
+use localdomain_shared::silent_cmd;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+use super::{ca, inventory, trust};
+use crate::paths;
+
+const ALIAS: &str = "localdomain-ca";
+const PREVIOUS_ALIAS: &str = "localdomain-ca-previous";
+/// Default password of every JDK's bundled `cacerts`
+const STOREPASS: &str = "changeit";
+
+#[cfg(target_os = "linux")]
+const JDK_ROOTS: &[&str] = &["/usr/lib/jvm", "/usr/java", "/opt/java", "/opt/jdk"];
+#[cfg(target_os = "macos")]
+const JDK_ROOTS: &[&str] = &["/Library/Java/JavaVirtualMachines"];
+#[cfg(target_os = "windows")]
+const JDK_ROOTS: &[&str] = &[
+    "C:\\Program Files\\Java",
+    "C:\\Program Files\\Eclipse Adoptium",
+    "C:\\Program Files\\Microsoft",
+    "C:\\Program Files\\Zulu",
+    "C:\\Program Files\\Amazon Corretto",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaKeystore {
+    /// JDK directory name, e.g. "java-17-openjdk-amd64"
+    pub label: String,
+    /// Canonical path; distributions often symlink every JDK to one shared file
+    pub cacerts: PathBuf,
+    pub keytool: PathBuf,
+}
+
+/// Find `cacerts` for each JDK installed under the given roots.
+pub fn discover_keystores(roots: &[&Path]) -> Vec<JavaKeystore> {
+    let mut seen = HashSet::new();
+    let mut keystores = Vec::new();
+
+    for root in roots {
+        let Ok(entries) = fs::read_dir(root) else {
+            continue;
+        };
+        let mut jdks: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+        jdks.sort();
+        for jdk in jdks {
+            // macOS bundles keep the JDK home inside Contents/Home
+            let home = if jdk.join("Contents/Home").is_dir() {
+                jdk.join("Contents/Home")
+            } else {
+                jdk.clone()
+            };
+            let Some(cacerts) = ["lib/security/cacerts", "jre/lib/security/cacerts"]
+                .iter()
+                .map(|rel| home.join(rel))
+                .find(|p| p.is_file())
+            else {
+                continue;
+            };
+            let cacerts = fs::canonicalize(&cacerts).unwrap_or(cacerts);
+            if !seen.insert(cacerts.clone()) {
+                continue;
+            }
+            let keytool = home.join("bin").join(if cfg!(windows) {
+                "keytool.exe"
+            } else {
+                "keytool"
+            });
+            keystores.push(JavaKeystore {
+                label: jdk
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                cacerts,
+                keytool,
+            });
+        }
+    }
+    keystores
+}
+
+fn system_keystores() -> Vec<JavaKeystore> {
+    let roots: Vec<&Path> = JDK_ROOTS.iter().map(Path::new).collect();
+    discover_keystores(&roots)
+}
+
+/// Report keystore and bundle state without changing anything.
+pub fn status() -> ToolchainTrustResult {
+    let fingerprint = ca_fingerprint();
+    let java_keystores = system_keystores()
+        .iter()
+        .map(|ks| {
+            store_status(
+                ks,
+                match &fingerprint {
+                    Some(fp) => contains(ks, ALIAS, fp),
+                    None => Ok(false),
+                },
+            )
+        })
+        .collect();
+    result(java_keystores)
+}
+
+/// Import the CA into every JDK keystore and write the combined bundle.
+pub fn install() -> Result<ToolchainTrustResult> {
+    if !ca::ca_exists() {
+        bail!("CA certificate does not exist. Generate it first.");
+    }
+    write_bundle()?;
+    let java_keystores = system_keystores()
+        .iter()
+        .map(|ks| store_status(ks, import(ks).map(|()| true)))
+        .collect();
+    info!("CA trusted in developer toolchains");
+    Ok(result(java_keystores))
+}
+
+/// Remove the CA from every JDK keystore and delete the combined bundle.
+pub fn remove() -> Result<ToolchainTrustResult> {
+    let java_keystores = system_keystores()
+        .iter()
+        .map(|ks| {
+            let removed = delete(ks, ALIAS).and_then(|()| delete(ks, PREVIOUS_ALIAS));
+            store_status(ks, removed.map(|()| false))
+        })
+        .collect();
+    if Path::new(paths::CA_BUNDLE).exists() {
+        fs::remove_file(paths::CA_BUNDLE).context("Failed to remove CA bundle")?;
+    }
+    info!("CA removed from developer toolchains");
+    Ok(result(java_keystores))
+}
+
+/// After the CA changes, update the integrations the user had installed.
+pub fn refresh() {
+    if Path::new(paths::CA_BUNDLE).exists() {
+        if let Err(e) = write_bundle() {
+            warn!("Failed to rewrite CA bundle: {}", e);
+        }
+    }
+    for ks in system_keystores() {
+        let installed = [ALIAS, PREVIOUS_ALIAS]
+            .iter()
+            .any(|alias| has_alias(&ks, alias).unwrap_or(false));
+        if installed {
+            if let Err(e) = import(&ks) {
+                warn!("Failed to update {}: {}", ks.cacerts.display(), e);
+            }
+        }
+    }
+}
+
+fn result(java_keystores: Vec<TrustStoreStatus>) -> ToolchainTrustResult {
+    let ca_cert_path = ca::ca_cert_path().to_string();
+    let bundle_path = Path::new(paths::CA_BUNDLE)
+        .exists()
+        .then(|| paths::CA_BUNDLE.to_string());
+    let env = env_vars(&ca_cert_path, bundle_path.as_deref());
+    ToolchainTrustResult {
+        java_keystores,
+        ca_cert_path,
+        bundle_path,
+        env,
+    }
+}
+
+fn store_status(ks: &JavaKeystore, outcome: Result<bool>) -> TrustStoreStatus {
+    let (trusted, error) = match outcome {
+        Ok(trusted) => (trusted, None),
+        Err(e) => {
+            warn!("Java keystore {}: {}", ks.cacerts.display(), e);
+            (false, Some(e.to_string()))
+        }
+    };
+    TrustStoreStatus {
+        kind: TrustStoreKind::Java,
+        label: ks.label.clone(),
+        path: ks.cacerts.to_string_lossy().to_string(),
+        trusted,
+        error,
+    }
+}
+
+/// Node adds NODE_EXTRA_CA_CERTS to its built-in roots, so it only needs the CA.
+/// The others replace their roots entirely and need the combined bundle.
+fn env_vars(ca_cert: &str, bundle: Option<&str>) -> Vec<EnvVar> {
+    let var = |name: &str, value: &str| EnvVar {
+        name: name.to_string(),
+        value: value.to_string(),
+    };
+    let mut env = vec![var("NODE_EXTRA_CA_CERTS", ca_cert)];
+    if let Some(bundle) = bundle {
+        for name in ["SSL_CERT_FILE", "REQUESTS_CA_BUNDLE", "CURL_CA_BUNDLE"] {
+            env.push(var(name, bundle));
+        }
+    }
+    env
+}
+
+fn ca_pems() -> Result<Vec<String>> {
+    let mut pems = vec![fs::read_to_string(ca::ca_cert_path()).context("Failed to read CA cert")?];
+    if ca::previous_ca_exists() {
+        pems.push(
+            fs::read_to_string(paths::CA_PREVIOUS_CERT)
+                .context("Failed to read previous CA cert")?,
+        );
+    }
+    Ok(pems)
+}
+
+fn ca_fingerprint() -> Option<String> {
+    let pem = fs::read_to_string(ca::ca_cert_path()).ok()?;
+    Some(inventory::fingerprint_sha256(
+        pem::parse(pem).ok()?.contents(),
+    ))
+}
+
+fn write_bundle() -> Result<()> {
+    let system_path =
+        trust::system_bundle_path().context("No system CA bundle found to combine with the CA")?;
+    let system = fs::read_to_string(&system_path)
+        .with_context(|| format!("Failed to read {}", system_path))?;
+    fs::write(paths::CA_BUNDLE, combine_bundle(&system, &ca_pems()?))
+        .context("Failed to write CA bundle")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // Read by tools running as the user
+        fs::set_permissions(paths::CA_BUNDLE, fs::Permissions::from_mode(0o644))?;
+    }
+    Ok(())
+}
+
+fn combine_bundle(system: &str, ca_pems: &[String]) -> String {
+    let mut bundle = system.trim_end().to_string();
+    bundle.push('\n');
+    for pem in ca_pems {
+        bundle.push_str("\n# LocalDomain Root CA\n");
+        bundle.push_str(pem.trim_end());
+        bundle.push('\n');
+    }
+    bundle
+}
+
+fn keytool(ks: &JavaKeystore, command: &str) -> Command {
+    // Fall back to keytool on PATH for JDK layouts without bin/
+    let program = if ks.keytool.is_file() {
+        ks.keytool.clone()
+    } else {
+        PathBuf::from("keytool")
+    };
+    let mut cmd = silent_cmd(program);
+    cmd.arg(command)
+        .arg("-keystore")
+        .arg(&ks.cacerts)
+        .args(["-storepass", STOREPASS, "-noprompt"]);
+    cmd
+}
+
+fn run(mut cmd: Command, what: &str) -> Result<std::process::Output> {
+    cmd.output().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            anyhow::anyhow!("keytool not found")
+        } else {
+            anyhow::anyhow!("Failed to run keytool {}: {}", what, e)
+        }
+    })
+}
+
+/// Replace any LocalDomain entries with the current CA (and the previous one during an overlap).
+fn import(ks: &JavaKeystore) -> Result<()> {
+    delete(ks, ALIAS)?;
+    delete(ks, PREVIOUS_ALIAS)?;
+    let mut sources = vec![(ALIAS, PathBuf::from(ca::ca_cert_path()))];
+    if ca::previous_ca_exists() {
+        sources.push((PREVIOUS_ALIAS, PathBuf::from(paths::CA_PREVIOUS_CERT)));
+    }
+    for (alias, file) in sources {
+        let mut cmd = keytool(ks, "-importcert");
+        cmd.args(["-trustcacerts", "-alias", alias, "-file"])
+            .arg(file);
+        let output = run(cmd, "-importcert")?;
+        if !output.status.success() {
+            bail!(
+                "keytool -importcert failed: {}",
+                String::from_utf8_lossy(&output.stdout).trim()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn delete(ks: &JavaKeystore, alias: &str) -> Result<()> {
+    if !has_alias(ks, alias)? {
+        return Ok(());
+    }
+    let mut cmd = keytool(ks, "-delete");
+    cmd.args(["-alias", alias]);
+    let output = run(cmd, "-delete")?;
+    if !output.status.success() {
+        bail!(
+            "keytool -delete failed: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+    Ok(())
+}
+
+fn has_alias(ks: &JavaKeystore, alias: &str) -> Result<bool> {
+    Ok(list_alias(ks, alias)?.is_some())
+}
+
+/// True if the keystore holds the CA under `alias` with a matching fingerprint.
+fn contains(ks: &JavaKeystore, alias: &str, fingerprint: &str) -> Result<bool> {
+    Ok(list_alias(ks, alias)?.is_some_and(|listing| listing.contains(fingerprint)))
+}
+
+fn list_alias(ks: &JavaKeystore, alias: &str) -> Result<Option<String>> {
+    let mut cmd = keytool(ks, "-list");
+    cmd.args(["-alias", alias]);
+    let output = run(cmd, "-list")?;
+    // keytool exits non-zero when the alias is missing
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_uppercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_keystores_dedupes_shared_cacerts() {
+        let root = tempfile::tempdir().unwrap();
+        let jdk = |name: &str, rel: &str| {
+            let dir = root.path().join(name).join(rel);
+            fs::create_dir_all(&dir).unwrap();
+            dir.join("cacerts")
+        };
+        fs::write(jdk("jdk-21", "lib/security"), b"").unwrap();
+        fs::write(jdk("jdk8", "jre/lib/security"), b"").unwrap();
+        fs::write(jdk("zulu-17.jdk", "Contents/Home/lib/security"), b"").unwrap();
+        fs::create_dir_all(root.path().join("not-a-jdk")).unwrap();
+        #[cfg(unix)]
+        {
+            // Debian-style: the JDK's cacerts links to the shared one
+            let shared = jdk("jdk-21", "lib/security");
+            let link = jdk("jdk-21-linked", "lib/security");
+            std::os::unix::fs::symlink(&shared, &link).unwrap();
+        }
+
+        let found = discover_keystores(&[root.path()]);
+        let labels: Vec<&str> = found.iter().map(|k| k.label.as_str()).collect();
+        assert_eq!(labels, vec!["jdk-21", "jdk8", "zulu-17.jdk"]);
+        assert!(found[2]
+            .keytool
+            .ends_with(Path::new("zulu-17.jdk/Contents/Home/bin/keytool")));
+    }
+
+    #[test]
+    fn test_env_vars_and_bundle() {
+        let env = env_vars("/certs/ca.crt", None);
+        assert_eq!(env.len(), 1);
+        assert_eq!(env[0].name, "NODE_EXTRA_CA_CERTS");
+
+        let env = env_vars("/certs/ca.crt", Some("/certs/bundle.pem"));
+        let names: Vec<&str> = env.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "NODE_EXTRA_CA_CERTS",
+                "SSL_CERT_FILE",
+                "REQUESTS_CA_BUNDLE",
+                "CURL_CA_BUNDLE"
+            ]
+        );
+        assert!(env[1..].iter().all(|v| v.value == "/certs/bundle.pem"));
+
+        let key = rcgen::KeyPair::generate().unwrap();
+        let ca = rcgen::CertificateParams::default()
+            .self_signed(&key)
+            .unwrap();
+        let system = rcgen::CertificateParams::default()
+            .self_signed(&key)
+            .unwrap()
+            .pem();
+        let bundle = combine_bundle(&system, &[ca.pem()]);
+        let certs = pem::parse_many(&bundle).unwrap();
+        assert_eq!(certs.len(), 2);
+        assert_eq!(certs[1].contents(), ca.der().as_ref());
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use localdomain_shared::protocol::{TrustStoreKind, TrustStoreStatus};
 use localdomain_shared::silent_cmd;
 use tracing::{info, warn};
 
@@ -6,34 +7,98 @@ use super::ca;
 
 // ---- macOS: System Keychain ----
 
-/// Remove stale "LocalDomain Root CA" certs from System Keychain.
-/// This handles duplicates from prior CA regenerations.
+#[cfg(target_os = "macos")]
+const SYSTEM_KEYCHAIN: &str = "/Library/Keychains/System.keychain";
+
+/// Remove CAs left by earlier LocalDomain installs from the System Keychain.
+/// Their fingerprints were never recorded, so they are recognised by the subject
+/// we generate; the current CA is kept by fingerprint, whatever its name.
 #[cfg(target_os = "macos")]
 fn cleanup_stale_ca_certs() {
-    for _ in 0..10 {
-        let output = silent_cmd("security")
-            .args([
-                "delete-certificate",
-                "-c",
-                "LocalDomain Root CA",
-                "/Library/Keychains/System.keychain",
-            ])
-            .output();
-        match output {
-            Ok(o) if o.status.success() => continue,
-            _ => break,
+    let Some(current) = read_cert_der(ca::ca_cert_path()) else {
+        return;
+    };
+    let current = super::inventory::fingerprint_sha256(&current);
+    for der in system_keychain_certs() {
+        if super::inventory::fingerprint_sha256(&der) == current || !is_generated_ca(&der) {
+            continue;
         }
+        if let Err(e) = delete_from_system_keychain(&der) {
+            warn!(
+                "Failed to remove a stale CA from the System Keychain: {}",
+                e
+            );
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn is_generated_ca(der: &[u8]) -> bool {
+    let Ok((_, cert)) = x509_parser::parse_x509_certificate(der) else {
+        return false;
+    };
+    cert.subject() == cert.issuer()
+        && cert
+            .subject()
+            .iter_common_name()
+            .any(|cn| cn.as_str().ok() == Some(ca::GENERATED_CA_NAME))
+}
+
+/// Every certificate in the System Keychain, as DER.
+#[cfg(target_os = "macos")]
+fn system_keychain_certs() -> Vec<Vec<u8>> {
+    match silent_cmd("security")
+        .args(["find-certificate", "-a", "-p", SYSTEM_KEYCHAIN])
+        .output()
+    {
+        Ok(o) if o.status.success() => pem::parse_many(&o.stdout)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| p.into_contents())
+            .collect(),
+        _ => Vec::new(),
     }
 }
 
+/// Drop a CA's trust settings and the certificate itself, found by its hash
+/// rather than its name.
+#[cfg(target_os = "macos")]
+fn delete_from_system_keychain(der: &[u8]) -> Result<()> {
+    let wanted = super::inventory::fingerprint_sha256(der);
+    if !system_keychain_certs()
+        .iter()
+        .any(|c| super::inventory::fingerprint_sha256(c) == wanted)
+    {
+        return Ok(());
+    }
+    let output = silent_cmd("security")
+        .args([
+            "delete-certificate",
+            "-t",
+            "-Z",
+            &sha1_thumbprint(der),
+            SYSTEM_KEYCHAIN,
+        ])
+        .output()
+        .context("Failed to run security command")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("security delete-certificate failed: {}", stderr.trim());
+    }
+    Ok(())
+}
+
 #[cfg(target_os = "macos")]
 pub fn install_ca_trust() -> Result<()> {
     if !ca::ca_exists() {
         anyhow::bail!("CA certificate does not exist. Generate it first.");
     }
 
-    // Clean up stale certs before adding
-    cleanup_stale_ca_certs();
+    // Clean up stale certs before adding. Skipped during a CA rotation so the
+    // previous CA stays trusted until it is retired.
+    if !ca::previous_ca_exists() {
+        cleanup_stale_ca_certs();
+    }
 
     let output = silent_cmd("security")
         .args([
@@ -44,7 +109,7 @@ pub fn install_ca_trust() -> Result<()> {
             "-p",
             "ssl",
             "-k",
-            "/Library/Keychains/System.keychain",
+            SYSTEM_KEYCHAIN,
             ca::ca_cert_path(),
         ])
         .output()
@@ -65,99 +130,241 @@ pub fn install_ca_trust() -> Result<()> {
 
 #[cfg(target_os = "macos")]
 pub fn remove_ca_trust() -> Result<()> {
-    let output = silent_cmd("security")
-        .args(["remove-trusted-cert", "-d", ca::ca_cert_path()])
-        .output()
-        .context("Failed to run security command")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if !stderr.contains("not found") {
-            anyhow::bail!("Failed to remove CA trust: {}", stderr);
-        }
+    if let Some(der) = read_cert_der(ca::ca_cert_path()) {
+        delete_from_system_keychain(&der)?;
     }
 
     info!("CA certificate removed from System Keychain");
     Ok(())
 }
 
+/// Remove trust for the rotated-out CA once its overlap window has ended.
+#[cfg(target_os = "macos")]
+pub fn remove_previous_ca_trust() -> Result<()> {
+    if let Some(der) = read_cert_der(crate::paths::CA_PREVIOUS_CERT) {
+        delete_from_system_keychain(&der)?;
+    }
+
+    info!("Previous CA certificate removed from System Keychain");
+    Ok(())
+}
+
+/// Whether the current CA is in the System Keychain. Both the daemon and the app
+/// add it there with `add-trusted-cert -r trustRoot`, so its presence means trust.
 #[cfg(target_os = "macos")]
 pub fn verify_ca_trust() -> bool {
-    // Check admin-level trust settings (daemon installs there)
-    if let Ok(output) = silent_cmd("security")
-        .args(["dump-trust-settings", "-d"])
-        .output()
-    {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if stdout.contains("LocalDomain Root CA") {
-            return true;
+    let Some(ca_der) = read_cert_der(ca::ca_cert_path()) else {
+        return false;
+    };
+    let wanted = super::inventory::fingerprint_sha256(&ca_der);
+    system_keychain_certs()
+        .iter()
+        .any(|der| super::inventory::fingerprint_sha256(der) == wanted)
+}
+
+// ---- Linux: distribution trust anchors ----
+
+/// How the running distribution manages its system trust store.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LinuxTrust {
+    /// Debian/Ubuntu: /usr/local/share/ca-certificates + update-ca-certificates
+    Debian,
+    /// Alpine: same layout as Debian, but its update-ca-certificates has no --fresh
+    Alpine,
+    /// Fedora/RHEL/CentOS: /etc/pki/ca-trust/source/anchors + update-ca-trust
+    Fedora,
+    /// Arch and derivatives: p11-kit `trust anchor`
+    Arch,
+    /// openSUSE/SLES: /etc/pki/trust/anchors + update-ca-certificates
+    Suse,
+}
+
+#[cfg(target_os = "linux")]
+impl LinuxTrust {
+    fn detect() -> Self {
+        let os_release = std::fs::read_to_string("/etc/os-release").unwrap_or_default();
+        if let Some(trust) = Self::from_os_release(&os_release) {
+            return trust;
+        }
+        // Unknown distribution: go by which tooling is installed
+        if command_exists("update-ca-trust") {
+            LinuxTrust::Fedora
+        } else if command_exists("trust") && !command_exists("update-ca-certificates") {
+            LinuxTrust::Arch
+        } else if std::path::Path::new("/etc/pki/trust/anchors").is_dir() {
+            LinuxTrust::Suse
+        } else {
+            LinuxTrust::Debian
         }
     }
-    // Check user-level trust settings (visible when running as the same user)
-    if let Ok(output) = silent_cmd("security")
-        .args(["dump-trust-settings"])
-        .output()
-    {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if stdout.contains("LocalDomain Root CA") {
-            return true;
+
+    /// Match ID, then each ID_LIKE entry, from /etc/os-release.
+    fn from_os_release(os_release: &str) -> Option<Self> {
+        let field = |key: &str| {
+            os_release
+                .lines()
+                .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+                .map(|v| v.trim().trim_matches('"').to_lowercase())
+                .unwrap_or_default()
+        };
+        let id = field("ID");
+        let id_like = field("ID_LIKE");
+        std::iter::once(id.as_str())
+            .chain(id_like.split_whitespace())
+            .find_map(|id| match id {
+                "debian" | "ubuntu" => Some(LinuxTrust::Debian),
+                "alpine" => Some(LinuxTrust::Alpine),
+                "fedora" | "rhel" | "centos" => Some(LinuxTrust::Fedora),
+                "arch" => Some(LinuxTrust::Arch),
+                id if id.starts_with("opensuse") || id == "suse" || id == "sles" => {
+                    Some(LinuxTrust::Suse)
+                }
+                _ => None,
+            })
+    }
+
+    /// Where anchor files are copied; Arch imports them through `trust anchor` instead.
+    fn anchor_dir(self) -> Option<&'static str> {
+        match self {
+            LinuxTrust::Debian | LinuxTrust::Alpine => Some("/usr/local/share/ca-certificates"),
+            LinuxTrust::Fedora => Some("/etc/pki/ca-trust/source/anchors"),
+            LinuxTrust::Suse => Some("/etc/pki/trust/anchors"),
+            LinuxTrust::Arch => None,
         }
     }
-    // Fallback for macOS Sequoia+: trust settings may be at user-level (not
-    // visible to the daemon running as root). Check if the current CA cert
-    // is in the System Keychain — its presence means it was added via
-    // `security add-trusted-cert`, so trust was established for the user.
-    ca_cert_in_system_keychain()
-}
 
-/// Check if the current CA cert on disk is present in the System Keychain.
-#[cfg(target_os = "macos")]
-fn ca_cert_in_system_keychain() -> bool {
-    let ca_pem = match std::fs::read_to_string(ca::ca_cert_path()) {
-        Ok(pem) => normalize_pem(&pem),
-        Err(_) => return false,
-    };
-    if ca_pem.is_empty() {
-        return false;
+    /// Compiled bundles that TLS clients read, in order of preference.
+    fn bundles(self) -> &'static [&'static str] {
+        match self {
+            LinuxTrust::Debian | LinuxTrust::Alpine => &["/etc/ssl/certs/ca-certificates.crt"],
+            LinuxTrust::Fedora => &[
+                "/etc/pki/ca-trust/extracted/pem/tls-ca-bundle.pem",
+                "/etc/pki/tls/certs/ca-bundle.crt",
+            ],
+            LinuxTrust::Arch => &[
+                "/etc/ca-certificates/extracted/tls-ca-bundle.pem",
+                "/etc/ssl/certs/ca-certificates.crt",
+            ],
+            LinuxTrust::Suse => &[
+                "/var/lib/ca-certificates/ca-bundle.pem",
+                "/etc/ssl/ca-bundle.pem",
+            ],
+        }
     }
 
-    // Export all "LocalDomain Root CA" certs from System Keychain as PEM
-    let output = match silent_cmd("security")
-        .args([
-            "find-certificate",
-            "-c",
-            "LocalDomain Root CA",
-            "-a",
-            "-p",
-            "/Library/Keychains/System.keychain",
-        ])
-        .output()
-    {
-        Ok(o) if o.status.success() => o,
-        _ => return false,
-    };
+    fn bundles_contain(self, ca_der: &[u8]) -> bool {
+        self.bundles().iter().any(|bundle| {
+            std::fs::read_to_string(bundle)
+                .map(|text| bundle_contains(&text, ca_der))
+                .unwrap_or(false)
+        })
+    }
 
-    let keychain_pems = String::from_utf8_lossy(&output.stdout);
-    // Split the concatenated PEM output into individual certs and compare
-    for cert_block in keychain_pems.split("-----END CERTIFICATE-----") {
-        let normalized = normalize_pem(cert_block);
-        if !normalized.is_empty() && normalized == ca_pem {
-            return true;
+    /// Regenerate the compiled bundles after anchors changed.
+    fn refresh(self, removed: bool) -> Result<()> {
+        let mut cmd = match self {
+            LinuxTrust::Debian => {
+                let mut cmd = silent_cmd("update-ca-certificates");
+                // --fresh drops stale symlinks for anchors that were deleted
+                if removed {
+                    cmd.arg("--fresh");
+                }
+                cmd
+            }
+            LinuxTrust::Alpine | LinuxTrust::Suse => silent_cmd("update-ca-certificates"),
+            LinuxTrust::Fedora => {
+                let mut cmd = silent_cmd("update-ca-trust");
+                cmd.arg("extract");
+                cmd
+            }
+            // `trust anchor` extracts the bundles itself
+            LinuxTrust::Arch => return Ok(()),
+        };
+        let output = cmd
+            .output()
+            .context("Failed to update the system CA bundle")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Failed to update CA trust: {}", stderr.trim());
+        }
+        Ok(())
+    }
+
+    /// Add a CA certificate as a trust anchor under `name` (without extension).
+    fn add_anchor(self, cert_path: &str, name: &str) -> Result<()> {
+        match self.anchor_dir() {
+            Some(dir) => {
+                // update-ca-certificates only picks up files ending in .crt
+                let dest = format!("{}/{}.crt", dir, name);
+                std::fs::create_dir_all(dir)
+                    .and_then(|_| std::fs::copy(cert_path, &dest))
+                    .with_context(|| format!("Failed to copy CA cert to {}", dir))?;
+                Ok(())
+            }
+            None => {
+                // Re-storing an anchor p11-kit already has is an error
+                if read_cert_der(cert_path).is_some_and(|der| self.bundles_contain(&der)) {
+                    return Ok(());
+                }
+                run_trust_anchor("--store", cert_path)
+            }
+        }
+    }
+
+    /// Remove an anchor added by `add_anchor`. Returns whether anything was removed.
+    fn remove_anchor(self, cert_path: &str, name: &str) -> Result<bool> {
+        match self.anchor_dir() {
+            Some(dir) => {
+                let dest = format!("{}/{}.crt", dir, name);
+                if !std::path::Path::new(&dest).exists() {
+                    return Ok(false);
+                }
+                std::fs::remove_file(&dest)
+                    .with_context(|| format!("Failed to remove {}", dest))?;
+                Ok(true)
+            }
+            None => {
+                // p11-kit matches the anchor by the certificate itself, and errors if absent
+                let Some(der) = read_cert_der(cert_path) else {
+                    return Ok(false);
+                };
+                if !self.bundles_contain(&der) {
+                    return Ok(false);
+                }
+                run_trust_anchor("--remove", cert_path)?;
+                Ok(true)
+            }
         }
     }
-    false
 }
 
-/// Strip PEM headers, whitespace, and newlines to get just the base64 content.
-#[cfg(target_os = "macos")]
-fn normalize_pem(pem: &str) -> String {
-    pem.lines()
-        .map(|l| l.trim())
-        .filter(|l| !l.starts_with("-----") && !l.is_empty())
-        .collect::<String>()
+#[cfg(target_os = "linux")]
+const LINUX_ANCHOR_NAME: &str = "localdomain-ca";
+#[cfg(target_os = "linux")]
+const LINUX_PREVIOUS_ANCHOR_NAME: &str = "localdomain-ca-previous";
+
+#[cfg(target_os = "linux")]
+fn command_exists(name: &str) -> bool {
+    silent_cmd("sh")
+        .args(["-c", &format!("command -v {}", name)])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
 }
 
-// ---- Linux: update-ca-certificates ----
+#[cfg(target_os = "linux")]
+fn run_trust_anchor(action: &str, cert_path: &str) -> Result<()> {
+    let output = silent_cmd("trust")
+        .args(["anchor", action, cert_path])
+        .output()
+        .context("Failed to run trust anchor")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("trust anchor {} failed: {}", action, stderr.trim());
+    }
+    Ok(())
+}
 
 #[cfg(target_os = "linux")]
 pub fn install_ca_trust() -> Result<()> {
@@ -165,48 +372,108 @@ pub fn install_ca_trust() -> Result<()> {
         anyhow::bail!("CA certificate does not exist. Generate it first.");
     }
 
-    let dest = "/usr/local/share/ca-certificates/localdomain-ca.crt";
-    std::fs::copy(ca::ca_cert_path(), dest)
-        .context("Failed to copy CA cert to /usr/local/share/ca-certificates/")?;
+    let distro = LinuxTrust::detect();
+    let ca_cert = ca::ca_cert_path();
+    distro.add_anchor(ca_cert, LINUX_ANCHOR_NAME)?;
 
-    let output = silent_cmd("update-ca-certificates")
-        .output()
-        .context("Failed to run update-ca-certificates")?;
+    // Keep the rotated-out CA trusted alongside the new one during the overlap window
+    if ca::previous_ca_exists() {
+        distro.add_anchor(crate::paths::CA_PREVIOUS_CERT, LINUX_PREVIOUS_ANCHOR_NAME)?;
+    }
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        warn!("CA trust failed: {}", stderr.trim());
-        anyhow::bail!("update-ca-certificates failed: {}", stderr.trim());
+    if let Err(e) = distro.refresh(false) {
+        warn!("CA trust failed: {}", e);
+        return Err(e);
     }
 
-    info!("CA certificate trusted via update-ca-certificates");
+    info!("CA certificate trusted in system store ({:?})", distro);
+
+    // Browsers keep their own NSS stores; failures there are reported per store in status
+    super::nss::install_all(true);
     Ok(())
 }
 
 #[cfg(target_os = "linux")]
 pub fn remove_ca_trust() -> Result<()> {
-    let dest = "/usr/local/share/ca-certificates/localdomain-ca.crt";
-    if std::path::Path::new(dest).exists() {
-        std::fs::remove_file(dest).context("Failed to remove CA cert")?;
+    let distro = LinuxTrust::detect();
+    let ca_cert = ca::ca_cert_path();
+    let mut removed =
+        distro.remove_anchor(crate::paths::CA_PREVIOUS_CERT, LINUX_PREVIOUS_ANCHOR_NAME)?;
+    removed |= distro.remove_anchor(ca_cert, LINUX_ANCHOR_NAME)?;
+    if removed {
+        distro.refresh(true)?;
+    }
 
-        let output = silent_cmd("update-ca-certificates")
-            .arg("--fresh")
-            .output()
-            .context("Failed to run update-ca-certificates")?;
+    info!("CA certificate removed from system trust store");
+    super::nss::remove_all();
+    Ok(())
+}
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Failed to update CA trust: {}", stderr);
-        }
+/// Remove trust for the rotated-out CA once its overlap window has ended.
+#[cfg(target_os = "linux")]
+pub fn remove_previous_ca_trust() -> Result<()> {
+    let distro = LinuxTrust::detect();
+    if !distro.remove_anchor(crate::paths::CA_PREVIOUS_CERT, LINUX_PREVIOUS_ANCHOR_NAME)? {
+        return Ok(());
     }
+    super::nss::install_all(false);
+    distro.refresh(true)?;
 
-    info!("CA certificate removed from system trust store");
+    info!("Previous CA certificate removed from system trust store");
     Ok(())
 }
 
+/// True only if the compiled bundle actually contains the current CA, not just the anchor file.
 #[cfg(target_os = "linux")]
 pub fn verify_ca_trust() -> bool {
-    std::path::Path::new("/usr/local/share/ca-certificates/localdomain-ca.crt").exists()
+    match read_cert_der(ca::ca_cert_path()) {
+        Some(ca_der) => LinuxTrust::detect().bundles_contain(&ca_der),
+        None => false,
+    }
+}
+
+fn read_cert_der(path: &str) -> Option<Vec<u8>> {
+    let pem = std::fs::read_to_string(path).ok()?;
+    Some(pem::parse(pem).ok()?.contents().to_vec())
+}
+
+/// Whether a PEM bundle contains a certificate with the same SHA-256 fingerprint.
+#[cfg(target_os = "linux")]
+fn bundle_contains(bundle: &str, ca_der: &[u8]) -> bool {
+    let wanted = super::inventory::fingerprint_sha256(ca_der);
+    // Bundles interleave comments with the PEM blocks; parse_many skips them
+    pem::parse_many(bundle)
+        .unwrap_or_default()
+        .iter()
+        .any(|p| super::inventory::fingerprint_sha256(p.contents()) == wanted)
+}
+
+/// SHA-1 thumbprint, which Keychain and the Windows certificate store use to
+/// address a certificate.
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn sha1_thumbprint(der: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, der)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn system_store_path() -> String {
+    system_bundle_path()
+        .or_else(|| LinuxTrust::detect().bundles().first().map(|b| b.to_string()))
+        .unwrap_or_default()
+}
+
+/// PEM bundle of the system's trusted roots, for tools that need a CA file.
+#[cfg(target_os = "linux")]
+pub fn system_bundle_path() -> Option<String> {
+    LinuxTrust::detect()
+        .bundles()
+        .iter()
+        .find(|b| std::path::Path::new(b).exists())
+        .map(|b| b.to_string())
 }
 
 // ---- Windows: Certificate Store via certutil ----
@@ -234,8 +501,32 @@ pub fn install_ca_trust() -> Result<()> {
 
 #[cfg(target_os = "windows")]
 pub fn remove_ca_trust() -> Result<()> {
+    if let Some(der) = read_cert_der(ca::ca_cert_path()) {
+        delete_from_root_store(&der)?;
+    }
+
+    info!("CA certificate removed from Windows certificate store");
+    Ok(())
+}
+
+/// Remove trust for the rotated-out CA once its overlap window has ended.
+/// Deletes by thumbprint so the current CA (same subject name) is kept.
+#[cfg(target_os = "windows")]
+pub fn remove_previous_ca_trust() -> Result<()> {
+    if let Some(der) = read_cert_der(crate::paths::CA_PREVIOUS_CERT) {
+        delete_from_root_store(&der)?;
+    }
+
+    info!("Previous CA certificate removed from Windows certificate store");
+    Ok(())
+}
+
+/// The store addresses certificates by thumbprint, a hash of the whole
+/// certificate, so this finds the CA whatever its name.
+#[cfg(target_os = "windows")]
+fn delete_from_root_store(der: &[u8]) -> Result<()> {
     let output = silent_cmd("certutil")
-        .args(["-delstore", "Root", "LocalDomain Root CA"])
+        .args(["-delstore", "Root", &sha1_thumbprint(der)])
         .output()
         .context("Failed to run certutil")?;
 
@@ -245,22 +536,122 @@ pub fn remove_ca_trust() -> Result<()> {
             anyhow::bail!("Failed to remove CA trust: {}", stderr);
         }
     }
-
-    info!("CA certificate removed from Windows certificate store");
     Ok(())
 }
 
 #[cfg(target_os = "windows")]
 pub fn verify_ca_trust() -> bool {
-    if let Ok(output) = silent_cmd("certutil")
-        .args(["-verifystore", "Root", "LocalDomain Root CA"])
+    let Some(ca_der) = read_cert_der(ca::ca_cert_path()) else {
+        return false;
+    };
+    silent_cmd("certutil")
+        .args(["-store", "Root", &sha1_thumbprint(&ca_der)])
         .output()
-    {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            // Confirm stdout actually contains the cert info
-            return stdout.contains("LocalDomain Root CA");
-        }
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+// ---- Per-store status ----
+
+#[cfg(target_os = "macos")]
+fn system_store_path() -> String {
+    "/Library/Keychains/System.keychain".to_string()
+}
+
+#[cfg(target_os = "windows")]
+fn system_store_path() -> String {
+    "LocalMachine\\Root".to_string()
+}
+
+/// PEM bundle of the system's trusted roots, for tools that need a CA file.
+#[cfg(target_os = "macos")]
+pub fn system_bundle_path() -> Option<String> {
+    let bundle = "/etc/ssl/cert.pem";
+    std::path::Path::new(bundle)
+        .exists()
+        .then(|| bundle.to_string())
+}
+
+/// Windows keeps its roots in the certificate store only, with no PEM bundle.
+#[cfg(target_os = "windows")]
+pub fn system_bundle_path() -> Option<String> {
+    None
+}
+
+/// Trust status of the CA in the system store and, on Linux, each browser NSS database.
+pub fn trust_store_status() -> Vec<TrustStoreStatus> {
+    #[allow(unused_mut)]
+    let mut stores = vec![TrustStoreStatus {
+        kind: TrustStoreKind::System,
+        label: "System".to_string(),
+        path: system_store_path(),
+        trusted: verify_ca_trust(),
+        error: None,
+    }];
+    #[cfg(target_os = "linux")]
+    stores.extend(super::nss::status_all());
+    stores
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_distribution_from_os_release() {
+        let detect = |text: &str| LinuxTrust::from_os_release(text);
+        assert_eq!(detect("ID=debian\n"), Some(LinuxTrust::Debian));
+        assert_eq!(
+            detect("ID=linuxmint\nID_LIKE=\"ubuntu debian\"\n"),
+            Some(LinuxTrust::Debian)
+        );
+        assert_eq!(detect("ID=fedora\n"), Some(LinuxTrust::Fedora));
+        assert_eq!(
+            detect("ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n"),
+            Some(LinuxTrust::Fedora)
+        );
+        assert_eq!(
+            detect("ID=endeavouros\nID_LIKE=arch\n"),
+            Some(LinuxTrust::Arch)
+        );
+        assert_eq!(
+            detect("ID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"\n"),
+            Some(LinuxTrust::Suse)
+        );
+        assert_eq!(detect("ID=alpine\n"), Some(LinuxTrust::Alpine));
+        // VERSION_ID must not be mistaken for ID
+        assert_eq!(detect("VERSION_ID=12\nID=nixos\n"), None);
+    }
+
+    #[test]
+    fn test_bundle_contains_matches_fingerprint() {
+        let cert = |cn: &str| {
+            let mut params = rcgen::CertificateParams::default();
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, cn);
+            params
+                .self_signed(&rcgen::KeyPair::generate().unwrap())
+                .unwrap()
+        };
+        let ours = cert("LocalDomain Root CA");
+        let other = cert("Some Public Root");
+        // Same subject, different key: a stale CA left in the bundle after rotation
+        let stale = cert("LocalDomain Root CA");
+
+        let bundle = format!("# Some Public Root\n{}\n{}", other.pem(), ours.pem());
+        assert!(bundle_contains(&bundle, ours.der()));
+        assert!(!bundle_contains(&other.pem(), ours.der()));
+        assert!(!bundle_contains(&stale.pem(), ours.der()));
+        assert!(!bundle_contains("", ours.der()));
+    }
+
+    #[test]
+    fn test_sha1_thumbprint() {
+        // FIPS 180 test vector, in the form certutil and `security -Z` accept
+        assert_eq!(
+            sha1_thumbprint(b"abc"),
+            "A9993E364706816ABA3E25717850C26C9CD0D89D"
+        );
     }
-    false
 }
@@ -0,0 +1,91 @@
+//! Events the app records in its audit log: certificate renewals, hosts file
+//! tampering and override reverts. They wait on disk until the app collects
+//! them, so a daemon restart in between doesn't lose them.
+
+use anyhow::{Context, Result};
+use localdomain_shared::protocol::{CertRenewalEvent, HostsTamperEvent, OverrideRevertEvent};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::paths;
+
+/// Events kept of each kind while the app isn't collecting them; the oldest go first.
+const MAX_PENDING: usize = 500;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PendingEvents {
+    #[serde(default)]
+    pub renewals: Vec<CertRenewalEvent>,
+    #[serde(default)]
+    pub hosts: Vec<HostsTamperEvent>,
+    #[serde(default)]
+    pub overrides: Vec<OverrideRevertEvent>,
+}
+
+static PENDING: Lazy<Mutex<PendingEvents>> = Lazy::new(|| Mutex::new(load()));
+
+fn load() -> PendingEvents {
+    match fs::read_to_string(paths::EVENTS_FILE) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring invalid pending events file: {}", e);
+            PendingEvents::default()
+        }),
+        Err(_) => PendingEvents::default(),
+    }
+}
+
+fn save(pending: &PendingEvents) -> Result<()> {
+    let json = serde_json::to_string_pretty(pending)?;
+    fs::write(paths::EVENTS_FILE, json).context("Failed to write pending events")
+}
+
+/// Queue events of the kind `select` picks until the app collects them.
+pub fn push<T>(
+    select: impl FnOnce(&mut PendingEvents) -> &mut Vec<T>,
+    events: impl IntoIterator<Item = T>,
+) {
+    let mut pending = PENDING.lock().unwrap();
+    cap(select(&mut pending), events);
+    if let Err(e) = save(&pending) {
+        warn!("{}", e);
+    }
+}
+
+/// Return and clear the queued events of the kind `select` picks.
+pub fn take<T>(select: impl FnOnce(&mut PendingEvents) -> &mut Vec<T>) -> Vec<T> {
+    let mut pending = PENDING.lock().unwrap();
+    let taken = std::mem::take(select(&mut pending));
+    if !taken.is_empty() {
+        if let Err(e) = save(&pending) {
+            warn!("{}", e);
+        }
+    }
+    taken
+}
+
+fn cap<T>(queue: &mut Vec<T>, events: impl IntoIterator<Item = T>) {
+    queue.extend(events);
+    let excess = queue.len().saturating_sub(MAX_PENDING);
+    queue.drain(..excess);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cap_drops_oldest() {
+        let mut queue: Vec<usize> = (0..MAX_PENDING - 1).collect();
+        cap(&mut queue, [MAX_PENDING - 1]);
+        assert_eq!(queue.len(), MAX_PENDING);
+        assert_eq!(queue[0], 0);
+
+        cap(&mut queue, [MAX_PENDING, MAX_PENDING + 1]);
+        assert_eq!(queue.len(), MAX_PENDING);
+        assert_eq!(queue[0], 2);
+        assert_eq!(queue.last(), Some(&(MAX_PENDING + 1)));
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::{bail, Context, Result};
+use localdomain_shared::protocol::{DiffLine, DiffOp, HostsBackup};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::paths;
+
+/// Snapshots kept besides the original; older ones are deleted.
+const MAX_BACKUPS: usize = 20;
+/// Id (and file stem) of the permanent pre-LocalDomain copy.
+pub const ORIGINAL_ID: &str = "original";
+const EXTENSION: &str = "hosts";
+/// Above this many line pairs the diff falls back to "all removed, all added".
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+fn sha256_hex(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Save `content` before the hosts file is replaced. The first call also keeps
+/// a permanent copy of the file as it was before LocalDomain touched it.
+pub fn snapshot(content: &str) -> Result<()> {
+    let now_ms = time::OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000;
+    snapshot_in(Path::new(paths::HOSTS_BACKUP_DIR), content, now_ms as i64)
+}
+
+pub fn list() -> Result<Vec<HostsBackup>> {
+    list_in(Path::new(paths::HOSTS_BACKUP_DIR))
+}
+
+pub fn read(id: &str) -> Result<String> {
+    read_in(Path::new(paths::HOSTS_BACKUP_DIR), id)
+}
+
+pub fn original() -> Option<String> {
+    read(ORIGINAL_ID).ok()
+}
+
+fn snapshot_in(dir: &Path, content: &str, now_ms: i64) -> Result<()> {
+    fs::create_dir_all(dir).context("Failed to create hosts backup directory")?;
+
+    let original_path = dir.join(format!("{}.{}", ORIGINAL_ID, EXTENSION));
+    if !original_path.exists() {
+        // Installs from before versioned backups already have a managed block;
+        // without it the file is as close to the original as we can get
+        let original = if content.lines().any(|l| l.trim() == super::SENTINEL_START) {
+            super::build_hosts_content(content, &[])
+        } else {
+            content.to_string()
+        };
+        fs::write(&original_path, original).context("Failed to save original hosts file")?;
+        info!("Saved original hosts file to {}", original_path.display());
+    }
+
+    let hash = sha256_hex(content);
+    let backups = list_in(dir)?;
+    if backups
+        .iter()
+        .find(|b| !b.original)
+        .is_some_and(|latest| latest.sha256 == hash)
+    {
+        return Ok(());
+    }
+    let path = dir.join(format!("{}-{}.{}", now_ms, &hash[..12], EXTENSION));
+    fs::write(&path, content).context("Failed to create hosts backup")?;
+
+    // Newest first; drop what falls past the limit, counting the one just written
+    for old in backups.iter().filter(|b| !b.original).skip(MAX_BACKUPS - 1) {
+        let old_path = dir.join(format!("{}.{}", old.id, EXTENSION));
+        if let Err(e) = fs::remove_file(&old_path) {
+            warn!("Failed to remove old hosts backup {}: {}", old.id, e);
+        }
+    }
+    Ok(())
+}
+
+/// Backups newest first, with the original last.
+fn list_in(dir: &Path) -> Result<Vec<HostsBackup>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to read hosts backup directory"),
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let original = id == ORIGINAL_ID;
+        let created_ms = if original {
+            None
+        } else {
+            match id
+                .split_once('-')
+                .and_then(|(ms, _)| ms.parse::<i64>().ok())
+            {
+                Some(ms) => Some(ms),
+                None => continue,
+            }
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let created_at = match created_ms {
+            Some(ms) => ms / 1000,
+            None => entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64),
+        };
+        backups.push((
+            created_ms.unwrap_or(i64::MIN),
+            HostsBackup {
+                id: id.to_string(),
+                created_at,
+                sha256: sha256_hex(&content),
+                size: content.len() as u64,
+                original,
+            },
+        ));
+    }
+    backups.sort_by_key(|(created_ms, _)| std::cmp::Reverse(*created_ms));
+    Ok(backups.into_iter().map(|(_, b)| b).collect())
+}
+
+fn read_in(dir: &Path, id: &str) -> Result<String> {
+    // Ids come from `list`; anything else could point outside the directory
+    if !list_in(dir)?.iter().any(|b| b.id == id) {
+        bail!("Hosts backup '{}' not found", id);
+    }
+    fs::read_to_string(dir.join(format!("{}.{}", id, EXTENSION)))
+        .with_context(|| format!("Failed to read hosts backup '{}'", id))
+}
+
+/// Line diff (longest common subsequence) from `old` to `new`.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let line = |op, text: &str| DiffLine {
+        op,
+        text: text.to_string(),
+    };
+
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        return a
+            .iter()
+            .map(|t| line(DiffOp::Removed, t))
+            .chain(b.iter().map(|t| line(DiffOp::Added, t)))
+            .collect();
+    }
+
+    // lcs[i][j]: common subsequence length of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::with_capacity(a.len().max(b.len()));
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push(line(DiffOp::Same, a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            out.push(line(DiffOp::Removed, a[i]));
+            i += 1;
+        } else {
+            out.push(line(DiffOp::Added, b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|t| line(DiffOp::Removed, t)));
+    out.extend(b[j..].iter().map(|t| line(DiffOp::Added, t)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use localdomain_shared::domain::HostsEntry;
+
+    #[test]
+    fn test_snapshots_rotate_and_keep_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let pristine = "127.0.0.1\tlocalhost\n\n\n";
+        snapshot_in(dir.path(), pristine, 1_000).unwrap();
+        // Unchanged content is not stored twice
+        snapshot_in(dir.path(), pristine, 2_000).unwrap();
+        assert_eq!(list_in(dir.path()).unwrap().len(), 2);
+
+        for n in 0..MAX_BACKUPS + 5 {
+            let content = format!("{}10.0.0.{}\thost{}\n", pristine, n, n);
+            snapshot_in(dir.path(), &content, 10_000 + n as i64).unwrap();
+        }
+        let backups = list_in(dir.path()).unwrap();
+        assert_eq!(backups.len(), MAX_BACKUPS + 1);
+        assert!(backups[0]
+            .id
+            .starts_with(&format!("{}-", 10_000 + MAX_BACKUPS + 4)));
+        assert_eq!(backups[0].created_at, 10);
+
+        // The original survives rotation byte for byte
+        let original = backups.last().unwrap();
+        assert!(original.original);
+        assert_eq!(read_in(dir.path(), ORIGINAL_ID).unwrap(), pristine);
+        assert_eq!(original.sha256, sha256_hex(pristine));
+
+        assert!(read_in(dir.path(), "../../etc/shadow").is_err());
+    }
+
+    #[test]
+    fn test_original_from_managed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let managed = super::super::build_hosts_content(
+            "127.0.0.1\tlocalhost\n",
+            &[HostsEntry {
+                domain: "app.test".to_string(),
+                ip: "127.0.0.1".to_string(),
+            }],
+        );
+        snapshot_in(dir.path(), &managed, 1_000).unwrap();
+        assert_eq!(
+            read_in(dir.path(), ORIGINAL_ID).unwrap(),
+            "127.0.0.1\tlocalhost\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let old = "127.0.0.1 localhost\n# LocalDomain Start\n127.0.0.1 a.test\n# LocalDomain End\n";
+        let new = "127.0.0.1 localhost\n10.0.0.1 vpn.corp\n# LocalDomain Start\n127.0.0.1 b.test\n# LocalDomain End\n";
+        let summary: Vec<String> = diff_lines(old, new)
+            .iter()
+            .map(|l| {
+                let sign = match l.op {
+                    DiffOp::Same => ' ',
+                    DiffOp::Added => '+',
+                    DiffOp::Removed => '-',
+                };
+                format!("{}{}", sign, l.text)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                " 127.0.0.1 localhost",
+                "+10.0.0.1 vpn.corp",
+                " # LocalDomain Start",
+                "-127.0.0.1 a.test",
+                "+127.0.0.1 b.test",
+                " # LocalDomain End",
+            ]
+        );
+    }
+}
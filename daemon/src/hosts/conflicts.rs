@@ -0,0 +1,232 @@
+use localdomain_shared::domain::HostsEntry;
+use localdomain_shared::protocol::{DomainWarning, DomainWarningKind};
+use std::fs;
+
+use super::{dns, watcher};
+use crate::paths;
+
+/// TLDs on the HSTS preload list as a whole. Browsers upgrade every name under
+/// them to HTTPS and won't let users click through certificate errors.
+const HSTS_TLDS: &[&str] = &[
+    "app",
+    "bank",
+    "boo",
+    "channel",
+    "dad",
+    "day",
+    "dev",
+    "esq",
+    "foo",
+    "gle",
+    "google",
+    "ing",
+    "insurance",
+    "meme",
+    "mov",
+    "new",
+    "nexus",
+    "page",
+    "phd",
+    "prof",
+    "rsvp",
+    "soy",
+    "zip",
+];
+
+/// Reserved or private-use TLDs (RFC 2606, RFC 6761, ICANN's `.internal`) that
+/// can't exist in public DNS, so there is nothing to look up.
+const PRIVATE_TLDS: &[&str] = &[
+    "test",
+    "localhost",
+    "invalid",
+    "example",
+    "local",
+    "internal",
+    "lan",
+    "home.arpa",
+    "localdomain",
+];
+
+/// Problems with syncing `entries`: clashes with lines outside the managed block,
+/// names that exist in public DNS, and TLDs browsers or resolvers treat specially.
+pub fn check(entries: &[HostsEntry]) -> Vec<DomainWarning> {
+    let content = fs::read_to_string(paths::HOSTS_FILE).unwrap_or_default();
+    let mut warnings = hosts_conflicts(&content, entries);
+    for entry in entries {
+        warnings.extend(tld_warning(&entry.domain));
+        if has_private_tld(&entry.domain) {
+            continue;
+        }
+        let public = dns::public_addresses(&entry.domain);
+        if let Some(addr) = public.first() {
+            warnings.push(DomainWarning {
+                domain: entry.domain.clone(),
+                kind: DomainWarningKind::PublicDns,
+                message: format!(
+                    "{} is a public name (it resolves to {}). The hosts entry hides the real site on this machine",
+                    entry.domain, addr
+                ),
+            });
+        }
+    }
+    warnings
+}
+
+/// Names from `entries` that also appear outside the managed block. Those lines
+/// come first in the file, so the resolver uses them instead of ours.
+pub fn hosts_conflicts(content: &str, entries: &[HostsEntry]) -> Vec<DomainWarning> {
+    let unmanaged: Vec<(&str, &str)> = watcher::outside_lines(content)
+        .into_iter()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            let ip = fields.next()?;
+            Some(fields.map(move |name| (ip, name)))
+        })
+        .flatten()
+        .collect();
+
+    let mut warnings = Vec::new();
+    for entry in entries {
+        let Some((ip, _)) = unmanaged
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(&entry.domain))
+        else {
+            continue;
+        };
+        let (kind, message) = if *ip == entry.ip {
+            (
+                DomainWarningKind::HostsDuplicate,
+                format!(
+                    "{} is already in the hosts file outside LocalDomain's block; removing that line avoids surprises if it changes",
+                    entry.domain
+                ),
+            )
+        } else {
+            (
+                DomainWarningKind::HostsConflict,
+                format!(
+                    "{} is already in the hosts file as {}, and that line takes precedence over LocalDomain's {}",
+                    entry.domain, ip, entry.ip
+                ),
+            )
+        };
+        warnings.push(DomainWarning {
+            domain: entry.domain.clone(),
+            kind,
+            message,
+        });
+    }
+    warnings
+}
+
+fn tld_of(name: &str) -> String {
+    name.trim_end_matches('.')
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+fn has_private_tld(name: &str) -> bool {
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+    PRIVATE_TLDS
+        .iter()
+        .any(|tld| name == *tld || name.ends_with(&format!(".{}", tld)))
+}
+
+fn tld_warning(name: &str) -> Option<DomainWarning> {
+    let tld = tld_of(name);
+    let (kind, message) = if HSTS_TLDS.contains(&tld.as_str()) {
+        (
+            DomainWarningKind::HstsTld,
+            format!(
+                ".{} is HSTS-preloaded: browsers only open {} over HTTPS with a trusted certificate. Consider .test",
+                tld, name
+            ),
+        )
+    } else if tld == "local" {
+        (
+            DomainWarningKind::MdnsTld,
+            format!(
+                ".local is reserved for multicast DNS (Bonjour/Avahi), so lookups of {} can be slow or bypass the hosts file. Consider .test",
+                name
+            ),
+        )
+    } else {
+        return None;
+    };
+    Some(DomainWarning {
+        domain: name.to_string(),
+        kind,
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(domain: &str, ip: &str) -> HostsEntry {
+        HostsEntry {
+            domain: domain.to_string(),
+            ip: ip.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_hosts_conflicts() {
+        let content = "127.0.0.1\tlocalhost\n\
+            10.0.0.5 api.project.test Docs.Project.Test # added by VPN\n\
+            127.0.0.1 project.test\n\
+            # 10.0.0.9 commented.test\n\
+            \n# LocalDomain Start\n127.0.0.1\tmanaged.test\n# LocalDomain End\n";
+        let warnings = hosts_conflicts(
+            content,
+            &[
+                entry("project.test", "127.0.0.1"),
+                entry("api.project.test", "127.0.0.1"),
+                entry("docs.project.test", "10.0.0.5"),
+                entry("commented.test", "127.0.0.1"),
+                entry("managed.test", "127.0.0.1"),
+                entry("added", "127.0.0.1"),
+            ],
+        );
+        let kinds: Vec<(&str, DomainWarningKind)> = warnings
+            .iter()
+            .map(|w| (w.domain.as_str(), w.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("project.test", DomainWarningKind::HostsDuplicate),
+                ("api.project.test", DomainWarningKind::HostsConflict),
+                ("docs.project.test", DomainWarningKind::HostsDuplicate),
+            ]
+        );
+        assert!(warnings[1].message.contains("10.0.0.5"));
+    }
+
+    #[test]
+    fn test_tld_warnings() {
+        assert_eq!(
+            tld_warning("project.dev").map(|w| w.kind),
+            Some(DomainWarningKind::HstsTld)
+        );
+        assert_eq!(
+            tld_warning("API.Project.APP").map(|w| w.kind),
+            Some(DomainWarningKind::HstsTld)
+        );
+        assert_eq!(
+            tld_warning("printer.local").map(|w| w.kind),
+            Some(DomainWarningKind::MdnsTld)
+        );
+        assert!(tld_warning("project.test").is_none());
+        assert!(tld_warning("devtools.test").is_none());
+
+        assert!(has_private_tld("project.test"));
+        assert!(has_private_tld("router.home.arpa"));
+        assert!(!has_private_tld("project.dev"));
+        assert!(!has_private_tld("latest.com"));
+    }
+}
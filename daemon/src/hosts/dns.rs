@@ -0,0 +1,277 @@
+//! Minimal DNS client for checking whether a name exists publicly. The system
+//! resolver can't answer that: it consults the hosts file first.
+
+use anyhow::{bail, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_millis(1500);
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+/// Nameservers tried per query, in resolv.conf order.
+const MAX_SERVERS: usize = 2;
+
+/// Publicly routable addresses DNS returns for `name`. Private and loopback
+/// answers don't count: those come from split-horizon DNS or resolvers that
+/// read the hosts file.
+pub fn public_addresses(name: &str) -> Vec<IpAddr> {
+    let servers = nameservers();
+    let mut addrs = Vec::new();
+    for qtype in [TYPE_A, TYPE_AAAA] {
+        for server in servers.iter().take(MAX_SERVERS) {
+            if let Ok(found) = query(*server, name, qtype) {
+                addrs.extend(found.into_iter().filter(is_public));
+                break;
+            }
+        }
+    }
+    addrs
+}
+
+fn query(server: SocketAddr, name: &str, qtype: u16) -> Result<Vec<IpAddr>> {
+    let bind: SocketAddr = if server.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(bind)?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+    socket.connect(server)?;
+
+    let id = (uuid::Uuid::new_v4().as_u128() & 0xffff) as u16;
+    socket.send(&build_query(id, name, qtype)?)?;
+    let mut buf = [0u8; 1500];
+    let len = socket.recv(&mut buf)?;
+    parse_response(id, &buf[..len])
+}
+
+fn build_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(18 + name.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Standard query, recursion desired; one question
+    packet.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            bail!("Invalid DNS name '{}'", name);
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes()); // class IN
+    Ok(packet)
+}
+
+fn parse_response(id: u16, buf: &[u8]) -> Result<Vec<IpAddr>> {
+    if buf.len() < 12 || u16::from_be_bytes([buf[0], buf[1]]) != id {
+        bail!("Unexpected DNS response");
+    }
+    if buf[2] & 0x80 == 0 {
+        bail!("DNS packet is not a response");
+    }
+    match buf[3] & 0x0f {
+        0 => {}
+        // NXDOMAIN: the name doesn't exist
+        3 => return Ok(Vec::new()),
+        rcode => bail!("DNS server returned error {}", rcode),
+    }
+    let questions = u16::from_be_bytes([buf[4], buf[5]]);
+    let answers = u16::from_be_bytes([buf[6], buf[7]]);
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(buf, pos)? + 4;
+    }
+    let mut addrs = Vec::new();
+    for _ in 0..answers {
+        pos = skip_name(buf, pos)?;
+        let Some(header) = buf.get(pos..pos + 10) else {
+            bail!("Truncated DNS answer");
+        };
+        let rtype = u16::from_be_bytes([header[0], header[1]]);
+        let rdlength = u16::from_be_bytes([header[8], header[9]]) as usize;
+        pos += 10;
+        let Some(rdata) = buf.get(pos..pos + rdlength) else {
+            bail!("Truncated DNS answer");
+        };
+        match (rtype, rdata.len()) {
+            (TYPE_A, 4) => addrs.push(IpAddr::from(<[u8; 4]>::try_from(rdata)?)),
+            (TYPE_AAAA, 16) => addrs.push(IpAddr::from(<[u8; 16]>::try_from(rdata)?)),
+            // CNAMEs and anything else; the addresses follow as separate records
+            _ => {}
+        }
+        pos += rdlength;
+    }
+    Ok(addrs)
+}
+
+/// Position after the (possibly compressed) name starting at `pos`.
+fn skip_name(buf: &[u8], mut pos: usize) -> Result<usize> {
+    loop {
+        let Some(&len) = buf.get(pos) else {
+            bail!("Truncated DNS name");
+        };
+        match len {
+            0 => return Ok(pos + 1),
+            // A pointer ends the name
+            l if l & 0xc0 == 0xc0 => return Ok(pos + 2),
+            l => pos += 1 + l as usize,
+        }
+    }
+}
+
+fn is_public(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                // Unique local fc00::/7 and link-local fe80::/10
+                || first & 0xfe00 == 0xfc00
+                || first & 0xffc0 == 0xfe80)
+        }
+    }
+}
+
+/// Upstream nameservers, preferring ones that don't answer from the hosts file.
+fn nameservers() -> Vec<SocketAddr> {
+    let mut servers = configured_nameservers();
+    // Local stubs like systemd-resolved read the hosts file; skip them when
+    // an upstream server is known
+    if servers.iter().any(|s| !s.ip().is_loopback()) {
+        servers.retain(|s| !s.ip().is_loopback());
+    }
+    servers
+}
+
+#[cfg(unix)]
+fn configured_nameservers() -> Vec<SocketAddr> {
+    // systemd-resolved keeps the upstream servers here; /etc/resolv.conf only
+    // lists its stub
+    ["/run/systemd/resolve/resolv.conf", "/etc/resolv.conf"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|content| parse_resolv_conf(&content))
+        .unwrap_or_default()
+}
+
+#[cfg(windows)]
+fn configured_nameservers() -> Vec<SocketAddr> {
+    let output = localdomain_shared::silent_cmd("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "(Get-DnsClientServerAddress).ServerAddresses",
+        ])
+        .output();
+    match output {
+        Ok(out) => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse::<IpAddr>().ok())
+            .map(|ip| SocketAddr::new(ip, 53))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(unix)]
+fn parse_resolv_conf(content: &str) -> Vec<SocketAddr> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        // Drop IPv6 zone ids such as fe80::1%eth0
+        .filter_map(|addr| addr.trim().split('%').next()?.parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 53))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_query() {
+        let packet = build_query(0x1234, "app.dev", TYPE_A).unwrap();
+        assert_eq!(
+            packet,
+            [
+                0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0, //
+                3, b'a', b'p', b'p', 3, b'd', b'e', b'v', 0, //
+                0, 1, 0, 1,
+            ]
+        );
+        assert!(build_query(1, "a..dev", TYPE_A).is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let mut response = build_query(0x1234, "www.app.dev", TYPE_A).unwrap();
+        response[2] = 0x81;
+        response[3] = 0x80;
+        // Two answers: CNAME www.app.dev -> app.dev, using a pointer into the
+        // question name, then A app.dev -> 216.239.32.21
+        response[7] = 2;
+        response.extend_from_slice(&[0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 2, 0xc0, 16]);
+        response.extend_from_slice(&[0xc0, 16, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 216, 239, 32, 21]);
+        assert_eq!(
+            parse_response(0x1234, &response).unwrap(),
+            vec![IpAddr::from([216, 239, 32, 21])]
+        );
+        assert!(parse_response(0x9999, &response).is_err());
+
+        // NXDOMAIN
+        response[3] = 0x83;
+        assert!(parse_response(0x1234, &response).unwrap().is_empty());
+
+        // Truncated answer
+        response[3] = 0x80;
+        response.truncate(response.len() - 2);
+        assert!(parse_response(0x1234, &response).is_err());
+    }
+
+    #[test]
+    fn test_is_public() {
+        assert!(is_public(&"216.239.32.21".parse().unwrap()));
+        assert!(is_public(&"2001:4860:4802:32::15".parse().unwrap()));
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "192.168.1.20",
+            "172.17.0.2",
+            "100.64.0.1",
+            "169.254.1.1",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+        ] {
+            assert!(!is_public(&private.parse().unwrap()), "{}", private);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_resolv_conf() {
+        let servers = parse_resolv_conf(
+            "# generated\nnameserver 127.0.0.53\nnameserver fe80::1%eth0\noptions edns0\nsearch lan\n",
+        );
+        assert_eq!(
+            servers,
+            vec![
+                "127.0.0.53:53".parse().unwrap(),
+                "[fe80::1]:53".parse().unwrap()
+            ]
+        );
+    }
+}
@@ -1,8 +1,14 @@
+pub mod backup;
+pub mod conflicts;
+mod dns;
+pub mod watcher;
+
 use anyhow::{bail, Context, Result};
 use localdomain_shared::domain::HostsEntry;
+use localdomain_shared::protocol::DiffLine;
 use std::fs;
 use std::io::Write;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Validate a hosts entry to prevent injection of arbitrary entries.
 fn validate_hosts_entry(entry: &HostsEntry) -> Result<()> {
@@ -22,7 +28,7 @@ fn validate_hosts_entry(entry: &HostsEntry) -> Result<()> {
     Ok(())
 }
 
-use crate::paths;
+use crate::{overrides, paths};
 
 const SENTINEL_START: &str = "# LocalDomain Start";
 const SENTINEL_END: &str = "# LocalDomain End";
@@ -33,14 +39,54 @@ pub fn sync_hosts(entries: &[HostsEntry]) -> Result<()> {
         validate_hosts_entry(entry)?;
     }
 
-    let hosts_path = paths::HOSTS_FILE;
-    let current = fs::read_to_string(hosts_path).context("Failed to read hosts file")?;
+    // Expired overrides stay out even if the app hasn't noticed yet
+    let entries: Vec<HostsEntry> = entries
+        .iter()
+        .filter(|entry| !overrides::is_expired(&entry.domain))
+        .cloned()
+        .collect();
 
-    // Create backup
-    let backup_path = format!("{}.localdomain.bak", hosts_path);
-    fs::write(&backup_path, &current).context("Failed to create hosts backup")?;
+    let mut state = watcher::state();
+    let content = write_hosts(&entries)?;
+    // The watcher repairs or reports anything that later changes this block
+    state.record(&entries, content);
+    Ok(())
+}
 
+/// Drop `names` from the managed block, keeping the other entries.
+pub fn remove_names(names: &[String]) -> Result<()> {
+    let mut state = watcher::state();
+    let current = fs::read_to_string(paths::HOSTS_FILE).context("Failed to read hosts file")?;
+    let entries: Vec<HostsEntry> = parse_managed_entries(&current)
+        .into_iter()
+        .filter(|entry| !names.iter().any(|n| n.eq_ignore_ascii_case(&entry.domain)))
+        .collect();
+    let new_content = build_hosts_content(&current, &entries);
+    if new_content != current {
+        write_file(&current, &new_content)?;
+        info!("Removed {} from the hosts file", names.join(", "));
+    }
+    state.record(&entries, new_content);
+    Ok(())
+}
+
+/// Replace the managed block and return the new file content.
+fn write_hosts(entries: &[HostsEntry]) -> Result<String> {
+    let current = fs::read_to_string(paths::HOSTS_FILE).context("Failed to read hosts file")?;
+    for warning in conflicts::hosts_conflicts(&current, entries) {
+        warn!("{}", warning.message);
+    }
     let new_content = build_hosts_content(&current, entries);
+    write_file(&current, &new_content)?;
+
+    info!("Updated hosts file with {} entries", entries.len());
+    Ok(new_content)
+}
+
+/// Snapshot `current`, then replace the hosts file with `new_content`.
+fn write_file(current: &str, new_content: &str) -> Result<()> {
+    let hosts_path = paths::HOSTS_FILE;
+    backup::snapshot(current)?;
 
     // Write new content via temp file + rename (atomic on Unix).
     // On Windows, rename can fail on the hosts file because the DNS Client service
@@ -60,11 +106,85 @@ pub fn sync_hosts(entries: &[HostsEntry]) -> Result<()> {
             let _ = fs::remove_file(&tmp_path);
         }
     }
+    Ok(())
+}
 
-    info!("Updated hosts file with {} entries", entries.len());
+/// Line diff from a stored backup to the current hosts file.
+pub fn diff_backup(id: &str) -> Result<Vec<DiffLine>> {
+    let backup = backup::read(id)?;
+    let current = fs::read_to_string(paths::HOSTS_FILE).context("Failed to read hosts file")?;
+    Ok(backup::diff_lines(&backup, &current))
+}
+
+/// Replace the hosts file with a stored backup. The current file is
+/// snapshotted first, so a restore can itself be undone.
+pub fn restore_backup(id: &str) -> Result<()> {
+    let content = backup::read(id)?;
+    for entry in parse_managed_entries(&content) {
+        validate_hosts_entry(&entry)?;
+    }
+
+    let mut state = watcher::state();
+    let current = fs::read_to_string(paths::HOSTS_FILE).context("Failed to read hosts file")?;
+    write_file(&current, &content)?;
+    // The restored block becomes what the watcher protects
+    state.record(&parse_managed_entries(&content), content);
+    info!("Restored hosts file from backup {}", id);
     Ok(())
 }
 
+/// Put back the hosts file as it was before LocalDomain changed it. Returns
+/// false when the file was edited outside the managed block since then; those
+/// edits are kept and only the block is removed.
+pub fn restore_original() -> Result<bool> {
+    let mut state = watcher::state();
+    let current = fs::read_to_string(paths::HOSTS_FILE).context("Failed to read hosts file")?;
+    let stripped = build_hosts_content(&current, &[]);
+    let (content, exact) = match backup::original() {
+        Some(original) if build_hosts_content(&original, &[]) == stripped => (original, true),
+        _ => (stripped, false),
+    };
+    if content != current {
+        write_file(&current, &content)?;
+    }
+    state.record(&[], content);
+    info!("Restored original hosts file (exact: {})", exact);
+    Ok(exact)
+}
+
+/// Domains in the LocalDomain block of the hosts file.
+pub fn managed_domains() -> Vec<String> {
+    fs::read_to_string(paths::HOSTS_FILE)
+        .map(|content| parse_managed_domains(&content))
+        .unwrap_or_default()
+}
+
+fn parse_managed_domains(content: &str) -> Vec<String> {
+    parse_managed_entries(content)
+        .into_iter()
+        .map(|entry| entry.domain)
+        .collect()
+}
+
+fn parse_managed_entries(content: &str) -> Vec<HostsEntry> {
+    content
+        .lines()
+        .skip_while(|line| line.trim() != SENTINEL_START)
+        .skip(1)
+        .take_while(|line| line.trim() != SENTINEL_END)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some(ip), Some(domain)) => Some(HostsEntry {
+                    domain: domain.to_string(),
+                    ip: ip.to_string(),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
 fn build_hosts_content(current: &str, entries: &[HostsEntry]) -> String {
     let mut lines: Vec<&str> = Vec::new();
     let mut in_block = false;
@@ -143,4 +263,24 @@ mod tests {
         assert!(!result.contains("old.test"));
         assert!(result.contains("new.test"));
     }
+
+    #[test]
+    fn test_parse_managed_domains() {
+        let entries = vec![
+            HostsEntry {
+                domain: "app.test".to_string(),
+                ip: "127.0.0.1".to_string(),
+            },
+            HostsEntry {
+                domain: "api.test".to_string(),
+                ip: "127.0.0.1".to_string(),
+            },
+        ];
+        let content = build_hosts_content("127.0.0.1\tlocalhost\n", &entries);
+        assert_eq!(
+            parse_managed_domains(&content),
+            vec!["app.test", "api.test"]
+        );
+        assert!(parse_managed_domains("127.0.0.1\tlocalhost\n").is_empty());
+    }
 }
@@ -0,0 +1,406 @@
+use localdomain_shared::domain::HostsEntry;
+use localdomain_shared::protocol::{HostsTamperEvent, HostsTamperPolicy};
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::fs;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{info, warn};
+
+use super::{SENTINEL_END, SENTINEL_START};
+use crate::{events, paths, settings};
+
+/// Let a burst of writes (editors, temp file + rename) settle before reading.
+const SETTLE: Duration = Duration::from_millis(300);
+/// How often the file is checked where inotify is unavailable.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Stop repairing when something rewrites the block this often, rather than
+/// fighting another program over the file.
+const MAX_REPAIRS: usize = 5;
+const REPAIR_WINDOW: Duration = Duration::from_secs(60);
+/// Cap on lines copied into an event.
+const MAX_REPORTED_LINES: usize = 20;
+
+#[derive(Default)]
+pub struct WatchState {
+    /// Entries of the last sync; None until the app syncs after the daemon starts
+    applied: Option<Vec<HostsEntry>>,
+    /// File content as last written or inspected, the baseline for the next diff
+    baseline: String,
+    repairs: VecDeque<Instant>,
+}
+
+impl WatchState {
+    pub(super) fn record(&mut self, entries: &[HostsEntry], content: String) {
+        self.applied = Some(entries.to_vec());
+        self.baseline = content;
+    }
+}
+
+/// Held while the hosts file is written so syncs and repairs don't interleave.
+static STATE: Lazy<Mutex<WatchState>> = Lazy::new(|| Mutex::new(WatchState::default()));
+
+pub(super) fn state() -> MutexGuard<'static, WatchState> {
+    STATE.lock().unwrap()
+}
+
+pub fn take_hosts_events() -> Vec<HostsTamperEvent> {
+    events::take(|p| &mut p.hosts)
+}
+
+/// Watch the hosts file for the life of the daemon on a dedicated thread.
+pub fn start() {
+    if let Err(e) = std::thread::Builder::new()
+        .name("hosts-watcher".to_string())
+        .spawn(run)
+    {
+        warn!("Failed to start hosts file watcher: {}", e);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn run() {
+    let path = std::path::Path::new(paths::HOSTS_FILE);
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+    let watch = match inotify::Watch::new(dir) {
+        Ok(watch) => watch,
+        Err(e) => {
+            warn!(
+                "inotify unavailable ({}); polling the hosts file instead",
+                e
+            );
+            return poll();
+        }
+    };
+    info!("Watching {} for external changes", paths::HOSTS_FILE);
+    loop {
+        if let Err(e) = watch.wait_for(name) {
+            warn!("inotify failed ({}); polling the hosts file instead", e);
+            return poll();
+        }
+        std::thread::sleep(SETTLE);
+        check();
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run() {
+    info!("Polling {} for external changes", paths::HOSTS_FILE);
+    poll();
+}
+
+fn poll() {
+    let stamp = || -> Option<(SystemTime, u64)> {
+        let meta = fs::metadata(paths::HOSTS_FILE).ok()?;
+        Some((meta.modified().ok()?, meta.len()))
+    };
+    let mut last = stamp();
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let current = stamp();
+        if current != last {
+            last = current;
+            check();
+        }
+    }
+}
+
+/// Compare the hosts file with the last applied entries and apply the tamper policy.
+fn check() {
+    let Ok(mut content) = fs::read_to_string(paths::HOSTS_FILE) else {
+        return;
+    };
+    let mut state = state();
+    let Some(entries) = state.applied.clone() else {
+        return;
+    };
+    if content == state.baseline {
+        return;
+    }
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let Some(mut event) = detect(&entries, &state.baseline, &content, now) else {
+        state.baseline = content;
+        return;
+    };
+    warn!(
+        "Managed hosts block was changed externally ({} missing, {} unexpected)",
+        event.missing.len(),
+        event.unexpected.len()
+    );
+
+    if settings::get().hosts_tamper_policy == HostsTamperPolicy::Repair {
+        let now = Instant::now();
+        state
+            .repairs
+            .retain(|t| now.duration_since(*t) < REPAIR_WINDOW);
+        if state.repairs.len() >= MAX_REPAIRS {
+            event.error = Some("Not repaired: the hosts file keeps being rewritten".to_string());
+        } else {
+            state.repairs.push_back(now);
+            match super::write_hosts(&entries) {
+                Ok(written) => {
+                    info!("Repaired managed hosts block");
+                    event.repaired = true;
+                    content = written;
+                }
+                Err(e) => event.error = Some(e.to_string()),
+            }
+        }
+    }
+    state.baseline = content;
+    events::push(|p| &mut p.hosts, [event]);
+}
+
+/// Report how `current` differs from what was applied, if the managed block no
+/// longer matches `entries` and has changed since `baseline` (so one edit is
+/// reported once, not on every later write).
+fn detect(
+    entries: &[HostsEntry],
+    baseline: &str,
+    current: &str,
+    detected_at: i64,
+) -> Option<HostsTamperEvent> {
+    let block = managed_block(current);
+    if block == managed_block(baseline) {
+        return None;
+    }
+
+    let found: Vec<(&str, &str)> = block.iter().filter_map(|line| pair(line)).collect();
+    let missing: Vec<HostsEntry> = entries
+        .iter()
+        .filter(|e| !found.contains(&(e.ip.as_str(), e.domain.as_str())))
+        .cloned()
+        .collect();
+    let unexpected: Vec<String> = block
+        .iter()
+        .filter(|line| {
+            !pair(line).is_some_and(|(ip, domain)| {
+                entries.iter().any(|e| e.ip == ip && e.domain == domain)
+            })
+        })
+        .take(MAX_REPORTED_LINES)
+        .map(|line| line.to_string())
+        .collect();
+    if missing.is_empty() && unexpected.is_empty() {
+        return None;
+    }
+
+    let before = outside_lines(baseline);
+    let after = outside_lines(current);
+    let diff = |a: &[&str], b: &[&str]| -> Vec<String> {
+        a.iter()
+            .filter(|line| !b.contains(line))
+            .take(MAX_REPORTED_LINES)
+            .map(|line| line.to_string())
+            .collect()
+    };
+    Some(HostsTamperEvent {
+        detected_at,
+        missing,
+        unexpected,
+        added_lines: diff(&after, &before),
+        removed_lines: diff(&before, &after),
+        repaired: false,
+        error: None,
+    })
+}
+
+/// Non-empty lines between the sentinels; empty when the block is gone.
+fn managed_block(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .skip_while(|line| line.trim() != SENTINEL_START)
+        .skip(1)
+        .take_while(|line| line.trim() != SENTINEL_END)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+pub(super) fn outside_lines(content: &str) -> Vec<&str> {
+    let mut in_block = false;
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            if *line == SENTINEL_START {
+                in_block = true;
+            } else if *line == SENTINEL_END {
+                in_block = false;
+                return false;
+            }
+            !in_block && !line.is_empty()
+        })
+        .collect()
+}
+
+/// `(ip, domain)` of a hosts line, ignoring comments.
+fn pair(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let mut fields = line.split_whitespace();
+    Some((fields.next()?, fields.next()?))
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::ffi::{CString, OsStr};
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Watches a directory: the hosts file is usually replaced by rename, which
+    /// a watch on the file itself would stop seeing.
+    pub struct Watch {
+        fd: libc::c_int,
+    }
+
+    impl Watch {
+        pub fn new(dir: &Path) -> io::Result<Self> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let watch = Self { fd };
+            let path = CString::new(dir.as_os_str().as_bytes())?;
+            let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE | libc::IN_DELETE;
+            if unsafe { libc::inotify_add_watch(fd, path.as_ptr(), mask) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(watch)
+        }
+
+        /// Block until an event names `file_name`.
+        pub fn wait_for(&self, file_name: &OsStr) -> io::Result<()> {
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = unsafe { libc::read(self.fd, buf.as_mut_ptr().cast(), buf.len()) };
+                if n < 0 {
+                    let err = io::Error::last_os_error();
+                    if err.kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return Err(err);
+                }
+                if event_names(&buf[..n as usize]).any(|name| name == file_name.as_bytes()) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    impl Drop for Watch {
+        fn drop(&mut self) {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+    }
+
+    /// Names carried by a buffer of `struct inotify_event` records.
+    pub(super) fn event_names(buf: &[u8]) -> impl Iterator<Item = &[u8]> {
+        const HEADER: usize = std::mem::size_of::<libc::inotify_event>();
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            if offset + HEADER > buf.len() {
+                return None;
+            }
+            let len_bytes = buf[offset + HEADER - 4..offset + HEADER].try_into().ok()?;
+            let len = u32::from_ne_bytes(len_bytes) as usize;
+            let end = (offset + HEADER + len).min(buf.len());
+            let name = &buf[offset + HEADER..end];
+            offset += HEADER + len;
+            name.split(|&b| b == 0).next()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(domain: &str) -> HostsEntry {
+        HostsEntry {
+            domain: domain.to_string(),
+            ip: "127.0.0.1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_detect_tampering() {
+        let entries = vec![entry("app.test"), entry("api.test")];
+        let baseline = super::super::build_hosts_content("127.0.0.1\tlocalhost\n", &entries);
+
+        // Unrelated edits outside the block are not tampering
+        let edited = format!("{}10.0.0.5\tnas.lan\n", baseline);
+        assert!(detect(&entries, &baseline, &edited, 0).is_none());
+
+        // A tool rewriting the file without part of our block
+        let tampered = format!(
+            "127.0.0.1\tlocalhost\n# Added by Docker Desktop\n192.168.65.2\thost.docker.internal\n\n{}\n127.0.0.1\tapp.test\n10.8.0.1\tapi.test\n{}\n",
+            SENTINEL_START, SENTINEL_END
+        );
+        let event = detect(&entries, &baseline, &tampered, 42).unwrap();
+        assert_eq!(event.detected_at, 42);
+        assert_eq!(event.missing.len(), 1);
+        assert_eq!(event.missing[0].domain, "api.test");
+        assert_eq!(event.unexpected, vec!["10.8.0.1\tapi.test"]);
+        assert_eq!(
+            event.added_lines,
+            vec![
+                "# Added by Docker Desktop",
+                "192.168.65.2\thost.docker.internal"
+            ]
+        );
+        assert!(event.removed_lines.is_empty());
+
+        // The same block seen again is not reported twice
+        let later = format!("{}# touched\n", tampered);
+        assert!(detect(&entries, &tampered, &later, 43).is_none());
+
+        // Block removed entirely
+        let event = detect(&entries, &baseline, "127.0.0.1\tlocalhost\n", 44).unwrap();
+        assert_eq!(event.missing.len(), 2);
+        assert!(event.unexpected.is_empty());
+    }
+
+    #[test]
+    fn test_whitespace_changes_are_not_tampering() {
+        let entries = vec![entry("app.test")];
+        let baseline = super::super::build_hosts_content("", &entries);
+        let reformatted = baseline.replace("127.0.0.1\tapp.test", "127.0.0.1   app.test");
+        assert!(detect(&entries, &baseline, &reformatted, 0).is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_inotify_event_names() {
+        let mut buf = Vec::new();
+        for name in ["hosts.localdomain.tmp", "hosts"] {
+            let mut padded = name.as_bytes().to_vec();
+            padded.resize(name.len().div_ceil(16) * 16, 0);
+            buf.extend(1i32.to_ne_bytes());
+            buf.extend(libc::IN_MOVED_TO.to_ne_bytes());
+            buf.extend(0u32.to_ne_bytes());
+            buf.extend((padded.len() as u32).to_ne_bytes());
+            buf.extend(padded);
+        }
+        let names: Vec<&[u8]> = inotify::event_names(&buf).collect();
+        assert_eq!(names, [&b"hosts.localdomain.tmp"[..], &b"hosts"[..]]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_inotify_sees_rename() {
+        let dir = tempfile::tempdir().unwrap();
+        let watch = inotify::Watch::new(dir.path()).unwrap();
+        let tmp = dir.path().join("hosts.tmp");
+        fs::write(dir.path().join("other"), "x").unwrap();
+        fs::write(&tmp, "127.0.0.1\tlocalhost\n").unwrap();
+        fs::rename(&tmp, dir.path().join("hosts")).unwrap();
+        watch.wait_for(std::ffi::OsStr::new("hosts")).unwrap();
+    }
+}
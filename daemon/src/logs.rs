@@ -1,10 +1,17 @@
 use anyhow::{bail, Result};
-use localdomain_shared::protocol::AccessLogEntry;
+use localdomain_shared::protocol::{AccessLogEntry, AccessLogFilter, AccessLogPage, HeaderMatch};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::fs;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::paths;
 
+/// How often a tail checks its log for new lines.
+pub const TAIL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Validate that a domain name is safe for use in file paths.
 /// Rejects path traversal characters like '/', '..', '\', and null bytes.
 fn validate_domain_for_path(domain: &str) -> Result<()> {
@@ -28,57 +35,292 @@ fn log_path(domain: &str) -> Result<String> {
         .to_string())
 }
 
-pub fn read_access_log(domain: &str, limit: u64) -> Result<Vec<AccessLogEntry>> {
-    let path = log_path(domain)?;
-    let file = match fs::File::open(&path) {
-        Ok(f) => f,
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
-        Err(e) => return Err(e.into()),
-    };
+/// Parse one line of Caddy's JSON access log.
+fn parse_entry(line: &str) -> Option<AccessLogEntry> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    let parsed: serde_json::Value = serde_json::from_str(line).ok()?;
 
-    let reader = BufReader::new(file);
-    // Use a ring buffer to keep only the last `limit` entries in memory
-    let limit = limit as usize;
-    let mut entries: std::collections::VecDeque<AccessLogEntry> = std::collections::VecDeque::with_capacity(limit.min(1024) + 1);
+    let request = &parsed["request"];
+    Some(AccessLogEntry {
+        timestamp: parsed["ts"].as_f64().unwrap_or(0.0),
+        method: request["method"].as_str().unwrap_or("").to_string(),
+        uri: request["uri"].as_str().unwrap_or("").to_string(),
+        status: parsed["status"].as_u64().unwrap_or(0) as u16,
+        duration: parsed["duration"].as_f64().unwrap_or(0.0),
+        size: parsed["size"].as_u64().unwrap_or(0),
+        host: request["host"].as_str().unwrap_or("").to_string(),
+        headers: request["headers"].clone(),
+        resp_headers: parsed["resp_headers"].clone(),
+        remote_ip: request["remote_ip"].as_str().unwrap_or("").to_string(),
+        proto: request["proto"].as_str().unwrap_or("").to_string(),
+    })
+}
 
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => continue,
-        };
-        if line.trim().is_empty() {
-            continue;
+/// Position in a newest-first listing: everything after `ts` has been seen,
+/// and so have the first `skip` entries logged exactly at `ts`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cursor {
+    ts: f64,
+    skip: usize,
+}
+
+impl Cursor {
+    fn parse(cursor: &str) -> Result<Self> {
+        let parsed = cursor
+            .split_once(':')
+            .and_then(|(ts, skip)| Some((ts.parse().ok()?, skip.parse().ok()?)));
+        match parsed {
+            Some((ts, skip)) => Ok(Self { ts, skip }),
+            None => bail!("Invalid access log cursor '{}'", cursor),
         }
-        let parsed: serde_json::Value = match serde_json::from_str(&line) {
-            Ok(v) => v,
-            Err(_) => continue,
+    }
+
+    fn encode(&self) -> String {
+        format!("{}:{}", self.ts, self.skip)
+    }
+}
+
+/// An `AccessLogFilter` ready to test entries against.
+struct Matcher<'a> {
+    filter: &'a AccessLogFilter,
+    uri_regex: Option<regex_lite::Regex>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(filter: &'a AccessLogFilter) -> Result<Self> {
+        let uri_regex = match &filter.uri {
+            Some(pattern) if filter.uri_regex => Some(
+                regex_lite::Regex::new(pattern)
+                    .map_err(|e| anyhow::anyhow!("Invalid URI pattern: {}", e))?,
+            ),
+            _ => None,
         };
+        Ok(Self { filter, uri_regex })
+    }
+
+    fn matches(&self, entry: &AccessLogEntry) -> bool {
+        let f = self.filter;
+        if f.since.is_some_and(|since| entry.timestamp < since)
+            || f.until.is_some_and(|until| entry.timestamp > until)
+            || f.status_min.is_some_and(|min| entry.status < min)
+            || f.status_max.is_some_and(|max| entry.status > max)
+            || f.min_duration.is_some_and(|min| entry.duration < min)
+        {
+            return false;
+        }
+        if let Some(method) = &f.method {
+            if !entry.method.eq_ignore_ascii_case(method) {
+                return false;
+            }
+        }
+        if let Some(ip) = &f.remote_ip {
+            if entry.remote_ip != *ip {
+                return false;
+            }
+        }
+        if let Some(uri) = &f.uri {
+            let found = match &self.uri_regex {
+                Some(re) => re.is_match(&entry.uri),
+                None => entry.uri.contains(uri.as_str()),
+            };
+            if !found {
+                return false;
+            }
+        }
+        match &f.header {
+            Some(header) => header_matches(&entry.headers, header),
+            None => true,
+        }
+    }
+}
+
+/// Caddy logs request headers as `{"Name": ["value", ...]}`.
+fn header_matches(headers: &serde_json::Value, header: &HeaderMatch) -> bool {
+    let Some(headers) = headers.as_object() else {
+        return false;
+    };
+    let wanted = header.value.as_deref().map(str::to_lowercase);
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case(&header.name))
+        .any(|(_, values)| match &wanted {
+            None => true,
+            Some(wanted) => values
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str())
+                .any(|v| v.to_lowercase().contains(wanted.as_str())),
+        })
+}
+
+/// A domain's log files, oldest first. Caddy names rotated files after the
+/// log plus the rotation time (`app.test.access-2024-05-01T10-00-00.000.log`),
+/// so they sort in order; the current log comes last.
+fn log_files_in(dir: &Path, domain: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}.access-", domain);
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".log"))
+        })
+        .collect();
+    files.sort();
+    files.push(dir.join(format!("{}.access.log", domain)));
+    files
+}
+
+/// Domains with an access log, current or rotated.
+pub fn logged_domains() -> Vec<String> {
+    logged_domains_in(Path::new(paths::LOGS_DIR))
+}
 
-        let request = &parsed["request"];
-        let entry = AccessLogEntry {
-            timestamp: parsed["ts"].as_f64().unwrap_or(0.0),
-            method: request["method"].as_str().unwrap_or("").to_string(),
-            uri: request["uri"].as_str().unwrap_or("").to_string(),
-            status: parsed["status"].as_u64().unwrap_or(0) as u16,
-            duration: parsed["duration"].as_f64().unwrap_or(0.0),
-            size: parsed["size"].as_u64().unwrap_or(0),
-            host: request["host"].as_str().unwrap_or("").to_string(),
-            headers: request["headers"].clone(),
-            resp_headers: parsed["resp_headers"].clone(),
-            remote_ip: request["remote_ip"].as_str().unwrap_or("").to_string(),
-            proto: request["proto"].as_str().unwrap_or("").to_string(),
+/// Domains with a current or rotated log in `dir`, sorted.
+fn logged_domains_in(dir: &Path) -> Vec<String> {
+    let mut domains: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| {
+            let domain = match name.strip_suffix(".access.log") {
+                Some(domain) => domain,
+                None => &name[..name.strip_suffix(".log")?.rfind(".access-")?],
+            };
+            Some(domain.to_string())
+        })
+        .collect();
+    domains.sort();
+    domains.dedup();
+    domains
+}
+
+/// The newest `keep` matching entries at or before the cursor (oldest first),
+/// and how many matched in all.
+fn scan(
+    files: &[PathBuf],
+    matcher: &Matcher,
+    cursor: Option<Cursor>,
+    keep: usize,
+) -> (VecDeque<AccessLogEntry>, usize) {
+    // Use a ring buffer to keep only the last `keep` entries in memory
+    let mut entries = VecDeque::with_capacity(keep.min(1024) + 1);
+    let mut matched = 0;
+    for path in files {
+        let Ok(file) = fs::File::open(path) else {
+            continue;
         };
-        entries.push_back(entry);
-        if entries.len() > limit {
-            entries.pop_front();
+        for line in BufReader::new(file).lines() {
+            let Some(entry) = line.ok().as_deref().and_then(parse_entry) else {
+                continue;
+            };
+            if cursor.is_some_and(|c| entry.timestamp > c.ts) || !matcher.matches(&entry) {
+                continue;
+            }
+            matched += 1;
+            entries.push_back(entry);
+            if entries.len() > keep {
+                entries.pop_front();
+            }
         }
     }
+    (entries, matched)
+}
+
+/// One page of matching entries from the logs of `domains`, newest first.
+fn query_in(
+    dir: &Path,
+    domains: &[String],
+    limit: u64,
+    filter: &AccessLogFilter,
+    cursor: Option<&str>,
+) -> Result<AccessLogPage> {
+    let limit = (limit as usize).max(1);
+    let cursor = cursor.map(Cursor::parse).transpose()?;
+    let skip = cursor.map_or(0, |c| c.skip);
+    let matcher = Matcher::new(filter)?;
+
+    let mut entries = Vec::new();
+    let mut matched = 0;
+    for domain in domains {
+        let (found, count) = scan(&log_files_in(dir, domain), &matcher, cursor, limit + skip);
+        entries.extend(found);
+        matched += count;
+    }
+    // Stable, so entries logged at the same moment keep a fixed order
+    entries.reverse();
+    entries.sort_by(|a, b| b.timestamp.total_cmp(&a.timestamp));
+
+    let skipped = match cursor {
+        Some(c) => entries
+            .iter()
+            .take(skip)
+            .take_while(|e| e.timestamp == c.ts)
+            .count(),
+        None => 0,
+    };
+    entries.drain(..skipped);
+    let more = matched - skipped > limit;
+    entries.truncate(limit);
+
+    let next_cursor = match entries.last() {
+        Some(last) if more => {
+            let same = entries
+                .iter()
+                .rev()
+                .take_while(|e| e.timestamp == last.timestamp)
+                .count();
+            let seen = match cursor {
+                Some(c) if c.ts == last.timestamp => skipped,
+                _ => 0,
+            };
+            Some(
+                Cursor {
+                    ts: last.timestamp,
+                    skip: seen + same,
+                }
+                .encode(),
+            )
+        }
+        _ => None,
+    };
+    Ok(AccessLogPage {
+        entries,
+        next_cursor,
+    })
+}
 
-    // Return entries newest first
-    let mut result: Vec<AccessLogEntry> = entries.into();
-    result.reverse();
+pub fn read_access_log(
+    domain: &str,
+    limit: u64,
+    filter: &AccessLogFilter,
+    cursor: Option<&str>,
+) -> Result<AccessLogPage> {
+    validate_domain_for_path(domain)?;
+    query_in(
+        Path::new(paths::LOGS_DIR),
+        &[domain.to_string()],
+        limit,
+        filter,
+        cursor,
+    )
+}
 
-    Ok(result)
+/// Query the logs of every domain at once.
+pub fn query_access_logs(
+    limit: u64,
+    filter: &AccessLogFilter,
+    cursor: Option<&str>,
+) -> Result<AccessLogPage> {
+    let dir = Path::new(paths::LOGS_DIR);
+    query_in(dir, &logged_domains_in(dir), limit, filter, cursor)
 }
 
 pub fn clear_access_log(domain: &str) -> Result<()> {
@@ -89,3 +331,467 @@ pub fn clear_access_log(domain: &str) -> Result<()> {
     }
     Ok(())
 }
+
+/// Identifies the file behind a path, so a rotation shows up as a change.
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino()
+}
+
+/// Identifies the file behind a path, so a rotation shows up as a change.
+/// Windows may carry the creation time over to a file recreated right after a
+/// rename; the new file is then shorter than the offset and read as truncated.
+#[cfg(windows)]
+fn file_id(meta: &fs::Metadata) -> u64 {
+    use std::os::windows::fs::MetadataExt;
+    meta.creation_time()
+}
+
+/// How far a `LogTail` has read, so a later one can carry on from there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TailPosition {
+    pub file_id: u64,
+    pub offset: u64,
+}
+
+/// Follows a domain's access log from its current end. When Caddy rotates the
+/// log at `roll_size`, the rest of the old file is read before the new one;
+/// when the file is truncated by `clear_access_log`, reading restarts at the top.
+pub struct LogTail {
+    path: PathBuf,
+    /// The open log and the identity of the file it was opened as
+    file: Option<(fs::File, u64)>,
+    /// Start of the first line not yet returned
+    offset: u64,
+}
+
+impl LogTail {
+    pub fn open(domain: &str) -> Result<Self> {
+        Self::at(PathBuf::from(log_path(domain)?))
+    }
+
+    /// Carry on from `position`. Without one, or once the log has been
+    /// replaced since, the current log is read from the start.
+    pub fn resume(domain: &str, position: Option<TailPosition>) -> Result<Self> {
+        Self::resume_at(PathBuf::from(log_path(domain)?), position)
+    }
+
+    fn at(path: PathBuf) -> Result<Self> {
+        let mut tail = Self::resume_at(path, None)?;
+        // Only entries written from now on are reported
+        if let Some((file, _)) = &tail.file {
+            tail.offset = file.metadata()?.len();
+        }
+        Ok(tail)
+    }
+
+    fn resume_at(path: PathBuf, position: Option<TailPosition>) -> Result<Self> {
+        let mut tail = Self {
+            path,
+            file: None,
+            offset: 0,
+        };
+        tail.file = tail.open_current()?;
+        if let (Some((_, id)), Some(position)) = (&tail.file, position) {
+            if *id == position.file_id {
+                tail.offset = position.offset;
+            }
+        }
+        Ok(tail)
+    }
+
+    pub fn position(&self) -> Option<TailPosition> {
+        self.file.as_ref().map(|(_, id)| TailPosition {
+            file_id: *id,
+            offset: self.offset,
+        })
+    }
+
+    fn open_current(&self) -> Result<Option<(fs::File, u64)>> {
+        match fs::File::open(&self.path) {
+            Ok(file) => {
+                let id = file_id(&file.metadata()?);
+                Ok(Some((file, id)))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Entries appended since the last poll, oldest first.
+    pub fn poll(&mut self) -> Result<Vec<AccessLogEntry>> {
+        let mut entries = self.read_new()?;
+
+        // A missing file keeps the old one open until Caddy creates the next
+        let current = self.open_current()?;
+        let replaced = match (&self.file, &current) {
+            (Some((_, old)), Some((_, new))) => old != new,
+            (None, Some(_)) => true,
+            _ => false,
+        };
+        if replaced {
+            self.file = current;
+            self.offset = 0;
+            entries.extend(self.read_new()?);
+        }
+        Ok(entries)
+    }
+
+    /// Entries from the complete lines after `offset` in the open file. A
+    /// partly written last line is left for the next poll.
+    fn read_new(&mut self) -> Result<Vec<AccessLogEntry>> {
+        let Some((file, _)) = &mut self.file else {
+            return Ok(Vec::new());
+        };
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            self.offset = 0;
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        let mut buf = Vec::new();
+        file.seek(SeekFrom::Start(self.offset))?;
+        file.take(len - self.offset).read_to_end(&mut buf)?;
+        let Some(end) = buf.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        self.offset += end as u64 + 1;
+
+        Ok(buf[..end]
+            .split(|&b| b == b'\n')
+            .filter_map(|line| std::str::from_utf8(line).ok())
+            .filter_map(parse_entry)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn line(uri: &str) -> String {
+        format!(
+            "{{\"ts\":1700000000.5,\"status\":200,\"duration\":0.01,\"size\":12,\"request\":{{\"method\":\"GET\",\"uri\":\"{}\",\"host\":\"app.test\"}}}}\n",
+            uri
+        )
+    }
+
+    fn append(path: &std::path::Path, content: &str) {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    fn uris(entries: Vec<AccessLogEntry>) -> Vec<String> {
+        entries.into_iter().map(|e| e.uri).collect()
+    }
+
+    /// A log line for a request to `uri` at `ts`, from 10.0.0.1 unless it's a POST.
+    fn request(ts: f64, method: &str, uri: &str, status: u16, duration: f64) -> String {
+        let ip = if method == "POST" {
+            "10.0.0.2"
+        } else {
+            "10.0.0.1"
+        };
+        format!(
+            "{}\n",
+            serde_json::json!({
+                "ts": ts,
+                "status": status,
+                "duration": duration,
+                "size": 0,
+                "request": {
+                    "method": method,
+                    "uri": uri,
+                    "host": "app.test",
+                    "remote_ip": ip,
+                    "headers": {"User-Agent": [format!("curl/{}", status)]},
+                },
+            })
+        )
+    }
+
+    fn query(dir: &Path, domains: &[&str], limit: u64, filter: &AccessLogFilter) -> Vec<String> {
+        let domains: Vec<String> = domains.iter().map(|d| d.to_string()).collect();
+        uris(
+            query_in(dir, &domains, limit, filter, None)
+                .unwrap()
+                .entries,
+        )
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let entry = parse_entry(line("/api").trim()).unwrap();
+        assert_eq!(entry.method, "GET");
+        assert_eq!(entry.uri, "/api");
+        assert_eq!(entry.status, 200);
+        assert_eq!(entry.host, "app.test");
+        assert!(parse_entry("").is_none());
+        assert!(parse_entry("not json").is_none());
+    }
+
+    #[test]
+    fn test_tail_follows_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.test.access.log");
+        append(&path, &line("/old"));
+
+        let mut tail = LogTail::at(path.clone()).unwrap();
+        assert!(tail.poll().unwrap().is_empty());
+
+        // The partial line waits until it is complete
+        let next = line("/b");
+        let (head, rest) = next.split_at(20);
+        append(&path, &format!("{}{}", line("/a"), head));
+        assert_eq!(uris(tail.poll().unwrap()), ["/a"]);
+        append(&path, rest);
+        assert_eq!(uris(tail.poll().unwrap()), ["/b"]);
+
+        // Truncated by clear_access_log
+        fs::write(&path, b"").unwrap();
+        append(&path, &line("/c"));
+        assert_eq!(uris(tail.poll().unwrap()), ["/c"]);
+    }
+
+    #[test]
+    fn test_tail_resumes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.test.access.log");
+        append(&path, &line("/a"));
+
+        let mut tail = LogTail::resume_at(path.clone(), None).unwrap();
+        assert_eq!(uris(tail.poll().unwrap()), ["/a"]);
+        let position = tail.position();
+        append(&path, &line("/b"));
+        drop(tail);
+
+        let mut tail = LogTail::resume_at(path.clone(), position).unwrap();
+        assert_eq!(uris(tail.poll().unwrap()), ["/b"]);
+
+        // A position in a file that has since been rotated away
+        let stale = position.map(|p| TailPosition {
+            file_id: p.file_id + 1,
+            ..p
+        });
+        let mut tail = LogTail::resume_at(path, stale).unwrap();
+        assert_eq!(uris(tail.poll().unwrap()), ["/a", "/b"]);
+    }
+
+    #[test]
+    fn test_tail_follows_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.test.access.log");
+
+        // The log doesn't exist until the first request
+        let mut tail = LogTail::at(path.clone()).unwrap();
+        append(&path, &line("/first"));
+        assert_eq!(uris(tail.poll().unwrap()), ["/first"]);
+
+        // Caddy renames the full log (a last line may still land in it) and
+        // starts a new one
+        let rolled = dir
+            .path()
+            .join("app.test.access-2024-01-01T00-00-00.000.log");
+        append(&path, &line("/before"));
+        fs::rename(&path, &rolled).unwrap();
+        append(&rolled, &line("/late"));
+        append(&path, &line("/after"));
+        assert_eq!(uris(tail.poll().unwrap()), ["/before", "/late", "/after"]);
+        assert!(tail.poll().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        append(
+            &dir.path().join("app.test.access.log"),
+            &[
+                request(100.0, "GET", "/", 200, 0.002),
+                request(101.0, "POST", "/api/users", 201, 0.150),
+                request(102.0, "GET", "/api/users/7", 404, 0.010),
+                request(103.0, "get", "/static/app.js", 304, 0.001),
+                request(104.0, "DELETE", "/api/users/7", 500, 1.250),
+            ]
+            .concat(),
+        );
+        let all = |filter: AccessLogFilter| query(dir.path(), &["app.test"], 100, &filter);
+
+        assert_eq!(all(AccessLogFilter::default()).len(), 5);
+        assert_eq!(
+            all(AccessLogFilter {
+                since: Some(101.0),
+                until: Some(103.0),
+                ..Default::default()
+            }),
+            ["/static/app.js", "/api/users/7", "/api/users"]
+        );
+        assert_eq!(
+            all(AccessLogFilter {
+                method: Some("GET".to_string()),
+                ..Default::default()
+            }),
+            ["/static/app.js", "/api/users/7", "/"]
+        );
+        assert_eq!(
+            all(AccessLogFilter {
+                status_min: Some(400),
+                status_max: Some(499),
+                ..Default::default()
+            }),
+            ["/api/users/7"]
+        );
+        assert_eq!(
+            all(AccessLogFilter {
+                uri: Some("/api/".to_string()),
+                min_duration: Some(0.1),
+                ..Default::default()
+            }),
+            ["/api/users/7", "/api/users"]
+        );
+        assert_eq!(
+            all(AccessLogFilter {
+                uri: Some(r"^/api/users/\d+$".to_string()),
+                uri_regex: true,
+                ..Default::default()
+            }),
+            ["/api/users/7", "/api/users/7"]
+        );
+        assert_eq!(
+            all(AccessLogFilter {
+                remote_ip: Some("10.0.0.2".to_string()),
+                ..Default::default()
+            }),
+            ["/api/users"]
+        );
+        assert_eq!(
+            all(AccessLogFilter {
+                header: Some(HeaderMatch {
+                    name: "user-agent".to_string(),
+                    value: Some("CURL/30".to_string()),
+                }),
+                ..Default::default()
+            }),
+            ["/static/app.js"]
+        );
+        assert!(all(AccessLogFilter {
+            header: Some(HeaderMatch {
+                name: "Authorization".to_string(),
+                value: None,
+            }),
+            ..Default::default()
+        })
+        .is_empty());
+
+        let bad_regex = AccessLogFilter {
+            uri: Some("(".to_string()),
+            uri_regex: true,
+            ..Default::default()
+        };
+        assert!(query_in(dir.path(), &[], 10, &bad_regex, None).is_err());
+    }
+
+    #[test]
+    fn test_pages_span_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        append(
+            &dir.path()
+                .join("app.test.access-2024-01-01T00-00-00.000.log"),
+            &[
+                request(1.0, "GET", "/1", 200, 0.0),
+                request(2.0, "GET", "/2", 200, 0.0),
+            ]
+            .concat(),
+        );
+        append(
+            &dir.path()
+                .join("app.test.access-2024-01-02T00-00-00.000.log"),
+            &[
+                request(3.0, "GET", "/3", 200, 0.0),
+                request(4.0, "GET", "/4a", 200, 0.0),
+            ]
+            .concat(),
+        );
+        // Three entries logged at the same moment straddle a page boundary
+        append(
+            &dir.path().join("app.test.access.log"),
+            &[
+                request(4.0, "GET", "/4b", 200, 0.0),
+                request(4.0, "GET", "/4c", 200, 0.0),
+                request(5.0, "GET", "/5", 200, 0.0),
+            ]
+            .concat(),
+        );
+
+        let filter = AccessLogFilter::default();
+        let domains = ["app.test".to_string()];
+        let mut cursor = None;
+        let mut pages = Vec::new();
+        loop {
+            let page = query_in(dir.path(), &domains, 2, &filter, cursor.as_deref()).unwrap();
+            pages.push(uris(page.entries));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(
+            pages,
+            [
+                vec!["/5", "/4c"],
+                vec!["/4b", "/4a"],
+                vec!["/3", "/2"],
+                vec!["/1"]
+            ]
+        );
+        assert!(Cursor::parse("soon").is_err());
+    }
+
+    #[test]
+    fn test_query_all_domains() {
+        let dir = tempfile::tempdir().unwrap();
+        append(
+            &dir.path().join("app.test.access.log"),
+            &[
+                request(1.0, "GET", "/app1", 200, 0.0),
+                request(3.0, "GET", "/app3", 500, 0.0),
+            ]
+            .concat(),
+        );
+        append(
+            &dir.path()
+                .join("api.app.test.access-2024-01-01T00-00-00.000.log"),
+            &request(2.0, "GET", "/api2", 500, 0.0),
+        );
+        append(
+            &dir.path().join("api.app.test.access.log"),
+            &request(4.0, "GET", "/api4", 200, 0.0),
+        );
+        fs::write(dir.path().join("caddy.log"), "").unwrap();
+
+        let domains = logged_domains_in(dir.path());
+        assert_eq!(domains, ["api.app.test", "app.test"]);
+        let domains: Vec<&str> = domains.iter().map(String::as_str).collect();
+        assert_eq!(
+            query(dir.path(), &domains, 10, &AccessLogFilter::default()),
+            ["/api4", "/app3", "/api2", "/app1"]
+        );
+        let errors = AccessLogFilter {
+            status_min: Some(500),
+            ..Default::default()
+        };
+        assert_eq!(query(dir.path(), &domains, 10, &errors), ["/app3", "/api2"]);
+        // A domain's files don't include those of its subdomains
+        assert_eq!(
+            query(dir.path(), &["app.test"], 10, &AccessLogFilter::default()),
+            ["/app3", "/app1"]
+        );
+    }
+}
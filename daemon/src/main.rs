@@ -1,9 +1,15 @@
+mod acme;
 mod caddy;
 mod certs;
+mod events;
 mod hosts;
 mod logs;
+mod onboarding;
+mod overrides;
 pub mod paths;
 mod server;
+mod settings;
+mod traffic;
 pub mod tunnel;
 mod xampp;
 
@@ -138,6 +144,23 @@ async fn run_daemon(as_service: bool) -> anyhow::Result<()> {
     // Clean up stale state from a previous daemon instance
     cleanup_stale_state();
 
+    // Reissue domain certs before they expire (checks at startup, then daily)
+    tokio::spawn(certs::renewal::run_scheduler());
+
+    // Repair or report external edits to the managed hosts block
+    hosts::watcher::start();
+
+    // Revert hostname overrides when they expire, with or without the app
+    tokio::spawn(overrides::run_scheduler());
+
+    // Roll access logs up into per-domain traffic statistics
+    tokio::spawn(traffic::run_ingester());
+
+    // Local ACME directory, when enabled in settings
+    if let Err(e) = acme::apply_settings(&settings::get()) {
+        tracing::warn!("Failed to start ACME server: {}", e);
+    }
+
     // Register signal handlers for graceful shutdown (non-service mode).
     // Windows Service mode handles shutdown via the service control handler in service_main().
     #[cfg(unix)]
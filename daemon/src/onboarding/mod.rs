@@ -0,0 +1,304 @@
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use localdomain_shared::protocol::DeviceOnboardingInfo;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::certs::{ca, inventory};
+use crate::{paths, settings};
+
+pub const PEM_FILE: &str = "localdomain-ca.pem";
+pub const DER_FILE: &str = "localdomain-ca.crt";
+pub const PROFILE_FILE: &str = "localdomain-ca.mobileconfig";
+
+/// Content types phones act on: iOS offers to install `aspen-config`, Android's
+/// downloader hands `x509-ca-cert` to the certificate installer.
+pub const CONTENT_TYPES: [(&str, &str); 3] = [
+    (PEM_FILE, "application/x-pem-file"),
+    (DER_FILE, "application/x-x509-ca-cert"),
+    (PROFILE_FILE, "application/x-apple-aspen-config"),
+];
+
+/// The onboarding site Caddy publishes while LAN sharing is on.
+#[derive(Debug, Clone)]
+pub struct Site {
+    pub host: String,
+    pub lan_ip: Ipv4Addr,
+    pub port: u16,
+    pub root: String,
+}
+
+/// The address other devices on the network reach this machine on.
+pub fn lan_ip() -> Option<Ipv4Addr> {
+    // Connecting a UDP socket only selects the outgoing interface; nothing is sent
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        std::net::IpAddr::V4(ip) if !ip.is_loopback() && !ip.is_unspecified() => Some(ip),
+        _ => None,
+    }
+}
+
+/// The onboarding host ends up in the Caddyfile and the hosts file.
+pub fn validate_host(host: &str) -> Result<()> {
+    let valid = !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if !valid {
+        bail!("Invalid onboarding host name: '{}'", host);
+    }
+    Ok(())
+}
+
+/// Refresh the onboarding files from the current root CA and return the site to
+/// publish, or None while LAN sharing is off or there is nothing to serve.
+pub fn prepare() -> Result<Option<Site>> {
+    let settings = settings::get();
+    if !settings.lan_sharing {
+        return Ok(None);
+    }
+    if !ca::ca_exists() {
+        warn!("LAN sharing is on but no CA exists yet; skipping the onboarding site");
+        return Ok(None);
+    }
+    let Some(lan_ip) = lan_ip() else {
+        warn!("LAN sharing is on but no LAN address was found");
+        return Ok(None);
+    };
+    validate_host(&settings.onboarding_host)?;
+    write_files(Path::new(paths::ONBOARDING_DIR))?;
+    Ok(Some(Site {
+        host: settings.onboarding_host,
+        lan_ip,
+        port: settings.onboarding_port,
+        root: paths::ONBOARDING_DIR.to_string(),
+    }))
+}
+
+/// Where phones should point their browser, with a QR code of the URL.
+pub fn info() -> DeviceOnboardingInfo {
+    let settings = settings::get();
+    let lan_ip = lan_ip();
+    let url = match lan_ip {
+        Some(ip) if settings.lan_sharing && ca::ca_exists() => {
+            Some(format!("http://{}:{}/", ip, settings.onboarding_port))
+        }
+        _ => None,
+    };
+    let qr_svg = url.as_deref().and_then(qr_svg);
+    DeviceOnboardingInfo {
+        enabled: settings.lan_sharing,
+        host: settings.onboarding_host,
+        lan_ip: lan_ip.map(|ip| ip.to_string()),
+        url,
+        qr_svg,
+    }
+}
+
+/// Standalone SVG document, which the app shows as a data URI.
+fn qr_svg(url: &str) -> Option<String> {
+    let qr = QrCode::new(url.as_bytes()).ok()?;
+    Some(qr.render::<svg::Color>().min_dimensions(180, 180).build())
+}
+
+fn write_files(dir: &Path) -> Result<()> {
+    let pem_text =
+        fs::read_to_string(ca::ca_cert_path()).context("Failed to read CA certificate")?;
+    let der = pem::parse(&pem_text)
+        .context("Invalid CA certificate PEM")?
+        .into_contents();
+    let (_, cert) = x509_parser::parse_x509_certificate(&der)
+        .map_err(|e| anyhow::anyhow!("Failed to parse CA certificate: {}", e))?;
+    let subject = cert.subject().to_string();
+    let fingerprint = inventory::fingerprint_sha256(&der);
+
+    fs::create_dir_all(dir).context("Failed to create onboarding directory")?;
+    fs::write(dir.join(PEM_FILE), &pem_text)?;
+    fs::write(dir.join(DER_FILE), &der)?;
+    fs::write(dir.join(PROFILE_FILE), mobileconfig(&der, &subject))?;
+    fs::write(dir.join("index.html"), index_html(&subject, &fingerprint))?;
+    info!("Wrote device onboarding files to {}", dir.display());
+    Ok(())
+}
+
+/// UUIDs derived from the certificate, so reinstalling the profile for the same CA
+/// replaces it instead of adding a second one.
+fn payload_uuid(der: &[u8], purpose: &str) -> uuid::Uuid {
+    let digest = Sha256::new()
+        .chain_update(purpose.as_bytes())
+        .chain_update(der)
+        .finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_random_bytes(bytes).into_uuid()
+}
+
+/// Apple configuration profile with a single root certificate payload.
+fn mobileconfig(der: &[u8], subject: &str) -> String {
+    let data = base64::engine::general_purpose::STANDARD.encode(der);
+    let wrapped: Vec<&str> = data
+        .as_bytes()
+        .chunks(64)
+        .map(|line| std::str::from_utf8(line).unwrap_or_default())
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>PayloadContent</key>
+	<array>
+		<dict>
+			<key>PayloadCertificateFileName</key>
+			<string>{der_file}</string>
+			<key>PayloadContent</key>
+			<data>
+			{data}
+			</data>
+			<key>PayloadDescription</key>
+			<string>Trusts HTTPS certificates issued by LocalDomain on your development machine.</string>
+			<key>PayloadDisplayName</key>
+			<string>{subject}</string>
+			<key>PayloadIdentifier</key>
+			<string>ge.any.localdomain.ca.{cert_uuid}</string>
+			<key>PayloadType</key>
+			<string>com.apple.security.root</string>
+			<key>PayloadUUID</key>
+			<string>{cert_uuid}</string>
+			<key>PayloadVersion</key>
+			<integer>1</integer>
+		</dict>
+	</array>
+	<key>PayloadDisplayName</key>
+	<string>LocalDomain CA</string>
+	<key>PayloadIdentifier</key>
+	<string>ge.any.localdomain.onboarding.{profile_uuid}</string>
+	<key>PayloadRemovalDisallowed</key>
+	<false/>
+	<key>PayloadType</key>
+	<string>Configuration</string>
+	<key>PayloadUUID</key>
+	<string>{profile_uuid}</string>
+	<key>PayloadVersion</key>
+	<integer>1</integer>
+</dict>
+</plist>
+"#,
+        der_file = DER_FILE,
+        data = wrapped.join("\n\t\t\t"),
+        subject = escape(subject),
+        cert_uuid = payload_uuid(der, "certificate").hyphenated(),
+        profile_uuid = payload_uuid(der, "profile").hyphenated(),
+    )
+}
+
+fn index_html(subject: &str, fingerprint: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>LocalDomain CA</title>
+<style>
+body {{ font-family: -apple-system, system-ui, sans-serif; max-width: 40rem; margin: 0 auto; padding: 1.5rem; line-height: 1.5; color: #1f2937; }}
+a.button {{ display: block; margin: 0.5rem 0; padding: 0.75rem 1rem; border-radius: 0.5rem; background: #2563eb; color: #fff; text-decoration: none; text-align: center; }}
+code {{ word-break: break-all; font-size: 0.8rem; }}
+</style>
+</head>
+<body>
+<h1>Trust LocalDomain on this device</h1>
+<p>Install the certificate below to open <code>https://</code> development sites from this phone or tablet without warnings.</p>
+<p>CA: <strong>{subject}</strong><br>SHA-256: <code>{fingerprint}</code></p>
+
+<h2>iPhone and iPad</h2>
+<a class="button" href="/{profile}">Download profile</a>
+<ol>
+<li>Open this page in Safari and tap <em>Download profile</em>, then <em>Allow</em>.</li>
+<li>Go to Settings &rsaquo; General &rsaquo; VPN &amp; Device Management, choose <em>LocalDomain CA</em> and tap <em>Install</em>.</li>
+<li>Go to Settings &rsaquo; General &rsaquo; About &rsaquo; Certificate Trust Settings and turn on full trust for the certificate.</li>
+</ol>
+
+<h2>Android</h2>
+<a class="button" href="/{der}">Download certificate</a>
+<ol>
+<li>Tap <em>Download certificate</em>.</li>
+<li>Go to Settings &rsaquo; Security &rsaquo; Encryption &amp; credentials &rsaquo; Install a certificate &rsaquo; CA certificate and pick <code>{der}</code>.</li>
+<li>Apps only trust user CAs if they opt in; Chrome does, most release builds of other apps do not.</li>
+</ol>
+
+<h2>Other devices</h2>
+<p><a href="/{pem}">PEM certificate</a> &middot; <a href="/{der}">DER certificate</a></p>
+<p>Check that the SHA-256 fingerprint matches the one shown in the LocalDomain app before trusting it.</p>
+</body>
+</html>
+"#,
+        subject = escape(subject),
+        fingerprint = fingerprint,
+        profile = PROFILE_FILE,
+        der = DER_FILE,
+        pem = PEM_FILE,
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_host() {
+        assert!(validate_host("ca.test").is_ok());
+        assert!(validate_host("onboarding-1.dev.test").is_ok());
+        assert!(validate_host("").is_err());
+        assert!(validate_host("ca..test").is_err());
+        assert!(validate_host("-ca.test").is_err());
+        assert!(validate_host("ca.test {").is_err());
+        assert!(validate_host("ca.test\n}").is_err());
+    }
+
+    #[test]
+    fn test_qr_svg() {
+        let svg = qr_svg("http://192.168.1.20:8554/").unwrap();
+        assert!(svg.contains("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_mobileconfig() {
+        let der = b"not really a certificate";
+        let profile = mobileconfig(der, "CN=Dev <Team> CA");
+        assert!(profile.contains("<string>com.apple.security.root</string>"));
+        assert!(profile.contains("<string>CN=Dev &lt;Team&gt; CA</string>"));
+        let data = base64::engine::general_purpose::STANDARD.encode(der);
+        assert!(profile.contains(&data));
+
+        // Stable per certificate, distinct per payload
+        assert_eq!(profile, mobileconfig(der, "CN=Dev <Team> CA"));
+        assert_ne!(
+            payload_uuid(der, "certificate"),
+            payload_uuid(der, "profile")
+        );
+        assert_ne!(
+            payload_uuid(der, "profile"),
+            payload_uuid(b"another", "profile")
+        );
+    }
+}
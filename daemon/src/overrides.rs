@@ -0,0 +1,163 @@
+//! Real hostnames pointed at a local upstream for a limited time. The app sets
+//! the expiry; the daemon enforces it, so an override reverts even when the app
+//! isn't running.
+
+use anyhow::{Context, Result};
+use localdomain_shared::protocol::{HostnameOverride, OverrideRevertEvent};
+use once_cell::sync::Lazy;
+use std::fs;
+use std::sync::Mutex;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::{error, info, warn};
+
+use crate::{caddy, events, hosts, paths};
+
+/// How often expiry is checked, which bounds how long an override outlives it.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Overrides from the last sync, persisted so they still expire after a restart.
+static OVERRIDES: Lazy<Mutex<Vec<HostnameOverride>>> = Lazy::new(|| Mutex::new(load()));
+
+fn load() -> Vec<HostnameOverride> {
+    match fs::read_to_string(paths::OVERRIDES_FILE) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring invalid hostname overrides file: {}", e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn save(overrides: &[HostnameOverride]) -> Result<()> {
+    let json = serde_json::to_string_pretty(overrides)?;
+    fs::write(paths::OVERRIDES_FILE, json).context("Failed to write hostname overrides")
+}
+
+fn now() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+/// Replace the set of active overrides.
+pub fn sync(overrides: Vec<HostnameOverride>) -> Result<()> {
+    let mut current = OVERRIDES.lock().unwrap();
+    if *current == overrides {
+        return Ok(());
+    }
+    save(&overrides)?;
+    for o in overrides.iter().filter(|o| !current.contains(o)) {
+        info!(
+            "Hostname override for {} active until {}",
+            o.domain, o.expires_at
+        );
+    }
+    *current = overrides;
+    Ok(())
+}
+
+/// Whether `name` belongs to an override whose time is up. Syncs leave these
+/// names out even before the app has caught up with the revert.
+pub fn is_expired(name: &str) -> bool {
+    is_expired_in(&OVERRIDES.lock().unwrap(), name, now())
+}
+
+fn is_expired_in(overrides: &[HostnameOverride], name: &str, now: i64) -> bool {
+    overrides
+        .iter()
+        .any(|o| o.expires_at <= now && o.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+}
+
+fn expired_at(overrides: &[HostnameOverride], now: i64) -> Vec<HostnameOverride> {
+    overrides
+        .iter()
+        .filter(|o| o.expires_at <= now)
+        .cloned()
+        .collect()
+}
+
+pub fn take_override_events() -> Vec<OverrideRevertEvent> {
+    events::take(|p| &mut p.overrides)
+}
+
+/// Remove expired overrides from the hosts file and Caddy.
+fn revert_expired() -> Result<()> {
+    let now = now();
+    let expired = expired_at(&OVERRIDES.lock().unwrap(), now);
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    // The overrides stay registered until both are rewritten, so the
+    // Caddyfile rebuild still filters them out
+    let names: Vec<String> = expired.iter().flat_map(|o| o.names.clone()).collect();
+    hosts::remove_names(&names)?;
+    if caddy::config::regenerate()? && caddy::process::is_caddy_running() {
+        caddy::process::reload_caddy()?;
+    }
+
+    let mut current = OVERRIDES.lock().unwrap();
+    current.retain(|o| !expired.contains(o));
+    save(&current)?;
+    drop(current);
+
+    events::push(
+        |p| &mut p.overrides,
+        expired.into_iter().map(|o| {
+            info!("Hostname override for {} expired; reverted", o.domain);
+            OverrideRevertEvent {
+                domain: o.domain,
+                names: o.names,
+                expires_at: o.expires_at,
+                reverted_at: now,
+            }
+        }),
+    );
+    Ok(())
+}
+
+/// Background task: revert overrides as they expire.
+pub async fn run_scheduler() {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        match tokio::task::spawn_blocking(revert_expired).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Failed to revert expired hostname overrides: {}", e),
+            Err(e) => error!("Hostname override task panicked: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hostname_override(domain: &str, aliases: &[&str], expires_at: i64) -> HostnameOverride {
+        HostnameOverride {
+            domain: domain.to_string(),
+            names: std::iter::once(domain)
+                .chain(aliases.iter().copied())
+                .map(str::to_string)
+                .collect(),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_expiry() {
+        let overrides = [
+            hostname_override("api.example.com", &["cdn.example.com"], 1_000),
+            hostname_override("shop.example.com", &[], 2_000),
+        ];
+
+        assert!(!is_expired_in(&overrides, "api.example.com", 999));
+        assert!(expired_at(&overrides, 999).is_empty());
+
+        assert!(is_expired_in(&overrides, "api.example.com", 1_000));
+        assert!(is_expired_in(&overrides, "CDN.example.com", 1_000));
+        assert!(!is_expired_in(&overrides, "shop.example.com", 1_000));
+        assert!(!is_expired_in(&overrides, "example.com", 5_000));
+        assert_eq!(expired_at(&overrides, 1_500), overrides[..1]);
+        assert_eq!(expired_at(&overrides, 2_000), overrides);
+    }
+}
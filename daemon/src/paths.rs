@@ -13,12 +13,42 @@ pub const CA_CERT: &str = "/var/lib/localdomain/certs/localdomain-ca.crt";
 #[cfg(target_os = "macos")]
 pub const CA_KEY: &str = "/var/lib/localdomain/certs/localdomain-ca.key";
 #[cfg(target_os = "macos")]
+pub const CA_KEY_ENCRYPTED: &str = "/var/lib/localdomain/certs/localdomain-ca.key.enc";
+#[cfg(target_os = "macos")]
+pub const INTERMEDIATE_CERT: &str = "/var/lib/localdomain/certs/localdomain-intermediate.crt";
+#[cfg(target_os = "macos")]
+pub const INTERMEDIATE_KEY: &str = "/var/lib/localdomain/certs/localdomain-intermediate.key";
+#[cfg(target_os = "macos")]
+pub const CA_PREVIOUS_CERT: &str = "/var/lib/localdomain/certs/localdomain-ca.previous.crt";
+#[cfg(target_os = "macos")]
+pub const CA_PREVIOUS_KEY: &str = "/var/lib/localdomain/certs/localdomain-ca.previous.key";
+#[cfg(target_os = "macos")]
+pub const CA_ROTATION_STATE: &str = "/var/lib/localdomain/certs/ca-rotation.json";
+#[cfg(target_os = "macos")]
+pub const CA_BUNDLE: &str = "/var/lib/localdomain/certs/localdomain-bundle.pem";
+#[cfg(target_os = "macos")]
 pub const CADDYFILE: &str = "/var/lib/localdomain/caddy/Caddyfile";
 #[cfg(target_os = "macos")]
 pub const CADDY_PID: &str = "/var/lib/localdomain/caddy/caddy.pid";
 #[cfg(target_os = "macos")]
 pub const HOSTS_FILE: &str = "/etc/hosts";
 #[cfg(target_os = "macos")]
+pub const HOSTS_BACKUP_DIR: &str = "/var/lib/localdomain/hosts-backups";
+#[cfg(target_os = "macos")]
+pub const SETTINGS_FILE: &str = "/var/lib/localdomain/daemon-settings.json";
+#[cfg(target_os = "macos")]
+pub const OVERRIDES_FILE: &str = "/var/lib/localdomain/overrides.json";
+#[cfg(target_os = "macos")]
+pub const CADDY_SYNC_FILE: &str = "/var/lib/localdomain/caddy-sync.json";
+#[cfg(target_os = "macos")]
+pub const EVENTS_FILE: &str = "/var/lib/localdomain/events.json";
+#[cfg(target_os = "macos")]
+pub const TRAFFIC_FILE: &str = "/var/lib/localdomain/traffic.json";
+#[cfg(target_os = "macos")]
+pub const ACME_ACCOUNTS: &str = "/var/lib/localdomain/acme-accounts.json";
+#[cfg(target_os = "macos")]
+pub const ONBOARDING_DIR: &str = "/var/lib/localdomain/onboarding";
+#[cfg(target_os = "macos")]
 pub const SOCKET_PATH: &str = "/var/run/localdomain.sock";
 
 #[cfg(target_os = "linux")]
@@ -34,12 +64,42 @@ pub const CA_CERT: &str = "/var/lib/localdomain/certs/localdomain-ca.crt";
 #[cfg(target_os = "linux")]
 pub const CA_KEY: &str = "/var/lib/localdomain/certs/localdomain-ca.key";
 #[cfg(target_os = "linux")]
+pub const CA_KEY_ENCRYPTED: &str = "/var/lib/localdomain/certs/localdomain-ca.key.enc";
+#[cfg(target_os = "linux")]
+pub const INTERMEDIATE_CERT: &str = "/var/lib/localdomain/certs/localdomain-intermediate.crt";
+#[cfg(target_os = "linux")]
+pub const INTERMEDIATE_KEY: &str = "/var/lib/localdomain/certs/localdomain-intermediate.key";
+#[cfg(target_os = "linux")]
+pub const CA_PREVIOUS_CERT: &str = "/var/lib/localdomain/certs/localdomain-ca.previous.crt";
+#[cfg(target_os = "linux")]
+pub const CA_PREVIOUS_KEY: &str = "/var/lib/localdomain/certs/localdomain-ca.previous.key";
+#[cfg(target_os = "linux")]
+pub const CA_ROTATION_STATE: &str = "/var/lib/localdomain/certs/ca-rotation.json";
+#[cfg(target_os = "linux")]
+pub const CA_BUNDLE: &str = "/var/lib/localdomain/certs/localdomain-bundle.pem";
+#[cfg(target_os = "linux")]
 pub const CADDYFILE: &str = "/var/lib/localdomain/caddy/Caddyfile";
 #[cfg(target_os = "linux")]
 pub const CADDY_PID: &str = "/var/lib/localdomain/caddy/caddy.pid";
 #[cfg(target_os = "linux")]
 pub const HOSTS_FILE: &str = "/etc/hosts";
 #[cfg(target_os = "linux")]
+pub const HOSTS_BACKUP_DIR: &str = "/var/lib/localdomain/hosts-backups";
+#[cfg(target_os = "linux")]
+pub const SETTINGS_FILE: &str = "/var/lib/localdomain/daemon-settings.json";
+#[cfg(target_os = "linux")]
+pub const OVERRIDES_FILE: &str = "/var/lib/localdomain/overrides.json";
+#[cfg(target_os = "linux")]
+pub const CADDY_SYNC_FILE: &str = "/var/lib/localdomain/caddy-sync.json";
+#[cfg(target_os = "linux")]
+pub const EVENTS_FILE: &str = "/var/lib/localdomain/events.json";
+#[cfg(target_os = "linux")]
+pub const TRAFFIC_FILE: &str = "/var/lib/localdomain/traffic.json";
+#[cfg(target_os = "linux")]
+pub const ACME_ACCOUNTS: &str = "/var/lib/localdomain/acme-accounts.json";
+#[cfg(target_os = "linux")]
+pub const ONBOARDING_DIR: &str = "/var/lib/localdomain/onboarding";
+#[cfg(target_os = "linux")]
 pub const SOCKET_PATH: &str = "/var/run/localdomain.sock";
 
 #[cfg(target_os = "windows")]
@@ -57,12 +117,42 @@ pub const CA_CERT: &str = "C:\\ProgramData\\LocalDomain\\certs\\localdomain-ca.c
 #[cfg(target_os = "windows")]
 pub const CA_KEY: &str = "C:\\ProgramData\\LocalDomain\\certs\\localdomain-ca.key";
 #[cfg(target_os = "windows")]
+pub const CA_KEY_ENCRYPTED: &str = "C:\\ProgramData\\LocalDomain\\certs\\localdomain-ca.key.enc";
+#[cfg(target_os = "windows")]
+pub const INTERMEDIATE_CERT: &str = "C:\\ProgramData\\LocalDomain\\certs\\localdomain-intermediate.crt";
+#[cfg(target_os = "windows")]
+pub const INTERMEDIATE_KEY: &str = "C:\\ProgramData\\LocalDomain\\certs\\localdomain-intermediate.key";
+#[cfg(target_os = "windows")]
+pub const CA_PREVIOUS_CERT: &str = "C:\\ProgramData\\LocalDomain\\certs\\localdomain-ca.previous.crt";
+#[cfg(target_os = "windows")]
+pub const CA_PREVIOUS_KEY: &str = "C:\\ProgramData\\LocalDomain\\certs\\localdomain-ca.previous.key";
+#[cfg(target_os = "windows")]
+pub const CA_ROTATION_STATE: &str = "C:\\ProgramData\\LocalDomain\\certs\\ca-rotation.json";
+#[cfg(target_os = "windows")]
+pub const CA_BUNDLE: &str = "C:\\ProgramData\\LocalDomain\\certs\\localdomain-bundle.pem";
+#[cfg(target_os = "windows")]
 pub const CADDYFILE: &str = "C:\\ProgramData\\LocalDomain\\caddy\\Caddyfile";
 #[cfg(target_os = "windows")]
 pub const CADDY_PID: &str = "C:\\ProgramData\\LocalDomain\\caddy\\caddy.pid";
 #[cfg(target_os = "windows")]
 pub const HOSTS_FILE: &str = "C:\\Windows\\System32\\drivers\\etc\\hosts";
 #[cfg(target_os = "windows")]
+pub const HOSTS_BACKUP_DIR: &str = "C:\\ProgramData\\LocalDomain\\hosts-backups";
+#[cfg(target_os = "windows")]
+pub const SETTINGS_FILE: &str = "C:\\ProgramData\\LocalDomain\\daemon-settings.json";
+#[cfg(target_os = "windows")]
+pub const OVERRIDES_FILE: &str = "C:\\ProgramData\\LocalDomain\\overrides.json";
+#[cfg(target_os = "windows")]
+pub const CADDY_SYNC_FILE: &str = "C:\\ProgramData\\LocalDomain\\caddy-sync.json";
+#[cfg(target_os = "windows")]
+pub const EVENTS_FILE: &str = "C:\\ProgramData\\LocalDomain\\events.json";
+#[cfg(target_os = "windows")]
+pub const TRAFFIC_FILE: &str = "C:\\ProgramData\\LocalDomain\\traffic.json";
+#[cfg(target_os = "windows")]
+pub const ACME_ACCOUNTS: &str = "C:\\ProgramData\\LocalDomain\\acme-accounts.json";
+#[cfg(target_os = "windows")]
+pub const ONBOARDING_DIR: &str = "C:\\ProgramData\\LocalDomain\\onboarding";
+#[cfg(target_os = "windows")]
 pub const PIPE_NAME: &str = r"\\.\pipe\localdomain";
 
 // Tunnel paths
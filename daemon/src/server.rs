@@ -3,11 +3,16 @@ use localdomain_shared::protocol::*;
 use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 use tracing::{error, info};
 
+use crate::acme;
 use crate::caddy;
 use crate::certs;
 use crate::hosts;
 use crate::logs;
+use crate::onboarding;
+use crate::overrides;
 use crate::paths;
+use crate::settings;
+use crate::traffic;
 use crate::tunnel;
 use crate::xampp;
 
@@ -139,20 +144,87 @@ where
 
     while reader.read_line(&mut line).await? > 0 {
         let response = match serde_json::from_str::<JsonRpcRequest>(&line) {
+            // Streams until the client disconnects, so it takes over the connection
+            Ok(request) if request.method == "tail_access_log" => {
+                return tail_access_log(request, reader, writer).await;
+            }
             Ok(request) => dispatch(request).await,
             Err(e) => JsonRpcResponse::error(0, PARSE_ERROR, format!("Parse error: {}", e)),
         };
 
-        let response_json = serde_json::to_string(&response)?;
-        writer.write_all(response_json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-        writer.flush().await?;
+        write_message(&mut writer, &response).await?;
         line.clear();
     }
 
     Ok(())
 }
 
+async fn write_message<W, T>(writer: &mut W, message: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: serde::Serialize,
+{
+    let json = serde_json::to_string(message)?;
+    writer.write_all(json.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Follow a domain's access log, sending each new entry as an
+/// `ACCESS_LOG_ENTRY` notification until the client closes the connection.
+async fn tail_access_log<R, W>(
+    request: JsonRpcRequest,
+    mut reader: BufReader<R>,
+    mut writer: W,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let id = request.id;
+    let tail = match serde_json::from_value::<TailAccessLogParams>(request.params) {
+        Ok(params) => tokio::task::spawn_blocking(move || logs::LogTail::open(&params.domain))
+            .await?
+            .map_err(|e| JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string())),
+        Err(e) => Err(JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string())),
+    };
+    let mut tail = match tail {
+        Ok(tail) => tail,
+        Err(response) => return write_message(&mut writer, &response).await,
+    };
+    let subscribed = JsonRpcResponse::success(id, serde_json::json!(null));
+    write_message(&mut writer, &subscribed).await?;
+
+    let mut interval = tokio::time::interval(logs::TAIL_INTERVAL);
+    let mut line = String::new();
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                // The log is read with blocking file I/O, so poll off the runtime
+                let entries;
+                (tail, entries) = tokio::task::spawn_blocking(move || {
+                    let entries = tail.poll();
+                    (tail, entries)
+                })
+                .await?;
+                for entry in entries? {
+                    let notification =
+                        JsonRpcNotification::new(ACCESS_LOG_ENTRY, serde_json::to_value(entry)?);
+                    write_message(&mut writer, &notification).await?;
+                }
+            }
+            // Nothing more is expected from the client; EOF means it's gone
+            read = reader.read_line(&mut line) => {
+                if read? == 0 {
+                    return Ok(());
+                }
+                line.clear();
+            }
+        }
+    }
+}
+
 async fn dispatch(request: JsonRpcRequest) -> JsonRpcResponse {
     let id = request.id;
     match request.method.as_str() {
@@ -161,7 +233,7 @@ async fn dispatch(request: JsonRpcRequest) -> JsonRpcResponse {
         "status" => {
             let caddy_running = caddy::process::is_caddy_running();
             let ca_installed = certs::ca::ca_exists();
-            let ca_trusted = certs::trust::verify_ca_trust();
+            let trust_stores = certs::trust::trust_store_status();
             // Check XAMPP/Apache status using detected or default path
             let xampp_detect = xampp::detect::detect_xampp();
             let xampp_running = xampp_detect
@@ -175,7 +247,7 @@ async fn dispatch(request: JsonRpcRequest) -> JsonRpcResponse {
                     daemon_running: true,
                     caddy_running,
                     ca_installed,
-                    ca_trusted,
+                    trust_stores,
                     xampp_running,
                 }),
             )
@@ -222,13 +294,269 @@ async fn dispatch(request: JsonRpcRequest) -> JsonRpcResponse {
         },
 
         "generate_cert" => match serde_json::from_value::<GenerateCertParams>(request.params) {
-            Ok(params) => match certs::domain::generate_domain_cert(&params.domain) {
+            Ok(params) => match certs::domain::generate_domain_cert(
+                &params.domain,
+                &params.sans,
+                params.key_algorithm,
+                params.force,
+            ) {
+                Ok(result) => JsonRpcResponse::success(id, serde_json::to_value(result).unwrap()),
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+            },
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+        },
+
+        "list_certificates" => {
+            match serde_json::from_value::<ListCertificatesParams>(request.params) {
+                Ok(params) => match certs::inventory::list_certificates(&params.active_domains) {
+                    Ok(certificates) => JsonRpcResponse::success(
+                        id,
+                        serde_json::to_value(ListCertificatesResult { certificates }).unwrap(),
+                    ),
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "delete_certificate" => {
+            match serde_json::from_value::<DeleteCertificateParams>(request.params) {
+                Ok(params) => match certs::inventory::delete_certificate(&params.name) {
+                    Ok(()) => JsonRpcResponse::success(id, serde_json::json!(null)),
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "install_custom_cert" => {
+            match serde_json::from_value::<InstallCustomCertParams>(request.params) {
+                Ok(params) => match certs::custom::install_custom_cert(
+                    &params.domain,
+                    &params.names,
+                    &params.cert_pem,
+                    &params.key_pem,
+                    params.passphrase.as_deref(),
+                ) {
+                    Ok(result) => {
+                        JsonRpcResponse::success(id, serde_json::to_value(result).unwrap())
+                    }
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "remove_custom_cert" => {
+            match serde_json::from_value::<RemoveCustomCertParams>(request.params) {
+                Ok(params) => match certs::custom::remove_custom_cert(&params.domain) {
+                    Ok(()) => JsonRpcResponse::success(id, serde_json::json!(null)),
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "renew_certificates" => match certs::renewal::renew_due_certificates(
+            &certs::renewal::SystemClock,
+        ) {
+            Ok(events) => JsonRpcResponse::success(id, serde_json::to_value(events).unwrap()),
+            Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+        },
+
+        "take_hosts_events" => JsonRpcResponse::success(
+            id,
+            serde_json::to_value(TakeHostsEventsResult {
+                events: hosts::watcher::take_hosts_events(),
+            })
+            .unwrap(),
+        ),
+
+        "sync_overrides" => match serde_json::from_value::<SyncOverridesParams>(request.params) {
+            Ok(params) => match overrides::sync(params.overrides) {
+                Ok(()) => JsonRpcResponse::success(id, serde_json::json!(null)),
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+            },
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+        },
+
+        "take_override_events" => JsonRpcResponse::success(
+            id,
+            serde_json::to_value(TakeOverrideEventsResult {
+                events: overrides::take_override_events(),
+            })
+            .unwrap(),
+        ),
+
+        "check_domains" => match serde_json::from_value::<CheckDomainsParams>(request.params) {
+            Ok(params) => JsonRpcResponse::success(
+                id,
+                serde_json::to_value(CheckDomainsResult {
+                    warnings: hosts::conflicts::check(&params.entries),
+                })
+                .unwrap(),
+            ),
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+        },
+
+        "list_hosts_backups" => match hosts::backup::list() {
+            Ok(backups) => JsonRpcResponse::success(
+                id,
+                serde_json::to_value(ListHostsBackupsResult { backups }).unwrap(),
+            ),
+            Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+        },
+
+        "diff_hosts_backup" => match serde_json::from_value::<HostsBackupParams>(request.params) {
+            Ok(params) => match hosts::diff_backup(&params.id) {
+                Ok(lines) => JsonRpcResponse::success(
+                    id,
+                    serde_json::to_value(HostsBackupDiff {
+                        id: params.id,
+                        lines,
+                    })
+                    .unwrap(),
+                ),
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+            },
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+        },
+
+        "restore_hosts_backup" => {
+            match serde_json::from_value::<HostsBackupParams>(request.params) {
+                Ok(params) => match hosts::restore_backup(&params.id) {
+                    Ok(()) => JsonRpcResponse::success(id, serde_json::json!(null)),
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "restore_original_hosts" => match hosts::restore_original() {
+            Ok(exact) => JsonRpcResponse::success(
+                id,
+                serde_json::to_value(RestoreOriginalHostsResult { exact }).unwrap(),
+            ),
+            Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+        },
+
+        "take_renewal_events" => JsonRpcResponse::success(
+            id,
+            serde_json::to_value(TakeRenewalEventsResult {
+                events: certs::renewal::take_renewal_events(),
+            })
+            .unwrap(),
+        ),
+
+        "get_daemon_settings" => {
+            JsonRpcResponse::success(id, serde_json::to_value(settings::get()).unwrap())
+        }
+
+        "set_daemon_settings" => match serde_json::from_value::<DaemonSettings>(request.params) {
+            Ok(params) => match settings::update(params.clone())
+                .and_then(|()| acme::apply_settings(&params))
+            {
+                Ok(()) => JsonRpcResponse::success(id, serde_json::json!(null)),
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+            },
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+        },
+
+        "rotate_ca" => match serde_json::from_value::<RotateCaParams>(request.params) {
+            Ok(params) => match certs::rotation::rotate_ca(
+                params.overlap_days,
+                &certs::renewal::SystemClock,
+            ) {
                 Ok(result) => JsonRpcResponse::success(id, serde_json::to_value(result).unwrap()),
                 Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
             },
             Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
         },
 
+        "import_ca" => match serde_json::from_value::<ImportCaParams>(request.params) {
+            Ok(params) => match certs::rotation::import_ca(
+                &params.cert_pem,
+                &params.key_pem,
+                params.passphrase.as_deref(),
+                params.overlap_days,
+                &certs::renewal::SystemClock,
+            ) {
+                Ok(result) => JsonRpcResponse::success(id, serde_json::to_value(result).unwrap()),
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+            },
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+        },
+
+        "export_ca_bundle" => {
+            match serde_json::from_value::<ExportCaBundleParams>(request.params) {
+                Ok(params) => match certs::export::export_ca_bundle(
+                    &params.passphrase,
+                    params.root_passphrase.as_deref(),
+                ) {
+                    Ok(result) => {
+                        JsonRpcResponse::success(id, serde_json::to_value(result).unwrap())
+                    }
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "retire_previous_ca" => match certs::rotation::retire_previous_ca() {
+            Ok(()) => JsonRpcResponse::success(id, serde_json::json!(null)),
+            Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+        },
+
+        "export_certificate" => {
+            match serde_json::from_value::<ExportCertificateParams>(request.params) {
+                Ok(params) => match certs::export::export_certificate(
+                    &params.name,
+                    params.format,
+                    params.passphrase.as_deref(),
+                ) {
+                    Ok(result) => {
+                        JsonRpcResponse::success(id, serde_json::to_value(result).unwrap())
+                    }
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "get_ca_status" => {
+            JsonRpcResponse::success(id, serde_json::to_value(certs::ca::ca_status()).unwrap())
+        }
+
+        "protect_root_key" => {
+            match serde_json::from_value::<ProtectRootKeyParams>(request.params) {
+                Ok(params) => match certs::ca::protect_root_key(&params.passphrase, params.offline)
+                {
+                    Ok(encrypted_key_pem) => JsonRpcResponse::success(
+                        id,
+                        serde_json::to_value(ProtectRootKeyResult { encrypted_key_pem }).unwrap(),
+                    ),
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "renew_intermediate" => {
+            match serde_json::from_value::<RenewIntermediateParams>(request.params) {
+                Ok(params) => match certs::renewal::renew_intermediate(
+                    params.root_key_pem.as_deref(),
+                    params.passphrase.as_deref(),
+                ) {
+                    Ok(events) => JsonRpcResponse::success(
+                        id,
+                        serde_json::to_value(TakeRenewalEventsResult { events }).unwrap(),
+                    ),
+                    Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                },
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
         "install_ca_trust" => match certs::trust::install_ca_trust() {
             Ok(()) => JsonRpcResponse::success(id, serde_json::json!(null)),
             Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
@@ -239,19 +567,56 @@ async fn dispatch(request: JsonRpcRequest) -> JsonRpcResponse {
             Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
         },
 
+        "get_toolchain_trust" => JsonRpcResponse::success(
+            id,
+            serde_json::to_value(certs::toolchains::status()).unwrap(),
+        ),
+
+        "install_toolchain_trust" => match certs::toolchains::install() {
+            Ok(result) => JsonRpcResponse::success(id, serde_json::to_value(result).unwrap()),
+            Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+        },
+
+        "remove_toolchain_trust" => match certs::toolchains::remove() {
+            Ok(result) => JsonRpcResponse::success(id, serde_json::to_value(result).unwrap()),
+            Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+        },
+
+        "get_device_onboarding" => {
+            JsonRpcResponse::success(id, serde_json::to_value(onboarding::info()).unwrap())
+        }
+
         "get_access_log" => match serde_json::from_value::<GetAccessLogParams>(request.params) {
             Ok(params) => {
                 let limit = params.limit.unwrap_or(100);
-                match logs::read_access_log(&params.domain, limit) {
-                    Ok(entries) => {
-                        JsonRpcResponse::success(id, serde_json::to_value(entries).unwrap())
-                    }
+                match logs::read_access_log(
+                    &params.domain,
+                    limit,
+                    &params.filter,
+                    params.cursor.as_deref(),
+                ) {
+                    Ok(page) => JsonRpcResponse::success(id, serde_json::to_value(page).unwrap()),
                     Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
                 }
             }
             Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
         },
 
+        "query_access_logs" => {
+            match serde_json::from_value::<QueryAccessLogsParams>(request.params) {
+                Ok(params) => {
+                    let limit = params.limit.unwrap_or(100);
+                    match logs::query_access_logs(limit, &params.filter, params.cursor.as_deref()) {
+                        Ok(page) => {
+                            JsonRpcResponse::success(id, serde_json::to_value(page).unwrap())
+                        }
+                        Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+                    }
+                }
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
         "clear_access_log" => {
             match serde_json::from_value::<ClearAccessLogParams>(request.params) {
                 Ok(params) => match logs::clear_access_log(&params.domain) {
@@ -262,6 +627,16 @@ async fn dispatch(request: JsonRpcRequest) -> JsonRpcResponse {
             }
         }
 
+        "get_traffic_stats" => {
+            match serde_json::from_value::<GetTrafficStatsParams>(request.params) {
+                Ok(params) => {
+                    let stats = traffic::get_traffic_stats(params.window, params.domain.as_deref());
+                    JsonRpcResponse::success(id, serde_json::to_value(stats).unwrap())
+                }
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e.to_string()),
+            }
+        }
+
         "start_tunnel" => match serde_json::from_value::<StartTunnelParams>(request.params) {
             Ok(params) => match tunnel::manager::start_tunnel(params) {
                 Ok(result) => JsonRpcResponse::success(id, serde_json::to_value(result).unwrap()),
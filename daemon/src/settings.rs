@@ -0,0 +1,37 @@
+use anyhow::{Context, Result};
+use localdomain_shared::protocol::DaemonSettings;
+use once_cell::sync::Lazy;
+use std::fs;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::certs::constraints::CaConstraints;
+use crate::paths;
+
+static SETTINGS: Lazy<Mutex<DaemonSettings>> = Lazy::new(|| Mutex::new(load()));
+
+fn load() -> DaemonSettings {
+    match fs::read_to_string(paths::SETTINGS_FILE) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring invalid daemon settings file: {}", e);
+            DaemonSettings::default()
+        }),
+        Err(_) => DaemonSettings::default(),
+    }
+}
+
+pub fn get() -> DaemonSettings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+pub fn update(settings: DaemonSettings) -> Result<()> {
+    // Reject malformed suffixes/ranges now rather than at the next CA generation
+    CaConstraints::from_settings(&settings)?;
+    crate::onboarding::validate_host(&settings.onboarding_host)?;
+    let mut current = SETTINGS.lock().unwrap();
+    let json = serde_json::to_string_pretty(&settings)?;
+    fs::write(paths::SETTINGS_FILE, json).context("Failed to write daemon settings")?;
+    *current = settings;
+    info!("Updated daemon settings");
+    Ok(())
+}
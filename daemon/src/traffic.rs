@@ -0,0 +1,539 @@
+//! Request statistics per domain and route, rolled up from Caddy's access logs
+//! as they're written. Minute buckets serve the short windows and hour buckets
+//! the long ones. Both are persisted together with how far each log has been
+//! read, so a restart neither loses nor double-counts requests.
+
+use anyhow::{Context, Result};
+use localdomain_shared::protocol::{
+    AccessLogEntry, DomainTrafficStats, GetTrafficStatsResult, RouteTrafficStats,
+    StatusClassCounts, TrafficStats, TrafficWindow,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use tracing::{error, warn};
+
+use crate::logs::{self, LogTail, TailPosition};
+use crate::paths;
+
+/// How often the logs are checked for new entries.
+const INGEST_INTERVAL: Duration = Duration::from_secs(10);
+/// Least time between writes of the statistics file.
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+/// Minute buckets serve windows of up to an hour, hour buckets the longer ones.
+const MINUTE_RETENTION: i64 = 2 * HOUR;
+const HOUR_RETENTION: i64 = 30 * 24 * HOUR;
+
+/// Routes kept per bucket; requests to any others are counted together.
+const MAX_ROUTES: usize = 200;
+const OTHER_ROUTE: &str = "(other)";
+
+/// Upper bound of the first latency bucket in seconds. Each later bucket ends
+/// `LATENCY_GROWTH` times higher, which bounds the error of the percentiles.
+const LATENCY_BASE: f64 = 0.0001;
+const LATENCY_GROWTH: f64 = 1.2;
+
+/// Request counts, bytes, status classes and latencies of a set of requests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Aggregate {
+    requests: u64,
+    bytes: u64,
+    /// Responses by class, 1xx to 5xx
+    status: [u64; 5],
+    /// Requests by latency bucket
+    latency: BTreeMap<u32, u64>,
+}
+
+impl Aggregate {
+    fn record(&mut self, entry: &AccessLogEntry) {
+        self.requests += 1;
+        self.bytes += entry.size;
+        // Status 0 means the client went away before a response
+        if let Some(count) = (entry.status / 100)
+            .checked_sub(1)
+            .and_then(|class| self.status.get_mut(class as usize))
+        {
+            *count += 1;
+        }
+        *self
+            .latency
+            .entry(latency_bucket(entry.duration))
+            .or_default() += 1;
+    }
+
+    fn merge(&mut self, other: &Aggregate) {
+        self.requests += other.requests;
+        self.bytes += other.bytes;
+        for (count, other) in self.status.iter_mut().zip(other.status) {
+            *count += other;
+        }
+        for (bucket, count) in &other.latency {
+            *self.latency.entry(*bucket).or_default() += count;
+        }
+    }
+
+    /// The upper bound of the latency bucket holding the `p` quantile.
+    fn percentile(&self, p: f64) -> Option<f64> {
+        let total: u64 = self.latency.values().sum();
+        let rank = ((p * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in &self.latency {
+            seen += count;
+            if seen >= rank {
+                return Some(latency_bound(*bucket));
+            }
+        }
+        None
+    }
+
+    fn stats(&self, minutes: f64) -> TrafficStats {
+        let [informational, success, redirect, client_error, server_error] = self.status;
+        TrafficStats {
+            requests: self.requests,
+            requests_per_minute: self.requests as f64 / minutes,
+            bytes: self.bytes,
+            status: StatusClassCounts {
+                informational,
+                success,
+                redirect,
+                client_error,
+                server_error,
+            },
+            p50: self.percentile(0.50),
+            p95: self.percentile(0.95),
+            p99: self.percentile(0.99),
+        }
+    }
+}
+
+fn latency_bucket(duration: f64) -> u32 {
+    if duration <= LATENCY_BASE {
+        return 0;
+    }
+    ((duration / LATENCY_BASE).ln() / LATENCY_GROWTH.ln()).ceil() as u32
+}
+
+fn latency_bound(bucket: u32) -> f64 {
+    LATENCY_BASE * LATENCY_GROWTH.powi(bucket as i32)
+}
+
+/// The route a request counts under: method and path without the query, with
+/// ids (numbers, UUIDs, long hex strings) replaced by `:id` so that
+/// `/users/7` and `/users/8` add up.
+fn route_of(method: &str, uri: &str) -> String {
+    let path = uri.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(|segment| if is_id(segment) { ":id" } else { segment })
+        .collect();
+    format!("{} {}", method.to_ascii_uppercase(), segments.join("/"))
+}
+
+fn is_id(segment: &str) -> bool {
+    !segment.is_empty()
+        && (segment.bytes().all(|b| b.is_ascii_digit())
+            || uuid::Uuid::try_parse(segment).is_ok()
+            || (segment.len() >= 16 && segment.bytes().all(|b| b.is_ascii_hexdigit())))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Bucket {
+    total: Aggregate,
+    routes: BTreeMap<String, Aggregate>,
+}
+
+impl Bucket {
+    fn record(&mut self, route: &str, entry: &AccessLogEntry) {
+        self.total.record(entry);
+        let route = if self.routes.len() < MAX_ROUTES || self.routes.contains_key(route) {
+            route
+        } else {
+            OTHER_ROUTE
+        };
+        self.routes
+            .entry(route.to_string())
+            .or_default()
+            .record(entry);
+    }
+
+    fn merge(&mut self, other: &Bucket) {
+        self.total.merge(&other.total);
+        for (route, aggregate) in &other.routes {
+            self.routes
+                .entry(route.clone())
+                .or_default()
+                .merge(aggregate);
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DomainTraffic {
+    /// Keyed by bucket start (unix seconds)
+    minutes: BTreeMap<i64, Bucket>,
+    hours: BTreeMap<i64, Bucket>,
+    /// How far the domain's log has been read
+    position: Option<TailPosition>,
+}
+
+impl DomainTraffic {
+    fn record(&mut self, entry: &AccessLogEntry, now: i64) {
+        let ts = entry.timestamp.floor() as i64;
+        let route = route_of(&entry.method, &entry.uri);
+        if ts > now - MINUTE_RETENTION {
+            let start = ts - ts.rem_euclid(MINUTE);
+            self.minutes.entry(start).or_default().record(&route, entry);
+        }
+        if ts > now - HOUR_RETENTION {
+            let start = ts - ts.rem_euclid(HOUR);
+            self.hours.entry(start).or_default().record(&route, entry);
+        }
+    }
+
+    /// Drop buckets past retention; true if any were.
+    fn prune(&mut self, now: i64) -> bool {
+        let (minutes, hours) = (self.minutes.len(), self.hours.len());
+        self.minutes
+            .retain(|start, _| start + MINUTE > now - MINUTE_RETENTION);
+        self.hours
+            .retain(|start, _| start + HOUR > now - HOUR_RETENTION);
+        minutes != self.minutes.len() || hours != self.hours.len()
+    }
+
+    /// Everything in the buckets overlapping the window from `since`, so the
+    /// window reaches back by up to one bucket more than asked.
+    fn window(&self, window: TrafficWindow, since: i64) -> Bucket {
+        let (buckets, size) = if window.seconds() <= HOUR {
+            (&self.minutes, MINUTE)
+        } else {
+            (&self.hours, HOUR)
+        };
+        let mut merged = Bucket::default();
+        for bucket in buckets.range(since - size + 1..).map(|(_, bucket)| bucket) {
+            merged.merge(bucket);
+        }
+        merged
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrafficStore {
+    domains: BTreeMap<String, DomainTraffic>,
+}
+
+static STORE: Lazy<Mutex<TrafficStore>> = Lazy::new(|| Mutex::new(load()));
+
+/// Open logs by domain, used only by the ingest task.
+static TAILS: Lazy<Mutex<HashMap<String, LogTail>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn load() -> TrafficStore {
+    match fs::read_to_string(paths::TRAFFIC_FILE) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring invalid traffic statistics file: {}", e);
+            TrafficStore::default()
+        }),
+        Err(_) => TrafficStore::default(),
+    }
+}
+
+fn save() -> Result<()> {
+    let json = serde_json::to_string(&*STORE.lock().unwrap())?;
+    fs::write(paths::TRAFFIC_FILE, json).context("Failed to write traffic statistics")
+}
+
+fn now() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+/// Add new log entries to the statistics; true if anything changed.
+fn ingest() -> Result<bool> {
+    let now = now();
+    let logged = logs::logged_domains();
+    let mut tails = TAILS.lock().unwrap();
+    let mut store = STORE.lock().unwrap();
+    let mut changed = false;
+
+    tails.retain(|domain, _| logged.contains(domain));
+    for domain in &logged {
+        let traffic = store.domains.entry(domain.clone()).or_default();
+        if !tails.contains_key(domain) {
+            match LogTail::resume(domain, traffic.position) {
+                Ok(tail) => {
+                    tails.insert(domain.clone(), tail);
+                }
+                Err(e) => {
+                    warn!("Failed to open access log of {}: {}", domain, e);
+                    continue;
+                }
+            }
+        }
+        let Some(tail) = tails.get_mut(domain) else {
+            continue;
+        };
+        let entries = match tail.poll() {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to read access log of {}: {}", domain, e);
+                continue;
+            }
+        };
+        for entry in &entries {
+            traffic.record(entry, now);
+        }
+        if tail.position() != traffic.position {
+            traffic.position = tail.position();
+            changed = true;
+        }
+    }
+
+    for traffic in store.domains.values_mut() {
+        changed |= traffic.prune(now);
+    }
+    // Forget domains once both their logs and their history are gone
+    let before = store.domains.len();
+    store
+        .domains
+        .retain(|domain, traffic| logged.contains(domain) || !traffic.hours.is_empty());
+    Ok(changed || store.domains.len() != before)
+}
+
+/// Background task: keep the statistics up to date with the access logs.
+pub async fn run_ingester() {
+    let mut interval = tokio::time::interval(INGEST_INTERVAL);
+    let mut last_save = Instant::now();
+    let mut unsaved = false;
+    loop {
+        interval.tick().await;
+        match tokio::task::spawn_blocking(ingest).await {
+            Ok(Ok(changed)) => unsaved |= changed,
+            Ok(Err(e)) => error!("Failed to update traffic statistics: {}", e),
+            Err(e) => error!("Traffic statistics task panicked: {}", e),
+        }
+        if unsaved && last_save.elapsed() >= SAVE_INTERVAL {
+            match tokio::task::spawn_blocking(save).await {
+                Ok(Ok(())) => {
+                    unsaved = false;
+                    last_save = Instant::now();
+                }
+                Ok(Err(e)) => error!("{}", e),
+                Err(e) => error!("Traffic statistics task panicked: {}", e),
+            }
+        }
+    }
+}
+
+/// Statistics over `window` for one domain, or every domain with history.
+pub fn get_traffic_stats(window: TrafficWindow, domain: Option<&str>) -> GetTrafficStatsResult {
+    stats_at(&STORE.lock().unwrap(), window, domain, now())
+}
+
+fn stats_at(
+    store: &TrafficStore,
+    window: TrafficWindow,
+    domain: Option<&str>,
+    now: i64,
+) -> GetTrafficStatsResult {
+    let since = now - window.seconds();
+    let minutes = window.seconds() as f64 / 60.0;
+    let domains = store
+        .domains
+        .iter()
+        .filter(|(name, _)| domain.is_none_or(|d| d.eq_ignore_ascii_case(name)))
+        .map(|(name, traffic)| {
+            let bucket = traffic.window(window, since);
+            let mut routes: Vec<RouteTrafficStats> = bucket
+                .routes
+                .iter()
+                .map(|(route, aggregate)| RouteTrafficStats {
+                    route: route.clone(),
+                    stats: aggregate.stats(minutes),
+                })
+                .collect();
+            routes.sort_by_key(|route| Reverse(route.stats.requests));
+            DomainTrafficStats {
+                domain: name.clone(),
+                stats: bucket.total.stats(minutes),
+                routes,
+            }
+        })
+        .collect();
+    GetTrafficStatsResult {
+        window,
+        since,
+        domains,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2023-11-14 22:00:00 UTC, on an hour boundary
+    const NOW: i64 = 1_700_000_000 - 1_700_000_000 % HOUR;
+
+    fn entry(ts: i64, method: &str, uri: &str, status: u16, duration: f64) -> AccessLogEntry {
+        AccessLogEntry {
+            timestamp: ts as f64 + 0.25,
+            method: method.to_string(),
+            uri: uri.to_string(),
+            status,
+            duration,
+            size: 100,
+            host: "app.test".to_string(),
+            headers: serde_json::Value::Null,
+            resp_headers: serde_json::Value::Null,
+            remote_ip: "127.0.0.1".to_string(),
+            proto: "HTTP/2.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_route_of() {
+        assert_eq!(route_of("get", "/"), "GET /");
+        assert_eq!(route_of("GET", "/users/42?tab=posts"), "GET /users/:id");
+        assert_eq!(
+            route_of(
+                "DELETE",
+                "/orgs/7/keys/0b5e1d2c-62b1-4b0e-9a57-3d3f0f3c1a2b#x"
+            ),
+            "DELETE /orgs/:id/keys/:id"
+        );
+        assert_eq!(
+            route_of("GET", "/assets/app.3f9a1c2b4d5e6f70.js"),
+            "GET /assets/app.3f9a1c2b4d5e6f70.js"
+        );
+        assert_eq!(
+            route_of("GET", "/commits/3f9a1c2b4d5e6f708192"),
+            "GET /commits/:id"
+        );
+        assert_eq!(route_of("GET", "/v2/users"), "GET /v2/users");
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut aggregate = Aggregate::default();
+        assert_eq!(aggregate.percentile(0.5), None);
+        // 1ms to 100ms
+        for ms in 1..=100 {
+            aggregate.record(&entry(NOW, "GET", "/", 200, ms as f64 / 1000.0));
+        }
+        for (p, expected) in [(0.50, 0.050), (0.95, 0.095), (0.99, 0.099)] {
+            let estimate = aggregate.percentile(p).unwrap();
+            assert!(
+                estimate >= expected && estimate <= expected * LATENCY_GROWTH,
+                "p{} = {}",
+                p * 100.0,
+                estimate
+            );
+        }
+        assert_eq!(latency_bucket(0.0), 0);
+        assert!(latency_bound(latency_bucket(2.5)) >= 2.5);
+    }
+
+    #[test]
+    fn test_windows() {
+        let mut traffic = DomainTraffic::default();
+        traffic.record(&entry(NOW - 60, "GET", "/users/1", 200, 0.010), NOW);
+        traffic.record(&entry(NOW - 120, "GET", "/users/2", 404, 0.020), NOW);
+        traffic.record(&entry(NOW - 30 * MINUTE, "POST", "/users", 201, 0.300), NOW);
+        traffic.record(&entry(NOW - 5 * HOUR, "GET", "/", 500, 1.0), NOW);
+        // Older than any bucket kept
+        traffic.record(&entry(NOW - 40 * 24 * HOUR, "GET", "/", 200, 0.0), NOW);
+
+        let mut store = TrafficStore::default();
+        store.domains.insert("app.test".to_string(), traffic);
+
+        let recent = stats_at(&store, TrafficWindow::FifteenMinutes, None, NOW);
+        assert_eq!(recent.since, NOW - 15 * MINUTE);
+        let app = &recent.domains[0];
+        assert_eq!(app.domain, "app.test");
+        assert_eq!(app.stats.requests, 2);
+        assert_eq!(app.stats.bytes, 200);
+        assert!((app.stats.requests_per_minute - 2.0 / 15.0).abs() < 1e-9);
+        assert_eq!(app.stats.status.success, 1);
+        assert_eq!(app.stats.status.client_error, 1);
+        assert_eq!(app.routes.len(), 1);
+        assert_eq!(app.routes[0].route, "GET /users/:id");
+
+        let hour = stats_at(&store, TrafficWindow::Hour, Some("APP.test"), NOW);
+        assert_eq!(hour.domains[0].stats.requests, 3);
+        let day = stats_at(&store, TrafficWindow::Day, None, NOW);
+        let app = &day.domains[0];
+        assert_eq!(app.stats.requests, 4);
+        assert_eq!(app.stats.status.server_error, 1);
+        assert_eq!(app.routes[0].route, "GET /users/:id");
+        assert_eq!(app.routes[0].stats.requests, 2);
+        assert_eq!(
+            stats_at(&store, TrafficWindow::Month, None, NOW).domains[0]
+                .stats
+                .requests,
+            4
+        );
+        assert!(
+            stats_at(&store, TrafficWindow::Day, Some("other.test"), NOW)
+                .domains
+                .is_empty()
+        );
+
+        // Minute buckets go after two hours, hour buckets after thirty days
+        let traffic = store.domains.get_mut("app.test").unwrap();
+        assert!(traffic.prune(NOW + 2 * HOUR));
+        assert!(traffic.minutes.is_empty());
+        assert_eq!(traffic.hours.len(), 2);
+        assert!(!traffic.prune(NOW + 2 * HOUR));
+        assert!(traffic.prune(NOW + 31 * 24 * HOUR));
+        assert!(traffic.hours.is_empty());
+    }
+
+    #[test]
+    fn test_route_limit() {
+        let mut bucket = Bucket::default();
+        for i in 0..MAX_ROUTES + 5 {
+            bucket.record(
+                &format!("GET /page-{}", i),
+                &entry(NOW, "GET", "/", 200, 0.0),
+            );
+        }
+        bucket.record("GET /page-0", &entry(NOW, "GET", "/", 200, 0.0));
+        assert_eq!(bucket.total.requests, MAX_ROUTES as u64 + 6);
+        assert_eq!(bucket.routes.len(), MAX_ROUTES + 1);
+        assert_eq!(bucket.routes[OTHER_ROUTE].requests, 5);
+        assert_eq!(bucket.routes["GET /page-0"].requests, 2);
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let mut store = TrafficStore::default();
+        let mut traffic = DomainTraffic {
+            position: Some(TailPosition {
+                file_id: 7,
+                offset: 1024,
+            }),
+            ..Default::default()
+        };
+        traffic.record(&entry(NOW - 60, "GET", "/", 200, 0.004), NOW);
+        store.domains.insert("app.test".to_string(), traffic);
+
+        let json = serde_json::to_string(&store).unwrap();
+        let loaded: TrafficStore = serde_json::from_str(&json).unwrap();
+        let traffic = &loaded.domains["app.test"];
+        assert_eq!(traffic.position, store.domains["app.test"].position);
+        assert_eq!(
+            traffic.minutes.values().next().unwrap().total,
+            store.domains["app.test"]
+                .minutes
+                .values()
+                .next()
+                .unwrap()
+                .total
+        );
+    }
+}
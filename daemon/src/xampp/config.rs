@@ -19,6 +19,11 @@ fn validate_vhost_config(vhost: &XamppVhostConfig) -> Result<()> {
     if vhost.name.contains(' ') || vhost.name.contains('\t') {
         bail!("XAMPP vhost name contains whitespace");
     }
+    for alias in &vhost.aliases {
+        if alias.is_empty() || alias.contains(char::is_whitespace) || alias.contains('\0') {
+            bail!("XAMPP vhost alias '{}' is invalid", alias.trim());
+        }
+    }
     Ok(())
 }
 
@@ -248,11 +253,19 @@ fn build_localhost_vhost(xampp_path: &str, http_port: u16) -> String {
     )
 }
 
+fn server_alias_line(vhost: &XamppVhostConfig) -> String {
+    if vhost.aliases.is_empty() {
+        String::new()
+    } else {
+        format!("    ServerAlias {}\n", vhost.aliases.join(" "))
+    }
+}
+
 fn build_http_vhost(vhost: &XamppVhostConfig, http_port: u16) -> String {
     format!(
         r#"<VirtualHost *:{port}>
     ServerName {name}
-    DocumentRoot "{document_root}"
+{server_alias}    DocumentRoot "{document_root}"
     <Directory "{document_root}">
         Options Indexes FollowSymLinks
         AllowOverride All
@@ -263,6 +276,7 @@ fn build_http_vhost(vhost: &XamppVhostConfig, http_port: u16) -> String {
 "#,
         port = http_port,
         name = vhost.name,
+        server_alias = server_alias_line(vhost),
         document_root = vhost.document_root,
     )
 }
@@ -274,7 +288,7 @@ fn build_https_vhost(vhost: &XamppVhostConfig, ssl_port: u16) -> String {
     format!(
         r#"<VirtualHost *:{port}>
     ServerName {name}
-    DocumentRoot "{document_root}"
+{server_alias}    DocumentRoot "{document_root}"
     SSLEngine on
     SSLCertificateFile "{cert_path}"
     SSLCertificateKeyFile "{key_path}"
@@ -288,6 +302,7 @@ fn build_https_vhost(vhost: &XamppVhostConfig, ssl_port: u16) -> String {
 "#,
         port = ssl_port,
         name = vhost.name,
+        server_alias = server_alias_line(vhost),
         document_root = vhost.document_root,
         cert_path = cert_path,
         key_path = key_path,
@@ -412,6 +427,7 @@ mod tests {
     fn test_build_vhosts_content_http_only() {
         let vhosts = vec![XamppVhostConfig {
             name: "mysite.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/mysite".to_string(),
             protocol: "http".to_string(),
             cert_path: None,
@@ -426,10 +442,30 @@ mod tests {
         assert!(!result.contains("<VirtualHost *:443>"));
     }
 
+    #[test]
+    fn test_build_vhosts_content_server_alias() {
+        let vhosts = vec![XamppVhostConfig {
+            name: "shop.test".to_string(),
+            aliases: vec!["admin.shop.test".to_string(), "api.shop.test".to_string()],
+            document_root: "/var/www/shop".to_string(),
+            protocol: "http".to_string(),
+            cert_path: None,
+            key_path: None,
+        }];
+        let result = build_vhosts_content("", &vhosts, TEST_XAMPP_PATH, 80, 443);
+        assert!(result
+            .contains("    ServerName shop.test\n    ServerAlias admin.shop.test api.shop.test\n"));
+
+        let mut bad = vhosts[0].clone();
+        bad.aliases = vec!["a.test\n    Include /etc/passwd".to_string()];
+        assert!(validate_vhost_config(&bad).is_err());
+    }
+
     #[test]
     fn test_build_vhosts_content_https_only() {
         let vhosts = vec![XamppVhostConfig {
             name: "secure.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/secure".to_string(),
             protocol: "https".to_string(),
             cert_path: Some("/certs/secure.crt".to_string()),
@@ -450,6 +486,7 @@ mod tests {
     fn test_build_vhosts_content_both_protocols() {
         let vhosts = vec![XamppVhostConfig {
             name: "both.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/both".to_string(),
             protocol: "both".to_string(),
             cert_path: Some("/certs/both.crt".to_string()),
@@ -468,6 +505,7 @@ mod tests {
         let existing = "# My custom vhost\n<VirtualHost *:80>\n    ServerName custom.local\n</VirtualHost>\n";
         let vhosts = vec![XamppVhostConfig {
             name: "managed.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/managed".to_string(),
             protocol: "http".to_string(),
             cert_path: None,
@@ -486,6 +524,7 @@ mod tests {
         );
         let vhosts = vec![XamppVhostConfig {
             name: "new.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/new".to_string(),
             protocol: "http".to_string(),
             cert_path: None,
@@ -501,6 +540,7 @@ mod tests {
     fn test_build_vhosts_content_localhost_preservation() {
         let vhosts = vec![XamppVhostConfig {
             name: "mysite.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/mysite".to_string(),
             protocol: "http".to_string(),
             cert_path: None,
@@ -515,6 +555,7 @@ mod tests {
     fn test_build_vhosts_content_localhost_uses_xampp_path() {
         let vhosts = vec![XamppVhostConfig {
             name: "mysite.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/mysite".to_string(),
             protocol: "http".to_string(),
             cert_path: None,
@@ -528,6 +569,7 @@ mod tests {
     fn test_build_vhosts_content_custom_ports() {
         let vhosts = vec![XamppVhostConfig {
             name: "mysite.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/mysite".to_string(),
             protocol: "both".to_string(),
             cert_path: Some("/certs/mysite.crt".to_string()),
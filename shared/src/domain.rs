@@ -16,13 +16,75 @@ impl Protocol {
             Protocol::Both => "both",
         }
     }
+}
+
+impl std::str::FromStr for Protocol {
+    type Err = String;
 
-    pub fn from_str(s: &str) -> Option<Self> {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "http" => Some(Protocol::Http),
-            "https" => Some(Protocol::Https),
-            "both" => Some(Protocol::Both),
-            _ => None,
+            "http" => Ok(Protocol::Http),
+            "https" => Ok(Protocol::Https),
+            "both" => Ok(Protocol::Both),
+            _ => Err(format!("Unknown protocol '{}'", s)),
+        }
+    }
+}
+
+/// Key algorithm for a domain certificate. RSA exists for legacy clients and
+/// Java stacks that cannot negotiate ECDSA.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAlgorithm {
+    #[default]
+    EcdsaP256,
+    EcdsaP384,
+    Rsa2048,
+    Rsa4096,
+}
+
+impl KeyAlgorithm {
+    pub fn as_str(&self) -> &str {
+        match self {
+            KeyAlgorithm::EcdsaP256 => "ecdsa_p256",
+            KeyAlgorithm::EcdsaP384 => "ecdsa_p384",
+            KeyAlgorithm::Rsa2048 => "rsa2048",
+            KeyAlgorithm::Rsa4096 => "rsa4096",
+        }
+    }
+
+    /// Key type as reported in certificate inventory, e.g. "ECDSA P-256".
+    pub fn key_type(&self) -> &str {
+        match self {
+            KeyAlgorithm::EcdsaP256 => "ECDSA P-256",
+            KeyAlgorithm::EcdsaP384 => "ECDSA P-384",
+            KeyAlgorithm::Rsa2048 => "RSA 2048",
+            KeyAlgorithm::Rsa4096 => "RSA 4096",
+        }
+    }
+
+    pub fn from_key_type(key_type: &str) -> Option<Self> {
+        [
+            KeyAlgorithm::EcdsaP256,
+            KeyAlgorithm::EcdsaP384,
+            KeyAlgorithm::Rsa2048,
+            KeyAlgorithm::Rsa4096,
+        ]
+        .into_iter()
+        .find(|alg| alg.key_type() == key_type)
+    }
+}
+
+impl std::str::FromStr for KeyAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ecdsa_p256" => Ok(KeyAlgorithm::EcdsaP256),
+            "ecdsa_p384" => Ok(KeyAlgorithm::EcdsaP384),
+            "rsa2048" => Ok(KeyAlgorithm::Rsa2048),
+            "rsa4096" => Ok(KeyAlgorithm::Rsa4096),
+            _ => Err(format!("Unknown key algorithm '{}'", s)),
         }
     }
 }
@@ -47,6 +109,9 @@ pub struct HostsEntry {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CaddyDomainConfig {
     pub name: String,
+    /// Other hostnames served by the same site
+    #[serde(default)]
+    pub aliases: Vec<String>,
     pub target_host: String,
     pub target_port: u16,
     /// "http", "https", or "both"
@@ -57,24 +122,40 @@ pub struct CaddyDomainConfig {
     pub access_log: bool,
 }
 
+/// ASCII form of a domain name, mapped and punycode-encoded per UTS-46:
+/// `Café.test` becomes `xn--caf-dma.test`. This is the form written to the
+/// hosts file, the Caddyfile and certificates.
+pub fn domain_to_ascii(name: &str) -> Result<String, String> {
+    idna::domain_to_ascii(name)
+        .map_err(|_| format!("Invalid internationalized domain name '{}'", name))
+}
+
+/// Unicode form of a domain name, for display. Labels that aren't valid
+/// punycode are left as they are.
+pub fn domain_to_unicode(name: &str) -> String {
+    idna::domain_to_unicode(name).0
+}
+
 /// Validates a domain name for use in /etc/hosts.
 /// Allows alphanumeric, hyphens, dots. Must end with a valid TLD-like segment.
+/// Unicode names are checked in their punycode form.
 pub fn validate_domain_name(name: &str) -> Result<(), String> {
     if name.is_empty() {
         return Err("Domain name cannot be empty".to_string());
     }
-    if name.len() > 253 {
+    let ascii = domain_to_ascii(name)?;
+    if ascii.len() > 253 {
         return Err("Domain name too long (max 253 characters)".to_string());
     }
 
     use std::sync::OnceLock;
     static DOMAIN_RE: OnceLock<regex_lite::Regex> = OnceLock::new();
     let re = DOMAIN_RE.get_or_init(|| {
-        regex_lite::Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9-]*[a-zA-Z0-9])?(\.[a-zA-Z0-9]([a-zA-Z0-9-]*[a-zA-Z0-9])?)*\.[a-zA-Z]{2,}$")
+        regex_lite::Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9-]*[a-zA-Z0-9])?(\.[a-zA-Z0-9]([a-zA-Z0-9-]*[a-zA-Z0-9])?)*\.([a-zA-Z]{2,}|xn--[a-zA-Z0-9-]+)$")
             .expect("domain validation regex is valid")
     });
 
-    if !re.is_match(name) {
+    if !re.is_match(&ascii) {
         return Err(format!(
             "Invalid domain name '{}'. Use format like 'project.test'",
             name
@@ -84,10 +165,77 @@ pub fn validate_domain_name(name: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Address a domain's hosts entry points at unless the user picks another.
+pub const DEFAULT_RESOLVE_IP: &str = "127.0.0.1";
+
+/// Validates the address written to the hosts file for a domain: an IPv4 or
+/// IPv6 address that a client can connect to.
+pub fn validate_resolve_ip(ip: &str) -> Result<(), String> {
+    match ip.parse::<std::net::IpAddr>() {
+        Ok(addr) if addr.is_unspecified() || addr.is_multicast() => {
+            Err(format!("'{}' cannot be used as a domain address", ip))
+        }
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!(
+            "Invalid IP address '{}'. Use an IPv4 or IPv6 address like 192.168.1.20",
+            ip
+        )),
+    }
+}
+
+/// True for addresses on this machine (127.0.0.0/8, ::1), where Caddy answers.
+pub fn is_loopback_ip(ip: &str) -> bool {
+    ip.parse::<std::net::IpAddr>()
+        .is_ok_and(|addr| addr.is_loopback())
+}
+
+/// Validates a subject alternative name for a domain certificate: a domain
+/// name, a `*.` wildcard over one, `localhost`, or an IPv4/IPv6 address.
+pub fn validate_san(san: &str) -> Result<(), String> {
+    if san.parse::<std::net::IpAddr>().is_ok() || san.eq_ignore_ascii_case("localhost") {
+        return Ok(());
+    }
+    let name = san.strip_prefix("*.").unwrap_or(san);
+    validate_domain_name(name).map_err(|_| {
+        format!(
+            "Invalid certificate name '{}'. Use a domain, '*.domain', 'localhost', or an IP address",
+            san
+        )
+    })
+}
+
+/// The form of a SAN that goes into a certificate: domain names (and the name
+/// under a `*.` wildcard) in ASCII, addresses and `localhost` unchanged.
+pub fn san_to_ascii(san: &str) -> Result<String, String> {
+    validate_san(san)?;
+    if san.parse::<std::net::IpAddr>().is_ok() || san.eq_ignore_ascii_case("localhost") {
+        return Ok(san.to_string());
+    }
+    match san.strip_prefix("*.") {
+        Some(name) => Ok(format!("*.{}", domain_to_ascii(name)?)),
+        None => domain_to_ascii(san),
+    }
+}
+
+/// Parse a comma- or whitespace-separated SAN list, dropping blanks and duplicates.
+pub fn parse_san_list(list: &str) -> Vec<String> {
+    let mut sans: Vec<String> = Vec::new();
+    for san in list.split(|c: char| c == ',' || c.is_whitespace()) {
+        let san = san.trim();
+        if !san.is_empty() && !sans.iter().any(|s| s.eq_ignore_ascii_case(san)) {
+            sans.push(san.to_string());
+        }
+    }
+    sans
+}
+
 /// XAMPP VirtualHost configuration sent to the daemon.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XamppVhostConfig {
     pub name: String,
+    /// Other hostnames served by the same VirtualHost (ServerAlias)
+    #[serde(default)]
+    pub aliases: Vec<String>,
     pub document_root: String,
     /// "http", "https", or "both"
     pub protocol: String,
@@ -137,6 +285,108 @@ mod tests {
         assert!(validate_domain_name(".test").is_err());
     }
 
+    #[test]
+    fn test_idn_round_trip() {
+        for (unicode, ascii) in [
+            ("café.test", "xn--caf-dma.test"),
+            ("münchen.test", "xn--mnchen-3ya.test"),
+            ("shop.bücher.test", "shop.xn--bcher-kva.test"),
+            ("例え.テスト", "xn--r8jz45g.xn--zckzah"),
+            ("project.test", "project.test"),
+        ] {
+            assert_eq!(domain_to_ascii(unicode).unwrap(), ascii);
+            assert_eq!(domain_to_unicode(ascii), unicode);
+            assert_eq!(domain_to_ascii(ascii).unwrap(), ascii);
+            assert!(validate_domain_name(unicode).is_ok(), "{}", unicode);
+            assert!(validate_domain_name(ascii).is_ok(), "{}", ascii);
+        }
+
+        // UTS-46 mapping: case folding and full-width characters
+        assert_eq!(domain_to_ascii("CAFÉ.Test").unwrap(), "xn--caf-dma.test");
+        assert_eq!(
+            domain_to_ascii("ｐｒｏｊｅｃｔ.test").unwrap(),
+            "project.test"
+        );
+
+        assert!(validate_domain_name("caf é.test").is_err());
+        assert!(validate_domain_name("café").is_err());
+        assert!(validate_domain_name("xn--.test").is_err());
+    }
+
+    #[test]
+    fn test_san_to_ascii() {
+        assert_eq!(san_to_ascii("*.café.test").unwrap(), "*.xn--caf-dma.test");
+        assert_eq!(
+            san_to_ascii("api.café.test").unwrap(),
+            "api.xn--caf-dma.test"
+        );
+        assert_eq!(san_to_ascii("127.0.0.1").unwrap(), "127.0.0.1");
+        assert_eq!(san_to_ascii("::1").unwrap(), "::1");
+        assert_eq!(san_to_ascii("localhost").unwrap(), "localhost");
+        assert!(san_to_ascii("*.*.café.test").is_err());
+    }
+
+    #[test]
+    fn test_san_validation() {
+        assert!(validate_san("api.project.test").is_ok());
+        assert!(validate_san("*.project.test").is_ok());
+        assert!(validate_san("localhost").is_ok());
+        assert!(validate_san("127.0.0.1").is_ok());
+        assert!(validate_san("::1").is_ok());
+        assert!(validate_san("192.168.1.20").is_ok());
+        assert!(validate_san("*.*.project.test").is_err());
+        assert!(validate_san("project.test/../x").is_err());
+        assert!(validate_san("").is_err());
+    }
+
+    #[test]
+    fn test_resolve_ip_validation() {
+        assert!(validate_resolve_ip("127.0.0.1").is_ok());
+        assert!(validate_resolve_ip("172.17.0.2").is_ok());
+        assert!(validate_resolve_ip("::1").is_ok());
+        assert!(validate_resolve_ip("fd00::20").is_ok());
+        assert!(validate_resolve_ip("0.0.0.0").is_err());
+        assert!(validate_resolve_ip("::").is_err());
+        assert!(validate_resolve_ip("224.0.0.1").is_err());
+        assert!(validate_resolve_ip("192.168.1").is_err());
+        assert!(validate_resolve_ip("10.0.0.1 evil.test").is_err());
+        assert!(validate_resolve_ip("").is_err());
+
+        assert!(is_loopback_ip("127.0.0.1"));
+        assert!(is_loopback_ip("127.0.1.1"));
+        assert!(is_loopback_ip("::1"));
+        assert!(!is_loopback_ip("192.168.1.20"));
+        assert!(!is_loopback_ip("not-an-ip"));
+    }
+
+    #[test]
+    fn test_parse_san_list() {
+        assert_eq!(
+            parse_san_list("localhost, 127.0.0.1\n::1  LOCALHOST,,"),
+            vec!["localhost", "127.0.0.1", "::1"]
+        );
+        assert!(parse_san_list("").is_empty());
+    }
+
+    #[test]
+    fn test_key_algorithm_round_trip() {
+        for alg in [
+            KeyAlgorithm::EcdsaP256,
+            KeyAlgorithm::EcdsaP384,
+            KeyAlgorithm::Rsa2048,
+            KeyAlgorithm::Rsa4096,
+        ] {
+            assert_eq!(alg.as_str().parse(), Ok(alg));
+            assert_eq!(KeyAlgorithm::from_key_type(alg.key_type()), Some(alg));
+            assert_eq!(
+                serde_json::to_value(alg).unwrap(),
+                serde_json::Value::String(alg.as_str().to_string())
+            );
+        }
+        assert!("rsa1024".parse::<KeyAlgorithm>().is_err());
+        assert_eq!(KeyAlgorithm::default(), KeyAlgorithm::EcdsaP256);
+    }
+
     #[test]
     fn test_port_validation() {
         assert!(validate_port(3000).is_ok());
@@ -56,6 +56,26 @@ impl JsonRpcResponse {
     }
 }
 
+/// JSON-RPC 2.0 notification: a message without an id, pushed by the daemon on
+/// streaming connections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+impl JsonRpcNotification {
+    pub fn new(method: &str, params: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcError {
     pub code: i32,
@@ -97,12 +117,23 @@ fn default_https_port() -> u16 {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerateCertParams {
     pub domain: String,
+    /// Additional SANs besides `domain`: aliases, `*.` wildcards, `localhost`, IPs
+    #[serde(default)]
+    pub sans: Vec<String>,
+    #[serde(default)]
+    pub key_algorithm: super::domain::KeyAlgorithm,
+    /// Always mint a new key pair instead of reusing a valid existing cert
+    #[serde(default)]
+    pub force: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerateCertResult {
     pub cert_path: String,
     pub key_path: String,
+    /// True when an existing valid cert was returned instead of issuing a new one
+    #[serde(default)]
+    pub reused: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,12 +141,34 @@ pub struct StatusResult {
     pub daemon_running: bool,
     pub caddy_running: bool,
     pub ca_installed: bool,
+    /// Trust status of the CA in each certificate store the daemon manages
     #[serde(default)]
-    pub ca_trusted: bool,
+    pub trust_stores: Vec<TrustStoreStatus>,
     #[serde(default)]
     pub xampp_running: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustStoreKind {
+    System,
+    Firefox,
+    Chromium,
+    Java,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustStoreStatus {
+    pub kind: TrustStoreKind,
+    /// Human-readable store name, e.g. "Firefox (snap) default-release"
+    pub label: String,
+    pub path: String,
+    pub trusted: bool,
+    /// Why the store could not be checked or updated, if it could not
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
 // --- XAMPP types ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,10 +207,63 @@ pub struct AccessLogEntry {
     pub proto: String,
 }
 
+/// Conditions an access log entry must meet; unset fields match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessLogFilter {
+    /// Unix seconds, inclusive
+    pub since: Option<f64>,
+    pub until: Option<f64>,
+    /// Compared case-insensitively
+    pub method: Option<String>,
+    /// Status range, inclusive; 400 and 499 select all client errors
+    pub status_min: Option<u16>,
+    pub status_max: Option<u16>,
+    /// Substring of the URI, or a regular expression when `uri_regex` is set
+    pub uri: Option<String>,
+    pub uri_regex: bool,
+    pub remote_ip: Option<String>,
+    /// Seconds
+    pub min_duration: Option<f64>,
+    pub header: Option<HeaderMatch>,
+}
+
+/// A request header to look for. Names are case-insensitive; without a value
+/// any request carrying the header matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderMatch {
+    pub name: String,
+    /// Case-insensitive substring of one of the header's values
+    pub value: Option<String>,
+}
+
+/// Entries are returned newest first, searching rotated log files too.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetAccessLogParams {
     pub domain: String,
     pub limit: Option<u64>,
+    #[serde(default)]
+    pub filter: AccessLogFilter,
+    /// `next_cursor` of the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Like `GetAccessLogParams`, across the logs of every domain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryAccessLogsParams {
+    pub limit: Option<u64>,
+    #[serde(default)]
+    pub filter: AccessLogFilter,
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessLogPage {
+    pub entries: Vec<AccessLogEntry>,
+    /// Set when older matching entries remain
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -165,6 +271,17 @@ pub struct ClearAccessLogParams {
     pub domain: String,
 }
 
+/// Streaming: after the (null) response, the daemon sends an
+/// `ACCESS_LOG_ENTRY` notification for each new entry until the client
+/// disconnects.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailAccessLogParams {
+    pub domain: String,
+}
+
+/// Notification method carrying an `AccessLogEntry` on a `tail_access_log` stream.
+pub const ACCESS_LOG_ENTRY: &str = "access_log_entry";
+
 // --- Tunnel types ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -251,3 +368,625 @@ pub struct EnsureCloudflaredResult {
     #[serde(default)]
     pub version: Option<String>,
 }
+
+// --- Certificate inventory types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListCertificatesParams {
+    /// Domains that currently reference a certificate (HTTPS domains in the app DB).
+    #[serde(default)]
+    pub active_domains: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    /// File stem under CERTS_DIR (the domain name for leaf certs)
+    pub name: String,
+    pub cert_path: String,
+    #[serde(default)]
+    pub key_path: Option<String>,
+    pub subject: String,
+    pub sans: Vec<String>,
+    pub issuer: String,
+    pub serial: String,
+    /// Unix timestamps (seconds)
+    pub not_before: i64,
+    pub not_after: i64,
+    pub fingerprint_sha256: String,
+    pub key_type: String,
+    pub is_ca: bool,
+    /// True when the signature verifies against the current root CA
+    pub signed_by_current_ca: bool,
+    pub in_use: bool,
+    /// Permitted DNS/IP subtrees of a name-constrained CA; empty when unconstrained
+    #[serde(default)]
+    pub name_constraints: Vec<String>,
+    /// Supplied by the user; never renewed or reissued by the daemon
+    #[serde(default)]
+    pub custom: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListCertificatesResult {
+    pub certificates: Vec<CertificateInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCertificateParams {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallCustomCertParams {
+    pub domain: String,
+    /// Other names the certificate must cover besides `domain`
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Leaf certificate first, optionally followed by its intermediates
+    pub cert_pem: String,
+    pub key_pem: String,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallCustomCertResult {
+    pub cert_path: String,
+    pub key_path: String,
+    /// Unix timestamp (seconds)
+    pub not_after: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveCustomCertParams {
+    pub domain: String,
+}
+
+// --- Daemon settings ---
+
+/// Settings persisted by the daemon so background jobs keep working when the app is closed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonSettings {
+    /// Reissue domain certs this many days before they expire (0 disables renewal)
+    #[serde(default = "default_cert_renewal_window_days")]
+    pub cert_renewal_window_days: u32,
+    /// Embed X.509 name constraints in newly generated root CAs
+    #[serde(default = "default_true")]
+    pub ca_name_constraints: bool,
+    /// DNS suffixes a constrained CA may sign for (e.g. "test" covers *.test)
+    #[serde(default = "default_ca_permitted_suffixes")]
+    pub ca_permitted_suffixes: Vec<String>,
+    /// IP addresses or CIDR ranges a constrained CA may sign for
+    #[serde(default = "default_ca_permitted_ips")]
+    pub ca_permitted_ips: Vec<String>,
+    /// Serve an ACME directory on loopback backed by the LocalDomain CA
+    #[serde(default)]
+    pub acme_enabled: bool,
+    #[serde(default = "default_acme_port")]
+    pub acme_port: u16,
+    /// Loopback port HTTP-01 challenge responses are fetched from
+    #[serde(default = "default_acme_http01_port")]
+    pub acme_http01_port: u16,
+    /// Skip challenges for domains LocalDomain already manages
+    #[serde(default = "default_true")]
+    pub acme_auto_approve: bool,
+    /// Publish the CA onboarding site for phones and tablets on the LAN interface
+    #[serde(default)]
+    pub lan_sharing: bool,
+    #[serde(default = "default_onboarding_host")]
+    pub onboarding_host: String,
+    /// HTTP port of the onboarding site, kept apart from Caddy's domain listeners
+    #[serde(default = "default_onboarding_port")]
+    pub onboarding_port: u16,
+    /// What to do when another program rewrites the managed hosts block
+    #[serde(default)]
+    pub hosts_tamper_policy: HostsTamperPolicy,
+}
+
+impl Default for DaemonSettings {
+    fn default() -> Self {
+        Self {
+            cert_renewal_window_days: default_cert_renewal_window_days(),
+            ca_name_constraints: true,
+            ca_permitted_suffixes: default_ca_permitted_suffixes(),
+            ca_permitted_ips: default_ca_permitted_ips(),
+            acme_enabled: false,
+            acme_port: default_acme_port(),
+            acme_http01_port: default_acme_http01_port(),
+            acme_auto_approve: true,
+            lan_sharing: false,
+            onboarding_host: default_onboarding_host(),
+            onboarding_port: default_onboarding_port(),
+            hosts_tamper_policy: HostsTamperPolicy::default(),
+        }
+    }
+}
+
+fn default_cert_renewal_window_days() -> u32 {
+    30
+}
+
+fn default_true() -> bool {
+    true
+}
+
+pub fn default_acme_port() -> u16 {
+    8555
+}
+
+pub fn default_acme_http01_port() -> u16 {
+    80
+}
+
+pub fn default_onboarding_host() -> String {
+    "ca.test".to_string()
+}
+
+pub fn default_onboarding_port() -> u16 {
+    8554
+}
+
+pub fn default_ca_permitted_suffixes() -> Vec<String> {
+    ["test", "localhost", "internal"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+pub fn default_ca_permitted_ips() -> Vec<String> {
+    vec!["127.0.0.0/8".to_string(), "::1/128".to_string()]
+}
+
+// --- Certificate renewal types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertRenewalEvent {
+    pub name: String,
+    /// Unix timestamps (seconds)
+    pub old_not_after: i64,
+    #[serde(default)]
+    pub new_not_after: Option<i64>,
+    pub renewed_at: i64,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TakeRenewalEventsResult {
+    pub events: Vec<CertRenewalEvent>,
+}
+
+// --- Hosts backup types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostsBackup {
+    pub id: String,
+    /// Unix timestamp (seconds)
+    pub created_at: i64,
+    /// Hex SHA-256 of the file content
+    pub sha256: String,
+    pub size: u64,
+    /// The hosts file as it was before LocalDomain first changed it; never rotated
+    pub original: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListHostsBackupsResult {
+    pub backups: Vec<HostsBackup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostsBackupParams {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Line diff from a backup to the current hosts file: `Added` lines exist only
+/// in the current file, `Removed` lines only in the backup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostsBackupDiff {
+    pub id: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreOriginalHostsResult {
+    /// False when other programs changed the file since, so only the managed
+    /// block was removed instead of restoring the original byte for byte
+    pub exact: bool,
+}
+
+// --- Domain conflict types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckDomainsParams {
+    /// Hosts entries the app is about to sync, one per domain name or alias
+    pub entries: Vec<super::domain::HostsEntry>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainWarningKind {
+    /// The name appears outside the managed block with another address; that
+    /// line is listed first, so it wins
+    HostsConflict,
+    /// The name appears outside the managed block with the same address
+    HostsDuplicate,
+    /// Public DNS has records for the name, which the hosts entry will hide
+    PublicDns,
+    /// The TLD is HSTS-preloaded, so browsers refuse plain HTTP
+    HstsTld,
+    /// `.local` names go to multicast DNS before the hosts file on many systems
+    MdnsTld,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainWarning {
+    pub domain: String,
+    pub kind: DomainWarningKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckDomainsResult {
+    pub warnings: Vec<DomainWarning>,
+}
+
+// --- Hosts watcher types ---
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HostsTamperPolicy {
+    /// Rewrite the managed block as soon as it no longer matches
+    #[default]
+    Repair,
+    /// Leave the file alone and only report the change
+    Notify,
+}
+
+impl HostsTamperPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Repair => "repair",
+            Self::Notify => "notify",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "repair" => Some(Self::Repair),
+            "notify" => Some(Self::Notify),
+            _ => None,
+        }
+    }
+}
+
+/// An external edit to the hosts file that changed LocalDomain's managed block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostsTamperEvent {
+    /// Unix timestamp (seconds)
+    pub detected_at: i64,
+    /// Entries LocalDomain wrote that were removed or changed
+    pub missing: Vec<super::domain::HostsEntry>,
+    /// Lines inside the managed block that LocalDomain did not write
+    pub unexpected: Vec<String>,
+    /// Lines added to or removed from the rest of the file in the same edit;
+    /// tools usually leave a comment naming themselves here
+    pub added_lines: Vec<String>,
+    pub removed_lines: Vec<String>,
+    pub repaired: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TakeHostsEventsResult {
+    pub events: Vec<HostsTamperEvent>,
+}
+
+// --- Hostname override types ---
+
+/// A real hostname pointed at a local upstream until it expires. The daemon
+/// drops the names from the hosts file and Caddy on its own once it does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostnameOverride {
+    pub domain: String,
+    /// The domain and its aliases
+    pub names: Vec<String>,
+    /// Unix timestamp (seconds)
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncOverridesParams {
+    pub overrides: Vec<HostnameOverride>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverrideRevertEvent {
+    pub domain: String,
+    pub names: Vec<String>,
+    pub expires_at: i64,
+    /// Unix timestamp (seconds)
+    pub reverted_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TakeOverrideEventsResult {
+    pub events: Vec<OverrideRevertEvent>,
+}
+
+// --- CA rotation types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotateCaParams {
+    /// Days to keep the previous CA trusted after rotation (0 retires it immediately)
+    #[serde(default = "default_ca_overlap_days")]
+    pub overlap_days: u32,
+}
+
+fn default_ca_overlap_days() -> u32 {
+    7
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotateCaResult {
+    pub new_fingerprint: String,
+    pub previous_fingerprint: String,
+    pub reissued: Vec<String>,
+    #[serde(default)]
+    pub failed: Vec<String>,
+    /// False when the new CA could not be trusted non-interactively (macOS)
+    pub trust_updated: bool,
+    /// Unix timestamp when the previous CA will be retired, if still in overlap
+    #[serde(default)]
+    pub retire_at: Option<i64>,
+}
+
+// --- CA import/export types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportCaParams {
+    pub cert_pem: String,
+    /// PKCS#8 (optionally encrypted), PKCS#1/SEC1, or a LocalDomain encrypted key
+    pub key_pem: String,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    /// Days to keep the replaced CA trusted, as for a rotation
+    #[serde(default = "default_ca_overlap_days")]
+    pub overlap_days: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportCaResult {
+    pub subject: String,
+    pub fingerprint: String,
+    /// Set when the import replaced an existing CA
+    #[serde(default)]
+    pub rotation: Option<RotateCaResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportCaBundleParams {
+    /// Encrypts the root key inside the bundle
+    pub passphrase: String,
+    /// Unlocks the root key when it is encrypted at rest
+    #[serde(default)]
+    pub root_passphrase: Option<String>,
+}
+
+// --- CA hierarchy types ---
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RootKeyState {
+    /// Plaintext key on disk; the daemon can renew the intermediate on its own
+    Online,
+    /// Encrypted at rest; renewing the intermediate needs the passphrase
+    Encrypted,
+    /// Not on this machine; the user supplies it to renew the intermediate
+    Offline,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaStatus {
+    pub root_key: RootKeyState,
+    /// Unix timestamp (seconds)
+    #[serde(default)]
+    pub intermediate_not_after: Option<i64>,
+    pub intermediate_valid: bool,
+    /// DNS suffixes the CA may sign for; None when it is not name-constrained
+    #[serde(default)]
+    pub permitted_dns_suffixes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectRootKeyParams {
+    pub passphrase: String,
+    /// Return the encrypted key instead of keeping it on disk
+    #[serde(default)]
+    pub offline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectRootKeyResult {
+    #[serde(default)]
+    pub encrypted_key_pem: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenewIntermediateParams {
+    /// Root key supplied by the user when it is kept offline
+    #[serde(default)]
+    pub root_key_pem: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+// --- Certificate export types ---
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CertExportFormat {
+    /// .p12/.pfx with key and chain, protected by a passphrase
+    Pkcs12,
+    /// Single certificate in DER encoding
+    Der,
+    /// PEM certificate followed by its intermediates
+    PemChain,
+    /// PEM private key followed by the certificate chain
+    PemBundle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportCertificateParams {
+    pub name: String,
+    pub format: CertExportFormat,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportCertificateResult {
+    /// File contents, base64-encoded; the app writes them as the user
+    pub data_base64: String,
+    pub file_name: String,
+}
+
+// --- Toolchain trust types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnvVar {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolchainTrustResult {
+    /// Detected JDK `cacerts` keystores and whether each contains the CA
+    pub java_keystores: Vec<TrustStoreStatus>,
+    /// The CA certificate on its own (for NODE_EXTRA_CA_CERTS)
+    pub ca_cert_path: String,
+    /// System roots plus the CA, once written; tools that replace their roots use it
+    pub bundle_path: Option<String>,
+    /// Variables that point Node, Python, OpenSSL-based tools and curl at the CA
+    pub env: Vec<EnvVar>,
+}
+
+// --- Device onboarding types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceOnboardingInfo {
+    pub enabled: bool,
+    pub host: String,
+    pub lan_ip: Option<String>,
+    /// Set once the site is being published
+    pub url: Option<String>,
+    /// QR code of `url` as a standalone SVG document
+    pub qr_svg: Option<String>,
+}
+
+// --- Traffic statistics types ---
+
+/// How far back traffic statistics reach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrafficWindow {
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "24h")]
+    Day,
+    #[serde(rename = "7d")]
+    Week,
+    #[serde(rename = "30d")]
+    Month,
+}
+
+impl TrafficWindow {
+    pub fn seconds(&self) -> i64 {
+        match self {
+            TrafficWindow::FifteenMinutes => 15 * 60,
+            TrafficWindow::Hour => 60 * 60,
+            TrafficWindow::Day => 24 * 60 * 60,
+            TrafficWindow::Week => 7 * 24 * 60 * 60,
+            TrafficWindow::Month => 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTrafficStatsParams {
+    pub window: TrafficWindow,
+    /// Only this domain; all domains with access logging when unset
+    #[serde(default)]
+    pub domain: Option<String>,
+}
+
+/// Responses by status class.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusClassCounts {
+    #[serde(rename = "1xx")]
+    pub informational: u64,
+    #[serde(rename = "2xx")]
+    pub success: u64,
+    #[serde(rename = "3xx")]
+    pub redirect: u64,
+    #[serde(rename = "4xx")]
+    pub client_error: u64,
+    #[serde(rename = "5xx")]
+    pub server_error: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficStats {
+    pub requests: u64,
+    /// Averaged over the whole window
+    pub requests_per_minute: f64,
+    /// Response bytes
+    pub bytes: u64,
+    pub status: StatusClassCounts,
+    /// Duration percentiles in seconds, estimated to within about 20%; unset
+    /// without requests
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteTrafficStats {
+    /// Method and path, with ids in the path replaced by `:id`
+    pub route: String,
+    #[serde(flatten)]
+    pub stats: TrafficStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainTrafficStats {
+    pub domain: String,
+    #[serde(flatten)]
+    pub stats: TrafficStats,
+    /// Busiest first
+    pub routes: Vec<RouteTrafficStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTrafficStatsResult {
+    pub window: TrafficWindow,
+    /// Start of the window (unix seconds)
+    pub since: i64,
+    pub domains: Vec<DomainTrafficStats>,
+}
@@ -1,19 +1,60 @@
 use crate::error::AppError;
 use crate::state::AppState;
-use localdomain_shared::protocol::AccessLogEntry;
-use tauri::State;
+use localdomain_shared::protocol::{
+    AccessLogEntry, AccessLogFilter, AccessLogPage, GetAccessLogParams, GetTrafficStatsParams,
+    GetTrafficStatsResult, QueryAccessLogsParams, TrafficWindow,
+};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter, State};
+
+/// Bumped to end the running tail. The inspector shows one domain at a time,
+/// so starting a tail ends the previous one.
+static TAIL_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Serialize)]
+struct AccessLogTailEvent {
+    domain: String,
+    entry: AccessLogEntry,
+}
 
 #[tauri::command]
 pub fn get_access_log(
     state: State<AppState>,
     domain: String,
     limit: Option<u64>,
-) -> Result<Vec<AccessLogEntry>, AppError> {
+    filter: Option<AccessLogFilter>,
+    cursor: Option<String>,
+) -> Result<AccessLogPage, AppError> {
+    let client = state.daemon_client.lock().unwrap();
+    let page = client
+        .get_access_log(GetAccessLogParams {
+            domain,
+            limit,
+            filter: filter.unwrap_or_default(),
+            cursor,
+        })
+        .map_err(|e| AppError::Daemon(e.to_string()))?;
+    Ok(page)
+}
+
+/// Matching entries from the access logs of all domains, newest first.
+#[tauri::command]
+pub fn query_access_logs(
+    state: State<AppState>,
+    limit: Option<u64>,
+    filter: Option<AccessLogFilter>,
+    cursor: Option<String>,
+) -> Result<AccessLogPage, AppError> {
     let client = state.daemon_client.lock().unwrap();
-    let entries = client
-        .get_access_log(&domain, limit)
+    let page = client
+        .query_access_logs(QueryAccessLogsParams {
+            limit,
+            filter: filter.unwrap_or_default(),
+            cursor,
+        })
         .map_err(|e| AppError::Daemon(e.to_string()))?;
-    Ok(entries)
+    Ok(page)
 }
 
 #[tauri::command]
@@ -24,3 +65,63 @@ pub fn clear_access_log(state: State<AppState>, domain: String) -> Result<(), Ap
         .map_err(|e| AppError::Daemon(e.to_string()))?;
     Ok(())
 }
+
+/// Request counts, status classes and latency percentiles per domain and
+/// route over `window`, optionally for one domain only.
+#[tauri::command]
+pub fn get_traffic_stats(
+    state: State<AppState>,
+    window: TrafficWindow,
+    domain: Option<String>,
+) -> Result<GetTrafficStatsResult, AppError> {
+    let client = state.daemon_client.lock().unwrap();
+    let stats = client
+        .get_traffic_stats(GetTrafficStatsParams { window, domain })
+        .map_err(|e| AppError::Daemon(e.to_string()))?;
+    Ok(stats)
+}
+
+/// Emit "access-log-entry" for each new entry in `domain`'s access log until
+/// `stop_access_log_tail` or another tail starts. "access-log-tail-ended" is
+/// emitted if the daemon ends the stream first.
+#[tauri::command]
+pub fn start_access_log_tail(
+    app: AppHandle,
+    state: State<AppState>,
+    domain: String,
+) -> Result<(), AppError> {
+    let mut stream = state
+        .daemon_client
+        .lock()
+        .unwrap()
+        .tail_access_log(&domain)
+        .map_err(|e| AppError::Daemon(e.to_string()))?;
+    let generation = TAIL_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    std::thread::spawn(move || {
+        while TAIL_GENERATION.load(Ordering::SeqCst) == generation {
+            match stream.next_entry() {
+                Ok(Some(entry)) => {
+                    let event = AccessLogTailEvent {
+                        domain: domain.clone(),
+                        entry,
+                    };
+                    let _ = app.emit("access-log-entry", event);
+                }
+                Ok(None) => {}
+                Err(_) => {
+                    if TAIL_GENERATION.load(Ordering::SeqCst) == generation {
+                        let _ = app.emit("access-log-tail-ended", &domain);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_access_log_tail() {
+    TAIL_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
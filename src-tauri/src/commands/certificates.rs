@@ -0,0 +1,280 @@
+use crate::db::models;
+use crate::error::AppError;
+use crate::state::AppState;
+use base64::Engine;
+use localdomain_shared::protocol::{
+    CaStatus, CertExportFormat, CertificateInfo, DeviceOnboardingInfo, ImportCaResult,
+    RotateCaResult,
+};
+use tauri::State;
+
+/// Names of domains that serve HTTPS and therefore reference a certificate.
+fn https_domain_names(state: &AppState) -> Result<Vec<String>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let domains = models::list_domains(&conn)?;
+    Ok(domains
+        .into_iter()
+        .filter(|d| d.protocol == "https" || d.protocol == "both")
+        .map(|d| d.name)
+        .collect())
+}
+
+#[tauri::command]
+pub fn list_certificates(state: State<AppState>) -> Result<Vec<CertificateInfo>, AppError> {
+    let active_domains = https_domain_names(state.inner())?;
+    let client = state.daemon_client.lock().unwrap();
+    let result = client
+        .list_certificates(active_domains)
+        .map_err(|e| AppError::Daemon(e.to_string()))?;
+    Ok(result.certificates)
+}
+
+#[tauri::command]
+pub fn delete_certificate(state: State<AppState>, name: String) -> Result<(), AppError> {
+    // The daemon refuses certificates Caddy still serves
+    {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .delete_certificate(&name)
+            .map_err(|e| AppError::Daemon(e.to_string()))?;
+    }
+
+    let conn = state.db.lock().unwrap();
+    models::insert_audit_log(&conn, "certificate_deleted", None, Some(&name))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn rotate_ca(state: State<AppState>, overlap_days: u32) -> Result<RotateCaResult, AppError> {
+    let result = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .rotate_ca(overlap_days)
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+
+    let conn = state.db.lock().unwrap();
+    let details = format!(
+        "{} -> {} ({} reissued, {} failed)",
+        result.previous_fingerprint,
+        result.new_fingerprint,
+        result.reissued.len(),
+        result.failed.len()
+    );
+    models::insert_audit_log(&conn, "ca_rotated", None, Some(&details))?;
+    Ok(result)
+}
+
+/// Switch issuance to an existing CA such as mkcert's root. Without `key_path`,
+/// the key is read from the certificate file (an exported CA bundle).
+#[tauri::command]
+pub fn import_ca(
+    state: State<AppState>,
+    cert_path: String,
+    key_path: Option<String>,
+    passphrase: Option<String>,
+    overlap_days: u32,
+) -> Result<ImportCaResult, AppError> {
+    let cert_pem = std::fs::read_to_string(&cert_path)?;
+    let key_pem = match key_path {
+        Some(ref path) => std::fs::read_to_string(path)?,
+        None => cert_pem.clone(),
+    };
+
+    let result = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .import_ca(
+                cert_pem,
+                key_pem,
+                passphrase.filter(|p| !p.is_empty()),
+                overlap_days,
+            )
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+
+    let conn = state.db.lock().unwrap();
+    let details = match result.rotation {
+        Some(ref rotation) => format!(
+            "{} ({}), replacing {} ({} reissued, {} failed)",
+            result.subject,
+            result.fingerprint,
+            rotation.previous_fingerprint,
+            rotation.reissued.len(),
+            rotation.failed.len()
+        ),
+        None => format!("{} ({})", result.subject, result.fingerprint),
+    };
+    models::insert_audit_log(&conn, "ca_imported", None, Some(&details))?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn retire_previous_ca(state: State<AppState>) -> Result<(), AppError> {
+    {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .retire_previous_ca()
+            .map_err(|e| AppError::Daemon(e.to_string()))?;
+    }
+
+    let conn = state.db.lock().unwrap();
+    models::insert_audit_log(&conn, "ca_retired", None, None)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_ca_status(state: State<AppState>) -> Result<CaStatus, AppError> {
+    let client = state.daemon_client.lock().unwrap();
+    client
+        .get_ca_status()
+        .map_err(|e| AppError::Daemon(e.to_string()))
+}
+
+/// URL and QR code phones on the LAN open to install the CA.
+#[tauri::command]
+pub fn get_device_onboarding(state: State<AppState>) -> Result<DeviceOnboardingInfo, AppError> {
+    let client = state.daemon_client.lock().unwrap();
+    client
+        .get_device_onboarding()
+        .map_err(|e| AppError::Daemon(e.to_string()))
+}
+
+/// Encrypt the root CA key. With `offline_path`, the encrypted key is saved there
+/// (owned by the user) and removed from the daemon's data directory.
+#[tauri::command]
+pub fn protect_root_key(
+    state: State<AppState>,
+    passphrase: String,
+    offline_path: Option<String>,
+) -> Result<(), AppError> {
+    if passphrase.len() < 8 {
+        return Err(AppError::Validation(
+            "Passphrase must be at least 8 characters".to_string(),
+        ));
+    }
+    // Open the destination first: the daemon deletes its copy once it hands the key over
+    let offline_file = offline_path
+        .as_deref()
+        .map(std::fs::File::create)
+        .transpose()?;
+
+    let result = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .protect_root_key(&passphrase, offline_file.is_some())
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+    if let (Some(mut file), Some(pem)) = (offline_file, result.encrypted_key_pem) {
+        use std::io::Write;
+        file.write_all(pem.as_bytes())?;
+    }
+
+    let conn = state.db.lock().unwrap();
+    let details = match offline_path {
+        Some(ref path) => format!("Moved offline to {}", path),
+        None => "Encrypted at rest".to_string(),
+    };
+    models::insert_audit_log(&conn, "root_key_protected", None, Some(&details))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn renew_intermediate(
+    state: State<AppState>,
+    passphrase: Option<String>,
+    root_key_path: Option<String>,
+) -> Result<(), AppError> {
+    let root_key_pem = root_key_path
+        .as_deref()
+        .map(std::fs::read_to_string)
+        .transpose()?;
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+
+    let result = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .renew_intermediate(root_key_pem, passphrase)
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+
+    // Per-leaf results reach the audit log through the daemon's renewal event queue
+    let conn = state.db.lock().unwrap();
+    let details = format!("{} certificates reissued", result.events.len());
+    models::insert_audit_log(&conn, "intermediate_renewed", None, Some(&details))?;
+    Ok(())
+}
+
+/// Export a certificate to `path`. The daemon returns the bytes and the file is
+/// written here, so it is owned by the user rather than root.
+#[tauri::command]
+pub fn export_certificate(
+    state: State<AppState>,
+    name: String,
+    format: CertExportFormat,
+    passphrase: Option<String>,
+    path: String,
+) -> Result<(), AppError> {
+    let result = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .export_certificate(&name, format, passphrase.filter(|p| !p.is_empty()))
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(result.data_base64)
+        .map_err(|e| AppError::Other(format!("Invalid export data: {}", e)))?;
+
+    std::fs::write(&path, data)?;
+    // Files carrying a private key should only be readable by the user
+    #[cfg(unix)]
+    if matches!(format, CertExportFormat::Pkcs12 | CertExportFormat::PemBundle) {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    let conn = state.db.lock().unwrap();
+    models::insert_audit_log(
+        &conn,
+        "certificate_exported",
+        None,
+        Some(&format!("{} -> {}", name, path)),
+    )?;
+    Ok(())
+}
+
+/// Export the root CA and its passphrase-encrypted key to `path`, for importing
+/// the same CA on other machines.
+#[tauri::command]
+pub fn export_ca_bundle(
+    state: State<AppState>,
+    passphrase: String,
+    root_passphrase: Option<String>,
+    path: String,
+) -> Result<(), AppError> {
+    if passphrase.len() < 8 {
+        return Err(AppError::Validation(
+            "Passphrase must be at least 8 characters".to_string(),
+        ));
+    }
+    let result = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .export_ca_bundle(&passphrase, root_passphrase.filter(|p| !p.is_empty()))
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(result.data_base64)
+        .map_err(|e| AppError::Other(format!("Invalid export data: {}", e)))?;
+
+    std::fs::write(&path, data)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    let conn = state.db.lock().unwrap();
+    models::insert_audit_log(&conn, "ca_exported", None, Some(&path))?;
+    Ok(())
+}
@@ -1,14 +1,22 @@
+use crate::daemon_client::DaemonClient;
 use crate::db::models::{self, CreateDomainRequest, Domain, UpdateDomainRequest};
 use crate::error::AppError;
 use crate::state::AppState;
 use crate::tray;
 use crate::xampp;
 use localdomain_shared::domain::{
-    validate_document_root, validate_domain_name, validate_port, CaddyDomainConfig, HostsEntry,
-    XamppVhostConfig,
+    domain_to_ascii, is_loopback_ip, san_to_ascii, validate_document_root, validate_domain_name,
+    validate_port, validate_resolve_ip, validate_san, CaddyDomainConfig, HostsEntry, KeyAlgorithm,
+    XamppVhostConfig, DEFAULT_RESOLVE_IP,
 };
+use localdomain_shared::protocol::{self, HostnameOverride};
+use rusqlite::Connection;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Manager, State};
 
+/// Longest a hostname override stays active before it reverts.
+const MAX_OVERRIDE_TTL_MINUTES: i64 = 24 * 60;
+
 fn get_port_settings(state: &AppState) -> (u16, u16) {
     let conn = state.db.lock().unwrap();
     let http_port = models::get_setting(&conn, "http_port")
@@ -24,20 +32,309 @@ fn get_port_settings(state: &AppState) -> (u16, u16) {
     (http_port, https_port)
 }
 
+/// Names the domain's certificate must cover besides the domain itself.
+fn cert_sans(domain: &Domain) -> Vec<String> {
+    let mut sans = Vec::new();
+    if domain.wildcard {
+        sans.push(format!("*.{}", domain.name));
+    }
+    sans.extend(domain.aliases.iter().cloned());
+    sans.extend(domain.extra_sans.iter().cloned());
+    sans
+}
+
+/// Certificate and key paths for an HTTPS domain. User-supplied certificates are
+/// served as-is; otherwise the daemon issues (or reuses) one from the local CA.
+fn domain_cert(client: &DaemonClient, d: &Domain) -> Result<(String, String), AppError> {
+    if let (Some(cert), Some(key)) = (&d.custom_cert_path, &d.custom_key_path) {
+        return Ok((cert.clone(), key.clone()));
+    }
+    let key_algorithm = d.key_algorithm.parse::<KeyAlgorithm>().unwrap_or_default();
+    let result = client
+        .generate_cert(&d.name, cert_sans(d), key_algorithm)
+        .map_err(|e| AppError::Daemon(format!("cert generation failed for {}: {}", d.name, e)))?;
+    Ok((result.cert_path, result.key_path))
+}
+
+/// The certificate Caddy serves a domain with, or none when it isn't served over HTTPS.
+fn caddy_cert(
+    client: &DaemonClient,
+    d: &Domain,
+) -> Result<(Option<String>, Option<String>), AppError> {
+    if !(d.protocol == "https" || d.protocol == "both") {
+        return Ok((None, None));
+    }
+    let (cert, key) = domain_cert(client, d)?;
+    Ok((Some(cert), Some(key)))
+}
+
+fn validate_cert_options(
+    extra_sans: Option<&[String]>,
+    key_algorithm: Option<&str>,
+) -> Result<(), AppError> {
+    for san in extra_sans.unwrap_or_default() {
+        validate_san(san).map_err(AppError::Validation)?;
+    }
+    if let Some(alg) = key_algorithm {
+        alg.parse::<KeyAlgorithm>().map_err(AppError::Validation)?;
+    }
+    Ok(())
+}
+
+/// Convert names entered in Unicode to the ASCII (punycode) form that's stored
+/// and used for hosts entries, Caddy and certificates.
+fn to_ascii_names(
+    name: Option<&mut String>,
+    aliases: Option<&mut Vec<String>>,
+    extra_sans: Option<&mut Vec<String>>,
+) -> Result<(), AppError> {
+    for hostname in name.into_iter().chain(aliases.into_iter().flatten()) {
+        validate_domain_name(hostname.trim()).map_err(AppError::Validation)?;
+        *hostname = domain_to_ascii(hostname.trim()).map_err(AppError::Validation)?;
+    }
+    for san in extra_sans.into_iter().flatten() {
+        *san = san_to_ascii(san.trim()).map_err(AppError::Validation)?;
+    }
+    Ok(())
+}
+
+/// Aliases must be valid, distinct names, and neither the domain name nor an
+/// alias may be used by another domain. `domain_id` is None when creating.
+fn validate_aliases(
+    conn: &Connection,
+    domain_id: Option<&str>,
+    name: &str,
+    aliases: &[String],
+) -> Result<(), AppError> {
+    for (i, alias) in aliases.iter().enumerate() {
+        validate_domain_name(alias).map_err(AppError::Validation)?;
+        if alias.eq_ignore_ascii_case(name)
+            || aliases[..i].iter().any(|a| a.eq_ignore_ascii_case(alias))
+        {
+            return Err(AppError::Validation(format!(
+                "'{}' is listed more than once",
+                alias
+            )));
+        }
+    }
+    for hostname in std::iter::once(name).chain(aliases.iter().map(String::as_str)) {
+        if let Some(owner) = models::hostname_owner(conn, hostname)? {
+            if domain_id != Some(owner.as_str()) {
+                return Err(AppError::Validation(format!(
+                    "'{}' is already used by another domain",
+                    hostname
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check the hosts address against how the domain is served: XAMPP sites live
+/// on this machine, and proxying to another machine needs a port.
+fn validate_resolve_options(
+    resolve_ip: &str,
+    hosts_only: bool,
+    domain_type: &str,
+    target_port: i32,
+) -> Result<(), AppError> {
+    validate_resolve_ip(resolve_ip).map_err(AppError::Validation)?;
+    if is_loopback_ip(resolve_ip) {
+        return Ok(());
+    }
+    if domain_type == "xampp" {
+        return Err(AppError::Validation(
+            "XAMPP domains must resolve to this machine".to_string(),
+        ));
+    }
+    if !hosts_only && target_port <= 0 {
+        return Err(AppError::Validation(format!(
+            "Set a target port to proxy to {}, or make the domain hosts-only",
+            resolve_ip
+        )));
+    }
+    Ok(())
+}
+
+/// Address written to the hosts file. A proxied domain with a remote address
+/// still resolves to this machine, so Caddy can forward to that address.
+fn hosts_ip(resolve_ip: &str, hosts_only: bool) -> &str {
+    if hosts_only || is_loopback_ip(resolve_ip) {
+        resolve_ip
+    } else {
+        DEFAULT_RESOLVE_IP
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn is_override_domain(state: &AppState, id: &str) -> Result<bool, AppError> {
+    let conn = state.db.lock().unwrap();
+    Ok(models::get_domain(&conn, id)?.is_some_and(|d| d.domain_type == "override"))
+}
+
+/// DNS suffixes the CA can sign for, or None when it is unconstrained. Without
+/// the daemon, the ones a newly generated CA would get from the settings. Call it
+/// before locking the database: syncing holds the daemon client while it reads it.
+fn ca_permitted_suffixes(state: &AppState) -> Result<Option<Vec<String>>, AppError> {
+    let client = state.daemon_client.lock().unwrap();
+    if client.is_daemon_running() {
+        return client
+            .get_ca_status()
+            .map(|status| status.permitted_dns_suffixes)
+            .map_err(|e| AppError::Daemon(e.to_string()));
+    }
+    drop(client);
+    let conn = state.db.lock().unwrap();
+    let settings = super::settings::load_settings(&conn)?;
+    Ok(settings
+        .ca_name_constraints
+        .then_some(settings.ca_permitted_suffixes))
+}
+
+/// Names an override needs a certificate from the LocalDomain CA for: none when
+/// it is plain HTTP or brings its own certificate.
+fn ca_signed_names<'a>(
+    protocol: &str,
+    custom_cert: bool,
+    names: impl IntoIterator<Item = &'a String>,
+) -> Vec<String> {
+    if custom_cert || !(protocol == "https" || protocol == "both") {
+        return Vec::new();
+    }
+    names.into_iter().cloned().collect()
+}
+
+/// Matches the daemon's name constraint check: the suffix itself or any name under it.
+fn suffix_permits(permitted: &[String], name: &str) -> bool {
+    let name = name.trim_start_matches("*.").to_lowercase();
+    permitted
+        .iter()
+        .any(|suffix| name == *suffix || name.ends_with(&format!(".{}", suffix)))
+}
+
+/// Check an override before it's turned on: it points a real hostname at this
+/// machine, so it has to be confirmed, end on its own and go through Caddy.
+/// Over HTTPS the CA must be able to sign the names, which a name-constrained
+/// CA can't for real hostnames.
+fn validate_override(
+    ttl_minutes: i64,
+    confirmed: bool,
+    resolve_ip: &str,
+    hosts_only: bool,
+    target_port: i32,
+    ca_names: &[String],
+    permitted_suffixes: Option<&[String]>,
+) -> Result<(), AppError> {
+    if !(1..=MAX_OVERRIDE_TTL_MINUTES).contains(&ttl_minutes) {
+        return Err(AppError::Validation(format!(
+            "Override duration must be between 1 and {} minutes",
+            MAX_OVERRIDE_TTL_MINUTES
+        )));
+    }
+    if hosts_only || !is_loopback_ip(resolve_ip) || target_port <= 0 {
+        return Err(AppError::Validation(
+            "An override needs a local target port to proxy to".to_string(),
+        ));
+    }
+    if let Some(permitted) = permitted_suffixes {
+        if let Some(name) = ca_names.iter().find(|n| !suffix_permits(permitted, n)) {
+            return Err(AppError::Validation(format!(
+                "The LocalDomain CA is limited to {} and can't issue a certificate for {}. Use HTTP, add a custom certificate, or turn off name constraints and rotate the CA",
+                permitted.join(", "),
+                name
+            )));
+        }
+    }
+    if !confirmed {
+        return Err(AppError::Validation(
+            "Confirm the override: the real site is unreachable from this machine while it is active"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Start an override's timer and record the activation.
+fn activate_override(conn: &Connection, d: &Domain) -> Result<Domain, AppError> {
+    let expires_at = unix_now() + d.override_ttl_minutes * 60;
+    let domain = models::set_override_expiry(conn, &d.id, Some(expires_at))?
+        .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?;
+    models::insert_audit_log(
+        conn,
+        "override_activated",
+        Some(&d.id),
+        Some(&format!(
+            "{} -> {}:{} for {} minutes",
+            d.name, d.target_host, d.target_port, d.override_ttl_minutes
+        )),
+    )?;
+    Ok(domain)
+}
+
+/// Turn off an override whose time is up and record it. The daemon reverts
+/// the hosts file and Caddy itself; this brings the database in line.
+pub(crate) fn revert_override(conn: &Connection, d: &Domain) -> Result<(), AppError> {
+    models::toggle_domain(conn, &d.id, false)?;
+    models::set_override_expiry(conn, &d.id, None)?;
+    models::insert_audit_log(conn, "override_reverted", Some(&d.id), Some(&d.name))?;
+    Ok(())
+}
+
 pub fn sync_state_to_daemon(state: &AppState) -> Result<(), AppError> {
     let conn = state.db.lock().unwrap();
-    let domains = models::list_domains(&conn).map_err(AppError::Database)?;
+    let mut domains = models::list_domains(&conn).map_err(AppError::Database)?;
+    // Overrides that ran out while the app or daemon was down
+    let now = unix_now();
+    let expired: Vec<&Domain> = domains
+        .iter()
+        .filter(|d| {
+            d.domain_type == "override"
+                && d.enabled
+                && d.override_expires_at.is_none_or(|t| t <= now)
+        })
+        .collect();
+    if !expired.is_empty() {
+        for d in expired {
+            revert_override(&conn, d)?;
+        }
+        domains = models::list_domains(&conn).map_err(AppError::Database)?;
+    }
+    let onboarding_host = match models::get_setting(&conn, "lan_sharing")?.as_deref() {
+        Some("true") => models::get_setting(&conn, "onboarding_host")?
+            .or_else(|| Some(protocol::default_onboarding_host())),
+        _ => None,
+    };
     drop(conn);
 
     // Hosts: ALL domains (enabled + disabled) so entries persist when toggled off.
-    // Entries are only removed when a domain is deleted.
-    let hosts_entries: Vec<HostsEntry> = domains
+    // Entries are only removed when a domain is deleted. Overrides are the
+    // exception: they hide a real site, so they only resolve while active.
+    let mut hosts_entries: Vec<HostsEntry> = domains
         .iter()
-        .map(|d| HostsEntry {
-            domain: d.name.clone(),
-            ip: "127.0.0.1".to_string(),
+        .filter(|d| d.domain_type != "override" || d.enabled)
+        .flat_map(|d| {
+            std::iter::once(&d.name)
+                .chain(&d.aliases)
+                .map(move |hostname| HostsEntry {
+                    domain: hostname.clone(),
+                    ip: hosts_ip(&d.resolve_ip, d.hosts_only).to_string(),
+                })
         })
         .collect();
+    // The device onboarding site, so it can be opened on this machine too
+    if let Some(host) =
+        onboarding_host.filter(|h| !hosts_entries.iter().any(|entry| &entry.domain == h))
+    {
+        hosts_entries.push(HostsEntry {
+            domain: host,
+            ip: DEFAULT_RESOLVE_IP.to_string(),
+        });
+    }
 
     // Caddy: only ENABLED domains — toggling off stops the proxy/routing
     let enabled_domains: Vec<_> = domains.iter().filter(|d| d.enabled).collect();
@@ -47,6 +344,25 @@ pub fn sync_state_to_daemon(state: &AppState) -> Result<(), AppError> {
         return Ok(());
     }
 
+    // Expiry first, so the daemon's timer covers the entries synced next
+    let overrides = domains
+        .iter()
+        .filter(|d| d.domain_type == "override" && d.enabled)
+        .filter_map(|d| {
+            Some(HostnameOverride {
+                domain: d.name.clone(),
+                names: std::iter::once(&d.name)
+                    .chain(&d.aliases)
+                    .cloned()
+                    .collect(),
+                expires_at: d.override_expires_at?,
+            })
+        })
+        .collect();
+    client
+        .sync_overrides(overrides)
+        .map_err(|e| AppError::Daemon(e.to_string()))?;
+
     // Sync hosts first (most important - enables domain resolution)
     client
         .sync_hosts(hosts_entries)
@@ -98,57 +414,38 @@ pub fn sync_state_to_daemon(state: &AppState) -> Result<(), AppError> {
         (80u16, 443u16)
     };
 
-    // Build Caddy configs for proxy domains
+    // Caddy configs for proxy domains, then for enabled XAMPP domains
     let mut caddy_configs: Vec<CaddyDomainConfig> = Vec::new();
-    for d in &proxy_domains {
-        // Skip domains without a target port (hosts-only domains)
-        if d.target_port <= 0 || d.target_port > u16::MAX as i32 {
+    let mut skipped: Vec<(&Domain, AppError)> = Vec::new();
+    for d in proxy_domains.iter().chain(&xampp_enabled) {
+        let (target_host, target_port) = if d.domain_type == "xampp" {
+            // Caddy on standard ports (80/443) reverse proxies to Apache's HTTP
+            // port, so XAMPP domains work whatever port Apache is configured on
+            ("127.0.0.1".to_string(), xampp_http_port)
+        } else if d.hosts_only || d.target_port <= 0 || d.target_port > u16::MAX as i32 {
+            // Hosts-only domains have no target port to proxy to
             continue;
-        }
-
-        let wants_https = d.protocol == "https" || d.protocol == "both";
-        let mut cert_path = None;
-        let mut key_path = None;
-
-        if wants_https {
-            let result = client.generate_cert(&d.name).map_err(|e| {
-                AppError::Daemon(format!("cert generation failed for {}: {}", d.name, e))
-            })?;
-            cert_path = Some(result.cert_path);
-            key_path = Some(result.key_path);
-        }
-
-        caddy_configs.push(CaddyDomainConfig {
-            name: d.name.clone(),
-            target_host: d.target_host.clone(),
-            target_port: d.target_port as u16,
-            protocol: d.protocol.clone(),
-            cert_path,
-            key_path,
-            access_log: d.access_log,
-        });
-    }
+        } else if is_loopback_ip(&d.resolve_ip) {
+            (d.target_host.clone(), d.target_port as u16)
+        } else {
+            // A remote address is the upstream; the hosts entry points at Caddy
+            (d.resolve_ip.clone(), d.target_port as u16)
+        };
 
-    // Route enabled XAMPP domains through Caddy — Caddy on standard ports (80/443)
-    // reverse proxies to Apache's HTTP port. This ensures XAMPP domains work
-    // regardless of Apache's configured port (e.g., 8080, 444 for SSL).
-    for d in &xampp_enabled {
-        let wants_https = d.protocol == "https" || d.protocol == "both";
-        let mut cert_path = None;
-        let mut key_path = None;
-
-        if wants_https {
-            let result = client.generate_cert(&d.name).map_err(|e| {
-                AppError::Daemon(format!("cert generation failed for {}: {}", d.name, e))
-            })?;
-            cert_path = Some(result.cert_path);
-            key_path = Some(result.key_path);
-        }
+        // One domain the CA can't sign for must not hold up the others
+        let (cert_path, key_path) = match caddy_cert(&client, d) {
+            Ok(paths) => paths,
+            Err(e) => {
+                skipped.push((**d, e));
+                continue;
+            }
+        };
 
         caddy_configs.push(CaddyDomainConfig {
             name: d.name.clone(),
-            target_host: "127.0.0.1".to_string(),
-            target_port: xampp_http_port,
+            aliases: d.aliases.clone(),
+            target_host,
+            target_port,
             protocol: d.protocol.clone(),
             cert_path,
             key_path,
@@ -177,6 +474,7 @@ pub fn sync_state_to_daemon(state: &AppState) -> Result<(), AppError> {
                 .iter()
                 .map(|d| XamppVhostConfig {
                     name: d.name.clone(),
+                    aliases: d.aliases.clone(),
                     document_root: d.document_root.clone(),
                     protocol: "http".to_string(),
                     cert_path: None,
@@ -189,6 +487,20 @@ pub fn sync_state_to_daemon(state: &AppState) -> Result<(), AppError> {
             }
         }
     }
+    drop(client);
+
+    // Left out of Caddy until the problem is fixed; recorded so it doesn't go unnoticed
+    if !skipped.is_empty() {
+        let conn = state.db.lock().unwrap();
+        for (d, e) in skipped {
+            models::insert_audit_log(
+                &conn,
+                "domain_sync_skipped",
+                Some(&d.id),
+                Some(&e.to_string()),
+            )?;
+        }
+    }
 
     Ok(())
 }
@@ -199,19 +511,65 @@ pub fn list_domains(state: State<AppState>) -> Result<Vec<Domain>, AppError> {
     Ok(models::list_domains(&conn)?)
 }
 
+/// Warnings about a domain before it's saved: hosts file lines outside the
+/// managed block, names that exist in public DNS and special-use TLDs.
+#[tauri::command]
+pub async fn check_domain_conflicts(
+    app: AppHandle,
+    name: String,
+    aliases: Vec<String>,
+    resolve_ip: Option<String>,
+    hosts_only: bool,
+) -> Result<Vec<protocol::DomainWarning>, AppError> {
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.state::<AppState>();
+        let resolve_ip = resolve_ip.unwrap_or_else(|| DEFAULT_RESOLVE_IP.to_string());
+        let ip = hosts_ip(&resolve_ip, hosts_only);
+        let entries = std::iter::once(name)
+            .chain(aliases)
+            .filter_map(|hostname| domain_to_ascii(hostname.trim()).ok())
+            .filter(|hostname| !hostname.is_empty())
+            .map(|hostname| HostsEntry {
+                domain: hostname,
+                ip: ip.to_string(),
+            })
+            .collect();
+
+        let client = state.daemon_client.lock().unwrap();
+        if !client.is_daemon_running() {
+            return Ok(Vec::new());
+        }
+        let result = client
+            .check_domains(entries)
+            .map_err(|e| AppError::Daemon(e.to_string()))?;
+        Ok(result.warnings)
+    })
+    .await
+    .map_err(|e| AppError::Other(format!("check_domain_conflicts join error: {}", e)))?
+}
+
 #[tauri::command]
 pub async fn create_domain(app: AppHandle, request: CreateDomainRequest) -> Result<Domain, AppError> {
     let app_handle = app.clone();
     tauri::async_runtime::spawn_blocking(move || {
         let state = app_handle.state::<AppState>();
 
-        validate_domain_name(&request.name).map_err(AppError::Validation)?;
+        let mut request = request;
+        to_ascii_names(
+            Some(&mut request.name),
+            request.aliases.as_mut(),
+            request.extra_sans.as_mut(),
+        )?;
         if let Some(port) = request.target_port {
             validate_port(port as u16).map_err(AppError::Validation)?;
         }
+        validate_cert_options(
+            request.extra_sans.as_deref(),
+            request.key_algorithm.as_deref(),
+        )?;
 
         // XAMPP domain validation + auto-set port from XAMPP config
-        let mut request = request;
         if request.domain_type.as_deref() == Some("xampp") {
             let doc_root = request.document_root.as_deref().unwrap_or("");
             validate_document_root(doc_root).map_err(AppError::Validation)?;
@@ -231,9 +589,41 @@ pub async fn create_domain(app: AppHandle, request: CreateDomainRequest) -> Resu
                 }
             }
         }
+        validate_resolve_options(
+            request.resolve_ip.as_deref().unwrap_or(DEFAULT_RESOLVE_IP),
+            request.hosts_only.unwrap_or(false),
+            request.domain_type.as_deref().unwrap_or("proxy"),
+            request.target_port.unwrap_or(0),
+        )?;
+        let is_override = request.domain_type.as_deref() == Some("override");
+        if is_override {
+            let ca_names = ca_signed_names(
+                request.protocol.as_deref().unwrap_or("http"),
+                false,
+                std::iter::once(&request.name)
+                    .chain(request.aliases.iter().flatten())
+                    .chain(request.extra_sans.iter().flatten()),
+            );
+            let permitted_suffixes = ca_permitted_suffixes(&state)?;
+            validate_override(
+                request.override_ttl_minutes.unwrap_or(0),
+                request.confirm_override == Some(true),
+                request.resolve_ip.as_deref().unwrap_or(DEFAULT_RESOLVE_IP),
+                request.hosts_only.unwrap_or(false),
+                request.target_port.unwrap_or(0),
+                &ca_names,
+                permitted_suffixes.as_deref(),
+            )?;
+        }
 
         let domain = {
             let conn = state.db.lock().unwrap();
+            validate_aliases(
+                &conn,
+                None,
+                &request.name,
+                request.aliases.as_deref().unwrap_or_default(),
+            )?;
             let domain = models::create_domain(&conn, &request)?;
             models::insert_audit_log(
                 &conn,
@@ -241,7 +631,11 @@ pub async fn create_domain(app: AppHandle, request: CreateDomainRequest) -> Resu
                 Some(&domain.id),
                 Some(&serde_json::to_string(&request).unwrap_or_default()),
             )?;
-            domain
+            if is_override {
+                activate_override(&conn, &domain)?
+            } else {
+                domain
+            }
         };
 
         sync_state_to_daemon(state.inner())?;
@@ -258,12 +652,19 @@ pub async fn update_domain(app: AppHandle, request: UpdateDomainRequest) -> Resu
     tauri::async_runtime::spawn_blocking(move || {
         let state = app_handle.state::<AppState>();
 
-        if let Some(ref name) = request.name {
-            validate_domain_name(name).map_err(AppError::Validation)?;
-        }
+        let mut request = request;
+        to_ascii_names(
+            request.name.as_mut(),
+            request.aliases.as_mut(),
+            request.extra_sans.as_mut(),
+        )?;
         if let Some(port) = request.target_port {
             validate_port(port as u16).map_err(AppError::Validation)?;
         }
+        validate_cert_options(
+            request.extra_sans.as_deref(),
+            request.key_algorithm.as_deref(),
+        )?;
 
         // XAMPP domain validation
         if request.domain_type.as_deref() == Some("xampp") {
@@ -272,8 +673,65 @@ pub async fn update_domain(app: AppHandle, request: UpdateDomainRequest) -> Resu
             }
         }
 
+        let may_activate_override = request.enabled != Some(false)
+            && (request.domain_type.as_deref() == Some("override")
+                || (request.domain_type.is_none() && is_override_domain(&state, &request.id)?));
+        let permitted_suffixes = if may_activate_override {
+            ca_permitted_suffixes(&state)?
+        } else {
+            None
+        };
+
         let domain = {
             let conn = state.db.lock().unwrap();
+            if let Some(existing) = models::get_domain(&conn, &request.id)? {
+                // A custom certificate is only known to cover the current name
+                if let Some(name) = &request.name {
+                    if existing.custom_cert_path.is_some() && name != &existing.name {
+                        return Err(AppError::Validation(
+                            "Remove the custom certificate before renaming the domain".to_string(),
+                        ));
+                    }
+                }
+                let resolve_ip = request
+                    .resolve_ip
+                    .as_deref()
+                    .unwrap_or(&existing.resolve_ip);
+                let hosts_only = request.hosts_only.unwrap_or(existing.hosts_only);
+                let domain_type = request
+                    .domain_type
+                    .as_deref()
+                    .unwrap_or(&existing.domain_type);
+                let target_port = request.target_port.unwrap_or(existing.target_port);
+                validate_resolve_options(resolve_ip, hosts_only, domain_type, target_port)?;
+                // Saving an active override starts its timer again
+                if domain_type == "override" && request.enabled.unwrap_or(existing.enabled) {
+                    let ca_names = ca_signed_names(
+                        request.protocol.as_deref().unwrap_or(&existing.protocol),
+                        existing.custom_cert_path.is_some(),
+                        std::iter::once(request.name.as_ref().unwrap_or(&existing.name))
+                            .chain(request.aliases.as_ref().unwrap_or(&existing.aliases))
+                            .chain(request.extra_sans.as_ref().unwrap_or(&existing.extra_sans)),
+                    );
+                    validate_override(
+                        request
+                            .override_ttl_minutes
+                            .unwrap_or(existing.override_ttl_minutes),
+                        request.confirm_override == Some(true),
+                        resolve_ip,
+                        hosts_only,
+                        target_port,
+                        &ca_names,
+                        permitted_suffixes.as_deref(),
+                    )?;
+                }
+                validate_aliases(
+                    &conn,
+                    Some(existing.id.as_str()),
+                    request.name.as_deref().unwrap_or(&existing.name),
+                    request.aliases.as_deref().unwrap_or(&existing.aliases),
+                )?;
+            }
             let domain = models::update_domain(&conn, &request)?
                 .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?;
             models::insert_audit_log(
@@ -282,7 +740,14 @@ pub async fn update_domain(app: AppHandle, request: UpdateDomainRequest) -> Resu
                 Some(&domain.id),
                 Some(&serde_json::to_string(&request).unwrap_or_default()),
             )?;
-            domain
+            if domain.domain_type == "override" && domain.enabled {
+                activate_override(&conn, &domain)?
+            } else if domain.override_expires_at.is_some() {
+                models::set_override_expiry(&conn, &domain.id, None)?
+                    .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?
+            } else {
+                domain
+            }
         };
 
         sync_state_to_daemon(state.inner())?;
@@ -331,13 +796,45 @@ pub async fn delete_domain(app: AppHandle, id: String) -> Result<(), AppError> {
     .map_err(|e| AppError::Other(format!("delete_domain join error: {}", e)))?
 }
 
+/// Turning on an override domain needs `confirm_override`, as when saving one.
 #[tauri::command]
-pub async fn toggle_domain(app: AppHandle, id: String, enabled: bool) -> Result<Domain, AppError> {
+pub async fn toggle_domain(
+    app: AppHandle,
+    id: String,
+    enabled: bool,
+    confirm_override: Option<bool>,
+) -> Result<Domain, AppError> {
     let app_handle = app.clone();
     tauri::async_runtime::spawn_blocking(move || {
         let state = app_handle.state::<AppState>();
+        let permitted_suffixes = if enabled && is_override_domain(&state, &id)? {
+            ca_permitted_suffixes(&state)?
+        } else {
+            None
+        };
         let domain = {
             let conn = state.db.lock().unwrap();
+            let existing = models::get_domain(&conn, &id)?
+                .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?;
+            let is_override = existing.domain_type == "override";
+            if is_override && enabled {
+                let ca_names = ca_signed_names(
+                    &existing.protocol,
+                    existing.custom_cert_path.is_some(),
+                    std::iter::once(&existing.name)
+                        .chain(&existing.aliases)
+                        .chain(&existing.extra_sans),
+                );
+                validate_override(
+                    existing.override_ttl_minutes,
+                    confirm_override == Some(true),
+                    &existing.resolve_ip,
+                    existing.hosts_only,
+                    existing.target_port,
+                    &ca_names,
+                    permitted_suffixes.as_deref(),
+                )?;
+            }
             let domain = models::toggle_domain(&conn, &id, enabled)?
                 .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?;
             models::insert_audit_log(
@@ -350,7 +847,12 @@ pub async fn toggle_domain(app: AppHandle, id: String, enabled: bool) -> Result<
                 Some(&id),
                 Some(&domain.name),
             )?;
-            domain
+            match (is_override, enabled) {
+                (true, true) => activate_override(&conn, &domain)?,
+                (true, false) => models::set_override_expiry(&conn, &id, None)?
+                    .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?,
+                (false, _) => domain,
+            }
         };
 
         sync_state_to_daemon(state.inner())?;
@@ -394,6 +896,98 @@ pub async fn toggle_access_log(
     .map_err(|e| AppError::Other(format!("toggle_access_log join error: {}", e)))?
 }
 
+/// Serve a user-supplied certificate for a domain instead of one from the local CA.
+/// Without `key_path`, the key is read from the certificate file.
+#[tauri::command]
+pub async fn set_custom_certificate(
+    app: AppHandle,
+    id: String,
+    cert_path: String,
+    key_path: Option<String>,
+    passphrase: Option<String>,
+) -> Result<Domain, AppError> {
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.state::<AppState>();
+        let existing = {
+            let conn = state.db.lock().unwrap();
+            models::get_domain(&conn, &id)?
+                .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?
+        };
+        let cert_pem = std::fs::read_to_string(&cert_path)?;
+        let key_pem = match key_path {
+            Some(ref path) => std::fs::read_to_string(path)?,
+            None => cert_pem.clone(),
+        };
+
+        let result = {
+            let client = state.daemon_client.lock().unwrap();
+            client
+                .install_custom_cert(
+                    &existing.name,
+                    cert_sans(&existing),
+                    cert_pem,
+                    key_pem,
+                    passphrase.filter(|p| !p.is_empty()),
+                )
+                .map_err(|e| AppError::Daemon(e.to_string()))?
+        };
+
+        let domain = {
+            let conn = state.db.lock().unwrap();
+            let domain =
+                models::set_custom_cert(&conn, &id, Some((&result.cert_path, &result.key_path)))?
+                    .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?;
+            models::insert_audit_log(
+                &conn,
+                "custom_cert_installed",
+                Some(&id),
+                Some(&format!("{} <- {}", domain.name, cert_path)),
+            )?;
+            domain
+        };
+
+        sync_state_to_daemon(state.inner())?;
+        Ok(domain)
+    })
+    .await
+    .map_err(|e| AppError::Other(format!("set_custom_certificate join error: {}", e)))?
+}
+
+/// Go back to a certificate issued by the local CA.
+#[tauri::command]
+pub async fn clear_custom_certificate(app: AppHandle, id: String) -> Result<Domain, AppError> {
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.state::<AppState>();
+        let existing = {
+            let conn = state.db.lock().unwrap();
+            models::get_domain(&conn, &id)?
+                .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?
+        };
+
+        {
+            let client = state.daemon_client.lock().unwrap();
+            client
+                .remove_custom_cert(&existing.name)
+                .map_err(|e| AppError::Daemon(e.to_string()))?;
+        }
+
+        let domain = {
+            let conn = state.db.lock().unwrap();
+            let domain = models::set_custom_cert(&conn, &id, None)?
+                .ok_or_else(|| AppError::Validation("Domain not found".to_string()))?;
+            models::insert_audit_log(&conn, "custom_cert_removed", Some(&id), Some(&domain.name))?;
+            domain
+        };
+
+        sync_state_to_daemon(state.inner())?;
+        Ok(domain)
+    })
+    .await
+    .map_err(|e| AppError::Other(format!("clear_custom_certificate join error: {}", e)))?
+}
+
 #[tauri::command]
 pub fn trust_ca(state: State<AppState>) -> Result<(), AppError> {
     let client = state.daemon_client.lock().unwrap();
@@ -449,3 +1043,46 @@ pub fn trust_ca(state: State<AppState>) -> Result<(), AppError> {
         "CA trust failed: non-interactive".to_string(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_https_override_refused_under_default_constrained_ca() {
+        let permitted = protocol::default_ca_permitted_suffixes();
+        let validate = |protocol: &str, custom_cert: bool, hostnames: &[String]| {
+            let ca_names = ca_signed_names(protocol, custom_cert, hostnames);
+            validate_override(
+                60,
+                true,
+                "127.0.0.1",
+                false,
+                3000,
+                &ca_names,
+                Some(permitted.as_slice()),
+            )
+        };
+        let real = names(&["api.example.com"]);
+
+        for protocol in ["https", "both"] {
+            assert!(matches!(
+                validate(protocol, false, &real),
+                Err(AppError::Validation(msg)) if msg.contains("api.example.com")
+            ));
+        }
+        // An alias outside the permitted suffixes is enough to refuse it
+        assert!(validate("https", false, &names(&["app.test", "example.com"])).is_err());
+        assert!(validate("https", false, &names(&["app.test", "*.app.test"])).is_ok());
+        // Plain HTTP and custom certificates don't need the CA
+        assert!(validate("http", false, &real).is_ok());
+        assert!(validate("https", true, &real).is_ok());
+        // An unconstrained CA signs anything
+        let ca_names = ca_signed_names("https", false, &real);
+        assert!(validate_override(60, true, "127.0.0.1", false, 3000, &ca_names, None).is_ok());
+    }
+}
@@ -0,0 +1,37 @@
+use crate::db::models;
+use crate::error::AppError;
+use crate::state::AppState;
+use localdomain_shared::protocol::{HostsBackup, HostsBackupDiff};
+use tauri::State;
+
+#[tauri::command]
+pub fn list_hosts_backups(state: State<AppState>) -> Result<Vec<HostsBackup>, AppError> {
+    let client = state.daemon_client.lock().unwrap();
+    let result = client
+        .list_hosts_backups()
+        .map_err(|e| AppError::Daemon(e.to_string()))?;
+    Ok(result.backups)
+}
+
+#[tauri::command]
+pub fn diff_hosts_backup(state: State<AppState>, id: String) -> Result<HostsBackupDiff, AppError> {
+    let client = state.daemon_client.lock().unwrap();
+    client
+        .diff_hosts_backup(&id)
+        .map_err(|e| AppError::Daemon(e.to_string()))
+}
+
+/// Replace the hosts file with a backup. The managed block is rewritten from
+/// the domain list on the next sync, so this mainly recovers other entries.
+#[tauri::command]
+pub fn restore_hosts_backup(state: State<AppState>, id: String) -> Result<(), AppError> {
+    {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .restore_hosts_backup(&id)
+            .map_err(|e| AppError::Daemon(e.to_string()))?;
+    }
+    let conn = state.db.lock().unwrap();
+    models::insert_audit_log(&conn, "hosts_restored", None, Some(&id))?;
+    Ok(())
+}
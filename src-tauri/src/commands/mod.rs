@@ -1,6 +1,9 @@
 pub mod access_log;
 pub mod audit;
+pub mod certificates;
 pub mod domains;
+pub mod hosts;
 pub mod service;
 pub mod settings;
+pub mod toolchains;
 pub mod tunnel;
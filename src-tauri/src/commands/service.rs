@@ -1,16 +1,28 @@
+use crate::commands::domains::revert_override;
+use crate::daemon_client::DaemonClient;
 use crate::db::models;
 use crate::error::AppError;
 use crate::state::AppState;
 use crate::tray;
+use localdomain_shared::protocol::{
+    CertRenewalEvent, HostsTamperEvent, OverrideRevertEvent, TrustStoreStatus,
+};
 use serde::Serialize;
-use tauri::{AppHandle, State};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often events queued by the daemon are moved into the audit log.
+const EVENT_SYNC_INTERVAL: Duration = Duration::from_secs(15);
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ServiceStatus {
     pub daemon_running: bool,
     pub caddy_running: bool,
     pub ca_installed: bool,
+    /// True when every trust store the daemon could check trusts the CA. Stores
+    /// that couldn't be checked carry their own error in `trust_stores`.
     pub ca_trusted: bool,
+    pub trust_stores: Vec<TrustStoreStatus>,
     pub xampp_running: bool,
 }
 
@@ -28,7 +40,12 @@ pub fn get_service_status(state: State<AppState>) -> Result<ServiceStatus, AppEr
             daemon_running: status.daemon_running,
             caddy_running: status.caddy_running,
             ca_installed: status.ca_installed,
-            ca_trusted: status.ca_trusted,
+            ca_trusted: !status.trust_stores.is_empty()
+                && status
+                    .trust_stores
+                    .iter()
+                    .all(|s| s.trusted || s.error.is_some()),
+            trust_stores: status.trust_stores,
             xampp_running,
         }),
         Err(_) => Ok(ServiceStatus {
@@ -36,11 +53,112 @@ pub fn get_service_status(state: State<AppState>) -> Result<ServiceStatus, AppEr
             caddy_running: false,
             ca_installed: false,
             ca_trusted: false,
+            trust_stores: Vec::new(),
             xampp_running,
         }),
     }
 }
 
+/// Collect the events the daemon queued for the audit log every
+/// `EVENT_SYNC_INTERVAL`, for the life of the app.
+pub fn start_event_sync(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        sync_daemon_events(&app);
+        std::thread::sleep(EVENT_SYNC_INTERVAL);
+    });
+}
+
+/// Move queued renewals, hosts tampering and override reverts from the daemon
+/// into the audit log, and turn off the overrides it reverted.
+fn sync_daemon_events(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    // Take the events and release daemon_client before locking the db
+    let (renewals, hosts_events, override_events) = {
+        let client = state.daemon_client.lock().unwrap();
+        if !client.is_daemon_running() {
+            return;
+        }
+        (
+            client.take_renewal_events().map(|r| r.events),
+            client.take_hosts_events().map(|r| r.events),
+            client.take_override_events().map(|r| r.events),
+        )
+    };
+    record_renewal_events(state.inner(), &renewals.unwrap_or_default());
+    record_hosts_events(state.inner(), &hosts_events.unwrap_or_default());
+    if record_override_events(state.inner(), &override_events.unwrap_or_default()) {
+        tray::refresh_tray_menu(app);
+        let _ = app.emit("state-changed", ());
+    }
+}
+
+/// Copy certificate renewals performed by the daemon's scheduler into the audit log.
+fn record_renewal_events(state: &AppState, events: &[CertRenewalEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    let conn = state.db.lock().unwrap();
+    for event in events {
+        let action = if event.error.is_none() {
+            "certificate_renewed"
+        } else {
+            "certificate_renewal_failed"
+        };
+        models::insert_audit_log(
+            &conn,
+            action,
+            None,
+            Some(&serde_json::to_string(event).unwrap_or_default()),
+        )
+        .ok();
+    }
+}
+
+/// Record external edits to the managed hosts block, with what the other program
+/// changed, in the audit log.
+fn record_hosts_events(state: &AppState, events: &[HostsTamperEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    let conn = state.db.lock().unwrap();
+    for event in events {
+        let action = if event.repaired {
+            "hosts_repaired"
+        } else {
+            "hosts_tampered"
+        };
+        models::insert_audit_log(
+            &conn,
+            action,
+            None,
+            Some(&serde_json::to_string(event).unwrap_or_default()),
+        )
+        .ok();
+    }
+}
+
+/// Turn off the domains whose overrides the daemon reverted. Returns whether
+/// any domain changed.
+fn record_override_events(state: &AppState, events: &[OverrideRevertEvent]) -> bool {
+    if events.is_empty() {
+        return false;
+    }
+    let conn = state.db.lock().unwrap();
+    let domains = models::list_domains(&conn).unwrap_or_default();
+    let mut changed = false;
+    for event in events {
+        // A later activation of the same domain has its own expiry
+        let Some(domain) = domains
+            .iter()
+            .find(|d| d.name == event.domain && d.override_expires_at == Some(event.expires_at))
+        else {
+            continue;
+        };
+        changed |= revert_override(&conn, domain).is_ok();
+    }
+    changed
+}
+
 /// Get the configured or default XAMPP path.
 fn get_xampp_path(state: &AppState) -> String {
     let xampp_path = {
@@ -283,9 +401,23 @@ launchctl bootstrap system /Library/LaunchDaemons/com.localdomain.daemon.plist
     Ok(())
 }
 
+/// Put back the pre-LocalDomain hosts file while the daemon can still do it.
+/// The uninstall scripts strip the managed block regardless, in case it is not running.
+fn restore_original_hosts() {
+    let client = DaemonClient::new();
+    if !client.is_daemon_running() {
+        return;
+    }
+    if let Err(e) = client.restore_original_hosts() {
+        eprintln!("Failed to restore original hosts file: {}", e);
+    }
+}
+
 #[cfg(target_os = "macos")]
 #[tauri::command]
 pub async fn uninstall_daemon() -> Result<(), AppError> {
+    restore_original_hosts();
+
     let uninstall_script = r#"do shell script "
 # Stop Caddy as safety net (daemon's graceful shutdown should handle this)
 pkill -f 'caddy run' 2>/dev/null || true
@@ -412,6 +544,8 @@ systemctl enable --now localdomain-daemon"#,
 #[cfg(target_os = "linux")]
 #[tauri::command]
 pub async fn uninstall_daemon() -> Result<(), AppError> {
+    restore_original_hosts();
+
     let uninstall_script = "\
         pkill -f 'caddy run' 2>/dev/null || true; \
         systemctl stop localdomain-daemon 2>/dev/null; \
@@ -589,6 +723,8 @@ try {{
 #[cfg(target_os = "windows")]
 #[tauri::command]
 pub async fn uninstall_daemon() -> Result<(), AppError> {
+    restore_original_hosts();
+
     let ps_script = r#"$ErrorActionPreference = 'Stop'
 
 # Stop Caddy first as safety net (daemon's graceful shutdown should handle this,
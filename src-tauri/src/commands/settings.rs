@@ -1,7 +1,10 @@
+use crate::commands::domains::sync_state_to_daemon;
 use crate::db::models::{self, CreateDomainRequest};
 use crate::error::AppError;
 use crate::state::AppState;
 use crate::xampp::{self, ImportVhost, ScannedVhost};
+use localdomain_shared::protocol::{self, DaemonSettings, HostsTamperPolicy};
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
@@ -20,6 +23,51 @@ pub struct AppSettings {
     pub default_ssh_key_path: Option<String>,
     #[serde(default)]
     pub xampp_path: Option<String>,
+    #[serde(default = "default_cert_renewal_window_days")]
+    pub cert_renewal_window_days: u32,
+    #[serde(default = "default_ca_name_constraints")]
+    pub ca_name_constraints: bool,
+    #[serde(default = "protocol::default_ca_permitted_suffixes")]
+    pub ca_permitted_suffixes: Vec<String>,
+    #[serde(default = "protocol::default_ca_permitted_ips")]
+    pub ca_permitted_ips: Vec<String>,
+    #[serde(default)]
+    pub acme_enabled: bool,
+    #[serde(default = "protocol::default_acme_port")]
+    pub acme_port: u16,
+    #[serde(default = "protocol::default_acme_http01_port")]
+    pub acme_http01_port: u16,
+    #[serde(default = "default_acme_auto_approve")]
+    pub acme_auto_approve: bool,
+    #[serde(default)]
+    pub lan_sharing: bool,
+    #[serde(default = "protocol::default_onboarding_host")]
+    pub onboarding_host: String,
+    #[serde(default = "protocol::default_onboarding_port")]
+    pub onboarding_port: u16,
+    #[serde(default)]
+    pub hosts_tamper_policy: HostsTamperPolicy,
+}
+
+fn default_cert_renewal_window_days() -> u32 {
+    DaemonSettings::default().cert_renewal_window_days
+}
+
+fn default_ca_name_constraints() -> bool {
+    DaemonSettings::default().ca_name_constraints
+}
+
+fn default_acme_auto_approve() -> bool {
+    DaemonSettings::default().acme_auto_approve
+}
+
+/// Lists are stored comma-separated in the settings table.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
 }
 
 impl Default for AppSettings {
@@ -33,6 +81,18 @@ impl Default for AppSettings {
             default_ssh_user: None,
             default_ssh_key_path: None,
             xampp_path: None,
+            cert_renewal_window_days: default_cert_renewal_window_days(),
+            ca_name_constraints: default_ca_name_constraints(),
+            ca_permitted_suffixes: protocol::default_ca_permitted_suffixes(),
+            ca_permitted_ips: protocol::default_ca_permitted_ips(),
+            acme_enabled: false,
+            acme_port: protocol::default_acme_port(),
+            acme_http01_port: protocol::default_acme_http01_port(),
+            acme_auto_approve: default_acme_auto_approve(),
+            lan_sharing: false,
+            onboarding_host: protocol::default_onboarding_host(),
+            onboarding_port: protocol::default_onboarding_port(),
+            hosts_tamper_policy: HostsTamperPolicy::default(),
         }
     }
 }
@@ -40,39 +100,116 @@ impl Default for AppSettings {
 #[tauri::command]
 pub fn get_settings(state: State<AppState>) -> Result<AppSettings, AppError> {
     let conn = state.db.lock().unwrap();
+    load_settings(&conn)
+}
+
+pub(crate) fn load_settings(conn: &Connection) -> Result<AppSettings, AppError> {
     let mut settings = AppSettings::default();
 
-    if let Some(v) = models::get_setting(&conn, "start_on_boot")? {
+    if let Some(v) = models::get_setting(conn, "start_on_boot")? {
         settings.start_on_boot = v == "true";
     }
-    if let Some(v) = models::get_setting(&conn, "http_port")? {
+    if let Some(v) = models::get_setting(conn, "http_port")? {
         settings.http_port = v.parse().unwrap_or(80);
     }
-    if let Some(v) = models::get_setting(&conn, "https_port")? {
+    if let Some(v) = models::get_setting(conn, "https_port")? {
         settings.https_port = v.parse().unwrap_or(443);
     }
-    if let Some(v) = models::get_setting(&conn, "cloudflare_tunnel_token")? {
+    if let Some(v) = models::get_setting(conn, "cloudflare_tunnel_token")? {
         settings.cloudflare_tunnel_token = Some(v);
     }
-    if let Some(v) = models::get_setting(&conn, "default_ssh_host")? {
+    if let Some(v) = models::get_setting(conn, "default_ssh_host")? {
         settings.default_ssh_host = Some(v);
     }
-    if let Some(v) = models::get_setting(&conn, "default_ssh_user")? {
+    if let Some(v) = models::get_setting(conn, "default_ssh_user")? {
         settings.default_ssh_user = Some(v);
     }
-    if let Some(v) = models::get_setting(&conn, "default_ssh_key_path")? {
+    if let Some(v) = models::get_setting(conn, "default_ssh_key_path")? {
         settings.default_ssh_key_path = Some(v);
     }
-    if let Some(v) = models::get_setting(&conn, "xampp_path")? {
+    if let Some(v) = models::get_setting(conn, "xampp_path")? {
         settings.xampp_path = Some(v);
     }
+    if let Some(v) = models::get_setting(conn, "cert_renewal_window_days")? {
+        settings.cert_renewal_window_days = v
+            .parse()
+            .unwrap_or_else(|_| default_cert_renewal_window_days());
+    }
+    if let Some(v) = models::get_setting(conn, "ca_name_constraints")? {
+        settings.ca_name_constraints = v == "true";
+    }
+    if let Some(v) = models::get_setting(conn, "ca_permitted_suffixes")? {
+        settings.ca_permitted_suffixes = split_list(&v);
+    }
+    if let Some(v) = models::get_setting(conn, "ca_permitted_ips")? {
+        settings.ca_permitted_ips = split_list(&v);
+    }
+    if let Some(v) = models::get_setting(conn, "acme_enabled")? {
+        settings.acme_enabled = v == "true";
+    }
+    if let Some(v) = models::get_setting(conn, "acme_port")? {
+        settings.acme_port = v.parse().unwrap_or_else(|_| protocol::default_acme_port());
+    }
+    if let Some(v) = models::get_setting(conn, "acme_http01_port")? {
+        settings.acme_http01_port = v
+            .parse()
+            .unwrap_or_else(|_| protocol::default_acme_http01_port());
+    }
+    if let Some(v) = models::get_setting(conn, "acme_auto_approve")? {
+        settings.acme_auto_approve = v == "true";
+    }
+    if let Some(v) = models::get_setting(conn, "lan_sharing")? {
+        settings.lan_sharing = v == "true";
+    }
+    if let Some(v) = models::get_setting(conn, "onboarding_host")? {
+        settings.onboarding_host = v;
+    }
+    if let Some(v) = models::get_setting(conn, "onboarding_port")? {
+        settings.onboarding_port = v
+            .parse()
+            .unwrap_or_else(|_| protocol::default_onboarding_port());
+    }
+    if let Some(v) = models::get_setting(conn, "hosts_tamper_policy")? {
+        settings.hosts_tamper_policy = HostsTamperPolicy::parse(&v).unwrap_or_default();
+    }
 
     Ok(settings)
 }
 
 #[tauri::command]
 pub fn save_settings(state: State<AppState>, settings: AppSettings) -> Result<(), AppError> {
+    if settings.acme_port == 0 || settings.acme_port == settings.acme_http01_port {
+        return Err(AppError::Validation(
+            "ACME port must be non-zero and differ from the HTTP-01 port".to_string(),
+        ));
+    }
+    if settings.onboarding_port == 0
+        || [settings.http_port, settings.https_port, settings.acme_port]
+            .contains(&settings.onboarding_port)
+    {
+        return Err(AppError::Validation(
+            "Onboarding port must be non-zero and differ from the HTTP, HTTPS and ACME ports"
+                .to_string(),
+        ));
+    }
+    let onboarding_host = settings.onboarding_host.trim().to_lowercase();
+    // Only keep settings the daemon accepted, so the two never disagree
+    send_daemon_settings(
+        state.inner(),
+        DaemonSettings {
+            onboarding_host: onboarding_host.clone(),
+            ..daemon_settings(&settings)
+        },
+    )?;
+
     let conn = state.db.lock().unwrap();
+    // The onboarding site lives in the Caddyfile and hosts file, so changes need a sync
+    let onboarding_changed = models::get_setting(&conn, "lan_sharing")?.as_deref()
+        != Some(settings.lan_sharing.to_string().as_str())
+        || models::get_setting(&conn, "onboarding_host")?.as_deref()
+            != Some(onboarding_host.as_str())
+        || models::get_setting(&conn, "onboarding_port")?.as_deref()
+            != Some(settings.onboarding_port.to_string().as_str());
     models::set_setting(&conn, "start_on_boot", &settings.start_on_boot.to_string())?;
     models::set_setting(&conn, "http_port", &settings.http_port.to_string())?;
     models::set_setting(&conn, "https_port", &settings.https_port.to_string())?;
@@ -96,9 +233,88 @@ pub fn save_settings(state: State<AppState>, settings: AppSettings) -> Result<()
         Some(ref path) => models::set_setting(&conn, "xampp_path", path)?,
         None => models::delete_setting(&conn, "xampp_path")?,
     }
+    models::set_setting(
+        &conn,
+        "cert_renewal_window_days",
+        &settings.cert_renewal_window_days.to_string(),
+    )?;
+    models::set_setting(
+        &conn,
+        "ca_name_constraints",
+        &settings.ca_name_constraints.to_string(),
+    )?;
+    models::set_setting(
+        &conn,
+        "ca_permitted_suffixes",
+        &settings.ca_permitted_suffixes.join(","),
+    )?;
+    models::set_setting(&conn, "ca_permitted_ips", &settings.ca_permitted_ips.join(","))?;
+    models::set_setting(&conn, "acme_enabled", &settings.acme_enabled.to_string())?;
+    models::set_setting(&conn, "acme_port", &settings.acme_port.to_string())?;
+    models::set_setting(&conn, "acme_http01_port", &settings.acme_http01_port.to_string())?;
+    models::set_setting(
+        &conn,
+        "acme_auto_approve",
+        &settings.acme_auto_approve.to_string(),
+    )?;
+    models::set_setting(&conn, "lan_sharing", &settings.lan_sharing.to_string())?;
+    models::set_setting(&conn, "onboarding_host", &onboarding_host)?;
+    models::set_setting(
+        &conn,
+        "onboarding_port",
+        &settings.onboarding_port.to_string(),
+    )?;
+    models::set_setting(
+        &conn,
+        "hosts_tamper_policy",
+        settings.hosts_tamper_policy.as_str(),
+    )?;
+    drop(conn);
+
+    if onboarding_changed {
+        sync_state_to_daemon(state.inner())?;
+    }
     Ok(())
 }
 
+/// The settings the daemon acts on in the background (cert renewal, CA constraints,
+/// ACME server, device onboarding, hosts file repair).
+fn daemon_settings(settings: &AppSettings) -> DaemonSettings {
+    DaemonSettings {
+        cert_renewal_window_days: settings.cert_renewal_window_days,
+        ca_name_constraints: settings.ca_name_constraints,
+        ca_permitted_suffixes: settings.ca_permitted_suffixes.clone(),
+        ca_permitted_ips: settings.ca_permitted_ips.clone(),
+        acme_enabled: settings.acme_enabled,
+        acme_port: settings.acme_port,
+        acme_http01_port: settings.acme_http01_port,
+        acme_auto_approve: settings.acme_auto_approve,
+        lan_sharing: settings.lan_sharing,
+        onboarding_host: settings.onboarding_host.clone(),
+        onboarding_port: settings.onboarding_port,
+        hosts_tamper_policy: settings.hosts_tamper_policy,
+    }
+}
+
+fn send_daemon_settings(state: &AppState, settings: DaemonSettings) -> Result<(), AppError> {
+    let client = state.daemon_client.lock().unwrap();
+    if !client.is_daemon_running() {
+        return Ok(());
+    }
+    client
+        .set_daemon_settings(settings)
+        .map_err(|e| AppError::Daemon(e.to_string()))
+}
+
+/// Send the saved settings to the daemon, e.g. after it restarted.
+pub fn push_daemon_settings(state: &AppState) -> Result<(), AppError> {
+    let settings = {
+        let conn = state.db.lock().unwrap();
+        load_settings(&conn)?
+    };
+    send_daemon_settings(state, daemon_settings(&settings))
+}
+
 /// Detect XAMPP path directly (no daemon needed).
 #[tauri::command]
 pub fn detect_xampp_path() -> Result<Option<String>, AppError> {
@@ -167,7 +383,10 @@ pub fn scan_xampp_vhosts(
     let domains = models::list_domains(&conn)?;
     drop(conn);
 
-    let existing_names: Vec<String> = domains.iter().map(|d| d.name.clone()).collect();
+    let existing_names: Vec<String> = domains
+        .iter()
+        .flat_map(|d| std::iter::once(&d.name).chain(&d.aliases).cloned())
+        .collect();
     xampp::scan_vhosts(&xampp_path, &existing_names)
 }
 
@@ -198,7 +417,10 @@ pub fn import_xampp_vhosts(
     // Fetch existing domains once to avoid O(n*m) per-vhost lookup
     let existing_names: std::collections::HashSet<String> = {
         let conn = state.db.lock().unwrap();
-        models::list_domains(&conn)?.iter().map(|d| d.name.clone()).collect()
+        models::list_domains(&conn)?
+            .iter()
+            .flat_map(|d| std::iter::once(&d.name).chain(&d.aliases).cloned())
+            .collect()
     };
 
     for vhost in &vhosts {
@@ -216,6 +438,13 @@ pub fn import_xampp_vhosts(
             wildcard: None,
             domain_type: Some("xampp".to_string()),
             document_root: Some(vhost.document_root.clone()),
+            extra_sans: None,
+            key_algorithm: None,
+            resolve_ip: None,
+            hosts_only: None,
+            aliases: None,
+            override_ttl_minutes: None,
+            confirm_override: None,
         };
 
         let domain = models::create_domain(&conn, &req)?;
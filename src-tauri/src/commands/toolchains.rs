@@ -0,0 +1,356 @@
+use crate::db::models;
+use crate::error::AppError;
+use crate::state::AppState;
+use localdomain_shared::protocol::{EnvVar, ToolchainTrustResult};
+#[cfg(target_os = "windows")]
+use localdomain_shared::silent_cmd;
+use serde::Serialize;
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
+use tauri::State;
+
+#[cfg(unix)]
+const BLOCK_START: &str = "# >>> localdomain >>>";
+#[cfg(unix)]
+const BLOCK_END: &str = "# <<< localdomain <<<";
+#[cfg(unix)]
+const POSIX_PROFILES: &[&str] = &[".bashrc", ".bash_profile", ".zshrc"];
+
+#[derive(Debug, Serialize)]
+pub struct ToolchainTrust {
+    #[serde(flatten)]
+    pub trust: ToolchainTrustResult,
+    /// Environment snippet for the user's shell, ready to paste
+    pub snippet: String,
+    /// Shell profiles (or the Windows user environment) that load the variables
+    pub installed_profiles: Vec<String>,
+}
+
+fn with_profiles(trust: ToolchainTrustResult) -> Result<ToolchainTrust, AppError> {
+    let snippet = if cfg!(windows) {
+        powershell_snippet(&trust.env)
+    } else {
+        posix_snippet(&trust.env)
+    };
+    Ok(ToolchainTrust {
+        installed_profiles: installed_profiles(&trust.env)?,
+        snippet,
+        trust,
+    })
+}
+
+#[tauri::command]
+pub fn get_toolchain_trust(state: State<AppState>) -> Result<ToolchainTrust, AppError> {
+    let trust = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .get_toolchain_trust()
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+    with_profiles(trust)
+}
+
+/// Import the CA into detected JDK keystores and write the combined CA bundle.
+#[tauri::command]
+pub fn install_toolchain_trust(state: State<AppState>) -> Result<ToolchainTrust, AppError> {
+    let trust = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .install_toolchain_trust()
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+    let trusted = trust.java_keystores.iter().filter(|k| k.trusted).count();
+    {
+        let conn = state.db.lock().unwrap();
+        models::insert_audit_log(
+            &conn,
+            "toolchain_trust_installed",
+            None,
+            Some(&format!(
+                "{} of {} Java keystores",
+                trusted,
+                trust.java_keystores.len()
+            )),
+        )?;
+    }
+    with_profiles(trust)
+}
+
+#[tauri::command]
+pub fn remove_toolchain_trust(state: State<AppState>) -> Result<ToolchainTrust, AppError> {
+    let trust = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .remove_toolchain_trust()
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+    remove_env(&trust.env)?;
+    {
+        let conn = state.db.lock().unwrap();
+        models::insert_audit_log(&conn, "toolchain_trust_removed", None, None)?;
+    }
+    with_profiles(trust)
+}
+
+/// Make new shells pick up the CA variables. The daemon runs as root, so
+/// profile files are written here as the user.
+#[tauri::command]
+pub fn install_shell_env(state: State<AppState>) -> Result<ToolchainTrust, AppError> {
+    let trust = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .get_toolchain_trust()
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+    let profiles = install_env(&trust.env)?;
+    {
+        let conn = state.db.lock().unwrap();
+        models::insert_audit_log(
+            &conn,
+            "shell_env_installed",
+            None,
+            Some(&profiles.join(", ")),
+        )?;
+    }
+    with_profiles(trust)
+}
+
+#[tauri::command]
+pub fn remove_shell_env(state: State<AppState>) -> Result<ToolchainTrust, AppError> {
+    let trust = {
+        let client = state.daemon_client.lock().unwrap();
+        client
+            .get_toolchain_trust()
+            .map_err(|e| AppError::Daemon(e.to_string()))?
+    };
+    remove_env(&trust.env)?;
+    {
+        let conn = state.db.lock().unwrap();
+        models::insert_audit_log(&conn, "shell_env_removed", None, None)?;
+    }
+    with_profiles(trust)
+}
+
+fn home() -> Result<PathBuf, AppError> {
+    dirs::home_dir().ok_or_else(|| AppError::Other("Cannot find home directory".into()))
+}
+
+fn posix_snippet(env: &[EnvVar]) -> String {
+    env.iter()
+        .map(|v| format!("export {}=\"{}\"\n", v.name, v.value))
+        .collect()
+}
+
+#[cfg(unix)]
+fn fish_snippet(env: &[EnvVar]) -> String {
+    env.iter()
+        .map(|v| format!("set -gx {} \"{}\"\n", v.name, v.value))
+        .collect()
+}
+
+fn powershell_snippet(env: &[EnvVar]) -> String {
+    env.iter()
+        .map(|v| format!("$env:{} = \"{}\"\n", v.name, v.value))
+        .collect()
+}
+
+/// Append the LocalDomain block to a profile, replacing any previous one.
+#[cfg(unix)]
+fn with_block(profile: &str, body: &str) -> String {
+    let mut text = without_block(profile);
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&format!(
+        "{}\n{}\n{}\n",
+        BLOCK_START,
+        body.trim_end(),
+        BLOCK_END
+    ));
+    text
+}
+
+#[cfg(unix)]
+fn without_block(profile: &str) -> String {
+    let mut out = String::new();
+    let mut inside = false;
+    for line in profile.lines() {
+        match line.trim() {
+            BLOCK_START => inside = true,
+            BLOCK_END => inside = false,
+            _ if !inside => {
+                out.push_str(line);
+                out.push('\n');
+            }
+            _ => {}
+        }
+    }
+    if !profile.ends_with('\n') && out.ends_with('\n') {
+        out.pop();
+    }
+    out
+}
+
+#[cfg(unix)]
+fn env_dir(home: &Path) -> PathBuf {
+    home.join(".config").join("localdomain")
+}
+
+#[cfg(unix)]
+fn fish_conf(home: &Path) -> PathBuf {
+    home.join(".config/fish/conf.d/localdomain.fish")
+}
+
+/// Write env.sh and source it from the user's bash/zsh profiles (and fish, if used).
+#[cfg(unix)]
+fn install_env(env: &[EnvVar]) -> Result<Vec<String>, AppError> {
+    let home = home()?;
+    let dir = env_dir(&home);
+    std::fs::create_dir_all(&dir)?;
+    let env_file = dir.join("env.sh");
+    std::fs::write(&env_file, posix_snippet(env))?;
+
+    let source = format!("[ -f \"{0}\" ] && . \"{0}\"", env_file.to_string_lossy());
+    let mut targets: Vec<PathBuf> = POSIX_PROFILES
+        .iter()
+        .map(|p| home.join(p))
+        .filter(|p| p.exists())
+        .collect();
+    if targets.is_empty() {
+        let shell = std::env::var("SHELL").unwrap_or_default();
+        targets.push(home.join(if shell.ends_with("zsh") {
+            ".zshrc"
+        } else {
+            ".bashrc"
+        }));
+    }
+    for profile in &targets {
+        let current = std::fs::read_to_string(profile).unwrap_or_default();
+        std::fs::write(profile, with_block(&current, &source))?;
+    }
+
+    if home.join(".config/fish").is_dir() {
+        let conf = fish_conf(&home);
+        std::fs::create_dir_all(conf.parent().unwrap())?;
+        std::fs::write(&conf, fish_snippet(env))?;
+        targets.push(conf);
+    }
+    Ok(targets
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+#[cfg(unix)]
+fn remove_env(_env: &[EnvVar]) -> Result<(), AppError> {
+    let home = home()?;
+    for profile in POSIX_PROFILES.iter().map(|p| home.join(p)) {
+        let Ok(current) = std::fs::read_to_string(&profile) else {
+            continue;
+        };
+        if current.contains(BLOCK_START) {
+            std::fs::write(&profile, without_block(&current))?;
+        }
+    }
+    let _ = std::fs::remove_file(fish_conf(&home));
+    let _ = std::fs::remove_file(env_dir(&home).join("env.sh"));
+    Ok(())
+}
+
+#[cfg(unix)]
+fn installed_profiles(_env: &[EnvVar]) -> Result<Vec<String>, AppError> {
+    let home = home()?;
+    let mut installed: Vec<String> = POSIX_PROFILES
+        .iter()
+        .map(|p| home.join(p))
+        .filter(|p| {
+            std::fs::read_to_string(p)
+                .map(|text| text.contains(BLOCK_START))
+                .unwrap_or(false)
+        })
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    if fish_conf(&home).exists() {
+        installed.push(fish_conf(&home).to_string_lossy().to_string());
+    }
+    Ok(installed)
+}
+
+/// Windows has no shell profile for every terminal; persist the variables in
+/// the user environment instead.
+#[cfg(target_os = "windows")]
+fn install_env(env: &[EnvVar]) -> Result<Vec<String>, AppError> {
+    for var in env {
+        let output = silent_cmd("setx").args([&var.name, &var.value]).output()?;
+        if !output.status.success() {
+            return Err(AppError::Other(format!(
+                "setx {} failed: {}",
+                var.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+    }
+    Ok(vec!["User environment".to_string()])
+}
+
+#[cfg(target_os = "windows")]
+fn remove_env(env: &[EnvVar]) -> Result<(), AppError> {
+    for var in env {
+        // Missing values are fine; reg exits non-zero and there is nothing to remove
+        let _ = silent_cmd("reg")
+            .args(["delete", "HKCU\\Environment", "/v", &var.name, "/f"])
+            .output();
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn installed_profiles(env: &[EnvVar]) -> Result<Vec<String>, AppError> {
+    let Some(first) = env.first() else {
+        return Ok(Vec::new());
+    };
+    let installed = silent_cmd("reg")
+        .args(["query", "HKCU\\Environment", "/v", &first.name])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    Ok(if installed {
+        vec!["User environment".to_string()]
+    } else {
+        Vec::new()
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_block_is_replaced_and_removed() {
+        let profile = "alias ll='ls -l'\nexport PATH=\"$HOME/bin:$PATH\"";
+        let once = with_block(profile, ". ~/.config/localdomain/env.sh");
+        let twice = with_block(&once, ". ~/.config/localdomain/env.sh");
+        assert_eq!(once, twice);
+        assert_eq!(once.matches(BLOCK_START).count(), 1);
+        assert!(once.starts_with(profile));
+        assert_eq!(without_block(&once), format!("{}\n", profile));
+        assert_eq!(without_block(profile), profile);
+    }
+
+    #[test]
+    fn test_snippets() {
+        let env = vec![EnvVar {
+            name: "NODE_EXTRA_CA_CERTS".to_string(),
+            value: "/var/lib/localdomain/certs/localdomain-ca.crt".to_string(),
+        }];
+        assert_eq!(
+            posix_snippet(&env),
+            "export NODE_EXTRA_CA_CERTS=\"/var/lib/localdomain/certs/localdomain-ca.crt\"\n"
+        );
+        assert_eq!(
+            fish_snippet(&env),
+            "set -gx NODE_EXTRA_CA_CERTS \"/var/lib/localdomain/certs/localdomain-ca.crt\"\n"
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use localdomain_shared::protocol::{JsonRpcRequest, JsonRpcResponse};
+use localdomain_shared::protocol::{JsonRpcNotification, JsonRpcRequest, JsonRpcResponse};
 use std::io::{BufRead, BufReader, Write};
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -9,6 +9,15 @@ pub struct DaemonClient {
     next_id: AtomicU64,
 }
 
+#[cfg(unix)]
+type DaemonStream = std::os::unix::net::UnixStream;
+#[cfg(windows)]
+type DaemonStream = std::fs::File;
+
+/// How long a stream read waits before giving the caller a chance to stop.
+#[cfg(unix)]
+const STREAM_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
 #[cfg(unix)]
 fn connect_to_daemon() -> Result<std::os::unix::net::UnixStream> {
     let stream = std::os::unix::net::UnixStream::connect(paths::SOCKET_PATH)
@@ -30,6 +39,48 @@ fn connect_to_daemon() -> Result<std::fs::File> {
     Ok(file)
 }
 
+/// Connection for a streaming call. Reads time out on Unix so an idle stream
+/// can still be closed; a Windows pipe read waits for the next message.
+fn connect_for_stream() -> Result<DaemonStream> {
+    let stream = connect_to_daemon()?;
+    #[cfg(unix)]
+    stream.set_read_timeout(Some(STREAM_READ_TIMEOUT)).ok();
+    Ok(stream)
+}
+
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    )
+}
+
+/// New entries of one domain's access log, pushed by the daemon.
+pub struct AccessLogStream {
+    reader: BufReader<DaemonStream>,
+    line: String,
+}
+
+impl AccessLogStream {
+    /// The next entry, or None if nothing arrived before the read timed out.
+    pub fn next_entry(&mut self) -> Result<Option<localdomain_shared::protocol::AccessLogEntry>> {
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => anyhow::bail!("Daemon closed the access log stream"),
+            Ok(_) => {}
+            // Any partial line stays in the buffer for the next read
+            Err(e) if is_timeout(&e) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let notification: JsonRpcNotification =
+            serde_json::from_str(&self.line).context("Failed to parse daemon notification")?;
+        self.line.clear();
+        if notification.method != localdomain_shared::protocol::ACCESS_LOG_ENTRY {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_value(notification.params)?))
+    }
+}
+
 impl DaemonClient {
     pub fn new() -> Self {
         Self {
@@ -120,27 +171,318 @@ impl DaemonClient {
     pub fn generate_cert(
         &self,
         domain: &str,
+        sans: Vec<String>,
+        key_algorithm: localdomain_shared::domain::KeyAlgorithm,
     ) -> Result<localdomain_shared::protocol::GenerateCertResult> {
         let params = serde_json::to_value(localdomain_shared::protocol::GenerateCertParams {
             domain: domain.to_string(),
+            sans,
+            key_algorithm,
+            force: false,
         })?;
         let result = self.call("generate_cert", params)?;
         Ok(serde_json::from_value(result)?)
     }
 
-    pub fn get_access_log(
+    pub fn list_certificates(
+        &self,
+        active_domains: Vec<String>,
+    ) -> Result<localdomain_shared::protocol::ListCertificatesResult> {
+        let params = serde_json::to_value(localdomain_shared::protocol::ListCertificatesParams {
+            active_domains,
+        })?;
+        let result = self.call("list_certificates", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn delete_certificate(&self, name: &str) -> Result<()> {
+        let params = serde_json::to_value(localdomain_shared::protocol::DeleteCertificateParams {
+            name: name.to_string(),
+        })?;
+        self.call("delete_certificate", params)?;
+        Ok(())
+    }
+
+    pub fn install_custom_cert(
         &self,
         domain: &str,
-        limit: Option<u64>,
-    ) -> Result<Vec<localdomain_shared::protocol::AccessLogEntry>> {
-        let params = serde_json::to_value(localdomain_shared::protocol::GetAccessLogParams {
+        names: Vec<String>,
+        cert_pem: String,
+        key_pem: String,
+        passphrase: Option<String>,
+    ) -> Result<localdomain_shared::protocol::InstallCustomCertResult> {
+        let params = serde_json::to_value(localdomain_shared::protocol::InstallCustomCertParams {
+            domain: domain.to_string(),
+            names,
+            cert_pem,
+            key_pem,
+            passphrase,
+        })?;
+        let result = self.call("install_custom_cert", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn remove_custom_cert(&self, domain: &str) -> Result<()> {
+        let params = serde_json::to_value(localdomain_shared::protocol::RemoveCustomCertParams {
             domain: domain.to_string(),
-            limit,
         })?;
+        self.call("remove_custom_cert", params)?;
+        Ok(())
+    }
+
+    pub fn rotate_ca(
+        &self,
+        overlap_days: u32,
+    ) -> Result<localdomain_shared::protocol::RotateCaResult> {
+        let params =
+            serde_json::to_value(localdomain_shared::protocol::RotateCaParams { overlap_days })?;
+        let result = self.call("rotate_ca", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn import_ca(
+        &self,
+        cert_pem: String,
+        key_pem: String,
+        passphrase: Option<String>,
+        overlap_days: u32,
+    ) -> Result<localdomain_shared::protocol::ImportCaResult> {
+        let params = serde_json::to_value(localdomain_shared::protocol::ImportCaParams {
+            cert_pem,
+            key_pem,
+            passphrase,
+            overlap_days,
+        })?;
+        let result = self.call("import_ca", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn export_ca_bundle(
+        &self,
+        passphrase: &str,
+        root_passphrase: Option<String>,
+    ) -> Result<localdomain_shared::protocol::ExportCertificateResult> {
+        let params = serde_json::to_value(localdomain_shared::protocol::ExportCaBundleParams {
+            passphrase: passphrase.to_string(),
+            root_passphrase,
+        })?;
+        let result = self.call("export_ca_bundle", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn get_device_onboarding(
+        &self,
+    ) -> Result<localdomain_shared::protocol::DeviceOnboardingInfo> {
+        let result = self.call("get_device_onboarding", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn retire_previous_ca(&self) -> Result<()> {
+        self.call("retire_previous_ca", serde_json::Value::Null)?;
+        Ok(())
+    }
+
+    pub fn export_certificate(
+        &self,
+        name: &str,
+        format: localdomain_shared::protocol::CertExportFormat,
+        passphrase: Option<String>,
+    ) -> Result<localdomain_shared::protocol::ExportCertificateResult> {
+        let params = serde_json::to_value(localdomain_shared::protocol::ExportCertificateParams {
+            name: name.to_string(),
+            format,
+            passphrase,
+        })?;
+        let result = self.call("export_certificate", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn get_ca_status(&self) -> Result<localdomain_shared::protocol::CaStatus> {
+        let result = self.call("get_ca_status", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn protect_root_key(
+        &self,
+        passphrase: &str,
+        offline: bool,
+    ) -> Result<localdomain_shared::protocol::ProtectRootKeyResult> {
+        let params = serde_json::to_value(localdomain_shared::protocol::ProtectRootKeyParams {
+            passphrase: passphrase.to_string(),
+            offline,
+        })?;
+        let result = self.call("protect_root_key", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn renew_intermediate(
+        &self,
+        root_key_pem: Option<String>,
+        passphrase: Option<String>,
+    ) -> Result<localdomain_shared::protocol::TakeRenewalEventsResult> {
+        let params =
+            serde_json::to_value(localdomain_shared::protocol::RenewIntermediateParams {
+                root_key_pem,
+                passphrase,
+            })?;
+        let result = self.call("renew_intermediate", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn get_toolchain_trust(
+        &self,
+    ) -> Result<localdomain_shared::protocol::ToolchainTrustResult> {
+        let result = self.call("get_toolchain_trust", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn install_toolchain_trust(
+        &self,
+    ) -> Result<localdomain_shared::protocol::ToolchainTrustResult> {
+        let result = self.call("install_toolchain_trust", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn remove_toolchain_trust(
+        &self,
+    ) -> Result<localdomain_shared::protocol::ToolchainTrustResult> {
+        let result = self.call("remove_toolchain_trust", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn take_renewal_events(
+        &self,
+    ) -> Result<localdomain_shared::protocol::TakeRenewalEventsResult> {
+        let result = self.call("take_renewal_events", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn take_hosts_events(&self) -> Result<localdomain_shared::protocol::TakeHostsEventsResult> {
+        let result = self.call("take_hosts_events", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn sync_overrides(
+        &self,
+        overrides: Vec<localdomain_shared::protocol::HostnameOverride>,
+    ) -> Result<()> {
+        let params =
+            serde_json::to_value(localdomain_shared::protocol::SyncOverridesParams { overrides })?;
+        self.call("sync_overrides", params)?;
+        Ok(())
+    }
+
+    pub fn take_override_events(
+        &self,
+    ) -> Result<localdomain_shared::protocol::TakeOverrideEventsResult> {
+        let result = self.call("take_override_events", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn check_domains(
+        &self,
+        entries: Vec<localdomain_shared::domain::HostsEntry>,
+    ) -> Result<localdomain_shared::protocol::CheckDomainsResult> {
+        let params =
+            serde_json::to_value(localdomain_shared::protocol::CheckDomainsParams { entries })?;
+        let result = self.call("check_domains", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn list_hosts_backups(
+        &self,
+    ) -> Result<localdomain_shared::protocol::ListHostsBackupsResult> {
+        let result = self.call("list_hosts_backups", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn diff_hosts_backup(
+        &self,
+        id: &str,
+    ) -> Result<localdomain_shared::protocol::HostsBackupDiff> {
+        let params = serde_json::to_value(localdomain_shared::protocol::HostsBackupParams {
+            id: id.to_string(),
+        })?;
+        let result = self.call("diff_hosts_backup", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn restore_hosts_backup(&self, id: &str) -> Result<()> {
+        let params = serde_json::to_value(localdomain_shared::protocol::HostsBackupParams {
+            id: id.to_string(),
+        })?;
+        self.call("restore_hosts_backup", params)?;
+        Ok(())
+    }
+
+    pub fn restore_original_hosts(
+        &self,
+    ) -> Result<localdomain_shared::protocol::RestoreOriginalHostsResult> {
+        let result = self.call("restore_original_hosts", serde_json::Value::Null)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub fn set_daemon_settings(
+        &self,
+        settings: localdomain_shared::protocol::DaemonSettings,
+    ) -> Result<()> {
+        let params = serde_json::to_value(settings)?;
+        self.call("set_daemon_settings", params)?;
+        Ok(())
+    }
+
+    pub fn get_access_log(
+        &self,
+        params: localdomain_shared::protocol::GetAccessLogParams,
+    ) -> Result<localdomain_shared::protocol::AccessLogPage> {
+        let params = serde_json::to_value(params)?;
         let result = self.call("get_access_log", params)?;
         Ok(serde_json::from_value(result)?)
     }
 
+    pub fn query_access_logs(
+        &self,
+        params: localdomain_shared::protocol::QueryAccessLogsParams,
+    ) -> Result<localdomain_shared::protocol::AccessLogPage> {
+        let params = serde_json::to_value(params)?;
+        let result = self.call("query_access_logs", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Start following a domain's access log. The stream has its own connection.
+    pub fn tail_access_log(&self, domain: &str) -> Result<AccessLogStream> {
+        let params = serde_json::to_value(localdomain_shared::protocol::TailAccessLogParams {
+            domain: domain.to_string(),
+        })?;
+        let request = JsonRpcRequest::new("tail_access_log", params, self.next_id());
+        let request_json = serde_json::to_string(&request)?;
+
+        let mut stream = connect_for_stream()?;
+        writeln!(stream, "{}", request_json)?;
+        stream.flush()?;
+
+        let mut reader = BufReader::new(stream);
+        let mut response_line = String::new();
+        // The daemon answers right away; wait out read timeouts until it does
+        loop {
+            match reader.read_line(&mut response_line) {
+                Ok(_) => break,
+                Err(e) if is_timeout(&e) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let response: JsonRpcResponse =
+            serde_json::from_str(&response_line).context("Failed to parse daemon response")?;
+        if let Some(error) = response.error {
+            return Err(anyhow::anyhow!("Daemon error: {}", error.message));
+        }
+
+        Ok(AccessLogStream {
+            reader,
+            line: String::new(),
+        })
+    }
+
     pub fn clear_access_log(&self, domain: &str) -> Result<()> {
         let params = serde_json::to_value(localdomain_shared::protocol::ClearAccessLogParams {
             domain: domain.to_string(),
@@ -149,6 +491,15 @@ impl DaemonClient {
         Ok(())
     }
 
+    pub fn get_traffic_stats(
+        &self,
+        params: localdomain_shared::protocol::GetTrafficStatsParams,
+    ) -> Result<localdomain_shared::protocol::GetTrafficStatsResult> {
+        let params = serde_json::to_value(params)?;
+        let result = self.call("get_traffic_stats", params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
     pub fn start_tunnel(
         &self,
         params: localdomain_shared::protocol::StartTunnelParams,
@@ -84,5 +84,90 @@ pub fn run_migrations(conn: &Connection) -> Result<()> {
         )?;
     }
 
+    if version < 5 {
+        // Certificate options: extra SANs (comma-separated) and key algorithm
+        conn.execute_batch(
+            "
+            BEGIN;
+            ALTER TABLE domains ADD COLUMN extra_sans TEXT NOT NULL DEFAULT '';
+            ALTER TABLE domains ADD COLUMN key_algorithm TEXT NOT NULL DEFAULT 'ecdsa_p256';
+            INSERT OR REPLACE INTO schema_version (version) VALUES (5);
+            COMMIT;
+            ",
+        )?;
+    }
+
+    if version < 6 {
+        // User-supplied certificate paths; NULL means the local CA issues the cert
+        conn.execute_batch(
+            "
+            BEGIN;
+            ALTER TABLE domains ADD COLUMN custom_cert_path TEXT;
+            ALTER TABLE domains ADD COLUMN custom_key_path TEXT;
+            INSERT OR REPLACE INTO schema_version (version) VALUES (6);
+            COMMIT;
+            ",
+        )?;
+    }
+
+    if version < 7 {
+        // Address written to the hosts file; hosts_only skips Caddy for remote addresses
+        conn.execute_batch(
+            "
+            BEGIN;
+            ALTER TABLE domains ADD COLUMN resolve_ip TEXT NOT NULL DEFAULT '127.0.0.1';
+            ALTER TABLE domains ADD COLUMN hosts_only INTEGER NOT NULL DEFAULT 0;
+            INSERT OR REPLACE INTO schema_version (version) VALUES (7);
+            COMMIT;
+            ",
+        )?;
+    }
+
+    if version < 8 {
+        // Extra hostnames served by a domain; names are unique across all aliases
+        conn.execute_batch(
+            "
+            BEGIN;
+            CREATE TABLE domain_aliases (
+                domain_id TEXT NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+                name      TEXT NOT NULL UNIQUE COLLATE NOCASE
+            );
+            CREATE INDEX idx_domain_aliases_domain ON domain_aliases(domain_id);
+            INSERT OR REPLACE INTO schema_version (version) VALUES (8);
+            COMMIT;
+            ",
+        )?;
+    }
+
+    if version < 9 {
+        // Hostname overrides: how long each activation lasts, and when the
+        // current one ends (unix seconds; NULL while inactive)
+        conn.execute_batch(
+            "
+            BEGIN;
+            ALTER TABLE domains ADD COLUMN override_ttl_minutes INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE domains ADD COLUMN override_expires_at INTEGER;
+            INSERT OR REPLACE INTO schema_version (version) VALUES (9);
+            COMMIT;
+            ",
+        )?;
+    }
+
+    if version < 10 {
+        // Names are stored in ASCII (punycode); display_name keeps the Unicode
+        // form shown in the app. Existing names are ASCII, so the two start equal
+        conn.execute_batch(
+            "
+            BEGIN;
+            ALTER TABLE domains ADD COLUMN display_name TEXT NOT NULL DEFAULT '';
+            UPDATE domains SET display_name = name;
+            ALTER TABLE domain_aliases ADD COLUMN display_name TEXT NOT NULL DEFAULT '';
+            UPDATE domain_aliases SET display_name = name;
+            INSERT OR REPLACE INTO schema_version (version) VALUES (10);
+            COMMIT;
+            ",
+        )?;
+    }
+
     Ok(())
 }
@@ -1,10 +1,14 @@
+use localdomain_shared::domain::{domain_to_unicode, parse_san_list, DEFAULT_RESOLVE_IP};
 use rusqlite::{params, Connection, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Domain {
     pub id: String,
+    /// ASCII (punycode) form, used for hosts entries, Caddy and certificates
     pub name: String,
+    /// Unicode form of the name, for display
+    pub display_name: String,
     pub target_host: String,
     pub target_port: i32,
     pub protocol: String,
@@ -17,6 +21,24 @@ pub struct Domain {
     pub tunnel_domain: String,
     pub domain_type: String,
     pub document_root: String,
+    /// Certificate SANs besides the domain itself
+    pub extra_sans: Vec<String>,
+    pub key_algorithm: String,
+    /// Set when the domain serves a user-supplied certificate
+    pub custom_cert_path: Option<String>,
+    pub custom_key_path: Option<String>,
+    /// Address the hosts entry points at (loopback unless set)
+    pub resolve_ip: String,
+    /// Resolve only, without a Caddy route (for names served by another machine)
+    pub hosts_only: bool,
+    /// Other hostnames served by this domain, sharing its upstream and certificate
+    pub aliases: Vec<String>,
+    /// Unicode forms of `aliases`, in the same order
+    pub display_aliases: Vec<String>,
+    /// How long an override domain stays active each time it's turned on
+    pub override_ttl_minutes: i64,
+    /// When the active override reverts (unix seconds)
+    pub override_expires_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +50,14 @@ pub struct CreateDomainRequest {
     pub wildcard: Option<bool>,
     pub domain_type: Option<String>,
     pub document_root: Option<String>,
+    pub extra_sans: Option<Vec<String>>,
+    pub key_algorithm: Option<String>,
+    pub resolve_ip: Option<String>,
+    pub hosts_only: Option<bool>,
+    pub aliases: Option<Vec<String>>,
+    pub override_ttl_minutes: Option<i64>,
+    /// Must be true to save an active override domain
+    pub confirm_override: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +71,14 @@ pub struct UpdateDomainRequest {
     pub enabled: Option<bool>,
     pub domain_type: Option<String>,
     pub document_root: Option<String>,
+    pub extra_sans: Option<Vec<String>>,
+    pub key_algorithm: Option<String>,
+    pub resolve_ip: Option<String>,
+    pub hosts_only: Option<bool>,
+    pub aliases: Option<Vec<String>>,
+    pub override_ttl_minutes: Option<i64>,
+    /// Must be true to save an active override domain
+    pub confirm_override: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,14 +92,15 @@ pub struct AuditLogEntry {
 
 pub fn list_domains(conn: &Connection) -> Result<Vec<Domain>> {
     let mut stmt = conn.prepare(
-        "SELECT id, name, target_host, target_port, protocol, wildcard, enabled, access_log, created_at, updated_at, tunnel_subdomain, tunnel_domain, domain_type, document_root FROM domains ORDER BY name",
+        "SELECT id, name, target_host, target_port, protocol, wildcard, enabled, access_log, created_at, updated_at, tunnel_subdomain, tunnel_domain, domain_type, document_root, extra_sans, key_algorithm, custom_cert_path, custom_key_path, resolve_ip, hosts_only, override_ttl_minutes, override_expires_at, display_name FROM domains ORDER BY name",
     )?;
 
-    let domains = stmt
+    let mut domains = stmt
         .query_map([], |row| {
             Ok(Domain {
                 id: row.get(0)?,
                 name: row.get(1)?,
+                display_name: row.get(22)?,
                 target_host: row.get(2)?,
                 target_port: row.get(3)?,
                 protocol: row.get(4)?,
@@ -74,22 +113,47 @@ pub fn list_domains(conn: &Connection) -> Result<Vec<Domain>> {
                 tunnel_domain: row.get(11)?,
                 domain_type: row.get(12)?,
                 document_root: row.get(13)?,
+                extra_sans: parse_san_list(&row.get::<_, String>(14)?),
+                key_algorithm: row.get(15)?,
+                custom_cert_path: row.get(16)?,
+                custom_key_path: row.get(17)?,
+                resolve_ip: row.get(18)?,
+                hosts_only: row.get::<_, i32>(19)? != 0,
+                aliases: Vec::new(),
+                display_aliases: Vec::new(),
+                override_ttl_minutes: row.get(20)?,
+                override_expires_at: row.get(21)?,
             })
         })?
-        .collect::<Result<Vec<_>>>()?;
+        .collect::<Result<Vec<Domain>>>()?;
+
+    let mut alias_stmt =
+        conn.prepare("SELECT domain_id, name, display_name FROM domain_aliases ORDER BY rowid")?;
+    let aliases = alias_stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<Result<Vec<(String, String, String)>>>()?;
+    for (domain_id, alias, display_alias) in aliases {
+        if let Some(domain) = domains.iter_mut().find(|d| d.id == domain_id) {
+            domain.aliases.push(alias);
+            domain.display_aliases.push(display_alias);
+        }
+    }
 
     Ok(domains)
 }
 
 pub fn get_domain(conn: &Connection, id: &str) -> Result<Option<Domain>> {
     let mut stmt = conn.prepare(
-        "SELECT id, name, target_host, target_port, protocol, wildcard, enabled, access_log, created_at, updated_at, tunnel_subdomain, tunnel_domain, domain_type, document_root FROM domains WHERE id = ?1",
+        "SELECT id, name, target_host, target_port, protocol, wildcard, enabled, access_log, created_at, updated_at, tunnel_subdomain, tunnel_domain, domain_type, document_root, extra_sans, key_algorithm, custom_cert_path, custom_key_path, resolve_ip, hosts_only, override_ttl_minutes, override_expires_at, display_name FROM domains WHERE id = ?1",
     )?;
 
     let mut rows = stmt.query_map(params![id], |row| {
         Ok(Domain {
             id: row.get(0)?,
             name: row.get(1)?,
+            display_name: row.get(22)?,
             target_host: row.get(2)?,
             target_port: row.get(3)?,
             protocol: row.get(4)?,
@@ -102,9 +166,61 @@ pub fn get_domain(conn: &Connection, id: &str) -> Result<Option<Domain>> {
             tunnel_domain: row.get(11)?,
             domain_type: row.get(12)?,
             document_root: row.get(13)?,
+            extra_sans: parse_san_list(&row.get::<_, String>(14)?),
+            key_algorithm: row.get(15)?,
+            custom_cert_path: row.get(16)?,
+            custom_key_path: row.get(17)?,
+            resolve_ip: row.get(18)?,
+            hosts_only: row.get::<_, i32>(19)? != 0,
+            aliases: Vec::new(),
+            display_aliases: Vec::new(),
+            override_ttl_minutes: row.get(20)?,
+            override_expires_at: row.get(21)?,
         })
     })?;
 
+    match rows.next() {
+        Some(row) => {
+            let mut domain = row?;
+            (domain.aliases, domain.display_aliases) = list_aliases(conn, id)?.into_iter().unzip();
+            Ok(Some(domain))
+        }
+        None => Ok(None),
+    }
+}
+
+/// A domain's aliases as (name, display name) pairs.
+fn list_aliases(conn: &Connection, domain_id: &str) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, display_name FROM domain_aliases WHERE domain_id = ?1 ORDER BY rowid",
+    )?;
+    let aliases = stmt
+        .query_map(params![domain_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, String)>>>()?;
+    Ok(aliases)
+}
+
+/// Replace a domain's aliases.
+pub fn set_aliases(conn: &Connection, domain_id: &str, aliases: &[String]) -> Result<()> {
+    conn.execute("DELETE FROM domain_aliases WHERE domain_id = ?1", params![domain_id])?;
+    for alias in aliases {
+        conn.execute(
+            "INSERT INTO domain_aliases (domain_id, name, display_name) VALUES (?1, ?2, ?3)",
+            params![domain_id, alias, domain_to_unicode(alias)],
+        )?;
+    }
+    Ok(())
+}
+
+/// The id of the domain that uses `hostname` as its name or an alias.
+pub fn hostname_owner(conn: &Connection, hostname: &str) -> Result<Option<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM domains WHERE name = ?1 COLLATE NOCASE
+         UNION ALL
+         SELECT domain_id FROM domain_aliases WHERE name = ?1
+         LIMIT 1",
+    )?;
+    let mut rows = stmt.query_map(params![hostname], |row| row.get::<_, String>(0))?;
     match rows.next() {
         Some(row) => Ok(Some(row?)),
         None => Ok(None),
@@ -119,11 +235,20 @@ pub fn create_domain(conn: &Connection, req: &CreateDomainRequest) -> Result<Dom
     let wildcard = req.wildcard.unwrap_or(false);
     let domain_type = req.domain_type.as_deref().unwrap_or("proxy");
     let document_root = req.document_root.as_deref().unwrap_or("");
+    let extra_sans = req.extra_sans.as_deref().unwrap_or_default().join(",");
+    let key_algorithm = req.key_algorithm.as_deref().unwrap_or("ecdsa_p256");
+    let resolve_ip = req.resolve_ip.as_deref().unwrap_or(DEFAULT_RESOLVE_IP);
+    let hosts_only = req.hosts_only.unwrap_or(false);
+    let override_ttl_minutes = req.override_ttl_minutes.unwrap_or(0);
+    let display_name = domain_to_unicode(&req.name);
 
     conn.execute(
-        "INSERT INTO domains (id, name, target_host, target_port, protocol, wildcard, domain_type, document_root) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        params![id, req.name, target_host, target_port, protocol, wildcard as i32, domain_type, document_root],
+        "INSERT INTO domains (id, name, target_host, target_port, protocol, wildcard, domain_type, document_root, extra_sans, key_algorithm, resolve_ip, hosts_only, override_ttl_minutes, display_name) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![id, req.name, target_host, target_port, protocol, wildcard as i32, domain_type, document_root, extra_sans, key_algorithm, resolve_ip, hosts_only as i32, override_ttl_minutes, display_name],
     )?;
+    if let Some(aliases) = &req.aliases {
+        set_aliases(conn, &id, aliases)?;
+    }
 
     Ok(get_domain(conn, &id)?.unwrap())
 }
@@ -143,11 +268,20 @@ pub fn update_domain(conn: &Connection, req: &UpdateDomainRequest) -> Result<Opt
     let enabled = req.enabled.unwrap_or(existing.enabled);
     let domain_type = req.domain_type.as_deref().unwrap_or(&existing.domain_type);
     let document_root = req.document_root.as_deref().unwrap_or(&existing.document_root);
+    let extra_sans = req.extra_sans.as_ref().unwrap_or(&existing.extra_sans).join(",");
+    let key_algorithm = req.key_algorithm.as_deref().unwrap_or(&existing.key_algorithm);
+    let resolve_ip = req.resolve_ip.as_deref().unwrap_or(&existing.resolve_ip);
+    let hosts_only = req.hosts_only.unwrap_or(existing.hosts_only);
+    let override_ttl_minutes = req.override_ttl_minutes.unwrap_or(existing.override_ttl_minutes);
+    let display_name = domain_to_unicode(name);
 
     conn.execute(
-        "UPDATE domains SET name = ?1, target_host = ?2, target_port = ?3, protocol = ?4, wildcard = ?5, enabled = ?6, domain_type = ?7, document_root = ?8, updated_at = datetime('now') WHERE id = ?9",
-        params![name, target_host, target_port, protocol, wildcard as i32, enabled as i32, domain_type, document_root, req.id],
+        "UPDATE domains SET name = ?1, target_host = ?2, target_port = ?3, protocol = ?4, wildcard = ?5, enabled = ?6, domain_type = ?7, document_root = ?8, extra_sans = ?9, key_algorithm = ?10, resolve_ip = ?11, hosts_only = ?12, override_ttl_minutes = ?13, display_name = ?14, updated_at = datetime('now') WHERE id = ?15",
+        params![name, target_host, target_port, protocol, wildcard as i32, enabled as i32, domain_type, document_root, extra_sans, key_algorithm, resolve_ip, hosts_only as i32, override_ttl_minutes, display_name, req.id],
     )?;
+    if let Some(aliases) = &req.aliases {
+        set_aliases(conn, &req.id, aliases)?;
+    }
 
     Ok(get_domain(conn, &req.id)?)
 }
@@ -165,6 +299,33 @@ pub fn toggle_domain(conn: &Connection, id: &str, enabled: bool) -> Result<Optio
     get_domain(conn, id)
 }
 
+/// Start (with an expiry) or end a hostname override.
+pub fn set_override_expiry(
+    conn: &Connection,
+    id: &str,
+    expires_at: Option<i64>,
+) -> Result<Option<Domain>> {
+    conn.execute(
+        "UPDATE domains SET override_expires_at = ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![expires_at, id],
+    )?;
+    get_domain(conn, id)
+}
+
+/// Record (or with `None`, clear) the user-supplied certificate a domain serves.
+pub fn set_custom_cert(
+    conn: &Connection,
+    id: &str,
+    paths: Option<(&str, &str)>,
+) -> Result<Option<Domain>> {
+    let (cert_path, key_path) = paths.unzip();
+    conn.execute(
+        "UPDATE domains SET custom_cert_path = ?1, custom_key_path = ?2, updated_at = datetime('now') WHERE id = ?3",
+        params![cert_path, key_path, id],
+    )?;
+    get_domain(conn, id)
+}
+
 pub fn insert_audit_log(
     conn: &Connection,
     action: &str,
@@ -27,6 +27,11 @@ pub fn run() {
         .setup(|app| {
             let state = app.state::<AppState>();
 
+            // The daemon may have restarted since the settings were last pushed
+            if let Err(e) = commands::settings::push_daemon_settings(&state) {
+                eprintln!("Startup: failed to push settings to the daemon: {}", e);
+            }
+
             // Check start_on_boot setting and auto-sync if enabled
             let should_start = {
                 let conn = state.db.lock().unwrap();
@@ -53,6 +58,7 @@ pub fn run() {
             }
 
             tray::setup_tray(app.handle())?;
+            commands::service::start_event_sync(app.handle().clone());
 
             Ok(())
         })
@@ -64,6 +70,7 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             commands::domains::list_domains,
+            commands::domains::check_domain_conflicts,
             commands::domains::create_domain,
             commands::domains::update_domain,
             commands::domains::delete_domain,
@@ -76,11 +83,36 @@ pub fn run() {
             commands::service::start_apache,
             commands::service::stop_apache,
             commands::domains::toggle_access_log,
+            commands::domains::set_custom_certificate,
+            commands::domains::clear_custom_certificate,
             commands::domains::trust_ca,
             commands::audit::get_audit_log,
             commands::audit::clear_audit_log,
+            commands::certificates::list_certificates,
+            commands::certificates::delete_certificate,
+            commands::certificates::export_certificate,
+            commands::certificates::rotate_ca,
+            commands::certificates::import_ca,
+            commands::certificates::export_ca_bundle,
+            commands::certificates::retire_previous_ca,
+            commands::certificates::get_ca_status,
+            commands::certificates::get_device_onboarding,
+            commands::certificates::protect_root_key,
+            commands::certificates::renew_intermediate,
+            commands::toolchains::get_toolchain_trust,
+            commands::toolchains::install_toolchain_trust,
+            commands::toolchains::remove_toolchain_trust,
+            commands::toolchains::install_shell_env,
+            commands::toolchains::remove_shell_env,
+            commands::hosts::list_hosts_backups,
+            commands::hosts::diff_hosts_backup,
+            commands::hosts::restore_hosts_backup,
             commands::access_log::get_access_log,
+            commands::access_log::query_access_logs,
             commands::access_log::clear_access_log,
+            commands::access_log::start_access_log_tail,
+            commands::access_log::stop_access_log_tail,
+            commands::access_log::get_traffic_stats,
             commands::settings::get_settings,
             commands::settings::save_settings,
             commands::settings::detect_xampp_path,
@@ -5,7 +5,8 @@ use tauri::menu::{CheckMenuItem, MenuBuilder, MenuItem};
 use tauri::tray::TrayIconBuilder;
 use tauri::{AppHandle, Emitter, Manager};
 
-fn build_tray_menu(app: &AppHandle) -> tauri::Result<tauri::menu::Menu<tauri::Wry>> {
+/// The menu, and the tooltip naming any active hostname overrides.
+fn build_tray_menu(app: &AppHandle) -> tauri::Result<(tauri::menu::Menu<tauri::Wry>, String)> {
     let state = app.state::<AppState>();
 
     let (daemon_running, caddy_running) = {
@@ -49,20 +50,47 @@ fn build_tray_menu(app: &AppHandle) -> tauri::Result<tauri::menu::Menu<tauri::Wr
         )?)
         .separator();
 
+    // Overrides send a real site's traffic here; keep that in view until they end
+    let overrides: Vec<&models::Domain> = domains
+        .iter()
+        .filter(|d| d.domain_type == "override" && d.enabled)
+        .collect();
+    for domain in &overrides {
+        builder = builder.item(&MenuItem::with_id(
+            app,
+            format!("override-{}", domain.name),
+            format!("\u{26a0} Overriding {}", domain.display_name),
+            false,
+            None::<&str>,
+        )?);
+    }
+    if !overrides.is_empty() {
+        builder = builder.separator();
+    }
+
     for domain in &domains {
         let id = format!("domain-{}", domain.id);
+        // Activating an override needs the confirmation in the main window
+        let can_toggle = domain.domain_type != "override" || domain.enabled;
         let check = CheckMenuItem::with_id(
             app,
             &id,
-            &domain.name,
-            true,
+            &domain.display_name,
+            can_toggle,
             domain.enabled,
             None::<&str>,
         )?;
         builder = builder.item(&check);
     }
 
-    builder
+    let tooltip = if overrides.is_empty() {
+        "LocalDomain".to_string()
+    } else {
+        let names: Vec<&str> = overrides.iter().map(|d| d.display_name.as_str()).collect();
+        format!("LocalDomain \u{2014} overriding {}", names.join(", "))
+    };
+
+    let menu = builder
         .separator()
         .item(&MenuItem::with_id(
             app,
@@ -93,18 +121,19 @@ fn build_tray_menu(app: &AppHandle) -> tauri::Result<tauri::menu::Menu<tauri::Wr
             true,
             None::<&str>,
         )?)
-        .build()
+        .build()?;
+    Ok((menu, tooltip))
 }
 
 pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
-    let menu = build_tray_menu(app)?;
+    let (menu, tooltip) = build_tray_menu(app)?;
 
     let _tray = TrayIconBuilder::with_id("main-tray")
         .icon(tauri::image::Image::from_bytes(include_bytes!(
             "../icons/32x32.png"
         ))?)
         .icon_as_template(false)
-        .tooltip("LocalDomain")
+        .tooltip(&tooltip)
         .menu(&menu)
         .show_menu_on_left_click(true)
         .on_menu_event(|app, event| {
@@ -154,8 +183,9 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
 
 pub fn refresh_tray_menu(app: &AppHandle) {
     if let Some(tray) = app.tray_by_id("main-tray") {
-        if let Ok(menu) = build_tray_menu(app) {
+        if let Ok((menu, tooltip)) = build_tray_menu(app) {
             let _ = tray.set_menu(Some(menu));
+            let _ = tray.set_tooltip(Some(&tooltip));
         }
     }
 }
@@ -167,16 +197,21 @@ fn notify_frontend(app: &AppHandle) {
 fn handle_domain_toggle(app: &AppHandle, domain_id: &str) {
     let state = app.state::<AppState>();
 
-    let new_enabled = {
+    let (new_enabled, is_override) = {
         let conn = state.db.lock().unwrap();
         match models::get_domain(&conn, domain_id) {
-            Ok(Some(domain)) => !domain.enabled,
+            // Overrides can be ended from the tray, not started
+            Ok(Some(domain)) if domain.domain_type == "override" && !domain.enabled => return,
+            Ok(Some(domain)) => (!domain.enabled, domain.domain_type == "override"),
             _ => return,
         }
     };
 
     {
         let conn = state.db.lock().unwrap();
+        if is_override {
+            models::set_override_expiry(&conn, domain_id, None).ok();
+        }
         if let Ok(Some(domain)) = models::toggle_domain(&conn, domain_id, new_enabled) {
             models::insert_audit_log(
                 &conn,
@@ -346,11 +346,19 @@ fn build_localhost_vhost(xampp_path: &str, http_port: u16) -> String {
     )
 }
 
+fn server_alias_line(vhost: &XamppVhostConfig) -> String {
+    if vhost.aliases.is_empty() {
+        String::new()
+    } else {
+        format!("    ServerAlias {}\n", vhost.aliases.join(" "))
+    }
+}
+
 fn build_http_vhost(vhost: &XamppVhostConfig, http_port: u16) -> String {
     format!(
         r#"<VirtualHost *:{port}>
     ServerName {name}
-    DocumentRoot "{document_root}"
+{server_alias}    DocumentRoot "{document_root}"
     <Directory "{document_root}">
         Options Indexes FollowSymLinks
         AllowOverride All
@@ -361,6 +369,7 @@ fn build_http_vhost(vhost: &XamppVhostConfig, http_port: u16) -> String {
 "#,
         port = http_port,
         name = vhost.name,
+        server_alias = server_alias_line(vhost),
         document_root = vhost.document_root,
     )
 }
@@ -372,7 +381,7 @@ fn build_https_vhost(vhost: &XamppVhostConfig, ssl_port: u16) -> String {
     format!(
         r#"<VirtualHost *:{port}>
     ServerName {name}
-    DocumentRoot "{document_root}"
+{server_alias}    DocumentRoot "{document_root}"
     SSLEngine on
     SSLCertificateFile "{cert_path}"
     SSLCertificateKeyFile "{key_path}"
@@ -386,6 +395,7 @@ fn build_https_vhost(vhost: &XamppVhostConfig, ssl_port: u16) -> String {
 "#,
         port = ssl_port,
         name = vhost.name,
+        server_alias = server_alias_line(vhost),
         document_root = vhost.document_root,
         cert_path = cert_path,
         key_path = key_path,
@@ -581,6 +591,7 @@ mod tests {
     fn test_build_vhosts_content_uses_xampp_path_for_localhost() {
         let vhosts = vec![XamppVhostConfig {
             name: "mysite.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/mysite".to_string(),
             protocol: "http".to_string(),
             cert_path: None,
@@ -595,6 +606,7 @@ mod tests {
     fn test_build_vhosts_content_uses_custom_ports() {
         let vhosts = vec![XamppVhostConfig {
             name: "mysite.test".to_string(),
+            aliases: vec![],
             document_root: "/var/www/mysite".to_string(),
             protocol: "both".to_string(),
             cert_path: Some("/certs/mysite.crt".to_string()),
@@ -5,9 +5,11 @@ import { SetupScreen } from "./components/SetupScreen";
 import { DomainList } from "./components/DomainList";
 import { SettingsView } from "./components/SettingsView";
 import { AuditLogView } from "./components/AuditLogView";
+import { CertificatesView } from "./components/CertificatesView";
 import { RequestInspector } from "./components/RequestInspector";
 import { AboutView } from "./components/AboutView";
 import { XamppView } from "./components/XamppView";
+import { TrafficView } from "./components/TrafficView";
 import { useServiceStatus } from "./hooks/useServiceStatus";
 
 function App() {
@@ -101,6 +103,8 @@ function App() {
         />
       )}
       {currentView === "xampp" && <XamppView status={status} />}
+      {currentView === "certificates" && <CertificatesView />}
+      {currentView === "traffic" && <TrafficView />}
       {currentView === "audit" && <AuditLogView />}
       {currentView === "about" && <AboutView />}
     </Layout>
@@ -1,5 +1,5 @@
 import { useAuditLog } from "../hooks/useAuditLog";
-import type { AuditLogEntry } from "../lib/types";
+import type { AuditLogEntry, HostsTamperEvent } from "../lib/types";
 import { useLoading } from "../hooks/useLoading";
 import * as api from "../lib/api";
 
@@ -12,6 +12,24 @@ function formatAction(action: string): { label: string; className: string } {
     domain_disabled: { label: "Disabled", className: "audit-badge audit-badge-disabled" },
     access_log_enabled: { label: "Log On", className: "audit-badge audit-badge-enabled" },
     access_log_disabled: { label: "Log Off", className: "audit-badge audit-badge-disabled" },
+    certificate_deleted: { label: "Cert Deleted", className: "audit-badge audit-badge-deleted" },
+    certificate_renewed: { label: "Cert Renewed", className: "audit-badge audit-badge-updated" },
+    certificate_renewal_failed: { label: "Renew Failed", className: "audit-badge audit-badge-deleted" },
+    certificate_exported: { label: "Cert Exported", className: "audit-badge audit-badge-enabled" },
+    ca_rotated: { label: "CA Rotated", className: "audit-badge audit-badge-updated" },
+    ca_retired: { label: "CA Retired", className: "audit-badge audit-badge-deleted" },
+    root_key_protected: { label: "Root Key Locked", className: "audit-badge audit-badge-updated" },
+    intermediate_renewed: { label: "CA Renewed", className: "audit-badge audit-badge-updated" },
+    toolchain_trust_installed: { label: "Dev Trust Added", className: "audit-badge audit-badge-enabled" },
+    toolchain_trust_removed: { label: "Dev Trust Removed", className: "audit-badge audit-badge-deleted" },
+    shell_env_installed: { label: "Shell Env Added", className: "audit-badge audit-badge-enabled" },
+    shell_env_removed: { label: "Shell Env Removed", className: "audit-badge audit-badge-deleted" },
+    hosts_repaired: { label: "Hosts Repaired", className: "audit-badge audit-badge-updated" },
+    hosts_tampered: { label: "Hosts Changed", className: "audit-badge audit-badge-deleted" },
+    hosts_restored: { label: "Hosts Restored", className: "audit-badge audit-badge-updated" },
+    override_activated: { label: "Override On", className: "audit-badge audit-badge-deleted" },
+    override_reverted: { label: "Override Ended", className: "audit-badge audit-badge-enabled" },
+    domain_sync_skipped: { label: "Sync Skipped", className: "audit-badge audit-badge-deleted" },
   };
   return map[action] ?? { label: action, className: "audit-badge" };
 }
@@ -49,10 +67,26 @@ function formatFullTimestamp(raw: string): string {
   }
 }
 
+// Summarise an external hosts edit; the other program's comment line usually names it.
+function formatHostsChange(event: HostsTamperEvent): string {
+  const parts: string[] = [];
+  if (event.missing.length > 0) {
+    parts.push(`removed ${event.missing.map((e) => e.domain).join(", ")}`);
+  }
+  if (event.unexpected.length > 0) {
+    parts.push(`${event.unexpected.length} foreign line(s) in block`);
+  }
+  const hint = event.added_lines.find((line) => line.startsWith("#"));
+  if (hint) parts.push(`source: ${hint.replace(/^#\s*/, "")}`);
+  if (event.error) parts.push(event.error);
+  return parts.join(" · ");
+}
+
 function formatDetails(entry: AuditLogEntry): string | null {
   if (!entry.details) return null;
   try {
     const parsed = JSON.parse(entry.details);
+    if (Array.isArray(parsed.missing)) return formatHostsChange(parsed);
     if (parsed.name) return parsed.name;
     if (parsed.domain) return parsed.domain;
     return null;
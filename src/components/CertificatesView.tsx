@@ -0,0 +1,552 @@
+import { useEffect, useState } from "react";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import type { CaStatus, CertExportFormat, CertificateInfo, ToolchainTrust } from "../lib/types";
+import { useCertificates } from "../hooks/useCertificates";
+import { useLoading } from "../hooks/useLoading";
+import * as api from "../lib/api";
+import { TrashIcon } from "./Icons";
+
+const EXPIRY_WARNING_DAYS = 30;
+const CA_OVERLAP_DAYS = 7;
+
+function daysUntil(ts: number): number {
+  return Math.floor((ts * 1000 - Date.now()) / 86_400_000);
+}
+
+function expiryBadge(cert: CertificateInfo): { label: string; className: string } {
+  const days = daysUntil(cert.not_after);
+  if (days < 0) return { label: "Expired", className: "audit-badge audit-badge-deleted" };
+  if (days <= EXPIRY_WARNING_DAYS) {
+    return { label: `${days}d left`, className: "audit-badge audit-badge-updated" };
+  }
+  return { label: `${days}d left`, className: "audit-badge audit-badge-enabled" };
+}
+
+const EXPORT_FORMATS: { value: CertExportFormat; label: string; ext: string }[] = [
+  { value: "pem_chain", label: "PEM full chain", ext: "chain.pem" },
+  { value: "der", label: "DER", ext: "der" },
+  { value: "pem_bundle", label: "PEM key + chain", ext: "bundle.pem" },
+  { value: "pkcs12", label: "PKCS#12 (.p12)", ext: "p12" },
+];
+
+interface ExportControlsProps {
+  cert: CertificateInfo;
+  onExport: (format: CertExportFormat, passphrase: string | null, path: string) => Promise<void>;
+}
+
+function ExportControls({ cert, onExport }: ExportControlsProps) {
+  // CA private keys are never exported, so only key-less formats (and a cert-only p12) apply
+  const formats = EXPORT_FORMATS.filter((f) => !cert.is_ca || f.value !== "pem_bundle");
+  const [format, setFormat] = useState<CertExportFormat>("pem_chain");
+  const [passphrase, setPassphrase] = useState("");
+
+  const handleExport = async () => {
+    const ext = formats.find((f) => f.value === format)?.ext ?? "pem";
+    const path = await save({ title: "Export certificate", defaultPath: `${cert.name}.${ext}` });
+    if (!path) return;
+    await onExport(format, format === "pkcs12" ? passphrase : null, path);
+    setPassphrase("");
+  };
+
+  return (
+    <div className="cert-export-row">
+      <select value={format} onChange={(e) => setFormat(e.target.value as CertExportFormat)}>
+        {formats.map((f) => (
+          <option key={f.value} value={f.value}>
+            {f.label}
+          </option>
+        ))}
+      </select>
+      {format === "pkcs12" && (
+        <input
+          type="password"
+          placeholder="Export passphrase"
+          value={passphrase}
+          onChange={(e) => setPassphrase(e.target.value)}
+        />
+      )}
+      <button
+        className="btn btn-sm"
+        disabled={format === "pkcs12" && !passphrase}
+        onClick={handleExport}
+      >
+        Export...
+      </button>
+    </div>
+  );
+}
+
+function CertificateDetail({ cert }: { cert: CertificateInfo }) {
+  const rows: [string, string][] = [
+    ["Subject", cert.subject],
+    ["SANs", cert.sans.join(", ") || "-"],
+    ["Issuer", cert.issuer],
+    ["Serial", cert.serial],
+    ["Valid from", new Date(cert.not_before * 1000).toLocaleString()],
+    ["Valid until", new Date(cert.not_after * 1000).toLocaleString()],
+    ["Key", cert.key_type],
+    ["SHA-256", cert.fingerprint_sha256],
+    ["File", cert.cert_path],
+  ];
+  if (cert.is_ca) {
+    rows.push(["Permitted names", cert.name_constraints.join(", ") || "Any (unconstrained)"]);
+  }
+  return (
+    <table className="detail-kv-table cert-detail">
+      <tbody>
+        {rows.map(([key, value]) => (
+          <tr key={key}>
+            <td className="detail-kv-key">{key}</td>
+            <td className="detail-kv-value">{value}</td>
+          </tr>
+        ))}
+      </tbody>
+    </table>
+  );
+}
+
+const ROOT_KEY_LABELS: Record<CaStatus["root_key"], string> = {
+  online: "On disk (unencrypted)",
+  encrypted: "Encrypted at rest",
+  offline: "Offline",
+};
+
+interface RootKeyPanelProps {
+  status: CaStatus;
+  onProtect: (passphrase: string, offlinePath: string | null) => Promise<void>;
+  onRenew: (passphrase: string | null, rootKeyPath: string | null) => Promise<void>;
+}
+
+function RootKeyPanel({ status, onProtect, onRenew }: RootKeyPanelProps) {
+  const [passphrase, setPassphrase] = useState("");
+
+  const handleMoveOffline = async () => {
+    const path = await save({
+      title: "Save encrypted root key",
+      defaultPath: "localdomain-root-ca.key.enc",
+    });
+    if (path) await onProtect(passphrase, path);
+  };
+
+  const handleRenew = async () => {
+    let keyPath: string | null = null;
+    if (status.root_key === "offline") {
+      const selected = await open({ multiple: false, title: "Select encrypted root key" });
+      if (!selected) return;
+      keyPath = selected as string;
+    }
+    await onRenew(passphrase || null, keyPath);
+    setPassphrase("");
+  };
+
+  const intermediateDays =
+    status.intermediate_not_after !== undefined ? daysUntil(status.intermediate_not_after) : null;
+
+  return (
+    <div className="cert-root-panel">
+      <div className="cert-root-row">
+        <span>Root key: {ROOT_KEY_LABELS[status.root_key]}</span>
+        <span>
+          Intermediate:{" "}
+          {status.intermediate_valid && intermediateDays !== null
+            ? `${intermediateDays}d left`
+            : "missing or invalid"}
+        </span>
+      </div>
+      <div className="cert-root-row">
+        <input
+          type="password"
+          placeholder="Root key passphrase"
+          value={passphrase}
+          onChange={(e) => setPassphrase(e.target.value)}
+        />
+        {status.root_key === "online" ? (
+          <>
+            <button
+              className="btn btn-sm"
+              disabled={passphrase.length < 8}
+              onClick={() => onProtect(passphrase, null)}
+            >
+              Encrypt
+            </button>
+            <button
+              className="btn btn-sm"
+              disabled={passphrase.length < 8}
+              onClick={handleMoveOffline}
+            >
+              Move offline...
+            </button>
+          </>
+        ) : (
+          <button className="btn btn-sm" disabled={!passphrase} onClick={handleRenew}>
+            Renew intermediate
+          </button>
+        )}
+      </div>
+    </div>
+  );
+}
+
+interface CaSharingPanelProps {
+  status: CaStatus;
+  onImport: (certPath: string, keyPath: string | null, passphrase: string | null) => Promise<void>;
+  onExport: (passphrase: string, rootPassphrase: string | null, path: string) => Promise<void>;
+}
+
+/** Use an existing CA (e.g. mkcert's root) or share this one with a team. */
+function CaSharingPanel({ status, onImport, onExport }: CaSharingPanelProps) {
+  const [passphrase, setPassphrase] = useState("");
+  const [rootPassphrase, setRootPassphrase] = useState("");
+
+  const handleImport = async () => {
+    const certPath = await open({
+      multiple: false,
+      title: "Select CA certificate or exported bundle",
+      filters: [{ name: "PEM", extensions: ["pem", "crt", "cer"] }],
+    });
+    if (!certPath) return;
+    // Cancelling here means the key is in the same file (an exported bundle)
+    const keyPath = await open({
+      multiple: false,
+      title: "Select CA private key (cancel if it is in the same file)",
+      filters: [{ name: "PEM", extensions: ["pem", "key"] }],
+    });
+    await onImport(certPath as string, (keyPath as string | null) ?? null, passphrase || null);
+    setPassphrase("");
+  };
+
+  const handleExport = async () => {
+    const path = await save({
+      title: "Export CA bundle",
+      defaultPath: "localdomain-ca.bundle.pem",
+    });
+    if (!path) return;
+    await onExport(passphrase, rootPassphrase || null, path);
+    setPassphrase("");
+    setRootPassphrase("");
+  };
+
+  return (
+    <div className="cert-root-panel">
+      <div className="cert-root-row">
+        <span>Import an existing CA, or export this one to share it across machines</span>
+      </div>
+      <div className="cert-root-row">
+        <input
+          type="password"
+          placeholder="Bundle or key passphrase"
+          value={passphrase}
+          onChange={(e) => setPassphrase(e.target.value)}
+        />
+        {status.root_key === "encrypted" && (
+          <input
+            type="password"
+            placeholder="Root key passphrase"
+            value={rootPassphrase}
+            onChange={(e) => setRootPassphrase(e.target.value)}
+          />
+        )}
+        <button className="btn btn-sm" onClick={handleImport}>
+          Import CA...
+        </button>
+        <button
+          className="btn btn-sm"
+          disabled={passphrase.length < 8 || status.root_key === "offline"}
+          onClick={handleExport}
+        >
+          Export CA bundle...
+        </button>
+      </div>
+    </div>
+  );
+}
+
+interface ToolchainPanelProps {
+  onError: (message: string | null) => void;
+}
+
+/** JDK keystores, the combined CA bundle and shell variables for dev tools. */
+function ToolchainPanel({ onError }: ToolchainPanelProps) {
+  const { track } = useLoading();
+  const [trust, setTrust] = useState<ToolchainTrust | null>(null);
+
+  useEffect(() => {
+    api.getToolchainTrust().then(setTrust).catch(() => setTrust(null));
+  }, []);
+
+  const run = async (action: Promise<ToolchainTrust>) => {
+    try {
+      onError(null);
+      setTrust(await track(action));
+    } catch (e) {
+      onError(String(e));
+    }
+  };
+
+  if (!trust) return null;
+  const installed = trust.bundle_path !== null || trust.java_keystores.some((k) => k.trusted);
+
+  return (
+    <div className="cert-root-panel">
+      <div className="cert-root-row">
+        <span>Developer tools (Java, Node, Python, Go, curl)</span>
+        <span>
+          {installed ? (
+            <button className="btn btn-sm" onClick={() => run(api.removeToolchainTrust())}>
+              Remove
+            </button>
+          ) : (
+            <button className="btn btn-sm btn-primary" onClick={() => run(api.installToolchainTrust())}>
+              Trust in dev tools
+            </button>
+          )}
+        </span>
+      </div>
+      {trust.java_keystores.length > 0 && (
+        <ul className="trust-store-list">
+          {trust.java_keystores.map((ks) => (
+            <li key={ks.path} title={ks.error ?? ks.path}>
+              <span className={`status-dot ${ks.trusted ? "green" : "yellow"}`} />
+              <span>Java: {ks.label}</span>
+              {ks.error && <span className="trust-store-error">{ks.error}</span>}
+            </li>
+          ))}
+        </ul>
+      )}
+      <pre className="cert-env-snippet">{trust.snippet}</pre>
+      <div className="cert-root-row">
+        <span className="cert-note">
+          {trust.installed_profiles.length > 0
+            ? `Loaded by ${trust.installed_profiles.join(", ")}`
+            : "Not added to any shell profile"}
+        </span>
+        {trust.installed_profiles.length > 0 ? (
+          <button className="btn btn-sm" onClick={() => run(api.removeShellEnv())}>
+            Remove from shell
+          </button>
+        ) : (
+          <button className="btn btn-sm" onClick={() => run(api.installShellEnv())}>
+            Add to shell profile
+          </button>
+        )}
+      </div>
+    </div>
+  );
+}
+
+export function CertificatesView() {
+  const {
+    certificates,
+    caStatus,
+    loading,
+    error,
+    remove,
+    rotateCa,
+    importCa,
+    retirePreviousCa,
+    protectRootKey,
+    renewIntermediate,
+  } = useCertificates();
+  const { track } = useLoading();
+  const [expanded, setExpanded] = useState<string | null>(null);
+  const [deleteError, setDeleteError] = useState<string | null>(null);
+  const [rotationNote, setRotationNote] = useState<string | null>(null);
+
+  const previousCa = certificates.find((c) => c.is_ca && !c.signed_by_current_ca);
+  // Custom certificates are never renewed by the daemon, so call out expiring ones
+  const expiringCustom = certificates.filter(
+    (c) => c.custom && daysUntil(c.not_after) <= EXPIRY_WARNING_DAYS
+  );
+  const currentCa = certificates.find(
+    (c) => c.is_ca && c.signed_by_current_ca && c.subject === c.issuer
+  );
+
+  const handleRotate = async () => {
+    if (
+      !window.confirm(
+        `Rotate the root CA? All certificates will be reissued and the old CA stays trusted for ${CA_OVERLAP_DAYS} days.`
+      )
+    ) {
+      return;
+    }
+    try {
+      setDeleteError(null);
+      const result = await track(rotateCa(CA_OVERLAP_DAYS));
+      setRotationNote(
+        result.failed.length > 0
+          ? `Reissued ${result.reissued.length} certificates; failed: ${result.failed.join(", ")}`
+          : `Reissued ${result.reissued.length} certificates with the new CA.`
+      );
+    } catch (e) {
+      setDeleteError(String(e));
+    }
+  };
+
+  const handleImport = async (
+    certPath: string,
+    keyPath: string | null,
+    passphrase: string | null
+  ) => {
+    if (
+      currentCa &&
+      !window.confirm(
+        `Replace the current root CA with the imported one? All certificates will be reissued and the old CA stays trusted for ${CA_OVERLAP_DAYS} days.`
+      )
+    ) {
+      return;
+    }
+    try {
+      setDeleteError(null);
+      const result = await track(importCa(certPath, keyPath, passphrase, CA_OVERLAP_DAYS));
+      const rotation = result.rotation;
+      setRotationNote(
+        rotation
+          ? `Now issuing from ${result.subject}. Reissued ${rotation.reissued.length} certificates` +
+              (rotation.failed.length > 0 ? `; failed: ${rotation.failed.join(", ")}` : ".")
+          : `Now issuing from ${result.subject}.`
+      );
+    } catch (e) {
+      setDeleteError(String(e));
+    }
+  };
+
+  const runCaAction = async (action: Promise<void>) => {
+    try {
+      setDeleteError(null);
+      await track(action);
+    } catch (e) {
+      setDeleteError(String(e));
+    }
+  };
+
+  const handleRetire = async () => {
+    try {
+      setDeleteError(null);
+      await track(retirePreviousCa());
+      setRotationNote(null);
+    } catch (e) {
+      setDeleteError(String(e));
+    }
+  };
+
+  const handleDelete = async (name: string) => {
+    try {
+      setDeleteError(null);
+      await track(remove(name));
+    } catch (e) {
+      setDeleteError(String(e));
+    }
+  };
+
+  if (loading) {
+    return <div className="loading">Loading certificates...</div>;
+  }
+
+  return (
+    <div className="audit-log-view">
+      {(error || deleteError) && <div className="form-error">{error || deleteError}</div>}
+      {rotationNote && <div className="cert-note">{rotationNote}</div>}
+      {expiringCustom.length > 0 && (
+        <div className="cert-note cert-note-warn">
+          Custom certificates expiring soon: {expiringCustom.map((c) => c.name).join(", ")}.
+          Replace them from the domain settings; they are not renewed automatically.
+        </div>
+      )}
+      {currentCa && currentCa.name_constraints.length === 0 && (
+        <div className="cert-note cert-note-warn">
+          This root CA is not name-constrained, so a leaked key could sign certificates for any
+          website. Rotate the CA to replace it with one limited to the suffixes in Settings.
+        </div>
+      )}
+
+      {caStatus && (
+        <RootKeyPanel
+          status={caStatus}
+          onProtect={(passphrase, path) => runCaAction(protectRootKey(passphrase, path))}
+          onRenew={(passphrase, path) => runCaAction(renewIntermediate(passphrase, path))}
+        />
+      )}
+
+      {caStatus && (
+        <CaSharingPanel
+          status={caStatus}
+          onImport={handleImport}
+          onExport={(passphrase, rootPassphrase, path) =>
+            runCaAction(api.exportCaBundle(passphrase, rootPassphrase, path))
+          }
+        />
+      )}
+
+      {caStatus && <ToolchainPanel onError={setDeleteError} />}
+
+      <div className="cert-toolbar">
+        <button className="btn btn-sm" onClick={handleRotate} disabled={!!previousCa}>
+          Rotate CA
+        </button>
+        {previousCa && (
+          <button className="btn btn-sm btn-danger" onClick={handleRetire}>
+            Retire previous CA now
+          </button>
+        )}
+      </div>
+
+      {certificates.length === 0 ? (
+        <div className="empty-state">
+          <p>No certificates have been issued yet.</p>
+        </div>
+      ) : (
+        <div className="audit-list">
+          {certificates.map((cert) => {
+            const { label, className } = expiryBadge(cert);
+            const isOpen = expanded === cert.name;
+            return (
+              <div key={cert.cert_path} className="cert-entry">
+                <div className="audit-entry" onClick={() => setExpanded(isOpen ? null : cert.name)}>
+                  <span className={className}>{label}</span>
+                  <span className="audit-entry-detail">
+                    {cert.name}
+                    {cert.is_ca && (
+                      <span className="cert-tag">
+                        {cert.subject === cert.issuer ? "Root CA" : "Intermediate CA"}
+                      </span>
+                    )}
+                    {cert.custom ? (
+                      <span className="cert-tag">Custom</span>
+                    ) : (
+                      !cert.signed_by_current_ca && <span className="cert-tag cert-tag-warn">Other CA</span>
+                    )}
+                    {!cert.in_use && <span className="cert-tag">Unused</span>}
+                  </span>
+                  <span className="audit-entry-time">{cert.key_type}</span>
+                  {!cert.is_ca && !cert.in_use && (
+                    <button
+                      className="btn btn-sm btn-danger"
+                      title="Delete certificate"
+                      onClick={(e) => {
+                        e.stopPropagation();
+                        handleDelete(cert.name);
+                      }}
+                    >
+                      <TrashIcon />
+                    </button>
+                  )}
+                </div>
+                {isOpen && (
+                  <>
+                    <CertificateDetail cert={cert} />
+                    <ExportControls
+                      cert={cert}
+                      onExport={(format, passphrase, path) =>
+                        runCaAction(api.exportCertificate(cert.name, format, passphrase, path))
+                      }
+                    />
+                  </>
+                )}
+              </div>
+            );
+          })}
+        </div>
+      )}
+    </div>
+  );
+}
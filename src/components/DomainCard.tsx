@@ -35,7 +35,9 @@ export function DomainCard({
   tunnelStatus,
   isToggling
 }: DomainCardProps) {
-  const hasProxy = domain.target_port > 0;
+  const hasProxy = domain.target_port > 0 && !domain.hosts_only;
+  const remoteIp =
+    domain.resolve_ip !== "::1" && !domain.resolve_ip.startsWith("127.");
   const protocol =
     domain.protocol === "https" || domain.protocol === "both"
       ? "https"
@@ -76,11 +78,14 @@ export function DomainCard({
 
 
   const isXampp = domain.domain_type === "xampp";
+  const isOverride = domain.domain_type === "override";
   const targetDisplay = isXampp
     ? domain.document_root
     : hasProxy
-      ? `${domain.target_host}:${domain.target_port}`
-      : "hosts only";
+      ? `${remoteIp ? domain.resolve_ip : domain.target_host}:${domain.target_port}`
+      : domain.resolve_ip !== "127.0.0.1"
+        ? `hosts only (${domain.resolve_ip})`
+        : "hosts only";
 
   return (
     <div
@@ -97,10 +102,22 @@ export function DomainCard({
           <div className="domain-card-info">
             <div className="domain-card-name">
               <a href={domainUrl} onClick={handleOpenDomain}>
-                {domain.name}
+                {domain.display_name}
               </a>
             </div>
             <div className="domain-card-target">{targetDisplay}</div>
+            {domain.display_aliases.length > 0 && (
+              <div className="domain-card-aliases" title={domain.display_aliases.join("\n")}>
+                also {domain.display_aliases.join(", ")}
+              </div>
+            )}
+            {isOverride && (
+              <div className={`domain-card-override ${domain.enabled ? "active" : ""}`}>
+                {domain.enabled && domain.override_expires_at
+                  ? `Overriding the real site until ${new Date(domain.override_expires_at * 1000).toLocaleTimeString([], { hour: "2-digit", minute: "2-digit" })}`
+                  : "Override off"}
+              </div>
+            )}
           </div>
         </div>
         <div className="domain-card-right">
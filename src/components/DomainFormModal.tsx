@@ -1,40 +1,91 @@
 import { useState, useEffect } from "react";
 import { open } from "@tauri-apps/plugin-dialog";
 import * as api from "../lib/api";
-import type { Domain, CreateDomainRequest, UpdateDomainRequest } from "../lib/types";
+import type {
+  Domain,
+  DomainType,
+  DomainWarning,
+  CreateDomainRequest,
+  UpdateDomainRequest,
+  KeyAlgorithm,
+} from "../lib/types";
+
+function isLoopback(ip: string): boolean {
+  return ip === "" || ip === "::1" || ip.startsWith("127.");
+}
+
+const OVERRIDE_DURATIONS = [
+  { minutes: 15, label: "15 minutes" },
+  { minutes: 60, label: "1 hour" },
+  { minutes: 240, label: "4 hours" },
+  { minutes: 480, label: "8 hours" },
+  { minutes: 1440, label: "24 hours" },
+];
 
 interface DomainFormModalProps {
   domain: Domain | null;
   onSave: (request: CreateDomainRequest | UpdateDomainRequest) => Promise<void>;
+  onCertificateChanged: () => void;
   onClose: () => void;
 }
 
-export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProps) {
+export function DomainFormModal({
+  domain,
+  onSave,
+  onCertificateChanged,
+  onClose,
+}: DomainFormModalProps) {
   const [name, setName] = useState("");
+  const [aliases, setAliases] = useState("");
   const [targetHost, setTargetHost] = useState("");
   const [targetPort, setTargetPort] = useState("");
   const [protocol, setProtocol] = useState("http");
   const [wildcard, setWildcard] = useState(false);
-  const [domainType, setDomainType] = useState<"proxy" | "xampp">("proxy");
+  const [domainType, setDomainType] = useState<DomainType>("proxy");
   const [documentRoot, setDocumentRoot] = useState("");
+  const [extraSans, setExtraSans] = useState("");
+  const [keyAlgorithm, setKeyAlgorithm] = useState<KeyAlgorithm>("ecdsa_p256");
+  const [resolveIp, setResolveIp] = useState("");
+  const [hostsOnly, setHostsOnly] = useState(false);
+  const [overrideTtl, setOverrideTtl] = useState("60");
+  const [confirmOverride, setConfirmOverride] = useState(false);
+  const [customCertPath, setCustomCertPath] = useState<string | null>(null);
+  const [certPassphrase, setCertPassphrase] = useState("");
+  const [certBusy, setCertBusy] = useState(false);
   const [saving, setSaving] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  // Shown once per name/address; saving again while they're shown goes ahead
+  const [warnings, setWarnings] = useState<DomainWarning[] | null>(null);
   const [xamppPort, setXamppPort] = useState<number | null>(null);
 
   const isEditing = domain !== null;
+  const remoteIp = domainType === "proxy" && !isLoopback(resolveIp.trim());
+  // Saving an active override starts it again, so it needs the confirmation
+  const needsOverrideConfirm = domainType === "override" && (!domain || domain.enabled);
 
   useEffect(() => {
     if (domain) {
-      setName(domain.name);
+      setName(domain.display_name || domain.name);
+      setAliases((domain.display_aliases || domain.aliases || []).join(", "));
       setTargetHost(domain.target_host || "");
       setTargetPort(domain.target_port > 0 ? String(domain.target_port) : "");
       setProtocol(domain.protocol);
       setWildcard(domain.wildcard);
-      setDomainType((domain.domain_type as "proxy" | "xampp") || "proxy");
+      setDomainType(domain.domain_type || "proxy");
       setDocumentRoot(domain.document_root || "");
+      setExtraSans((domain.extra_sans || []).join(", "));
+      setKeyAlgorithm(domain.key_algorithm || "ecdsa_p256");
+      setCustomCertPath(domain.custom_cert_path ?? null);
+      setResolveIp(domain.resolve_ip === "127.0.0.1" ? "" : domain.resolve_ip);
+      setHostsOnly(domain.hosts_only);
+      setOverrideTtl(String(domain.override_ttl_minutes || 60));
     }
   }, [domain]);
 
+  useEffect(() => {
+    setWarnings(null);
+  }, [name, aliases, resolveIp, hostsOnly, domainType]);
+
   // Auto-fetch XAMPP default port when switching to XAMPP type
   useEffect(() => {
     if (domainType !== "xampp") return;
@@ -57,6 +108,54 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
     return () => { cancelled = true; };
   }, [domainType]);
 
+  const handleCustomCert = async () => {
+    if (!domain) return;
+    const certPath = await open({
+      multiple: false,
+      title: "Select certificate (leaf first, then intermediates)",
+      filters: [{ name: "PEM", extensions: ["pem", "crt", "cer"] }],
+    });
+    if (!certPath) return;
+    // Cancelling here means the key is in the same file
+    const keyPath = await open({
+      multiple: false,
+      title: "Select private key (cancel if it is in the same file)",
+      filters: [{ name: "PEM", extensions: ["pem", "key"] }],
+    });
+    setCertBusy(true);
+    setError(null);
+    try {
+      const updated = await api.setCustomCertificate(
+        domain.id,
+        certPath as string,
+        (keyPath as string | null) ?? null,
+        certPassphrase || null
+      );
+      setCustomCertPath(updated.custom_cert_path ?? null);
+      setCertPassphrase("");
+      onCertificateChanged();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setCertBusy(false);
+    }
+  };
+
+  const handleClearCustomCert = async () => {
+    if (!domain) return;
+    setCertBusy(true);
+    setError(null);
+    try {
+      await api.clearCustomCertificate(domain.id);
+      setCustomCertPath(null);
+      onCertificateChanged();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setCertBusy(false);
+    }
+  };
+
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     setSaving(true);
@@ -64,6 +163,23 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
 
     try {
       const portNum = targetPort ? parseInt(targetPort, 10) : undefined;
+      const sans = extraSans.split(/[\s,]+/).filter(Boolean);
+      const aliasList = aliases.split(/[\s,]+/).filter(Boolean);
+      const ip = domainType === "proxy" ? resolveIp.trim() || "127.0.0.1" : "127.0.0.1";
+
+      // Overrides are for public names on purpose; the confirmation covers them
+      if (warnings === null && domainType !== "override") {
+        let found: DomainWarning[] = [];
+        try {
+          found = await api.checkDomainConflicts(name, aliasList, ip, remoteIp && hostsOnly);
+        } catch {
+          // The check is advisory; don't block saving on it
+        }
+        if (found.length > 0) {
+          setWarnings(found);
+          return;
+        }
+      }
 
       if (isEditing) {
         const request: UpdateDomainRequest = {
@@ -75,6 +191,13 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
           wildcard,
           domain_type: domainType,
           document_root: domainType === "xampp" ? documentRoot : undefined,
+          extra_sans: sans,
+          key_algorithm: keyAlgorithm,
+          resolve_ip: ip,
+          aliases: aliasList,
+          hosts_only: remoteIp && hostsOnly,
+          override_ttl_minutes: domainType === "override" ? parseInt(overrideTtl, 10) : undefined,
+          confirm_override: domainType === "override" ? confirmOverride : undefined,
         };
         await onSave(request);
       } else {
@@ -86,6 +209,13 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
           wildcard,
           domain_type: domainType,
           document_root: domainType === "xampp" ? documentRoot : undefined,
+          extra_sans: sans,
+          key_algorithm: keyAlgorithm,
+          resolve_ip: ip,
+          aliases: aliasList,
+          hosts_only: remoteIp && hostsOnly,
+          override_ttl_minutes: domainType === "override" ? parseInt(overrideTtl, 10) : undefined,
+          confirm_override: domainType === "override" ? confirmOverride : undefined,
         };
         await onSave(request);
       }
@@ -119,6 +249,19 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
               autoFocus
             />
           </div>
+          <div className="form-group">
+            <label htmlFor="aliases">Aliases <span className="form-optional">(optional)</span></label>
+            <input
+              id="aliases"
+              type="text"
+              value={aliases}
+              onChange={(e) => setAliases(e.target.value)}
+              placeholder="admin.project.test, api.project.test"
+            />
+            <div className="form-hint">
+              Other hostnames served the same way. They share the target and the certificate.
+            </div>
+          </div>
           <div className="form-group">
             <label>Domain Type</label>
             <div className="form-row" style={{ gap: 0 }}>
@@ -133,20 +276,103 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
               <button
                 type="button"
                 className={`btn btn-sm ${domainType === "xampp" ? "btn-primary" : ""}`}
-                style={{ borderRadius: "0 6px 6px 0", flex: 1 }}
+                style={{ borderRadius: 0, flex: 1 }}
                 onClick={() => setDomainType("xampp")}
               >
                 XAMPP
               </button>
+              <button
+                type="button"
+                className={`btn btn-sm ${domainType === "override" ? "btn-primary" : ""}`}
+                style={{ borderRadius: "0 6px 6px 0", flex: 1 }}
+                onClick={() => setDomainType("override")}
+              >
+                Override
+              </button>
             </div>
           </div>
           {domainType === "proxy" && (
             <>
               <div className="form-row">
                 <div className="form-group">
-                  <label htmlFor="targetHost">Target Host <span className="form-optional">(optional)</span></label>
+                  <label htmlFor="resolveIp">Resolves To <span className="form-optional">(optional)</span></label>
                   <input
-                    id="targetHost"
+                    id="resolveIp"
+                    type="text"
+                    value={resolveIp}
+                    onChange={(e) => setResolveIp(e.target.value)}
+                    placeholder="127.0.0.1"
+                  />
+                </div>
+                {remoteIp && (
+                  <div className="form-group">
+                    <label htmlFor="resolveMode">Traffic</label>
+                    <select
+                      id="resolveMode"
+                      value={hostsOnly ? "hosts" : "proxy"}
+                      onChange={(e) => setHostsOnly(e.target.value === "hosts")}
+                    >
+                      <option value="proxy">Proxy through LocalDomain</option>
+                      <option value="hosts">Hosts file only</option>
+                    </select>
+                  </div>
+                )}
+              </div>
+              {remoteIp && (
+                <div className="form-hint">
+                  {hostsOnly
+                    ? `The domain resolves straight to ${resolveIp.trim()}. LocalDomain does not proxy it or serve HTTPS for it.`
+                    : `The domain resolves to this machine and LocalDomain forwards requests to ${resolveIp.trim()} on the target port.`}
+                </div>
+              )}
+              {!(remoteIp && hostsOnly) && (
+                <div className="form-row">
+                  {!remoteIp && (
+                    <div className="form-group">
+                      <label htmlFor="targetHost">Target Host <span className="form-optional">(optional)</span></label>
+                      <input
+                        id="targetHost"
+                        type="text"
+                        value={targetHost}
+                        onChange={(e) => setTargetHost(e.target.value)}
+                        placeholder="127.0.0.1"
+                      />
+                    </div>
+                  )}
+                  <div className="form-group">
+                    <label htmlFor="targetPort">
+                      Target Port {!remoteIp && <span className="form-optional">(optional)</span>}
+                    </label>
+                    <input
+                      id="targetPort"
+                      type="number"
+                      value={targetPort}
+                      onChange={(e) => setTargetPort(e.target.value)}
+                      placeholder="3000"
+                      min="1"
+                      max="65535"
+                      required={remoteIp}
+                    />
+                  </div>
+                </div>
+              )}
+              {!remoteIp && (
+                <div className="form-hint">
+                  Only the domain name is required. The domain will resolve to 127.0.0.1 unless you pick another address, such as a Docker container or a VM. Set a target port to enable reverse proxying.
+                </div>
+              )}
+            </>
+          )}
+          {domainType === "override" && (
+            <>
+              <div className="form-hint">
+                Points a real hostname, such as api.example.com, at a local server with a certificate from the LocalDomain CA. It turns itself off after the chosen time.
+              </div>
+              <div className="form-row">
+                <div className="form-group">
+                  <label htmlFor="overrideTargetHost">Target Host <span className="form-optional">(optional)</span></label>
+                  <input
+                    id="overrideTargetHost"
                     type="text"
                     value={targetHost}
                     onChange={(e) => setTargetHost(e.target.value)}
@@ -154,21 +380,46 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
                   />
                 </div>
                 <div className="form-group">
-                  <label htmlFor="targetPort">Target Port <span className="form-optional">(optional)</span></label>
+                  <label htmlFor="overrideTargetPort">Target Port</label>
                   <input
-                    id="targetPort"
+                    id="overrideTargetPort"
                     type="number"
                     value={targetPort}
                     onChange={(e) => setTargetPort(e.target.value)}
                     placeholder="3000"
                     min="1"
                     max="65535"
+                    required
                   />
                 </div>
+                <div className="form-group">
+                  <label htmlFor="overrideTtl">Turn Off After</label>
+                  <select
+                    id="overrideTtl"
+                    value={overrideTtl}
+                    onChange={(e) => setOverrideTtl(e.target.value)}
+                  >
+                    {OVERRIDE_DURATIONS.map((d) => (
+                      <option key={d.minutes} value={String(d.minutes)}>
+                        {d.label}
+                      </option>
+                    ))}
+                  </select>
+                </div>
               </div>
-              <div className="form-hint">
-                Only the domain name is required. The domain will resolve to 127.0.0.1. Set a target port to enable reverse proxying.
-              </div>
+              {needsOverrideConfirm && (
+                <div className="form-group form-warning">
+                  <label className="checkbox-label">
+                    <input
+                      type="checkbox"
+                      checked={confirmOverride}
+                      onChange={(e) => setConfirmOverride(e.target.checked)}
+                      required
+                    />
+                    Send {name.trim() || "this hostname"} to this machine. The real site is unreachable from this computer until the override ends.
+                  </label>
+                </div>
+              )}
             </>
           )}
           {domainType === "xampp" && (
@@ -227,6 +478,76 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
               HTTPS requires a trusted root certificate. It will be installed automatically if needed. You may need to restart your browser afterward.
             </div>
           )}
+          {(protocol === "https" || protocol === "both") && (
+            <div className="form-row">
+              <div className="form-group">
+                <label htmlFor="extraSans">Extra Certificate Names <span className="form-optional">(optional)</span></label>
+                <input
+                  id="extraSans"
+                  type="text"
+                  value={extraSans}
+                  onChange={(e) => setExtraSans(e.target.value)}
+                  placeholder="localhost, 127.0.0.1, ::1"
+                />
+              </div>
+              <div className="form-group">
+                <label htmlFor="keyAlgorithm">Key Algorithm</label>
+                <select
+                  id="keyAlgorithm"
+                  value={keyAlgorithm}
+                  onChange={(e) => setKeyAlgorithm(e.target.value as KeyAlgorithm)}
+                >
+                  <option value="ecdsa_p256">ECDSA P-256</option>
+                  <option value="ecdsa_p384">ECDSA P-384</option>
+                  <option value="rsa2048">RSA 2048</option>
+                  <option value="rsa4096">RSA 4096</option>
+                </select>
+              </div>
+            </div>
+          )}
+          {(protocol === "https" || protocol === "both") && (
+            <div className="form-hint">
+              Aliases, *.wildcards, localhost or IP addresses such as your LAN IP. Choose RSA for legacy clients and older Java runtimes.
+            </div>
+          )}
+          {isEditing && (protocol === "https" || protocol === "both") && (
+            <div className="form-group">
+              <label>Certificate</label>
+              {customCertPath ? (
+                <div className="form-row">
+                  <span className="form-hint">Custom: {customCertPath}</span>
+                  <button
+                    type="button"
+                    className="btn btn-sm"
+                    onClick={handleClearCustomCert}
+                    disabled={certBusy}
+                  >
+                    Use LocalDomain CA
+                  </button>
+                </div>
+              ) : (
+                <div className="form-row">
+                  <input
+                    type="password"
+                    placeholder="Key passphrase (if encrypted)"
+                    value={certPassphrase}
+                    onChange={(e) => setCertPassphrase(e.target.value)}
+                  />
+                  <button
+                    type="button"
+                    className="btn btn-sm"
+                    onClick={handleCustomCert}
+                    disabled={certBusy}
+                  >
+                    Use custom certificate...
+                  </button>
+                </div>
+              )}
+              <div className="form-hint">
+                Custom certificates are served as-is and are not renewed automatically.
+              </div>
+            </div>
+          )}
           <div className="form-group">
             <label className="checkbox-label">
               <input
@@ -237,13 +558,29 @@ export function DomainFormModal({ domain, onSave, onClose }: DomainFormModalProp
               Wildcard (*.domain)
             </label>
           </div>
+          {warnings && warnings.length > 0 && (
+            <div className="form-warning">
+              <ul>
+                {warnings.map((w, i) => (
+                  <li key={i}>{w.message}</li>
+                ))}
+              </ul>
+              <div>Save again to continue anyway.</div>
+            </div>
+          )}
           {error && <div className="form-error">{error}</div>}
           <div className="modal-actions">
             <button type="button" className="btn" onClick={onClose}>
               Cancel
             </button>
             <button type="submit" className="btn btn-primary" disabled={saving}>
-              {saving ? "Saving..." : isEditing ? "Update" : "Create"}
+              {saving
+                ? "Saving..."
+                : warnings && warnings.length > 0
+                  ? "Save Anyway"
+                  : isEditing
+                    ? "Update"
+                    : "Create"}
             </button>
           </div>
         </form>
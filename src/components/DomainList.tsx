@@ -25,7 +25,7 @@ interface DomainListProps {
 }
 
 export function DomainList({ onInspect, searchQuery, domainFilter, addTrigger }: DomainListProps) {
-  const { domains, loading, error, create, update, remove, toggle } = useDomains();
+  const { domains, loading, error, refresh, create, update, remove, toggle } = useDomains();
   const { track } = useLoading();
   const [showModal, setShowModal] = useState(false);
   const [editingDomain, setEditingDomain] = useState<Domain | null>(null);
@@ -41,6 +41,18 @@ export function DomainList({ onInspect, searchQuery, domainFilter, addTrigger }:
     // Prevent duplicate toggles while one is already in flight for this card.
     if (toggleProgressById[id] !== undefined) return;
 
+    const domain = domains.find((d) => d.id === id);
+    const confirmOverride = domain?.domain_type === "override" && enabled;
+    if (
+      domain &&
+      confirmOverride &&
+      !window.confirm(
+        `Send ${domain.display_name} to this machine for ${domain.override_ttl_minutes} minutes? The real site will be unreachable from here until the override ends.`
+      )
+    ) {
+      return;
+    }
+
     // Show per-card loading immediately at 10%.
     flushSync(() => {
       setToggleProgressById((prev) => ({ ...prev, [id]: 10 }));
@@ -61,7 +73,7 @@ export function DomainList({ onInspect, searchQuery, domainFilter, addTrigger }:
             });
           }, 120);
 
-          await toggle(id, enabled);
+          await toggle(id, enabled, confirmOverride || undefined);
         } finally {
           if (creepTimer) clearInterval(creepTimer);
 
@@ -114,7 +126,10 @@ export function DomainList({ onInspect, searchQuery, domainFilter, addTrigger }:
   }, [pollTunnels]);
 
   const filteredDomains = domains.filter((d) => {
-    if (searchQuery && !d.name.toLowerCase().includes(searchQuery.toLowerCase())) return false;
+    if (searchQuery) {
+      const query = searchQuery.toLowerCase();
+      if (!d.name.toLowerCase().includes(query) && !d.display_name.toLowerCase().includes(query)) return false;
+    }
     if (domainFilter === "active" && !d.enabled) return false;
     if (domainFilter === "inactive" && d.enabled) return false;
     return true;
@@ -248,6 +263,7 @@ export function DomainList({ onInspect, searchQuery, domainFilter, addTrigger }:
         <DomainFormModal
           domain={editingDomain}
           onSave={handleSave}
+          onCertificateChanged={refresh}
           onClose={() => setShowModal(false)}
         />
       )}
@@ -9,6 +9,7 @@ import {
   SettingsIcon,
   ListIcon,
   InfoIcon,
+  ShieldIcon,
   ChevronLeftIcon,
   ChevronRightIcon,
   SunIcon,
@@ -16,6 +17,7 @@ import {
   SearchIcon,
   PlusIcon,
   XamppIcon,
+  ActivityIcon,
 } from "./Icons";
 import logoImg from "../assets/logo.png";
 
@@ -23,8 +25,10 @@ const VIEW_TITLES: Record<View, string> = {
   domains: "Domains",
   xampp: "XAMPP",
   settings: "Settings",
+  certificates: "Certificates",
   audit: "Audit Log",
   inspect: "Inspect",
+  traffic: "Traffic",
   about: "About",
 };
 
@@ -38,6 +42,8 @@ const NAV_SECTIONS: NavSection[] = [
     items: [
       { view: "domains", label: "Domains", icon: <GlobeIcon /> },
       { view: "settings", label: "Settings", icon: <SettingsIcon /> },
+      { view: "certificates", label: "Certificates", icon: <ShieldIcon /> },
+      { view: "traffic", label: "Traffic", icon: <ActivityIcon /> },
       { view: "audit", label: "Audit Log", icon: <ListIcon /> },
       { view: "about", label: "About", icon: <InfoIcon /> },
     ],
@@ -1,8 +1,118 @@
 import { useState, useEffect } from "react";
-import type { AppSettings, ServiceStatus } from "../lib/types";
+import type {
+  AppSettings,
+  DeviceOnboardingInfo,
+  HostsBackup,
+  HostsBackupDiff,
+  HostsTamperPolicy,
+  ServiceStatus,
+} from "../lib/types";
 import { useLoading } from "../hooks/useLoading";
 import * as api from "../lib/api";
 
+function splitList(value: string): string[] {
+  return value
+    .split(",")
+    .map((s) => s.trim())
+    .filter((s) => s.length > 0);
+}
+
+function HostsBackupsSection() {
+  const { track } = useLoading();
+  const [backups, setBackups] = useState<HostsBackup[]>([]);
+  const [diff, setDiff] = useState<HostsBackupDiff | null>(null);
+  const [confirmRestore, setConfirmRestore] = useState<string | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  const load = () =>
+    api
+      .listHostsBackups()
+      .then(setBackups)
+      .catch(() => setBackups([]));
+
+  useEffect(() => {
+    load();
+  }, []);
+
+  const handleDiff = async (id: string) => {
+    if (diff?.id === id) {
+      setDiff(null);
+      return;
+    }
+    try {
+      setDiff(await api.diffHostsBackup(id));
+    } catch (e) {
+      setMessage(`Error: ${e}`);
+    }
+  };
+
+  const handleRestore = async (id: string) => {
+    if (confirmRestore !== id) {
+      setConfirmRestore(id);
+      setTimeout(() => setConfirmRestore(null), 4000);
+      return;
+    }
+    setConfirmRestore(null);
+    try {
+      await track(api.restoreHostsBackup(id));
+      setMessage("Hosts file restored. The previous version was kept as a backup.");
+      setDiff(null);
+      load();
+    } catch (e) {
+      setMessage(`Error: ${e}`);
+    }
+  };
+
+  return (
+    <section className="settings-section">
+      <h3>Hosts File Backups</h3>
+      <p className="settings-service-desc">
+        A copy of the hosts file is kept before each change. The original is kept for as long as
+        LocalDomain is installed and is put back when you uninstall.
+      </p>
+      {backups.length === 0 ? (
+        <p className="form-hint">No backups yet.</p>
+      ) : (
+        <ul className="hosts-backup-list">
+          {backups.map((backup) => (
+            <li key={backup.id}>
+              <div className="hosts-backup-info">
+                <span>
+                  {backup.original ? "Original" : new Date(backup.created_at * 1000).toLocaleString()}
+                </span>
+                <code title={backup.sha256}>{backup.sha256.slice(0, 12)}</code>
+                <span>{backup.size} B</span>
+              </div>
+              <button className="btn btn-sm" onClick={() => handleDiff(backup.id)}>
+                {diff?.id === backup.id ? "Hide" : "Compare"}
+              </button>
+              <button className="btn btn-sm" onClick={() => handleRestore(backup.id)}>
+                {confirmRestore === backup.id ? "Confirm" : "Restore"}
+              </button>
+            </li>
+          ))}
+        </ul>
+      )}
+      {diff && (
+        <pre className="hosts-diff">
+          {diff.lines.map((line, i) => (
+            <div key={i} className={`hosts-diff-${line.op}`}>
+              {line.op === "added" ? "+ " : line.op === "removed" ? "- " : "  "}
+              {line.text}
+            </div>
+          ))}
+        </pre>
+      )}
+      {diff && (
+        <p className="form-hint">
+          Lines marked + are in the current file only, lines marked - only in the backup.
+        </p>
+      )}
+      {message && <div className="form-message">{message}</div>}
+    </section>
+  );
+}
+
 interface SettingsViewProps {
   status: ServiceStatus;
   onStart: () => Promise<void>;
@@ -25,20 +135,39 @@ export function SettingsView({ status, onStart, onStop, onUninstall, onTrustCa }
   const [actionLoading, setActionLoading] = useState(false);
   const [trustingCa, setTrustingCa] = useState(false);
   const [trustHint, setTrustHint] = useState(false);
+  const [suffixesText, setSuffixesText] = useState("");
+  const [ipsText, setIpsText] = useState("");
+  const [onboarding, setOnboarding] = useState<DeviceOnboardingInfo | null>(null);
+
+  const loadOnboarding = () =>
+    api
+      .getDeviceOnboarding()
+      .then(setOnboarding)
+      .catch(() => setOnboarding(null));
 
   useEffect(() => {
     api.getSettings().then((s) => {
       setSettings(s);
+      setSuffixesText((s.ca_permitted_suffixes ?? []).join(", "));
+      setIpsText((s.ca_permitted_ips ?? []).join(", "));
       setLoading(false);
     });
+    loadOnboarding();
   }, []);
 
   const handleSave = async () => {
     setSaving(true);
     setMessage(null);
     try {
-      await track(api.saveSettings(settings));
+      await track(
+        api.saveSettings({
+          ...settings,
+          ca_permitted_suffixes: splitList(suffixesText),
+          ca_permitted_ips: splitList(ipsText),
+        })
+      );
       setMessage("Settings saved.");
+      loadOnboarding();
     } catch (e) {
       setMessage(`Error: ${e}`);
     } finally {
@@ -127,6 +256,205 @@ export function SettingsView({ status, onStart, onStop, onUninstall, onTrustCa }
           </div>
         </div>
 
+        <div className="form-group">
+          <label htmlFor="renewalWindow">Renew certificates before expiry (days)</label>
+          <input
+            id="renewalWindow"
+            type="number"
+            value={settings.cert_renewal_window_days ?? 30}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                cert_renewal_window_days: Math.max(0, parseInt(e.target.value, 10) || 0),
+              })
+            }
+            min="0"
+            max="365"
+          />
+          <p className="form-hint">Set to 0 to turn off automatic renewal.</p>
+        </div>
+
+        <div className="form-group">
+          <label className="checkbox-label">
+            <input
+              type="checkbox"
+              checked={settings.ca_name_constraints ?? true}
+              onChange={(e) =>
+                setSettings({ ...settings, ca_name_constraints: e.target.checked })
+              }
+            />
+            Restrict the root CA to development names
+          </label>
+        </div>
+
+        {(settings.ca_name_constraints ?? true) && (
+          <div className="form-row">
+            <div className="form-group">
+              <label htmlFor="caSuffixes">Permitted suffixes</label>
+              <input
+                id="caSuffixes"
+                type="text"
+                value={suffixesText}
+                onChange={(e) => setSuffixesText(e.target.value)}
+                placeholder="test, localhost, internal"
+              />
+            </div>
+            <div className="form-group">
+              <label htmlFor="caIps">Permitted IPs</label>
+              <input
+                id="caIps"
+                type="text"
+                value={ipsText}
+                onChange={(e) => setIpsText(e.target.value)}
+                placeholder="127.0.0.0/8, ::1/128"
+              />
+            </div>
+          </div>
+        )}
+        <p className="form-hint">
+          Applies to newly generated CAs. Rotate the CA from the Certificates view to apply changes.
+        </p>
+
+        <div className="form-group">
+          <label className="checkbox-label">
+            <input
+              type="checkbox"
+              checked={settings.acme_enabled ?? false}
+              onChange={(e) => setSettings({ ...settings, acme_enabled: e.target.checked })}
+            />
+            Run a local ACME server
+          </label>
+        </div>
+
+        {settings.acme_enabled && (
+          <>
+            <div className="form-row">
+              <div className="form-group">
+                <label htmlFor="acmePort">ACME port</label>
+                <input
+                  id="acmePort"
+                  type="number"
+                  value={settings.acme_port ?? 8555}
+                  onChange={(e) =>
+                    setSettings({ ...settings, acme_port: parseInt(e.target.value) || 8555 })
+                  }
+                  min="1"
+                  max="65535"
+                />
+              </div>
+              <div className="form-group">
+                <label htmlFor="acmeHttp01Port">HTTP-01 validation port</label>
+                <input
+                  id="acmeHttp01Port"
+                  type="number"
+                  value={settings.acme_http01_port ?? 80}
+                  onChange={(e) =>
+                    setSettings({ ...settings, acme_http01_port: parseInt(e.target.value) || 80 })
+                  }
+                  min="1"
+                  max="65535"
+                />
+              </div>
+            </div>
+            <div className="form-group">
+              <label className="checkbox-label">
+                <input
+                  type="checkbox"
+                  checked={settings.acme_auto_approve ?? true}
+                  onChange={(e) =>
+                    setSettings({ ...settings, acme_auto_approve: e.target.checked })
+                  }
+                />
+                Auto-approve domains managed by LocalDomain
+              </label>
+            </div>
+            <p className="form-hint">
+              Point ACME clients at{" "}
+              <code>http://127.0.0.1:{settings.acme_port ?? 8555}/directory</code> and trust the
+              LocalDomain root CA in the client.
+            </p>
+          </>
+        )}
+
+        <div className="form-group">
+          <label className="checkbox-label">
+            <input
+              type="checkbox"
+              checked={settings.lan_sharing ?? false}
+              onChange={(e) => setSettings({ ...settings, lan_sharing: e.target.checked })}
+            />
+            Share the CA with phones and tablets on the local network
+          </label>
+        </div>
+
+        {settings.lan_sharing && (
+          <>
+            <div className="form-row">
+              <div className="form-group">
+                <label htmlFor="onboardingHost">Onboarding host</label>
+                <input
+                  id="onboardingHost"
+                  type="text"
+                  value={settings.onboarding_host ?? "ca.test"}
+                  onChange={(e) => setSettings({ ...settings, onboarding_host: e.target.value })}
+                  placeholder="ca.test"
+                />
+              </div>
+              <div className="form-group">
+                <label htmlFor="onboardingPort">Onboarding port</label>
+                <input
+                  id="onboardingPort"
+                  type="number"
+                  value={settings.onboarding_port ?? 8554}
+                  onChange={(e) =>
+                    setSettings({ ...settings, onboarding_port: parseInt(e.target.value) || 8554 })
+                  }
+                  min="1"
+                  max="65535"
+                />
+              </div>
+            </div>
+            {onboarding?.url && onboarding.qr_svg ? (
+              <div className="onboarding-qr">
+                <img
+                  src={`data:image/svg+xml;utf8,${encodeURIComponent(onboarding.qr_svg)}`}
+                  alt={`QR code for ${onboarding.url}`}
+                  width={180}
+                  height={180}
+                />
+                <p className="form-hint">
+                  Scan with the device's camera or open <code>{onboarding.url}</code> to install
+                  the CA. The page is served over plain HTTP on your LAN while sharing is on.
+                </p>
+              </div>
+            ) : (
+              <p className="form-hint">
+                {onboarding?.enabled && !onboarding.lan_ip
+                  ? "No LAN address found. Connect to a network to share the CA."
+                  : "Save settings to publish the onboarding page."}
+              </p>
+            )}
+          </>
+        )}
+
+        <div className="form-group">
+          <label htmlFor="hostsTamperPolicy">Hosts file changes by other programs</label>
+          <select
+            id="hostsTamperPolicy"
+            value={settings.hosts_tamper_policy ?? "repair"}
+            onChange={(e) =>
+              setSettings({ ...settings, hosts_tamper_policy: e.target.value as HostsTamperPolicy })
+            }
+          >
+            <option value="repair">Restore LocalDomain's entries automatically</option>
+            <option value="notify">Leave the file and log the change</option>
+          </select>
+        </div>
+        <p className="form-hint">
+          VPN clients and Docker Desktop sometimes rewrite the hosts file. Changes are recorded in
+          the Audit Log with the lines the other program added.
+        </p>
+
         {message && <div className="form-message">{message}</div>}
 
         <button className="btn btn-primary" onClick={handleSave} disabled={saving}>
@@ -134,6 +462,8 @@ export function SettingsView({ status, onStart, onStop, onUninstall, onTrustCa }
         </button>
       </section>
 
+      <HostsBackupsSection />
+
       <section className="settings-section">
         <h3>Service</h3>
         <div className="settings-service-row">
@@ -177,6 +507,17 @@ export function SettingsView({ status, onStart, onStop, onUninstall, onTrustCa }
             <p className="settings-service-desc">
               The root certificate is used to sign HTTPS certificates for your local domains.
             </p>
+            {status.ca_installed && status.trust_stores.length > 1 && (
+              <ul className="trust-store-list">
+                {status.trust_stores.map((store) => (
+                  <li key={store.path} title={store.error ?? store.path}>
+                    <span className={`status-dot ${store.trusted ? "green" : "yellow"}`} />
+                    <span>{store.label}</span>
+                    {store.error && <span className="trust-store-error">{store.error}</span>}
+                  </li>
+                ))}
+              </ul>
+            )}
             {trustHint && (
               <p className="form-hint form-hint-info">
                 Certificate trusted. You may need to restart your browser for changes to take effect.
@@ -0,0 +1,156 @@
+import { useState } from "react";
+import { useTrafficStats } from "../hooks/useTrafficStats";
+import type { StatusClassCounts, TrafficStats, TrafficWindow } from "../lib/types";
+import { ChevronRightIcon } from "./Icons";
+
+const WINDOWS: { value: TrafficWindow; label: string }[] = [
+  { value: "15m", label: "15m" },
+  { value: "1h", label: "1h" },
+  { value: "24h", label: "24h" },
+  { value: "7d", label: "7d" },
+  { value: "30d", label: "30d" },
+];
+
+const STATUS_CLASSES: (keyof StatusClassCounts)[] = ["1xx", "2xx", "3xx", "4xx", "5xx"];
+
+function formatLatency(seconds: number | null): string {
+  if (seconds === null) return "-";
+  if (seconds < 0.001) return `${(seconds * 1_000_000).toFixed(0)}us`;
+  if (seconds < 1) return `${(seconds * 1000).toFixed(0)}ms`;
+  return `${seconds.toFixed(2)}s`;
+}
+
+function formatBytes(bytes: number): string {
+  if (bytes === 0) return "-";
+  if (bytes < 1024) return `${bytes}B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)}KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)}MB`;
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(1)}GB`;
+}
+
+function formatRate(perMinute: number): string {
+  if (perMinute === 0) return "-";
+  if (perMinute < 0.1) return "<0.1";
+  return perMinute < 10 ? perMinute.toFixed(1) : perMinute.toFixed(0);
+}
+
+function StatusBar({ status }: { status: StatusClassCounts }) {
+  const total = STATUS_CLASSES.reduce((sum, cls) => sum + status[cls], 0);
+  if (total === 0) return <span className="text-muted">-</span>;
+  const title = STATUS_CLASSES.filter((cls) => status[cls] > 0)
+    .map((cls) => `${cls}: ${status[cls]}`)
+    .join(", ");
+  return (
+    <div className="traffic-status-bar" title={title}>
+      {STATUS_CLASSES.filter((cls) => status[cls] > 0).map((cls) => (
+        <span
+          key={cls}
+          className={`traffic-status-segment traffic-status-${cls}`}
+          style={{ width: `${(status[cls] / total) * 100}%` }}
+        />
+      ))}
+    </div>
+  );
+}
+
+function StatsCells({ stats }: { stats: TrafficStats }) {
+  return (
+    <>
+      <td className="traffic-number">{stats.requests}</td>
+      <td className="traffic-number">{formatRate(stats.requests_per_minute)}</td>
+      <td>
+        <StatusBar status={stats.status} />
+      </td>
+      <td className="traffic-number">{formatBytes(stats.bytes)}</td>
+      <td className="traffic-number">{formatLatency(stats.p50)}</td>
+      <td className="traffic-number">{formatLatency(stats.p95)}</td>
+      <td className="traffic-number">{formatLatency(stats.p99)}</td>
+    </>
+  );
+}
+
+export function TrafficView() {
+  const [window, setWindow] = useState<TrafficWindow>("1h");
+  const [expanded, setExpanded] = useState<string | null>(null);
+  const { stats, loading } = useTrafficStats(window);
+
+  const domains = stats?.domains ?? [];
+
+  return (
+    <div className="traffic-view">
+      <div className="traffic-header">
+        <div className="traffic-windows">
+          {WINDOWS.map(({ value, label }) => (
+            <button
+              key={value}
+              className={`traffic-window-btn ${window === value ? "active" : ""}`}
+              onClick={() => setWindow(value)}
+            >
+              {label}
+            </button>
+          ))}
+        </div>
+        <span className="text-muted traffic-hint">
+          Percentiles are estimates, within 20% of the actual latency.
+        </span>
+      </div>
+
+      {loading && !stats ? (
+        <div className="loading">Loading traffic...</div>
+      ) : domains.length === 0 ? (
+        <div className="empty-state">
+          <p>No traffic recorded yet.</p>
+          <p className="text-muted">Enable the access log on a domain to collect statistics.</p>
+        </div>
+      ) : (
+        <div className="traffic-table-wrapper">
+          <table className="inspector-table traffic-table">
+            <thead>
+              <tr>
+                <th>Domain</th>
+                <th>Requests</th>
+                <th>Req/min</th>
+                <th>Status</th>
+                <th>Bytes</th>
+                <th>p50</th>
+                <th>p95</th>
+                <th>p99</th>
+              </tr>
+            </thead>
+            <tbody>
+              {domains.map((domain) => {
+                const isExpanded = expanded === domain.domain;
+                return [
+                  <tr
+                    key={domain.domain}
+                    className={`inspector-row-clickable ${isExpanded ? "inspector-row-selected" : ""}`}
+                    onClick={() => setExpanded(isExpanded ? null : domain.domain)}
+                  >
+                    <td className="traffic-domain">
+                      <ChevronRightIcon
+                        size={12}
+                        className={`traffic-chevron ${isExpanded ? "traffic-chevron-open" : ""}`}
+                      />
+                      {domain.domain}
+                    </td>
+                    <StatsCells stats={domain} />
+                  </tr>,
+                  ...(isExpanded
+                    ? domain.routes.map((route) => (
+                        <tr key={`${domain.domain} ${route.route}`} className="traffic-route-row">
+                          <td className="traffic-route inspector-path" title={route.route}>
+                            {route.route}
+                          </td>
+                          <StatsCells stats={route} />
+                        </tr>
+                      ))
+                    : []),
+                ];
+              })}
+            </tbody>
+          </table>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -1,5 +1,6 @@
 import { useState, useEffect, useCallback, useRef } from "react";
-import type { AccessLogEntry } from "../lib/types";
+import { listen } from "@tauri-apps/api/event";
+import type { AccessLogEntry, AccessLogTailEvent } from "../lib/types";
 import * as api from "../lib/api";
 
 export function useAccessLog(domain: string, limit = 100, intervalMs = 2000) {
@@ -9,8 +10,8 @@ export function useAccessLog(domain: string, limit = 100, intervalMs = 2000) {
 
   const fetch = useCallback(async () => {
     try {
-      const data = await api.getAccessLog(domain, limit);
-      setEntries(data);
+      const page = await api.getAccessLog(domain, limit);
+      setEntries(page.entries);
     } catch {
       // ignore polling errors
     } finally {
@@ -19,13 +20,42 @@ export function useAccessLog(domain: string, limit = 100, intervalMs = 2000) {
   }, [domain, limit]);
 
   useEffect(() => {
+    let cancelled = false;
+    // Polling is the fallback for when the live tail can't run
+    const startPolling = () => {
+      if (!cancelled && !timerRef.current) {
+        timerRef.current = setInterval(fetch, intervalMs);
+      }
+    };
+
+    const unlistenEntry = listen<AccessLogTailEvent>("access-log-entry", (event) => {
+      if (event.payload.domain !== domain) return;
+      setEntries((prev) => [event.payload.entry, ...prev].slice(0, limit));
+    });
+    const unlistenEnded = listen<string>("access-log-tail-ended", (event) => {
+      if (event.payload === domain) startPolling();
+    });
+
     setLoading(true);
-    fetch();
-    timerRef.current = setInterval(fetch, intervalMs);
+    api
+      .startAccessLogTail(domain)
+      .then(() => {
+        if (cancelled) api.stopAccessLogTail().catch(() => {});
+      })
+      .catch(startPolling)
+      .finally(fetch);
+
     return () => {
-      if (timerRef.current) clearInterval(timerRef.current);
+      cancelled = true;
+      if (timerRef.current) {
+        clearInterval(timerRef.current);
+        timerRef.current = null;
+      }
+      unlistenEntry.then((fn) => fn());
+      unlistenEnded.then((fn) => fn());
+      api.stopAccessLogTail().catch(() => {});
     };
-  }, [fetch, intervalMs]);
+  }, [domain, fetch, intervalMs, limit]);
 
   const clear = useCallback(async () => {
     await api.clearAccessLog(domain);
@@ -0,0 +1,101 @@
+import { useState, useEffect, useCallback } from "react";
+import type { CaStatus, CertificateInfo } from "../lib/types";
+import * as api from "../lib/api";
+
+export function useCertificates() {
+  const [certificates, setCertificates] = useState<CertificateInfo[]>([]);
+  const [caStatus, setCaStatus] = useState<CaStatus | null>(null);
+  const [loading, setLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = useCallback(async () => {
+    try {
+      setLoading(true);
+      setCertificates(await api.listCertificates());
+      setCaStatus(await api.getCaStatus());
+      setError(null);
+    } catch (e) {
+      setCertificates([]);
+      setError(String(e));
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  const remove = useCallback(
+    async (name: string) => {
+      await api.deleteCertificate(name);
+      await refresh();
+    },
+    [refresh]
+  );
+
+  const rotateCa = useCallback(
+    async (overlapDays: number) => {
+      const result = await api.rotateCa(overlapDays);
+      // macOS needs the admin prompt to trust the new CA
+      if (!result.trust_updated) {
+        await api.trustCa();
+      }
+      await refresh();
+      return result;
+    },
+    [refresh]
+  );
+
+  const importCa = useCallback(
+    async (
+      certPath: string,
+      keyPath: string | null,
+      passphrase: string | null,
+      overlapDays: number
+    ) => {
+      const result = await api.importCa(certPath, keyPath, passphrase, overlapDays);
+      if (result.rotation && !result.rotation.trust_updated) {
+        await api.trustCa();
+      }
+      await refresh();
+      return result;
+    },
+    [refresh]
+  );
+
+  const retirePreviousCa = useCallback(async () => {
+    await api.retirePreviousCa();
+    await refresh();
+  }, [refresh]);
+
+  const protectRootKey = useCallback(
+    async (passphrase: string, offlinePath: string | null) => {
+      await api.protectRootKey(passphrase, offlinePath);
+      await refresh();
+    },
+    [refresh]
+  );
+
+  const renewIntermediate = useCallback(
+    async (passphrase: string | null, rootKeyPath: string | null) => {
+      await api.renewIntermediate(passphrase, rootKeyPath);
+      await refresh();
+    },
+    [refresh]
+  );
+
+  return {
+    certificates,
+    caStatus,
+    loading,
+    error,
+    refresh,
+    remove,
+    rotateCa,
+    importCa,
+    retirePreviousCa,
+    protectRootKey,
+    renewIntermediate,
+  };
+}
@@ -71,10 +71,10 @@ export function useDomains() {
   );
 
   const toggle = useCallback(
-    async (id: string, enabled: boolean) => {
+    async (id: string, enabled: boolean, confirmOverride?: boolean) => {
       try {
         setError(null);
-        await track(api.toggleDomain(id, enabled));
+        await track(api.toggleDomain(id, enabled, confirmOverride));
         await refresh();
       } catch (e) {
         setError(String(e));
@@ -10,6 +10,7 @@ export function useServiceStatus(pollIntervalMs = 5000) {
     caddy_running: false,
     ca_installed: false,
     ca_trusted: false,
+    trust_stores: [],
     xampp_running: false,
   });
   const [loading, setLoading] = useState(true);
@@ -26,6 +27,7 @@ export function useServiceStatus(pollIntervalMs = 5000) {
         caddy_running: false,
         ca_installed: false,
         ca_trusted: false,
+        trust_stores: [],
         xampp_running: false,
       });
     } finally {
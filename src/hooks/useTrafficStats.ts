@@ -0,0 +1,28 @@
+import { useState, useEffect, useCallback } from "react";
+import type { TrafficStatsResult, TrafficWindow } from "../lib/types";
+import * as api from "../lib/api";
+
+export function useTrafficStats(window: TrafficWindow, intervalMs = 10000) {
+  const [stats, setStats] = useState<TrafficStatsResult | null>(null);
+  const [loading, setLoading] = useState(true);
+
+  const refresh = useCallback(async () => {
+    try {
+      setStats(await api.getTrafficStats(window));
+    } catch {
+      setStats(null);
+    } finally {
+      setLoading(false);
+    }
+  }, [window]);
+
+  useEffect(() => {
+    setLoading(true);
+    refresh();
+    // The daemon rolls up new log entries every few seconds
+    const timer = setInterval(refresh, intervalMs);
+    return () => clearInterval(timer);
+  }, [refresh, intervalMs]);
+
+  return { stats, loading, refresh };
+}
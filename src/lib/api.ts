@@ -5,8 +5,21 @@ import type {
   UpdateDomainRequest,
   ServiceStatus,
   AuditLogEntry,
-  AccessLogEntry,
+  AccessLogFilter,
+  AccessLogPage,
+  TrafficStatsResult,
+  TrafficWindow,
   AppSettings,
+  CaStatus,
+  CertExportFormat,
+  CertificateInfo,
+  DeviceOnboardingInfo,
+  DomainWarning,
+  HostsBackup,
+  HostsBackupDiff,
+  ImportCaResult,
+  RotateCaResult,
+  ToolchainTrust,
   StartTunnelRequest,
   StartTunnelResult,
   TunnelStatusResult,
@@ -24,6 +37,20 @@ export async function listDomains(): Promise<Domain[]> {
   return invoke("list_domains");
 }
 
+export async function checkDomainConflicts(
+  name: string,
+  aliases: string[],
+  resolveIp: string | undefined,
+  hostsOnly: boolean
+): Promise<DomainWarning[]> {
+  return invoke("check_domain_conflicts", {
+    name,
+    aliases,
+    resolveIp,
+    hostsOnly,
+  });
+}
+
 export async function createDomain(
   request: CreateDomainRequest
 ): Promise<Domain> {
@@ -42,9 +69,10 @@ export async function deleteDomain(id: string): Promise<void> {
 
 export async function toggleDomain(
   id: string,
-  enabled: boolean
+  enabled: boolean,
+  confirmOverride?: boolean
 ): Promise<Domain> {
-  return invoke("toggle_domain", { id, enabled });
+  return invoke("toggle_domain", { id, enabled, confirmOverride });
 }
 
 export async function getServiceStatus(): Promise<ServiceStatus> {
@@ -74,17 +102,55 @@ export async function toggleAccessLog(
   return invoke("toggle_access_log", { id, enabled });
 }
 
+export async function setCustomCertificate(
+  id: string,
+  certPath: string,
+  keyPath: string | null,
+  passphrase: string | null
+): Promise<Domain> {
+  return invoke("set_custom_certificate", { id, certPath, keyPath, passphrase });
+}
+
+export async function clearCustomCertificate(id: string): Promise<Domain> {
+  return invoke("clear_custom_certificate", { id });
+}
+
 export async function getAccessLog(
   domain: string,
+  limit?: number,
+  filter?: AccessLogFilter,
+  cursor?: string
+): Promise<AccessLogPage> {
+  return invoke("get_access_log", { domain, limit, filter, cursor });
+}
+
+export async function queryAccessLogs(
+  filter?: AccessLogFilter,
+  cursor?: string,
   limit?: number
-): Promise<AccessLogEntry[]> {
-  return invoke("get_access_log", { domain, limit });
+): Promise<AccessLogPage> {
+  return invoke("query_access_logs", { limit, filter, cursor });
 }
 
 export async function clearAccessLog(domain: string): Promise<void> {
   return invoke("clear_access_log", { domain });
 }
 
+export async function startAccessLogTail(domain: string): Promise<void> {
+  return invoke("start_access_log_tail", { domain });
+}
+
+export async function stopAccessLogTail(): Promise<void> {
+  return invoke("stop_access_log_tail");
+}
+
+export async function getTrafficStats(
+  window: TrafficWindow,
+  domain?: string
+): Promise<TrafficStatsResult> {
+  return invoke("get_traffic_stats", { window, domain });
+}
+
 export async function clearAuditLog(): Promise<void> {
   return invoke("clear_audit_log");
 }
@@ -108,6 +174,102 @@ export async function trustCa(): Promise<void> {
   return invoke("trust_ca");
 }
 
+export async function listCertificates(): Promise<CertificateInfo[]> {
+  return invoke("list_certificates");
+}
+
+export async function deleteCertificate(name: string): Promise<void> {
+  return invoke("delete_certificate", { name });
+}
+
+export async function exportCertificate(
+  name: string,
+  format: CertExportFormat,
+  passphrase: string | null,
+  path: string
+): Promise<void> {
+  return invoke("export_certificate", { name, format, passphrase, path });
+}
+
+export async function rotateCa(overlapDays: number): Promise<RotateCaResult> {
+  return invoke("rotate_ca", { overlapDays });
+}
+
+export async function importCa(
+  certPath: string,
+  keyPath: string | null,
+  passphrase: string | null,
+  overlapDays: number
+): Promise<ImportCaResult> {
+  return invoke("import_ca", { certPath, keyPath, passphrase, overlapDays });
+}
+
+export async function exportCaBundle(
+  passphrase: string,
+  rootPassphrase: string | null,
+  path: string
+): Promise<void> {
+  return invoke("export_ca_bundle", { passphrase, rootPassphrase, path });
+}
+
+export async function retirePreviousCa(): Promise<void> {
+  return invoke("retire_previous_ca");
+}
+
+export async function getCaStatus(): Promise<CaStatus> {
+  return invoke("get_ca_status");
+}
+
+export async function getDeviceOnboarding(): Promise<DeviceOnboardingInfo> {
+  return invoke("get_device_onboarding");
+}
+
+export async function listHostsBackups(): Promise<HostsBackup[]> {
+  return invoke("list_hosts_backups");
+}
+
+export async function diffHostsBackup(id: string): Promise<HostsBackupDiff> {
+  return invoke("diff_hosts_backup", { id });
+}
+
+export async function restoreHostsBackup(id: string): Promise<void> {
+  return invoke("restore_hosts_backup", { id });
+}
+
+export async function protectRootKey(
+  passphrase: string,
+  offlinePath: string | null
+): Promise<void> {
+  return invoke("protect_root_key", { passphrase, offlinePath });
+}
+
+export async function renewIntermediate(
+  passphrase: string | null,
+  rootKeyPath: string | null
+): Promise<void> {
+  return invoke("renew_intermediate", { passphrase, rootKeyPath });
+}
+
+export async function getToolchainTrust(): Promise<ToolchainTrust> {
+  return invoke("get_toolchain_trust");
+}
+
+export async function installToolchainTrust(): Promise<ToolchainTrust> {
+  return invoke("install_toolchain_trust");
+}
+
+export async function removeToolchainTrust(): Promise<ToolchainTrust> {
+  return invoke("remove_toolchain_trust");
+}
+
+export async function installShellEnv(): Promise<ToolchainTrust> {
+  return invoke("install_shell_env");
+}
+
+export async function removeShellEnv(): Promise<ToolchainTrust> {
+  return invoke("remove_shell_env");
+}
+
 export async function startTunnel(
   request: StartTunnelRequest
 ): Promise<StartTunnelResult> {
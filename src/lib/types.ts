@@ -1,6 +1,9 @@
 export interface Domain {
   id: string;
+  /** ASCII (punycode) form of the name */
   name: string;
+  /** Unicode form of the name, for display */
+  display_name: string;
   target_host: string;
   target_port: number;
   protocol: "http" | "https" | "both";
@@ -11,18 +14,40 @@ export interface Domain {
   updated_at: string;
   tunnel_subdomain: string;
   tunnel_domain: string;
-  domain_type: "proxy" | "xampp";
+  domain_type: DomainType;
   document_root: string;
+  extra_sans: string[];
+  key_algorithm: KeyAlgorithm;
+  custom_cert_path?: string | null;
+  custom_key_path?: string | null;
+  resolve_ip: string;
+  hosts_only: boolean;
+  aliases: string[];
+  display_aliases: string[];
+  override_ttl_minutes: number;
+  /** Unix seconds; set while an override is active */
+  override_expires_at?: number | null;
 }
 
+export type DomainType = "proxy" | "xampp" | "override";
+
+export type KeyAlgorithm = "ecdsa_p256" | "ecdsa_p384" | "rsa2048" | "rsa4096";
+
 export interface CreateDomainRequest {
   name: string;
   target_host?: string;
   target_port?: number;
   protocol?: string;
   wildcard?: boolean;
-  domain_type?: "proxy" | "xampp";
+  domain_type?: DomainType;
   document_root?: string;
+  extra_sans?: string[];
+  key_algorithm?: KeyAlgorithm;
+  resolve_ip?: string;
+  hosts_only?: boolean;
+  aliases?: string[];
+  override_ttl_minutes?: number;
+  confirm_override?: boolean;
 }
 
 export interface UpdateDomainRequest {
@@ -33,8 +58,28 @@ export interface UpdateDomainRequest {
   protocol?: string;
   wildcard?: boolean;
   enabled?: boolean;
-  domain_type?: "proxy" | "xampp";
+  domain_type?: DomainType;
   document_root?: string;
+  extra_sans?: string[];
+  key_algorithm?: KeyAlgorithm;
+  resolve_ip?: string;
+  hosts_only?: boolean;
+  aliases?: string[];
+  override_ttl_minutes?: number;
+  confirm_override?: boolean;
+}
+
+export type DomainWarningKind =
+  | "hosts_conflict"
+  | "hosts_duplicate"
+  | "public_dns"
+  | "hsts_tld"
+  | "mdns_tld";
+
+export interface DomainWarning {
+  domain: string;
+  kind: DomainWarningKind;
+  message: string;
 }
 
 export interface ServiceStatus {
@@ -42,9 +87,20 @@ export interface ServiceStatus {
   caddy_running: boolean;
   ca_installed: boolean;
   ca_trusted: boolean;
+  trust_stores: TrustStoreStatus[];
   xampp_running: boolean;
 }
 
+export type TrustStoreKind = "system" | "firefox" | "chromium";
+
+export interface TrustStoreStatus {
+  kind: TrustStoreKind;
+  label: string;
+  path: string;
+  trusted: boolean;
+  error: string | null;
+}
+
 export interface AuditLogEntry {
   id: number;
   action: string;
@@ -62,6 +118,43 @@ export interface AppSettings {
   default_ssh_user?: string;
   default_ssh_key_path?: string;
   xampp_path?: string;
+  cert_renewal_window_days?: number;
+  ca_name_constraints?: boolean;
+  ca_permitted_suffixes?: string[];
+  ca_permitted_ips?: string[];
+  acme_enabled?: boolean;
+  acme_port?: number;
+  acme_http01_port?: number;
+  acme_auto_approve?: boolean;
+  lan_sharing?: boolean;
+  onboarding_host?: string;
+  onboarding_port?: number;
+  hosts_tamper_policy?: HostsTamperPolicy;
+}
+
+export type HostsTamperPolicy = "repair" | "notify";
+
+export interface HostsTamperEvent {
+  detected_at: number;
+  missing: { domain: string; ip: string }[];
+  unexpected: string[];
+  added_lines: string[];
+  removed_lines: string[];
+  repaired: boolean;
+  error?: string | null;
+}
+
+export interface HostsBackup {
+  id: string;
+  created_at: number;
+  sha256: string;
+  size: number;
+  original: boolean;
+}
+
+export interface HostsBackupDiff {
+  id: string;
+  lines: { op: "same" | "added" | "removed"; text: string }[];
 }
 
 export interface AccessLogEntry {
@@ -78,6 +171,142 @@ export interface AccessLogEntry {
   proto: string;
 }
 
+/** Conditions for access log queries; omitted fields match everything */
+export interface AccessLogFilter {
+  /** Unix seconds, inclusive */
+  since?: number;
+  until?: number;
+  method?: string;
+  status_min?: number;
+  status_max?: number;
+  /** Substring of the URI, or a regular expression with uri_regex */
+  uri?: string;
+  uri_regex?: boolean;
+  remote_ip?: string;
+  /** Seconds */
+  min_duration?: number;
+  header?: { name: string; value?: string };
+}
+
+export interface AccessLogPage {
+  entries: AccessLogEntry[];
+  /** Pass back to get the next (older) page; null on the last one */
+  next_cursor: string | null;
+}
+
+/** Payload of the "access-log-entry" event while a tail is running */
+export interface AccessLogTailEvent {
+  domain: string;
+  entry: AccessLogEntry;
+}
+
+export type TrafficWindow = "15m" | "1h" | "24h" | "7d" | "30d";
+
+export interface StatusClassCounts {
+  "1xx": number;
+  "2xx": number;
+  "3xx": number;
+  "4xx": number;
+  "5xx": number;
+}
+
+export interface TrafficStats {
+  requests: number;
+  /** Averaged over the whole window */
+  requests_per_minute: number;
+  bytes: number;
+  status: StatusClassCounts;
+  /** Latency percentiles in seconds; null without requests */
+  p50: number | null;
+  p95: number | null;
+  p99: number | null;
+}
+
+export interface RouteTrafficStats extends TrafficStats {
+  /** Method and path, with ids in the path replaced by ":id" */
+  route: string;
+}
+
+export interface DomainTrafficStats extends TrafficStats {
+  domain: string;
+  /** Busiest first */
+  routes: RouteTrafficStats[];
+}
+
+export interface TrafficStatsResult {
+  window: TrafficWindow;
+  /** Start of the window (unix seconds) */
+  since: number;
+  domains: DomainTrafficStats[];
+}
+
+export interface CertificateInfo {
+  name: string;
+  cert_path: string;
+  key_path?: string;
+  subject: string;
+  sans: string[];
+  issuer: string;
+  serial: string;
+  not_before: number;
+  not_after: number;
+  fingerprint_sha256: string;
+  key_type: string;
+  is_ca: boolean;
+  signed_by_current_ca: boolean;
+  in_use: boolean;
+  name_constraints: string[];
+  custom: boolean;
+}
+
+export type CertExportFormat = "pkcs12" | "der" | "pem_chain" | "pem_bundle";
+
+export type RootKeyState = "online" | "encrypted" | "offline";
+
+export interface CaStatus {
+  root_key: RootKeyState;
+  intermediate_not_after?: number;
+  intermediate_valid: boolean;
+  permitted_dns_suffixes?: string[] | null;
+}
+
+export interface EnvVar {
+  name: string;
+  value: string;
+}
+
+export interface ToolchainTrust {
+  java_keystores: TrustStoreStatus[];
+  ca_cert_path: string;
+  bundle_path: string | null;
+  env: EnvVar[];
+  snippet: string;
+  installed_profiles: string[];
+}
+
+export interface RotateCaResult {
+  new_fingerprint: string;
+  previous_fingerprint: string;
+  reissued: string[];
+  failed: string[];
+  trust_updated: boolean;
+  retire_at?: number;
+}
+
+export interface ImportCaResult {
+  subject: string;
+  fingerprint: string;
+  rotation?: RotateCaResult;
+}
+
+export interface DeviceOnboardingInfo {
+  enabled: boolean;
+  host: string;
+  lan_ip?: string;
+  url?: string;
+  qr_svg?: string;
+}
+
 // Tunnel types
 
 export interface QuickTunnelConfig {
@@ -173,4 +402,12 @@ export interface ImportVhost {
   document_root: string;
 }
 
-export type View = "domains" | "settings" | "audit" | "inspect" | "about" | "xampp";
+export type View =
+  | "domains"
+  | "settings"
+  | "certificates"
+  | "audit"
+  | "inspect"
+  | "traffic"
+  | "about"
+  | "xampp";